@@ -54,26 +54,32 @@ pub fn affected_tasks(graph: &TaskGraph, changed: &[String]) -> HashSet<String>
         let Some(task) = graph.get_task(name) else {
             continue;
         };
-        let patterns: Vec<&String> = task
+        // `!pattern` watch entries exclude files from the task's inputs.
+        let (excludes, patterns): (Vec<&String>, Vec<&String>) = task
             .config
             .sources
             .iter()
             .chain(task.config.watch.iter())
-            .collect();
+            .partition(|p| p.starts_with('!'));
 
         let is_affected = if patterns.is_empty() {
             true
         } else {
-            let mut builder = GlobSetBuilder::new();
-            for p in patterns {
-                if let Ok(g) = Glob::new(p) {
-                    builder.add(g);
+            let build = |patterns: &[&String]| {
+                let mut builder = GlobSetBuilder::new();
+                for p in patterns {
+                    if let Ok(g) = Glob::new(p.trim_start_matches('!')) {
+                        builder.add(g);
+                    }
                 }
+                builder.build().ok()
+            };
+            match (build(&patterns), build(&excludes)) {
+                (Some(set), Some(negated)) => changed
+                    .iter()
+                    .any(|f| set.is_match(f) && !negated.is_match(f)),
+                _ => false,
             }
-            builder
-                .build()
-                .ok()
-                .is_some_and(|set| changed.iter().any(|f| set.is_match(f)))
         };
 
         if is_affected {
@@ -129,6 +135,21 @@ mod tests {
         assert!(aff.contains("app"));
     }
 
+    #[test]
+    fn negated_watch_patterns_exclude_changes() {
+        let g = graph(
+            r#"
+            [tasks.build]
+            run = ["x"]
+            watch = ["src/**", "!src/generated/**"]
+            "#,
+        );
+        let hit = affected_tasks(&g, &["src/generated/api.rs".into()]);
+        assert!(hit.is_empty());
+        let hit = affected_tasks(&g, &["src/lib.rs".into()]);
+        assert!(hit.contains("build"));
+    }
+
     #[test]
     fn no_sources_is_always_affected() {
         let g = graph(
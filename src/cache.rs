@@ -17,8 +17,12 @@
 #![allow(clippy::missing_errors_doc)]
 
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
     time::Duration,
 };
 
@@ -50,14 +54,97 @@ pub struct ActionResult {
     pub task: String,
     /// Timestamp of creation
     pub created_at: chrono::DateTime<chrono::Utc>,
+    /// When the entry was last served as a hit (its creation time until then).
+    /// Kept in a separate stamp file next to the action cache, so recording a
+    /// hit never rewrites the signed entry.
+    #[serde(skip)]
+    pub last_accessed_at: chrono::DateTime<chrono::Utc>,
     /// Duration of the original execution, in milliseconds
     pub duration_ms: u64,
     /// Whether the original execution succeeded
     pub success: bool,
-    /// Captured stdout of the task
+    /// Captured stdout of the task, when it is valid UTF-8
     pub stdout: String,
+    /// CAS digest of the captured stdout when it is binary (not UTF-8), in
+    /// which case `stdout` is empty. Skipped when empty so text entries and
+    /// their signatures are unchanged.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub stdout_blob: String,
     /// Declared output files captured into the CAS
     pub outputs: Vec<OutputEntry>,
+    /// Hash of just the task's commands (see [`Cache::command_fingerprint`]),
+    /// to tell a command change apart from a source change on a miss. Empty
+    /// for entries written by older versions or fetched over REAPI; skipped
+    /// when empty so older signed entries still verify.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub command_fingerprint: String,
+    /// BLAKE3 hex digest of each source file (relative path → digest) the
+    /// entry was built from, so a miss can name the files that changed (see
+    /// [`Cache::explain_miss`]). Ordered so the signed bytes are stable;
+    /// skipped when empty so older signed entries still verify.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub source_hashes: BTreeMap<String, String>,
+}
+
+impl ActionResult {
+    /// Every CAS blob this entry needs: declared outputs plus binary stdout.
+    fn blobs(&self) -> impl Iterator<Item = &str> {
+        self.outputs
+            .iter()
+            .map(|entry| entry.blob.as_str())
+            .chain((!self.stdout_blob.is_empty()).then_some(self.stdout_blob.as_str()))
+    }
+}
+
+/// Why a lookup for a task misses the cache (see [`Cache::explain_miss`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheMiss {
+    /// The task has no cached entries at all
+    NoEntry,
+    /// The task's commands or script changed since its last entry
+    CommandChanged,
+    /// Source files were added, removed or edited since the last entry
+    SourceChanged {
+        /// Relative paths of the changed files, sorted
+        changed_files: Vec<String>,
+    },
+    /// Another key input changed (env, `cwd`, `outputs`, ...) or the
+    /// matching entry is incomplete
+    Other,
+}
+
+impl std::fmt::Display for CacheMiss {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoEntry => f.write_str("no cached entry for this task"),
+            Self::CommandChanged => f.write_str("commands changed since the last entry"),
+            Self::SourceChanged { changed_files } => {
+                write!(f, "source files changed: {}", changed_files.join(", "))
+            }
+            Self::Other => f.write_str("inputs other than commands and sources changed"),
+        }
+    }
+}
+
+/// Predicted cache outcome for a task (see [`Cache::precompute`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheStatus {
+    /// A complete entry exists for the task's current inputs
+    Hit,
+    /// The task has no cached entries at all
+    Miss,
+    /// The task was cached before, but its inputs have changed since
+    Stale,
+}
+
+impl std::fmt::Display for CacheStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Hit => "hit",
+            Self::Miss => "miss",
+            Self::Stale => "stale",
+        })
+    }
 }
 
 /// On-disk/on-wire wrapper around an [`ActionResult`], carrying an optional
@@ -80,29 +167,63 @@ pub struct Cache {
     remote: Option<RemoteCache>,
     /// Optional 32-byte key for signing/verifying action results
     signing_key: Option<[u8; 32]>,
+    /// Session hit counter, shared by clones
+    hits: Arc<AtomicU64>,
+    /// Session miss counter, shared by clones
+    misses: Arc<AtomicU64>,
+    /// Pending access-time updates, shared by clones (`None` when disabled)
+    access: Option<Arc<AccessLog>>,
 }
 
 impl Cache {
     /// Create a new cache instance
     pub fn new(dir: Option<PathBuf>) -> Result<Self> {
-        let dir = dir.unwrap_or_else(|| {
-            directories::ProjectDirs::from("", "", "yatr").map_or_else(
-                || PathBuf::from(".yatr/cache"),
-                |d| d.cache_dir().to_path_buf(),
-            )
-        });
+        let dir = dir.unwrap_or_else(Self::default_dir);
 
         std::fs::create_dir_all(dir.join("ac"))?;
         std::fs::create_dir_all(dir.join("cas"))?;
 
+        let access = AccessLog::new(dir.join("access"), DEFAULT_ACCESS_FLUSH_INTERVAL);
         Ok(Self {
             dir,
             enabled: true,
             remote: None,
             signing_key: None,
+            hits: Arc::default(),
+            misses: Arc::default(),
+            access: Some(Arc::new(access)),
         })
     }
 
+    /// The per-user cache directory used when `cache_dir` isn't set
+    #[must_use]
+    pub fn default_dir() -> PathBuf {
+        directories::ProjectDirs::from("", "", "yatr").map_or_else(
+            || PathBuf::from(".yatr/cache"),
+            |d| d.cache_dir().to_path_buf(),
+        )
+    }
+
+    /// Set how often hit access times are written back to disk; zero writes
+    /// them on every hit (builder style).
+    #[must_use]
+    pub fn with_access_flush_interval(mut self, interval: Duration) -> Self {
+        if let Some(access) = self.access.take() {
+            access.flush();
+            let dir = access.dir.clone();
+            self.access = Some(Arc::new(AccessLog::new(dir, interval)));
+        }
+        self
+    }
+
+    /// Write any pending access times now instead of waiting for the next
+    /// background flush.
+    pub fn flush_access_times(&self) {
+        if let Some(access) = &self.access {
+            access.flush();
+        }
+    }
+
     /// Attach an optional remote backend (builder style).
     #[must_use]
     pub fn with_remote(mut self, remote: Option<RemoteCache>) -> Self {
@@ -125,12 +246,15 @@ impl Cache {
 
     /// Create a disabled cache (no-op)
     #[must_use]
-    pub const fn disabled() -> Self {
+    pub fn disabled() -> Self {
         Self {
             dir: PathBuf::new(),
             enabled: false,
             remote: None,
             signing_key: None,
+            hits: Arc::default(),
+            misses: Arc::default(),
+            access: None,
         }
     }
 
@@ -142,19 +266,92 @@ impl Cache {
 
     /// Look up a cached result for a task and, on a hit, restore its outputs.
     ///
-    /// Returns `None` (a miss) when there is no entry, the entry is for a
-    /// different task, or any recorded output blob is missing — in which case
-    /// the caller should run the task for real.
+    /// Returns the task's stdout as raw bytes, or `None` (a miss) when there
+    /// is no entry, the entry is for a different task, or any recorded blob is
+    /// missing — in which case the caller should run the task for real. Each
+    /// lookup is counted in the [`session_stats`](Self::session_stats).
     pub async fn get(
         &self,
         task_name: &str,
         config: &TaskConfig,
         cwd: &Path,
+    ) -> Result<Option<Vec<u8>>> {
+        let hit = self.get_entry(task_name, config, cwd).await?;
+        Ok(hit.map(|(stdout, _)| stdout))
+    }
+
+    /// [`get`](Self::get) for text output; binary stdout is decoded lossily.
+    pub async fn get_text(
+        &self,
+        task_name: &str,
+        config: &TaskConfig,
+        cwd: &Path,
     ) -> Result<Option<String>> {
+        let hit = self.get(task_name, config, cwd).await?;
+        Ok(hit.map(decode_lossy))
+    }
+
+    /// [`get_text`](Self::get_text), also returning the entry the output came
+    /// from (when it was made, how long the original run took, ...). The
+    /// entry's own `stdout` is left empty; the output is the returned text.
+    pub async fn get_with_metadata(
+        &self,
+        task_name: &str,
+        config: &TaskConfig,
+        cwd: &Path,
+    ) -> Result<Option<(String, ActionResult)>> {
+        let hit = self.get_entry(task_name, config, cwd).await?;
+        Ok(hit.map(|(stdout, result)| (decode_lossy(stdout), result)))
+    }
+
+    /// [`lookup`](Self::lookup), counted in the session stats.
+    async fn get_entry(
+        &self,
+        task_name: &str,
+        config: &TaskConfig,
+        cwd: &Path,
+    ) -> Result<Option<(Vec<u8>, ActionResult)>> {
         if !self.enabled {
             return Ok(None);
         }
 
+        let result = self.lookup(task_name, config, cwd).await;
+        if let Ok(hit) = &result {
+            let counter = if hit.is_some() {
+                &self.hits
+            } else {
+                &self.misses
+            };
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
+
+    /// Hit/miss counts of [`get`](Self::get) since this cache was created.
+    #[must_use]
+    pub fn session_stats(&self) -> SessionCacheStats {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let lookups = hits + misses;
+        #[allow(clippy::cast_precision_loss)]
+        let hit_rate = if lookups == 0 {
+            0.0
+        } else {
+            hits as f64 / lookups as f64
+        };
+        SessionCacheStats {
+            hits,
+            misses,
+            hit_rate,
+        }
+    }
+
+    async fn lookup(
+        &self,
+        task_name: &str,
+        config: &TaskConfig,
+        cwd: &Path,
+    ) -> Result<Option<(Vec<u8>, ActionResult)>> {
         let key = Self::compute_key(task_name, config, cwd)?;
 
         // Local action cache first.
@@ -165,7 +362,7 @@ impl Cache {
             self.fetch_from_remote(&key, task_name, cwd).await
         };
 
-        let Some(result) = result else {
+        let Some(mut result) = result else {
             return Ok(None);
         };
 
@@ -175,13 +372,40 @@ impl Cache {
             return Ok(None);
         }
 
-        Ok(Some(result.stdout))
+        let Some(stdout) = self.take_stdout(&mut result) else {
+            return Ok(None);
+        };
+        if let Some(access) = &self.access {
+            access.record(&key);
+        }
+        Ok(Some((stdout, result)))
+    }
+
+    /// Move the raw stdout out of a cached result, or `None` if its blob is
+    /// missing.
+    fn take_stdout(&self, result: &mut ActionResult) -> Option<Vec<u8>> {
+        if result.stdout_blob.is_empty() {
+            Some(std::mem::take(&mut result.stdout).into_bytes())
+        } else {
+            std::fs::read(self.cas_path(&result.stdout_blob)).ok()
+        }
     }
 
     /// Load and validate a local action-cache entry for `key`.
     fn load_local_ac(&self, key: &str, task_name: &str) -> Option<ActionResult> {
         let bytes = std::fs::read(self.ac_path(key)).ok()?;
-        self.extract_verified(&bytes, task_name)
+        let result = self.extract_verified(&bytes, task_name)?;
+        Some(self.with_access_time(result))
+    }
+
+    /// Fill in `last_accessed_at` from the entry's access stamp, if it has
+    /// one newer than the entry itself.
+    fn with_access_time(&self, mut result: ActionResult) -> ActionResult {
+        let stamp = std::fs::read_to_string(self.access_path(&result.key)).ok();
+        if let Some(at) = stamp.and_then(|s| chrono::DateTime::parse_from_rfc3339(s.trim()).ok()) {
+            result.last_accessed_at = result.last_accessed_at.max(at.to_utc());
+        }
+        result
     }
 
     /// Sign action-result bytes with the keyed MAC, if a signing key is set.
@@ -213,7 +437,9 @@ impl Cache {
             }
         }
 
-        (signed.result.task == task_name && signed.result.success).then_some(signed.result)
+        let mut result = signed.result;
+        result.last_accessed_at = result.created_at;
+        (result.task == task_name && result.success).then_some(result)
     }
 
     /// On a local miss, try the remote: download the action result and any
@@ -230,7 +456,7 @@ impl Cache {
             return None;
         }
         if remote.protocol == CacheProtocol::Reapi {
-            return Self::fetch_reapi(remote, key, cwd).await;
+            return self.fetch_reapi(remote, key, cwd).await;
         }
 
         let ac_bytes = match remote.get_ac(key).await {
@@ -245,19 +471,18 @@ impl Cache {
         let result = self.extract_verified(&ac_bytes, task_name)?;
 
         // Ensure every referenced blob is present locally.
-        for entry in &result.outputs {
-            let local = self.cas_path(&entry.blob);
+        for blob in result.blobs() {
+            let local = self.cas_path(blob);
             if local.exists() {
                 continue;
             }
-            match remote.get_cas(&entry.blob).await {
+            match remote.get_cas(blob).await {
                 Ok(Some(bytes)) => {
                     // Blobs are content-addressed: a digest mismatch means the
                     // remote served tampered or corrupt data — reject it.
-                    if blake3::hash(&bytes).to_hex().to_string() != entry.blob {
+                    if blake3::hash(&bytes).to_hex().to_string() != blob {
                         tracing::warn!(
-                            "remote blob {} failed integrity check — rejecting entry",
-                            entry.blob
+                            "remote blob {blob} failed integrity check — rejecting entry"
                         );
                         return None;
                     }
@@ -276,29 +501,40 @@ impl Cache {
     }
 
     /// Store a successful task result, capturing its declared outputs.
+    /// Stdout that isn't UTF-8 is kept as a CAS blob.
     pub async fn put(
         &self,
         task_name: &str,
         config: &TaskConfig,
         cwd: &Path,
-        stdout: &str,
+        stdout: &[u8],
         duration: Duration,
     ) -> Result<()> {
         if !self.enabled {
             return Ok(());
         }
 
-        let key = Self::compute_key(task_name, config, cwd)?;
+        let source_hashes = Self::source_file_hashes(cwd, &config.sources)?;
+        let key = Self::key_with_sources(task_name, config, cwd, &source_hashes);
         let outputs = self.capture_outputs(cwd, &config.outputs)?;
+        let (stdout, stdout_blob) = match std::str::from_utf8(stdout) {
+            Ok(text) => (text.to_string(), String::new()),
+            Err(_) => (String::new(), self.store_blob(stdout)?),
+        };
 
+        let now = chrono::Utc::now();
         let result = ActionResult {
             key: key.clone(),
             task: task_name.to_string(),
-            created_at: chrono::Utc::now(),
+            created_at: now,
+            last_accessed_at: now,
             duration_ms: u64::try_from(duration.as_millis()).unwrap_or(u64::MAX),
             success: true,
-            stdout: stdout.to_string(),
+            stdout,
+            stdout_blob,
             outputs,
+            command_fingerprint: Self::command_fingerprint(config),
+            source_hashes,
         };
 
         // Sign the canonical result, then wrap and store.
@@ -317,6 +553,19 @@ impl Cache {
         Ok(())
     }
 
+    /// [`put`](Self::put) for text output.
+    pub async fn put_text(
+        &self,
+        task_name: &str,
+        config: &TaskConfig,
+        cwd: &Path,
+        stdout: &str,
+        duration: Duration,
+    ) -> Result<()> {
+        self.put(task_name, config, cwd, stdout.as_bytes(), duration)
+            .await
+    }
+
     fn ser_err(e: &serde_json::Error) -> YatrError {
         YatrError::Cache {
             message: format!("Failed to serialize action result: {e}"),
@@ -343,16 +592,16 @@ impl Cache {
             return;
         }
 
-        for entry in &result.outputs {
+        for blob in result.blobs() {
             // Skip blobs the remote already has.
-            if remote.has_cas(&entry.blob).await.unwrap_or(false) {
+            if remote.has_cas(blob).await.unwrap_or(false) {
                 continue;
             }
-            let Ok(bytes) = std::fs::read(self.cas_path(&entry.blob)) else {
+            let Ok(bytes) = std::fs::read(self.cas_path(blob)) else {
                 continue;
             };
-            if let Err(e) = remote.put_cas(&entry.blob, bytes).await {
-                tracing::warn!("remote cache blob upload failed for {}: {e}", entry.blob);
+            if let Err(e) = remote.put_cas(blob, bytes).await {
+                tracing::warn!("remote cache blob upload failed for {blob}: {e}");
                 return; // don't publish an action result with missing blobs
             }
         }
@@ -394,10 +643,13 @@ impl Cache {
                 executable,
             });
         }
+        let Some(stdout) = self.take_stdout(&mut result.clone()) else {
+            return;
+        };
         let ar = reapi::ActionResult {
             output_files: files,
             exit_code: 0,
-            stdout: result.stdout.clone().into_bytes(),
+            stdout,
         };
         if let Err(e) = remote
             .put_ac(&ac_key, reapi::encode_action_result(&ar))
@@ -410,7 +662,12 @@ impl Cache {
     /// Fetch + restore from a REAPI cache. Output files are written directly to
     /// `cwd` (verified against their SHA-256 digests); the returned result has no
     /// local outputs, so the caller's restore step is a no-op.
-    async fn fetch_reapi(remote: &RemoteCache, key: &str, cwd: &Path) -> Option<ActionResult> {
+    async fn fetch_reapi(
+        &self,
+        remote: &RemoteCache,
+        key: &str,
+        cwd: &Path,
+    ) -> Option<ActionResult> {
         let ac_key = reapi::sha256_hex(key.as_bytes());
         let ac_bytes = match remote.get_ac(&ac_key).await {
             Ok(bytes) => bytes?,
@@ -439,14 +696,23 @@ impl Cache {
             }
         }
 
+        let (stdout, stdout_blob) = match String::from_utf8(ar.stdout) {
+            Ok(text) => (text, String::new()),
+            Err(e) => (String::new(), self.store_blob(e.as_bytes()).ok()?),
+        };
+        let now = chrono::Utc::now();
         Some(ActionResult {
             key: key.to_string(),
             task: String::new(),
-            created_at: chrono::Utc::now(),
+            created_at: now,
+            last_accessed_at: now,
             duration_ms: 0,
             success: true,
-            stdout: String::from_utf8_lossy(&ar.stdout).into_owned(),
+            stdout,
+            stdout_blob,
             outputs: Vec::new(),
+            command_fingerprint: String::new(),
+            source_hashes: BTreeMap::new(),
         })
     }
 
@@ -487,6 +753,7 @@ impl Cache {
         if ac_path.exists() {
             std::fs::remove_file(&ac_path)?;
         }
+        let _ = std::fs::remove_file(self.access_path(&key));
         Ok(())
     }
 
@@ -531,6 +798,7 @@ impl Cache {
             if let Ok(signed) = serde_json::from_str::<SignedAc>(&content) {
                 if signed.result.task == task_name {
                     std::fs::remove_file(&path)?;
+                    let _ = std::fs::remove_file(self.access_path(&signed.result.key));
                     removed += 1;
                 }
             }
@@ -538,6 +806,155 @@ impl Cache {
         Ok(removed)
     }
 
+    /// Find the most recently stored local action result for a named task,
+    /// regardless of inputs. Used to compare a fresh run against the last
+    /// cached one (`yatr diff`).
+    pub fn last_result(&self, task_name: &str) -> Result<Option<ActionResult>> {
+        if !self.enabled {
+            return Ok(None);
+        }
+
+        let ac_dir = self.dir.join("ac");
+        if !ac_dir.exists() {
+            return Ok(None);
+        }
+
+        let mut latest: Option<ActionResult> = None;
+        for entry in std::fs::read_dir(&ac_dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|e| e != "json") {
+                continue;
+            }
+            let Ok(bytes) = std::fs::read(&path) else {
+                continue;
+            };
+            let Some(result) = self.extract_verified(&bytes, task_name) else {
+                continue;
+            };
+            let result = self.with_access_time(result);
+            if latest
+                .as_ref()
+                .is_none_or(|l| result.created_at > l.created_at)
+            {
+                latest = Some(result);
+            }
+        }
+        Ok(latest)
+    }
+
+    /// Predict the cache status of each task without running anything.
+    ///
+    /// Computes each task's key (tasks without their own `cwd` are resolved
+    /// against `cwd`) and checks the local action cache: a complete entry is a
+    /// [`CacheStatus::Hit`], an entry for the same task under a different key
+    /// is [`CacheStatus::Stale`], and anything else is a [`CacheStatus::Miss`].
+    /// The remote backend is not consulted.
+    pub fn precompute(
+        &self,
+        tasks: &[(&str, &TaskConfig)],
+        cwd: &Path,
+    ) -> Result<Vec<(String, CacheStatus)>> {
+        let cached_tasks = self.cached_task_names()?;
+
+        let mut statuses = Vec::with_capacity(tasks.len());
+        for (name, config) in tasks {
+            let status = if !self.enabled
+                || config.no_cache.unwrap_or(false)
+                || config.foreground.unwrap_or(false)
+            {
+                CacheStatus::Miss
+            } else {
+                let task_cwd = config.cwd.clone().unwrap_or_else(|| cwd.to_path_buf());
+                let key = Self::compute_key(name, config, &task_cwd)?;
+                let complete = self.load_local_ac(&key, name).is_some_and(|result| {
+                    result
+                        .outputs
+                        .iter()
+                        .all(|entry| self.cas_path(&entry.blob).exists())
+                });
+                if complete {
+                    CacheStatus::Hit
+                } else if cached_tasks.contains(*name) {
+                    CacheStatus::Stale
+                } else {
+                    CacheStatus::Miss
+                }
+            };
+            statuses.push(((*name).to_string(), status));
+        }
+        Ok(statuses)
+    }
+
+    /// Why a lookup for `task_name` would miss, or `None` when it would hit
+    /// the local cache. Compares the current inputs against the task's most
+    /// recent entry ([`last_result`](Self::last_result)): first its commands,
+    /// then the digest of each source file.
+    pub fn explain_miss(
+        &self,
+        task_name: &str,
+        config: &TaskConfig,
+        cwd: &Path,
+    ) -> Result<Option<CacheMiss>> {
+        let current = Self::source_file_hashes(cwd, &config.sources)?;
+        let key = Self::key_with_sources(task_name, config, cwd, &current);
+        let hit = self
+            .load_local_ac(&key, task_name)
+            .is_some_and(|result| result.blobs().all(|blob| self.cas_path(blob).exists()));
+        if hit {
+            return Ok(None);
+        }
+
+        let Some(last) = self.last_result(task_name)? else {
+            return Ok(Some(CacheMiss::NoEntry));
+        };
+        if !last.command_fingerprint.is_empty()
+            && last.command_fingerprint != Self::command_fingerprint(config)
+        {
+            return Ok(Some(CacheMiss::CommandChanged));
+        }
+
+        let mut changed_files: Vec<String> = current
+            .iter()
+            .filter(|(path, hash)| last.source_hashes.get(*path) != Some(*hash))
+            .map(|(path, _)| path.clone())
+            .chain(
+                last.source_hashes
+                    .keys()
+                    .filter(|path| !current.contains_key(*path))
+                    .cloned(),
+            )
+            .collect();
+        changed_files.sort();
+        Ok(Some(if changed_files.is_empty() {
+            CacheMiss::Other
+        } else {
+            CacheMiss::SourceChanged { changed_files }
+        }))
+    }
+
+    /// Names of all tasks with at least one local action-cache entry.
+    fn cached_task_names(&self) -> Result<std::collections::HashSet<String>> {
+        let mut names = std::collections::HashSet::new();
+        let ac_dir = self.dir.join("ac");
+        if !self.enabled || !ac_dir.exists() {
+            return Ok(names);
+        }
+
+        for entry in std::fs::read_dir(&ac_dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|e| e != "json") {
+                continue;
+            }
+            let Ok(content) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            if let Ok(signed) = serde_json::from_str::<SignedAc>(&content) {
+                names.insert(signed.result.task);
+            }
+        }
+        Ok(names)
+    }
+
     /// Get cache statistics
     pub fn stats(&self) -> Result<CacheStats> {
         if !self.enabled {
@@ -572,14 +989,153 @@ impl Cache {
         })
     }
 
+    /// Scan the local cache for damage left by interrupted writes or manual
+    /// edits, and remove it:
+    ///
+    /// - leftover temp files from [`write_atomic`](Self::write_atomic), in
+    ///   either store, and zero-byte action-cache entries;
+    /// - CAS blobs whose contents no longer hash to their name (an empty
+    ///   blob is valid: it's how an empty output file is stored);
+    /// - action-cache entries that don't parse, or that reference a blob the
+    ///   CAS no longer has (they would only ever miss);
+    /// - CAS blobs no remaining entry references.
+    ///
+    /// Run it while no other yatr process is writing to the cache, since an
+    /// in-flight temp file is indistinguishable from an abandoned one.
+    pub fn repair(&self) -> Result<RepairReport> {
+        let mut report = RepairReport::default();
+        if !self.enabled {
+            return Ok(report);
+        }
+        self.flush_access_times();
+
+        let cas_dir = self.dir.join("cas");
+        let mut blobs = HashSet::new();
+        for path in Self::repair_dir(&cas_dir, false, &mut report)? {
+            let intact = std::fs::read(&path).is_ok_and(|content| {
+                path.file_name()
+                    .is_some_and(|name| *name == *blake3::hash(&content).to_hex())
+            });
+            if intact {
+                blobs.extend(path.file_name().map(|n| n.to_string_lossy().into_owned()));
+            } else {
+                std::fs::remove_file(&path)?;
+                report.corrupt_blobs += 1;
+            }
+        }
+
+        let mut referenced = HashSet::new();
+        let mut keys = HashSet::new();
+        for path in Self::repair_dir(&self.dir.join("ac"), true, &mut report)? {
+            let Some(signed) = std::fs::read(&path)
+                .ok()
+                .and_then(|bytes| serde_json::from_slice::<SignedAc>(&bytes).ok())
+            else {
+                std::fs::remove_file(&path)?;
+                report.unreadable_entries += 1;
+                continue;
+            };
+            if signed.result.blobs().all(|blob| blobs.contains(blob)) {
+                referenced.extend(signed.result.blobs().map(str::to_string));
+                keys.insert(signed.result.key);
+            } else {
+                std::fs::remove_file(&path)?;
+                report.incomplete_entries += 1;
+            }
+        }
+
+        for blob in blobs.difference(&referenced) {
+            std::fs::remove_file(self.cas_path(blob))?;
+            report.orphaned_blobs += 1;
+        }
+
+        for path in Self::repair_dir(&self.dir.join("access"), false, &mut report)? {
+            let key = path.file_name().map(|n| n.to_string_lossy().into_owned());
+            if key.is_none_or(|key| !keys.contains(&key)) {
+                std::fs::remove_file(&path)?;
+                report.orphaned_stamps += 1;
+            }
+        }
+        Ok(report)
+    }
+
+    /// First pass of [`repair`](Self::repair) over one store: removes temp
+    /// files (and zero-byte ones when `remove_empty`), and returns the
+    /// remaining files to check.
+    fn repair_dir(
+        dir: &Path,
+        remove_empty: bool,
+        report: &mut RepairReport,
+    ) -> Result<Vec<PathBuf>> {
+        let mut remaining = Vec::new();
+        if !dir.exists() {
+            return Ok(remaining);
+        }
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+            let path = entry.path();
+            if path.to_string_lossy().contains(".tmp.") {
+                std::fs::remove_file(&path)?;
+                report.temp_files += 1;
+            } else if remove_empty && metadata.len() == 0 {
+                std::fs::remove_file(&path)?;
+                report.empty_files += 1;
+            } else {
+                remaining.push(path);
+            }
+        }
+        Ok(remaining)
+    }
+
+    /// Hash of the non-source part of a task's action: its `run` commands and
+    /// `script`. Stored on each entry so a miss can be traced to a command
+    /// change (fingerprint differs) or an input change (fingerprint matches).
+    #[must_use]
+    pub fn command_fingerprint(config: &TaskConfig) -> String {
+        let mut hasher = Hasher::new();
+        for cmd in &config.run {
+            hasher.update(cmd.cmd().as_bytes());
+            if let Some(stdin) = cmd.stdin() {
+                hasher.update(b"stdin:");
+                hasher.update(stdin.as_bytes());
+            }
+            hasher.update(&[0]);
+        }
+        if let Some(script) = &config.script {
+            hasher.update(script.as_bytes());
+        }
+        hasher.finalize().to_hex()[..16].to_string()
+    }
+
     /// Compute the cache key for a task.
     fn compute_key(task_name: &str, config: &TaskConfig, cwd: &Path) -> Result<String> {
+        let sources = Self::source_file_hashes(cwd, &config.sources)?;
+        Ok(Self::key_with_sources(task_name, config, cwd, &sources))
+    }
+
+    /// [`compute_key`](Self::compute_key) given the task's
+    /// [`source_file_hashes`](Self::source_file_hashes), for callers that
+    /// also need the per-file digests.
+    fn key_with_sources(
+        task_name: &str,
+        config: &TaskConfig,
+        cwd: &Path,
+        sources: &BTreeMap<String, String>,
+    ) -> String {
         let mut hasher = Hasher::new();
 
         hasher.update(task_name.as_bytes());
 
         for cmd in &config.run {
-            hasher.update(cmd.as_bytes());
+            hasher.update(cmd.cmd().as_bytes());
+            if let Some(stdin) = cmd.stdin() {
+                hasher.update(b"stdin:");
+                hasher.update(stdin.as_bytes());
+            }
         }
         if let Some(script) = &config.script {
             hasher.update(script.as_bytes());
@@ -597,6 +1153,37 @@ impl Cache {
         }
         hasher.update(&[u8::from(config.shell.unwrap_or(false))]);
 
+        // Task args (sorted); only hashed when declared, so keys of tasks
+        // without args are unchanged.
+        let mut args: Vec<_> = config.args.iter().collect();
+        args.sort_by_key(|(k, _)| *k);
+        for (k, v) in args {
+            hasher.update(b"arg:");
+            hasher.update(k.as_bytes());
+            hasher.update(v.as_bytes());
+        }
+
+        // Stdin content, or the path it is read from and that file's
+        // contents. Only hashed when set.
+        if let Some(stdin) = &config.stdin {
+            hasher.update(b"stdin:");
+            hasher.update(stdin.as_bytes());
+        }
+        if let Some(path) = &config.stdin_file {
+            hasher.update(b"stdin_file:");
+            hasher.update(path.to_string_lossy().as_bytes());
+            hasher.update(&[0]);
+            hasher.update(&std::fs::read(cwd.join(path)).unwrap_or_default());
+        }
+
+        // An edit to a `task_file` invalidates its tasks, including edits to
+        // fields the key doesn't otherwise cover. Its contents are hashed,
+        // not its mtime, so a fresh checkout still hits a shared cache.
+        if let Some(path) = &config.task_file {
+            hasher.update(b"task_file:");
+            hasher.update(&std::fs::read(path).unwrap_or_default());
+        }
+
         // Environment variables (sorted for stability).
         let mut env_pairs: Vec<_> = config.env.iter().collect();
         env_pairs.sort_by_key(|(k, _)| *k);
@@ -614,17 +1201,52 @@ impl Cache {
 
         // Contents of source files.
         if !config.sources.is_empty() {
-            let source_hash = Self::hash_sources(cwd, &config.sources)?;
-            hasher.update(source_hash.as_bytes());
+            hasher.update(Self::combine_source_hashes(sources).as_bytes());
         }
 
-        Ok(hasher.finalize().to_hex()[..16].to_string())
+        hasher.finalize().to_hex()[..16].to_string()
     }
 
     /// Hash the contents of source files matching the glob patterns, rooted at
     /// `cwd` and respecting `.gitignore` (so build artifacts and `node_modules`
     /// don't bloat or destabilise the key).
-    fn hash_sources(cwd: &Path, patterns: &[String]) -> Result<String> {
+    pub(crate) fn hash_sources(cwd: &Path, patterns: &[String]) -> Result<String> {
+        let hashes = Self::source_file_hashes(cwd, patterns)?;
+        Ok(Self::combine_source_hashes(&hashes))
+    }
+
+    /// One digest over per-file [`source_file_hashes`](Self::source_file_hashes).
+    fn combine_source_hashes(files: &BTreeMap<String, String>) -> String {
+        let mut hasher = Hasher::new();
+        for (rel, digest) in files {
+            hasher.update(rel.as_bytes());
+            hasher.update(&[0]);
+            hasher.update(digest.as_bytes());
+        }
+        hasher.finalize().to_hex().to_string()
+    }
+
+    /// BLAKE3 hex digest of each file [`hash_sources`](Self::hash_sources)
+    /// covers, keyed by its path relative to `cwd`.
+    pub(crate) fn source_file_hashes(
+        cwd: &Path,
+        patterns: &[String],
+    ) -> Result<BTreeMap<String, String>> {
+        if patterns.is_empty() {
+            return Ok(BTreeMap::new());
+        }
+        Ok(Self::source_files(cwd, patterns)?
+            .into_iter()
+            .map(|(rel, path)| {
+                let content = std::fs::read(&path).unwrap_or_default();
+                (rel, blake3::hash(&content).to_hex().to_string())
+            })
+            .collect())
+    }
+
+    /// Files under `cwd` matching the source `patterns`, as (relative path,
+    /// full path) sorted by relative path.
+    fn source_files(cwd: &Path, patterns: &[String]) -> Result<Vec<(String, PathBuf)>> {
         let mut builder = GlobSetBuilder::new();
         for pattern in patterns {
             let glob = Glob::new(pattern).map_err(|e| YatrError::Cache {
@@ -657,15 +1279,7 @@ impl Cache {
 
         // Sort by relative path for a deterministic hash.
         files.sort_by(|a, b| a.0.cmp(&b.0));
-
-        let mut hasher = Hasher::new();
-        for (rel, path) in files {
-            hasher.update(rel.as_bytes());
-            let content = std::fs::read(&path).unwrap_or_default();
-            hasher.update(&content);
-        }
-
-        Ok(hasher.finalize().to_hex().to_string())
+        Ok(files)
     }
 
     /// Capture the files matched by the output patterns into the CAS.
@@ -755,25 +1369,126 @@ impl Cache {
     /// never observe a half-written blob or action result. The temp name is
     /// unique per call (pid + counter) so concurrent writers of the same target
     /// don't collide on the temp file.
-    fn write_atomic(path: &Path, content: &[u8]) -> Result<()> {
-        use std::sync::atomic::{AtomicU64, Ordering};
-        static COUNTER: AtomicU64 = AtomicU64::new(0);
-        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
-        let tmp = path.with_extension(format!("tmp.{}.{n}", std::process::id()));
+    pub(crate) fn write_atomic(path: &Path, content: &[u8]) -> Result<()> {
+        let tmp = Self::temp_path(path);
         std::fs::write(&tmp, content)?;
         std::fs::rename(&tmp, path)?;
         Ok(())
     }
 
+    /// A fresh temp file name beside `path`, for writing it atomically.
+    pub(crate) fn temp_path(path: &Path) -> PathBuf {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        path.with_extension(format!("tmp.{}.{n}", std::process::id()))
+    }
+
     /// Path for an action-cache entry.
-    fn ac_path(&self, key: &str) -> PathBuf {
+    pub(crate) fn ac_path(&self, key: &str) -> PathBuf {
         self.dir.join("ac").join(format!("{key}.json"))
     }
 
     /// Path for a CAS blob.
-    fn cas_path(&self, blob: &str) -> PathBuf {
+    pub(crate) fn cas_path(&self, blob: &str) -> PathBuf {
         self.dir.join("cas").join(blob)
     }
+
+    /// Path for an action-cache entry's access stamp (see [`AccessLog`]).
+    pub(crate) fn access_path(&self, key: &str) -> PathBuf {
+        self.dir.join("access").join(key)
+    }
+}
+
+/// How often [`AccessLog`] flushes unless configured otherwise.
+const DEFAULT_ACCESS_FLUSH_INTERVAL: Duration = Duration::from_mins(1);
+
+/// Batches access-time updates for action-cache entries: each hit marks its
+/// key dirty, and a background task writes the time to `access/<key>` for
+/// the dirty keys every `interval`, so a hit costs no extra write. The stamps
+/// live apart from the signed entries, which are only ever written whole by
+/// [`Cache::put`]. Dropping the last [`Cache`] clone stops the task and
+/// flushes whatever is still pending.
+#[derive(Debug)]
+struct AccessLog {
+    dir: PathBuf,
+    dirty: Arc<Mutex<HashSet<String>>>,
+    interval: Duration,
+    flusher: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl AccessLog {
+    /// Start tracking accesses, stamped under `dir`. The background flush
+    /// only runs inside a Tokio runtime; outside one, stamps are written on
+    /// drop.
+    fn new(dir: PathBuf, interval: Duration) -> Self {
+        let dirty: Arc<Mutex<HashSet<String>>> = Arc::default();
+        let flusher = tokio::runtime::Handle::try_current()
+            .ok()
+            .filter(|_| !interval.is_zero())
+            .map(|handle| {
+                let (dir, dirty) = (dir.clone(), Arc::clone(&dirty));
+                handle.spawn(async move {
+                    let mut ticker = tokio::time::interval(interval);
+                    ticker.tick().await;
+                    loop {
+                        ticker.tick().await;
+                        Self::write(&dir, &dirty);
+                    }
+                })
+            });
+        Self {
+            dir,
+            dirty,
+            interval,
+            flusher,
+        }
+    }
+
+    /// Note a hit on `key`, writing it straight away when batching is off.
+    fn record(&self, key: &str) {
+        if let Ok(mut dirty) = self.dirty.lock() {
+            dirty.insert(key.to_string());
+        }
+        if self.interval.is_zero() {
+            self.flush();
+        }
+    }
+
+    fn flush(&self) {
+        Self::write(&self.dir, &self.dirty);
+    }
+
+    /// Stamp every dirty key with the current time. A stamp whose entry was
+    /// removed in the meantime is harmless, and [`Cache::repair`] drops it.
+    fn write(dir: &Path, dirty: &Mutex<HashSet<String>>) {
+        let keys = match dirty.lock() {
+            Ok(mut dirty) => std::mem::take(&mut *dirty),
+            Err(_) => return,
+        };
+        if keys.is_empty() {
+            return;
+        }
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            tracing::debug!("failed to create {}: {e}", dir.display());
+            return;
+        }
+        let now = chrono::Utc::now().to_rfc3339();
+        for key in keys {
+            if let Err(e) = Cache::write_atomic(&dir.join(&key), now.as_bytes()) {
+                tracing::debug!("failed to record access time for {key}: {e}");
+            }
+        }
+    }
+}
+
+impl Drop for AccessLog {
+    fn drop(&mut self) {
+        if let Some(flusher) = &self.flusher {
+            flusher.abort();
+        }
+        self.flush();
+    }
 }
 
 /// Cache statistics
@@ -784,6 +1499,77 @@ pub struct CacheStats {
     pub cache_dir: PathBuf,
 }
 
+/// What [`Cache::repair`] removed, by reason
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RepairReport {
+    /// Leftover temp files from interrupted atomic writes
+    pub temp_files: usize,
+    /// Zero-byte action-cache entries
+    pub empty_files: usize,
+    /// Blobs whose contents don't match their digest
+    pub corrupt_blobs: usize,
+    /// Action-cache entries that couldn't be parsed
+    pub unreadable_entries: usize,
+    /// Action-cache entries referencing a missing blob
+    pub incomplete_entries: usize,
+    /// Blobs no action-cache entry references
+    pub orphaned_blobs: usize,
+    /// Access stamps whose action-cache entry is gone
+    pub orphaned_stamps: usize,
+}
+
+impl RepairReport {
+    /// Total number of files removed
+    #[must_use]
+    pub const fn total(&self) -> usize {
+        self.temp_files
+            + self.empty_files
+            + self.corrupt_blobs
+            + self.unreadable_entries
+            + self.incomplete_entries
+            + self.orphaned_blobs
+            + self.orphaned_stamps
+    }
+}
+
+impl std::fmt::Display for RepairReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} temp, {} empty, {} corrupt blobs, {} unreadable entries, \
+             {} incomplete entries, {} orphaned blobs, {} orphaned access stamps",
+            self.temp_files,
+            self.empty_files,
+            self.corrupt_blobs,
+            self.unreadable_entries,
+            self.incomplete_entries,
+            self.orphaned_blobs,
+            self.orphaned_stamps
+        )
+    }
+}
+
+/// Cache effectiveness over one session (see [`Cache::session_stats`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SessionCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// `hits / (hits + misses)`, or 0 before any lookup
+    pub hit_rate: f64,
+}
+
+impl std::fmt::Display for SessionCacheStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.0}% ({}/{})",
+            self.hit_rate * 100.0,
+            self.hits,
+            self.hits + self.misses
+        )
+    }
+}
+
 impl std::fmt::Display for CacheStats {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let size_str = if self.total_size < 1024 {
@@ -808,6 +1594,14 @@ impl std::fmt::Display for CacheStats {
     }
 }
 
+/// Cached stdout as text, decoding binary output lossily.
+fn decode_lossy(stdout: Vec<u8>) -> String {
+    match String::from_utf8(stdout) {
+        Ok(text) => text,
+        Err(e) => String::from_utf8_lossy(e.as_bytes()).into_owned(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -837,7 +1631,7 @@ mod tests {
 
         let config = task_with(&[], &[]);
         cache
-            .put(
+            .put_text(
                 "test",
                 &config,
                 work.path(),
@@ -847,8 +1641,81 @@ mod tests {
             .await
             .unwrap();
 
-        let output = cache.get("test", &config, work.path()).await.unwrap();
+        let output = cache.get_text("test", &config, work.path()).await.unwrap();
         assert_eq!(output, Some("hello world".to_string()));
+        // The entry keeps the real run time (dry-run timeline estimates use it).
+        let entry = cache.last_result("test").unwrap().unwrap();
+        assert_eq!(entry.duration_ms, 5);
+    }
+
+    #[tokio::test]
+    async fn test_session_stats_count_hits_and_misses() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let work = tempfile::tempdir().unwrap();
+        let cache = Cache::new(Some(cache_dir.path().to_path_buf())).unwrap();
+        assert_eq!(cache.session_stats(), SessionCacheStats::default());
+
+        let config = task_with(&[], &[]);
+        let d = Duration::from_millis(1);
+        assert!(cache
+            .get_text("t", &config, work.path())
+            .await
+            .unwrap()
+            .is_none());
+        cache
+            .put_text("t", &config, work.path(), "out", d)
+            .await
+            .unwrap();
+        // Clones share the counters.
+        let clone = cache.clone();
+        assert!(clone
+            .get_text("t", &config, work.path())
+            .await
+            .unwrap()
+            .is_some());
+        assert!(cache
+            .get_text("t", &config, work.path())
+            .await
+            .unwrap()
+            .is_some());
+
+        let stats = cache.session_stats();
+        assert_eq!((stats.hits, stats.misses), (2, 1));
+        assert_eq!(stats.to_string(), "67% (2/3)");
+    }
+
+    #[tokio::test]
+    async fn test_binary_stdout_round_trips() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let work = tempfile::tempdir().unwrap();
+        let cache = Cache::new(Some(cache_dir.path().to_path_buf())).unwrap();
+        let config = task_with(&[], &[]);
+        let binary = [0xff, 0xfe, 0x00, b'o', b'k'];
+
+        cache
+            .put("bin", &config, work.path(), &binary, Duration::ZERO)
+            .await
+            .unwrap();
+        let stored = cache.last_result("bin").unwrap().unwrap();
+        assert!(stored.stdout.is_empty());
+        assert!(cache.cas_path(&stored.stdout_blob).is_file());
+
+        let hit = cache.get("bin", &config, work.path()).await.unwrap();
+        assert_eq!(hit.as_deref(), Some(&binary[..]));
+        let text = cache.get_text("bin", &config, work.path()).await.unwrap();
+        assert_eq!(text.as_deref(), Some("\u{fffd}\u{fffd}\0ok"));
+
+        // Text output stays inline, with no blob.
+        cache
+            .put("txt", &config, work.path(), b"plain", Duration::ZERO)
+            .await
+            .unwrap();
+        assert!(cache
+            .last_result("txt")
+            .unwrap()
+            .unwrap()
+            .stdout_blob
+            .is_empty());
     }
 
     #[tokio::test]
@@ -864,7 +1731,7 @@ mod tests {
 
         let config = task_with(&[], &["dist"]);
         cache
-            .put(
+            .put_text(
                 "build",
                 &config,
                 work.path(),
@@ -878,7 +1745,7 @@ mod tests {
         std::fs::remove_dir_all(work.path().join("dist")).unwrap();
         assert!(!artifact.exists());
 
-        let output = cache.get("build", &config, work.path()).await.unwrap();
+        let output = cache.get_text("build", &config, work.path()).await.unwrap();
         assert_eq!(output, Some("built".to_string()));
         assert!(
             artifact.exists(),
@@ -898,17 +1765,276 @@ mod tests {
 
         let config = task_with(&["input.txt"], &[]);
         cache
-            .put("t", &config, work.path(), "out-v1", Duration::ZERO)
+            .put_text("t", &config, work.path(), "out-v1", Duration::ZERO)
             .await
             .unwrap();
         assert_eq!(
-            cache.get("t", &config, work.path()).await.unwrap(),
+            cache.get_text("t", &config, work.path()).await.unwrap(),
             Some("out-v1".to_string())
         );
 
         // Mutating the source must change the key → miss.
         std::fs::write(&src, b"v2").unwrap();
-        assert_eq!(cache.get("t", &config, work.path()).await.unwrap(), None);
+        assert_eq!(
+            cache.get_text("t", &config, work.path()).await.unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_task_file_contents_bust_key() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let work = tempfile::tempdir().unwrap();
+        let cache = Cache::new(Some(cache_dir.path().to_path_buf())).unwrap();
+
+        let task_file = work.path().join("build.toml");
+        std::fs::write(&task_file, "timeout = 60\n").unwrap();
+        let config = TaskConfig {
+            task_file: Some(task_file.clone()),
+            ..task_with(&[], &[])
+        };
+        cache
+            .put_text("t", &config, work.path(), "out", Duration::ZERO)
+            .await
+            .unwrap();
+
+        // A touched (or freshly checked out) but unchanged file still hits.
+        let file = std::fs::File::options()
+            .write(true)
+            .open(&task_file)
+            .unwrap();
+        file.set_modified(std::time::SystemTime::now() + Duration::from_secs(5))
+            .unwrap();
+        assert!(cache
+            .get_text("t", &config, work.path())
+            .await
+            .unwrap()
+            .is_some());
+
+        std::fs::write(&task_file, "timeout = 90\n").unwrap();
+        assert_eq!(
+            cache.get_text("t", &config, work.path()).await.unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stdin_file_contents_bust_key() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let work = tempfile::tempdir().unwrap();
+        let cache = Cache::new(Some(cache_dir.path().to_path_buf())).unwrap();
+
+        std::fs::write(work.path().join("query.sql"), "select 1;").unwrap();
+        let config = TaskConfig {
+            stdin_file: Some("query.sql".into()),
+            ..task_with(&[], &[])
+        };
+        cache
+            .put_text("t", &config, work.path(), "out", Duration::ZERO)
+            .await
+            .unwrap();
+        assert!(cache
+            .get_text("t", &config, work.path())
+            .await
+            .unwrap()
+            .is_some());
+
+        std::fs::write(work.path().join("query.sql"), "select 2;").unwrap();
+        assert_eq!(
+            cache.get_text("t", &config, work.path()).await.unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_last_result_ignores_inputs() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let work = tempfile::tempdir().unwrap();
+        let cache = Cache::new(Some(cache_dir.path().to_path_buf())).unwrap();
+
+        let first = task_with(&[], &[]);
+        let mut second = task_with(&[], &[]);
+        second.run = vec!["echo changed".into()];
+        let d = Duration::from_millis(1);
+        cache
+            .put_text("t", &first, work.path(), "old", d)
+            .await
+            .unwrap();
+        cache
+            .put_text("t", &second, work.path(), "new", d)
+            .await
+            .unwrap();
+        cache
+            .put_text("other", &first, work.path(), "x", d)
+            .await
+            .unwrap();
+
+        let last = cache.last_result("t").unwrap().unwrap();
+        assert_eq!(last.stdout, "new");
+        assert!(cache.last_result("missing").unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_command_fingerprint_ignores_sources() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let work = tempfile::tempdir().unwrap();
+        let cache = Cache::new(Some(cache_dir.path().to_path_buf())).unwrap();
+
+        let config = task_with(&["*.rs"], &[]);
+        std::fs::write(work.path().join("main.rs"), "fn main() {}").unwrap();
+        let d = Duration::from_millis(1);
+        cache
+            .put_text("t", &config, work.path(), "out", d)
+            .await
+            .unwrap();
+        let key = Cache::compute_key("t", &config, work.path()).unwrap();
+
+        let stored = cache.last_result("t").unwrap().unwrap();
+        assert_eq!(
+            stored.command_fingerprint,
+            Cache::command_fingerprint(&config)
+        );
+
+        // A source edit misses the cache but keeps the fingerprint...
+        std::fs::write(work.path().join("main.rs"), "fn main() { }").unwrap();
+        assert!(cache
+            .get_text("t", &config, work.path())
+            .await
+            .unwrap()
+            .is_none());
+        assert_ne!(Cache::compute_key("t", &config, work.path()).unwrap(), key);
+        cache
+            .put_text("t", &config, work.path(), "out", d)
+            .await
+            .unwrap();
+        let updated = cache.last_result("t").unwrap().unwrap();
+        assert_eq!(updated.command_fingerprint, stored.command_fingerprint);
+
+        // ...while a command edit changes it.
+        let mut edited = config.clone();
+        edited.run = vec!["echo changed".into()];
+        assert_ne!(
+            stored.command_fingerprint,
+            Cache::command_fingerprint(&edited)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_explain_miss_names_changed_sources() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let work = tempfile::tempdir().unwrap();
+        let cache = Cache::new(Some(cache_dir.path().to_path_buf())).unwrap();
+
+        let config = task_with(&["*.rs"], &[]);
+        assert_eq!(
+            cache.explain_miss("t", &config, work.path()).unwrap(),
+            Some(CacheMiss::NoEntry)
+        );
+
+        std::fs::write(work.path().join("a.rs"), "a").unwrap();
+        std::fs::write(work.path().join("b.rs"), "b").unwrap();
+        let d = Duration::from_millis(1);
+        cache
+            .put_text("t", &config, work.path(), "out", d)
+            .await
+            .unwrap();
+        let stored = cache.last_result("t").unwrap().unwrap();
+        assert_eq!(
+            stored.source_hashes.keys().collect::<Vec<_>>(),
+            ["a.rs", "b.rs"]
+        );
+        assert_eq!(cache.explain_miss("t", &config, work.path()).unwrap(), None);
+
+        std::fs::write(work.path().join("b.rs"), "edited").unwrap();
+        std::fs::remove_file(work.path().join("a.rs")).unwrap();
+        std::fs::write(work.path().join("c.rs"), "c").unwrap();
+        assert_eq!(
+            cache.explain_miss("t", &config, work.path()).unwrap(),
+            Some(CacheMiss::SourceChanged {
+                changed_files: vec!["a.rs".into(), "b.rs".into(), "c.rs".into()]
+            })
+        );
+
+        let mut edited = config.clone();
+        edited.run = vec!["echo changed".into()];
+        assert_eq!(
+            cache.explain_miss("t", &edited, work.path()).unwrap(),
+            Some(CacheMiss::CommandChanged)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_precompute_predicts_hit_miss_stale() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let work = tempfile::tempdir().unwrap();
+        let cache = Cache::new(Some(cache_dir.path().to_path_buf())).unwrap();
+
+        std::fs::write(work.path().join("input.txt"), b"v1").unwrap();
+        let fresh = task_with(&[], &[]);
+        let sourced = task_with(&["input.txt"], &[]);
+        let d = Duration::ZERO;
+        cache
+            .put_text("fresh", &fresh, work.path(), "", d)
+            .await
+            .unwrap();
+        cache
+            .put_text("sourced", &sourced, work.path(), "", d)
+            .await
+            .unwrap();
+        std::fs::write(work.path().join("input.txt"), b"v2").unwrap();
+
+        let statuses = cache
+            .precompute(
+                &[("fresh", &fresh), ("sourced", &sourced), ("new", &fresh)],
+                work.path(),
+            )
+            .unwrap();
+        assert_eq!(
+            statuses,
+            vec![
+                ("fresh".to_string(), CacheStatus::Hit),
+                ("sourced".to_string(), CacheStatus::Stale),
+                ("new".to_string(), CacheStatus::Miss),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn hits_record_access_times_beside_the_entry() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let work = tempfile::tempdir().unwrap();
+        let cache = Cache::new(Some(cache_dir.path().to_path_buf()))
+            .unwrap()
+            .with_signing_key(Some(Cache::derive_key("secret")))
+            .with_access_flush_interval(Duration::from_hours(1));
+
+        let config = task_with(&[], &[]);
+        cache
+            .put_text("build", &config, work.path(), "ok", Duration::ZERO)
+            .await
+            .unwrap();
+        let stored = cache.last_result("build").unwrap().unwrap();
+        assert_eq!(stored.last_accessed_at, stored.created_at);
+        let entry = std::fs::read(cache.ac_path(&stored.key)).unwrap();
+
+        // Hits are batched: nothing is written until the next flush.
+        assert!(cache
+            .get_text("build", &config, work.path())
+            .await
+            .unwrap()
+            .is_some());
+        let pending = cache.last_result("build").unwrap().unwrap();
+        assert_eq!(pending.last_accessed_at, stored.created_at);
+
+        cache.flush_access_times();
+        let flushed = cache.last_result("build").unwrap().unwrap();
+        assert!(flushed.last_accessed_at > flushed.created_at);
+        // The signed entry itself is never rewritten, and still hits.
+        assert_eq!(std::fs::read(cache.ac_path(&stored.key)).unwrap(), entry);
+        assert_eq!(
+            cache.get_text("build", &config, work.path()).await.unwrap(),
+            Some("ok".to_string())
+        );
     }
 
     #[tokio::test]
@@ -919,18 +2045,21 @@ mod tests {
 
         let config = task_with(&[], &[]);
         cache
-            .put("a", &config, work.path(), "x", Duration::ZERO)
+            .put_text("a", &config, work.path(), "x", Duration::ZERO)
             .await
             .unwrap();
         cache
-            .put("b", &config, work.path(), "y", Duration::ZERO)
+            .put_text("b", &config, work.path(), "y", Duration::ZERO)
             .await
             .unwrap();
 
         assert_eq!(cache.clear_task("a").unwrap(), 1);
-        assert_eq!(cache.get("a", &config, work.path()).await.unwrap(), None);
         assert_eq!(
-            cache.get("b", &config, work.path()).await.unwrap(),
+            cache.get_text("a", &config, work.path()).await.unwrap(),
+            None
+        );
+        assert_eq!(
+            cache.get_text("b", &config, work.path()).await.unwrap(),
             Some("y".to_string())
         );
     }
@@ -965,13 +2094,17 @@ mod tests {
                 key: key.clone(),
                 task: "build".into(),
                 created_at: chrono::Utc::now(),
+                last_accessed_at: chrono::Utc::now(),
                 duration_ms: 0,
                 success: true,
                 stdout: "from-remote".into(),
+                stdout_blob: String::new(),
                 outputs: vec![OutputEntry {
                     path: "out.txt".into(),
                     blob: blob.clone(),
                 }],
+                command_fingerprint: String::new(),
+                source_hashes: BTreeMap::new(),
             },
         };
         let ac_json = serde_json::to_vec(&ac).unwrap();
@@ -993,7 +2126,7 @@ mod tests {
             .with_remote(Some(remote));
 
         // Local is empty; the hit must come from the remote and restore the output.
-        let out = cache.get("build", &config, work.path()).await.unwrap();
+        let out = cache.get_text("build", &config, work.path()).await.unwrap();
         assert_eq!(out, Some("from-remote".to_string()));
         assert_eq!(
             std::fs::read(work.path().join("out.txt")).unwrap(),
@@ -1042,7 +2175,7 @@ mod tests {
             .with_remote(Some(remote));
 
         cache
-            .put(
+            .put_text(
                 "build",
                 &config,
                 work.path(),
@@ -1066,11 +2199,11 @@ mod tests {
 
         let config = task_with(&[], &[]);
         cache
-            .put("t", &config, work.path(), "signed-output", Duration::ZERO)
+            .put_text("t", &config, work.path(), "signed-output", Duration::ZERO)
             .await
             .unwrap();
         assert_eq!(
-            cache.get("t", &config, work.path()).await.unwrap(),
+            cache.get_text("t", &config, work.path()).await.unwrap(),
             Some("signed-output".to_string())
         );
     }
@@ -1085,7 +2218,7 @@ mod tests {
         Cache::new(Some(cache_dir.path().to_path_buf()))
             .unwrap()
             .with_signing_key(Some(Cache::derive_key("key-A")))
-            .put("t", &config, work.path(), "x", Duration::ZERO)
+            .put_text("t", &config, work.path(), "x", Duration::ZERO)
             .await
             .unwrap();
 
@@ -1093,7 +2226,10 @@ mod tests {
         let reader = Cache::new(Some(cache_dir.path().to_path_buf()))
             .unwrap()
             .with_signing_key(Some(Cache::derive_key("key-B")));
-        assert_eq!(reader.get("t", &config, work.path()).await.unwrap(), None);
+        assert_eq!(
+            reader.get_text("t", &config, work.path()).await.unwrap(),
+            None
+        );
     }
 
     #[tokio::test]
@@ -1115,13 +2251,17 @@ mod tests {
                 key: key.clone(),
                 task: "build".into(),
                 created_at: chrono::Utc::now(),
+                last_accessed_at: chrono::Utc::now(),
                 duration_ms: 0,
                 success: true,
                 stdout: "x".into(),
+                stdout_blob: String::new(),
                 outputs: vec![OutputEntry {
                     path: "out.txt".into(),
                     blob: blob.clone(),
                 }],
+                command_fingerprint: String::new(),
+                source_hashes: BTreeMap::new(),
             },
         };
         let ac_json = serde_json::to_vec(&ac).unwrap();
@@ -1145,7 +2285,7 @@ mod tests {
 
         // Integrity check must reject the tampered blob → miss, nothing restored.
         assert_eq!(
-            cache.get("build", &config, work.path()).await.unwrap(),
+            cache.get_text("build", &config, work.path()).await.unwrap(),
             None
         );
         assert!(!work.path().join("out.txt").exists());
@@ -1197,7 +2337,7 @@ mod tests {
             .unwrap()
             .with_remote(Some(RemoteCache::from_config(&cfg).unwrap()));
 
-        let out = cache.get("build", &config, work.path()).await.unwrap();
+        let out = cache.get_text("build", &config, work.path()).await.unwrap();
         assert_eq!(out, Some("from-reapi".to_string()));
         assert_eq!(std::fs::read(work.path().join("out.txt")).unwrap(), content);
     }
@@ -1242,7 +2382,7 @@ mod tests {
             .with_remote(Some(RemoteCache::from_config(&cfg).unwrap()));
 
         cache
-            .put(
+            .put_text(
                 "build",
                 &config,
                 work.path(),
@@ -1253,4 +2393,77 @@ mod tests {
             .unwrap();
         // MockServer verifies the SHA-256-keyed PUTs were received on drop.
     }
+
+    #[tokio::test]
+    async fn test_repair_removes_damaged_files() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let work = tempfile::tempdir().unwrap();
+        std::fs::write(work.path().join("out.txt"), "artifact").unwrap();
+        let cache = Cache::new(Some(cache_dir.path().to_path_buf())).unwrap();
+
+        let config = task_with(&[], &["out.txt"]);
+        let put = |name| cache.put_text(name, &config, work.path(), "ok", Duration::ZERO);
+        put("good").await.unwrap();
+        assert_eq!(cache.repair().unwrap(), RepairReport::default());
+
+        // An entry whose blob is missing, a blob that no longer matches its
+        // digest, plus the usual debris.
+        std::fs::write(work.path().join("out.txt"), "other").unwrap();
+        put("broken").await.unwrap();
+        let other = blake3::hash(b"other").to_hex().to_string();
+        std::fs::remove_file(cache.cas_path(&other)).unwrap();
+        let artifact = blake3::hash(b"artifact").to_hex().to_string();
+        std::fs::write(cache.cas_path("0".repeat(64).as_str()), "junk").unwrap();
+        std::fs::write(cache.ac_path("garbled"), "{ not json").unwrap();
+        std::fs::write(cache.ac_path("empty"), "").unwrap();
+        std::fs::write(cache.cas_path("abc.tmp.1.0"), "partial").unwrap();
+        std::fs::create_dir_all(cache_dir.path().join("access")).unwrap();
+        std::fs::write(cache.access_path("gone"), chrono::Utc::now().to_rfc3339()).unwrap();
+
+        let report = cache.repair().unwrap();
+        assert_eq!(
+            report,
+            RepairReport {
+                temp_files: 1,
+                empty_files: 1,
+                corrupt_blobs: 1,
+                unreadable_entries: 1,
+                incomplete_entries: 1,
+                orphaned_blobs: 0,
+                orphaned_stamps: 1,
+            }
+        );
+        assert!(cache.cas_path(&artifact).exists());
+        assert!(cache.last_result("good").unwrap().is_some());
+        assert!(cache.last_result("broken").unwrap().is_none());
+
+        // With the last entry gone, its blob is orphaned.
+        cache.clear_task("good").unwrap();
+        assert_eq!(cache.repair().unwrap().orphaned_blobs, 1);
+        assert!(!cache.cas_path(&artifact).exists());
+    }
+
+    #[tokio::test]
+    async fn test_repair_keeps_empty_output_blobs() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let work = tempfile::tempdir().unwrap();
+        std::fs::write(work.path().join("empty.txt"), "").unwrap();
+        let cache = Cache::new(Some(cache_dir.path().to_path_buf())).unwrap();
+
+        let config = task_with(&[], &["empty.txt"]);
+        cache
+            .put_text("touch", &config, work.path(), "ok", Duration::ZERO)
+            .await
+            .unwrap();
+        let empty = blake3::hash(b"").to_hex().to_string();
+        assert!(cache.cas_path(&empty).exists());
+
+        assert_eq!(cache.repair().unwrap(), RepairReport::default());
+        assert!(cache.cas_path(&empty).exists());
+        assert!(cache.last_result("touch").unwrap().is_some());
+
+        // A zero-byte file under any other name is still a damaged blob.
+        std::fs::write(cache.cas_path("1".repeat(64).as_str()), "").unwrap();
+        assert_eq!(cache.repair().unwrap().corrupt_blobs, 1);
+    }
 }
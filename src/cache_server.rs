@@ -0,0 +1,481 @@
+//! A small HTTP server that shares a local cache with other machines
+//! (`yatr cache serve`).
+//!
+//! It speaks the same protocol as the [`remote`](crate::remote) client, mounted
+//! under `/cache`, so other machines only need
+//! `[settings.remote_cache] url = "http://<host>:<port>/cache"`:
+//!
+//! - `GET|PUT       /cache/ac/<key>`  → action-result JSON
+//! - `HEAD|GET|PUT  /cache/cas/<blob>` → content-addressed blobs
+//! - `GET           /cache/stats`      → JSON cache statistics
+//!
+//! Entries live in the server's own cache directory, with the same layout as
+//! any local yatr cache. When `YATR_CACHE_TOKEN` is set, every request must
+//! carry it as a bearer token. Each connection serves a single request; put a
+//! TLS-terminating proxy in front when the network isn't trusted.
+
+#![allow(clippy::missing_errors_doc)]
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::cache::Cache;
+use crate::error::{Result, YatrError};
+
+/// Environment variable holding the shared bearer token.
+pub const TOKEN_ENV: &str = "YATR_CACHE_TOKEN";
+
+/// Largest request body accepted (uploads beyond this are rejected).
+const MAX_BODY: u64 = 256 << 20;
+
+/// Most header lines read before a request is rejected.
+const MAX_HEADERS: usize = 100;
+
+/// Longest request or header line, in bytes.
+const MAX_LINE: u64 = 8 << 10;
+
+/// How long a client gets to send its whole request, body included.
+const REQUEST_TIMEOUT: Duration = Duration::from_mins(1);
+
+/// HTTP front end for a local [`Cache`].
+#[derive(Debug, Clone)]
+pub struct CacheServer {
+    cache: Cache,
+    read_only: bool,
+    token: Option<String>,
+}
+
+/// The request line and headers of a request; its body is read only once the
+/// request is authorized.
+struct RequestHead {
+    method: String,
+    path: String,
+    headers: HashMap<String, String>,
+}
+
+/// Status code and body of a response.
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    const fn empty(status: u16) -> Self {
+        Self {
+            status,
+            content_type: "text/plain",
+            body: Vec::new(),
+        }
+    }
+
+    const fn bytes(body: Vec<u8>) -> Self {
+        Self {
+            status: 200,
+            content_type: "application/octet-stream",
+            body,
+        }
+    }
+}
+
+impl CacheServer {
+    /// Serve `cache`, writable and without authentication.
+    #[must_use]
+    pub const fn new(cache: Cache) -> Self {
+        Self {
+            cache,
+            read_only: false,
+            token: None,
+        }
+    }
+
+    /// Reject uploads with `403 Forbidden` (builder style).
+    #[must_use]
+    pub const fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Require `Authorization: Bearer <token>` on every request (builder style).
+    #[must_use]
+    pub fn with_token(mut self, token: Option<String>) -> Self {
+        self.token = token;
+        self
+    }
+
+    /// Bind `host:port` and serve until the process exits.
+    pub async fn bind_and_serve(self, host: &str, port: u16) -> Result<()> {
+        let listener = TcpListener::bind((host, port)).await?;
+        self.serve(listener).await
+    }
+
+    /// Accept connections on `listener` forever, one task per connection.
+    pub async fn serve(self, listener: TcpListener) -> Result<()> {
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            let server = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = server.handle(stream).await {
+                    tracing::debug!("cache server: request from {peer} failed: {e}");
+                }
+            });
+        }
+    }
+
+    async fn handle(&self, stream: TcpStream) -> Result<()> {
+        let mut stream = BufReader::new(stream);
+        let response = tokio::time::timeout(REQUEST_TIMEOUT, self.respond(&mut stream))
+            .await
+            .unwrap_or_else(|_| Response::empty(408));
+
+        let head = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            response.status,
+            reason(response.status),
+            response.content_type,
+            response.body.len()
+        );
+        let stream = stream.get_mut();
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(&response.body).await?;
+        stream.shutdown().await?;
+        Ok(())
+    }
+
+    /// Read a request from `stream` and answer it. The token is checked
+    /// before any of the body is read.
+    async fn respond(&self, stream: &mut BufReader<TcpStream>) -> Response {
+        let Ok(request) = read_head(stream).await else {
+            return Response::empty(400);
+        };
+        if !self.authorized(&request) {
+            return Response::empty(401);
+        }
+
+        let Some(route) = request.path.strip_prefix("/cache/") else {
+            return Response::empty(404);
+        };
+        if route == "stats" {
+            return match request.method.as_str() {
+                "GET" => self.stats(),
+                _ => Response::empty(405),
+            };
+        }
+
+        let Some(path) = self.entry_path(route) else {
+            return Response::empty(404);
+        };
+        match request.method.as_str() {
+            "GET" => tokio::fs::read(&path)
+                .await
+                .map_or_else(|_| Response::empty(404), Response::bytes),
+            "HEAD" => Response::empty(if path.is_file() { 200 } else { 404 }),
+            "PUT" if self.read_only => Response::empty(403),
+            "PUT" => Self::store(stream, &request, &path).await,
+            _ => Response::empty(405),
+        }
+    }
+
+    /// Whether `request` carries the bearer token, when one is required.
+    fn authorized(&self, request: &RequestHead) -> bool {
+        let Some(token) = &self.token else {
+            return true;
+        };
+        let expected = format!("Bearer {token}");
+        request
+            .headers
+            .get("authorization")
+            .is_some_and(|given| constant_time_eq(given.as_bytes(), expected.as_bytes()))
+    }
+
+    /// Stream a PUT body into `path` through a temp file, so an upload never
+    /// has to fit in memory and a reader never sees half of it.
+    async fn store(
+        stream: &mut BufReader<TcpStream>,
+        request: &RequestHead,
+        path: &Path,
+    ) -> Response {
+        let Ok(length) = request
+            .headers
+            .get("content-length")
+            .map_or(Ok(0), |len| len.parse::<u64>())
+        else {
+            return Response::empty(400);
+        };
+        if length > MAX_BODY {
+            return Response::empty(413);
+        }
+
+        let tmp = Cache::temp_path(path);
+        let stored = async {
+            let mut file = tokio::fs::File::create(&tmp).await?;
+            let copied = tokio::io::copy(&mut (&mut *stream).take(length), &mut file).await?;
+            if copied != length {
+                return Err(bad("request body ended early"));
+            }
+            file.sync_all().await?;
+            tokio::fs::rename(&tmp, path).await?;
+            Ok(())
+        };
+        match stored.await {
+            Ok(()) => Response::empty(200),
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&tmp).await;
+                tracing::warn!("cache server: failed to store {}: {e}", path.display());
+                Response::empty(500)
+            }
+        }
+    }
+
+    /// Map `ac/<key>` or `cas/<blob>` to a file in the cache. Ids are limited
+    /// to `[A-Za-z0-9_-]` so a request can't escape the cache directory.
+    fn entry_path(&self, route: &str) -> Option<PathBuf> {
+        let (kind, id) = route.split_once('/')?;
+        let valid = !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return None;
+        }
+        match kind {
+            "ac" => Some(self.cache.ac_path(id)),
+            "cas" => Some(self.cache.cas_path(id)),
+            _ => None,
+        }
+    }
+
+    fn stats(&self) -> Response {
+        match self.cache.stats() {
+            Ok(stats) => Response {
+                status: 200,
+                content_type: "application/json",
+                body: serde_json::json!({
+                    "entries": stats.entries,
+                    "total_size": stats.total_size,
+                    "read_only": self.read_only,
+                })
+                .to_string()
+                .into_bytes(),
+            },
+            Err(_) => Response::empty(500),
+        }
+    }
+}
+
+fn bad(message: &str) -> YatrError {
+    YatrError::Io(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        message.to_string(),
+    ))
+}
+
+/// Compare `a` and `b` without stopping at the first differing byte, so the
+/// response time doesn't tell a client how much of a guessed token was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Read one line of at most [`MAX_LINE`] bytes into `line`.
+async fn read_line(stream: &mut BufReader<TcpStream>, line: &mut String) -> Result<()> {
+    line.clear();
+    (&mut *stream).take(MAX_LINE).read_line(line).await?;
+    if !line.ends_with('\n') {
+        return Err(bad("line too long or connection closed"));
+    }
+    Ok(())
+}
+
+/// Read the request line and headers of one request.
+async fn read_head(stream: &mut BufReader<TcpStream>) -> Result<RequestHead> {
+    let mut line = String::new();
+    read_line(stream, &mut line).await?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err(bad("malformed request line"));
+    };
+    let (method, path) = (method.to_string(), path.to_string());
+
+    let mut headers = HashMap::new();
+    loop {
+        read_line(stream, &mut line).await?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if headers.len() >= MAX_HEADERS {
+            return Err(bad("too many headers"));
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+
+    Ok(RequestHead {
+        method,
+        path,
+        headers,
+    })
+}
+
+const fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        413 => "Payload Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{CacheProtocol, RemoteCacheConfig};
+    use crate::remote::RemoteCache;
+
+    /// Start a server on an ephemeral port, returning its `/cache` base URL.
+    async fn start(server: CacheServer) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(server.serve(listener));
+        format!("http://{addr}/cache")
+    }
+
+    fn client(url: String, token_env: Option<&str>) -> RemoteCache {
+        RemoteCache::from_config(&RemoteCacheConfig {
+            url,
+            token_env: token_env.map(String::from),
+            sign_key_env: None,
+            read: true,
+            write: true,
+            protocol: CacheProtocol::Native,
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn serves_the_remote_cache_protocol() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(Some(dir.path().to_path_buf())).unwrap();
+        let url = start(CacheServer::new(cache.clone())).await;
+        let remote = client(url.clone(), None);
+
+        assert_eq!(remote.get_ac("abc123").await.unwrap(), None);
+        remote.put_ac("abc123", b"{}".to_vec()).await.unwrap();
+        assert_eq!(remote.get_ac("abc123").await.unwrap(), Some(b"{}".to_vec()));
+        assert!(cache.ac_path("abc123").is_file());
+
+        assert!(!remote.has_cas("deadbeef").await.unwrap());
+        remote.put_cas("deadbeef", b"blob".to_vec()).await.unwrap();
+        assert!(remote.has_cas("deadbeef").await.unwrap());
+        assert_eq!(
+            remote.get_cas("deadbeef").await.unwrap(),
+            Some(b"blob".to_vec())
+        );
+
+        let stats = reqwest::get(format!("{url}/stats"))
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        let stats: serde_json::Value = serde_json::from_str(&stats).unwrap();
+        assert_eq!(stats["entries"], 1);
+
+        let escape = reqwest::get(format!("{url}/ac/..%2F..%2Fetc"))
+            .await
+            .unwrap();
+        assert_eq!(escape.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn enforces_read_only_and_token() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(Some(dir.path().to_path_buf())).unwrap();
+        let url = start(
+            CacheServer::new(cache)
+                .read_only(true)
+                .with_token(Some("s3cret".to_string())),
+        )
+        .await;
+        let http = reqwest::Client::new();
+
+        let anonymous = http.get(format!("{url}/stats")).send().await.unwrap();
+        assert_eq!(anonymous.status(), 401);
+
+        let put = http
+            .put(format!("{url}/ac/key"))
+            .bearer_auth("s3cret")
+            .body("{}")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(put.status(), 403);
+
+        let get = http
+            .get(format!("{url}/ac/key"))
+            .bearer_auth("s3cret")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(get.status(), 404);
+    }
+
+    /// Send `request` as raw bytes and return the response's status line.
+    async fn raw_status(url: &str, request: &[u8]) -> String {
+        let addr = url.trim_start_matches("http://").trim_end_matches("/cache");
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request).await.unwrap();
+        let mut response = String::new();
+        let _ = stream.read_to_string(&mut response).await;
+        response.lines().next().unwrap_or_default().to_string()
+    }
+
+    #[tokio::test]
+    async fn rejects_requests_before_reading_their_body() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(Some(dir.path().to_path_buf())).unwrap();
+        let url = start(CacheServer::new(cache).with_token(Some("s3cret".to_string()))).await;
+
+        // Answered without waiting for the (never sent) body.
+        let huge = format!(
+            "PUT /cache/cas/blob HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            1u64 << 40
+        );
+        assert_eq!(
+            raw_status(&url, huge.as_bytes()).await,
+            "HTTP/1.1 401 Unauthorized"
+        );
+
+        let authorized = format!(
+            "PUT /cache/cas/blob HTTP/1.1\r\nAuthorization: Bearer s3cret\r\nContent-Length: {}\r\n\r\n",
+            1u64 << 40
+        );
+        assert_eq!(
+            raw_status(&url, authorized.as_bytes()).await,
+            "HTTP/1.1 413 Payload Too Large"
+        );
+
+        let long_header = format!(
+            "GET /cache/stats HTTP/1.1\r\nX: {}\r\n\r\n",
+            "a".repeat(16 << 10)
+        );
+        assert_eq!(
+            raw_status(&url, long_header.as_bytes()).await,
+            "HTTP/1.1 400 Bad Request"
+        );
+
+        assert!(constant_time_eq(b"Bearer a", b"Bearer a"));
+        assert!(!constant_time_eq(b"Bearer a", b"Bearer b"));
+        assert!(!constant_time_eq(b"Bearer a", b"Bearer ab"));
+    }
+}
@@ -4,7 +4,11 @@
 
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+
+use crate::error::Result;
+use crate::report::ReportFormat;
 
 /// YATR - Yet Another Task Runner for Rust projects
 #[derive(Parser, Debug)]
@@ -16,6 +20,25 @@ pub struct Cli {
     #[arg(short, long, global = true)]
     pub config: Option<PathBuf>,
 
+    /// Parent directories to search for a config file before giving up
+    /// (default: 20)
+    #[arg(
+        long,
+        global = true,
+        value_name = "N",
+        env = "YATR_CONFIG_SEARCH_LIMIT"
+    )]
+    pub config_search_limit: Option<usize>,
+
+    /// Activate a `[profiles.<name>]` block of env and settings overrides
+    #[arg(long, global = true, value_name = "NAME", env = "YATR_PROFILE")]
+    pub config_profile: Option<String>,
+
+    /// Set an environment variable for every task, overriding config `env`
+    /// (repeatable)
+    #[arg(long = "env", global = true, value_name = "KEY=VALUE")]
+    pub env: Vec<String>,
+
     /// Verbose output
     #[arg(short, long, global = true)]
     pub verbose: bool,
@@ -32,6 +55,10 @@ pub struct Cli {
     #[arg(long, global = true)]
     pub no_color: bool,
 
+    /// Print the fully resolved config (includes merged) as TOML and exit
+    #[arg(long, global = true)]
+    pub print_config: bool,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 
@@ -48,6 +75,10 @@ pub enum Commands {
         #[arg(required = false)]
         tasks: Vec<String>,
 
+        /// Also run every task with this tag (repeatable)
+        #[arg(long, value_name = "TAG")]
+        tag: Vec<String>,
+
         /// Show execution plan without running
         #[arg(long)]
         dry_run: bool,
@@ -79,6 +110,39 @@ pub enum Commands {
         /// Warn when a task writes files outside its declared `outputs`
         #[arg(long)]
         trace_io: bool,
+
+        /// With --dry-run, show an estimated timeline based on previous run times
+        #[arg(long)]
+        timing: bool,
+
+        /// Write each task's stdout, stderr and metadata (JSON) into this directory
+        #[arg(long, value_name = "PATH")]
+        output_dir: Option<PathBuf>,
+
+        /// Print each task's expected cache status (hit/miss/stale) without running
+        #[arg(long)]
+        predict_cache: bool,
+
+        /// Skip, up front, every task whose cache is valid and whose dependencies are skipped too
+        #[arg(long)]
+        incremental: bool,
+
+        /// Expert mode: start all tasks at once, ignoring dependency ordering.
+        /// A task may run before the tasks it depends on have finished.
+        #[arg(long)]
+        unconstrained: bool,
+
+        /// Write a run report in this format (requires --report-file)
+        #[arg(long, value_enum, requires = "report_file")]
+        report: Option<ReportFormat>,
+
+        /// Where to write the --report
+        #[arg(long, value_name = "PATH", requires = "report")]
+        report_file: Option<PathBuf>,
+
+        /// Task arguments after `--`, e.g. `-- --filter my_test`
+        #[arg(last = true)]
+        args: Vec<String>,
     },
 
     /// List available tasks
@@ -90,16 +154,44 @@ pub enum Commands {
         /// Show task dependencies
         #[arg(long)]
         deps: bool,
+
+        /// Group tasks into independent clusters (no shared dependencies)
+        #[arg(long)]
+        groups: bool,
+
+        /// Only list tasks with this tag (repeatable)
+        #[arg(long, value_name = "TAG")]
+        tag: Vec<String>,
     },
 
     /// Watch for file changes and re-run task
     Watch {
-        /// Task to run on changes
-        task: String,
+        /// Tasks to run on changes (together, as one run)
+        #[arg(required = true)]
+        tasks: Vec<String>,
 
         /// Clear screen before each run
         #[arg(long)]
         clear: bool,
+
+        /// Exit after the first successful run (fails if interrupted first)
+        #[arg(long)]
+        watch_once: bool,
+
+        /// Rhai script deciding what to run on each change (overrides the
+        /// tasks' `on_change_script`)
+        #[arg(long, value_name = "PATH")]
+        on_change_script: Option<PathBuf>,
+    },
+
+    /// Run a task and diff its output against the last cached run
+    Diff {
+        /// Task to run and compare
+        task: String,
+
+        /// Output format
+        #[arg(short, long, default_value = "unified")]
+        format: DiffFormat,
     },
 
     /// Show task dependency graph
@@ -110,6 +202,15 @@ pub enum Commands {
         /// Output format
         #[arg(short, long, default_value = "text")]
         format: GraphFormat,
+
+        /// Show graph metrics and transitive dependency counts instead of the graph
+        #[arg(long)]
+        stats: bool,
+
+        /// Print the shortest dependency chain between two tasks; give both
+        /// `--path from=<task>` and `--path to=<task>`
+        #[arg(long, value_name = "from=TASK|to=TASK")]
+        path: Vec<String>,
     },
 
     /// Manage the task cache
@@ -123,10 +224,33 @@ pub enum Commands {
         /// Overwrite existing config
         #[arg(short, long)]
         force: bool,
+
+        /// Convert another tool's config instead of writing the template
+        #[arg(long)]
+        from: Option<ImportFormat>,
+
+        /// File to convert with --from (default: the format's usual file name)
+        #[arg(requires = "from")]
+        path: Option<PathBuf>,
+    },
+
+    /// Show what a task does: description, dependencies, commands and args
+    Explain {
+        /// Task to explain
+        task: String,
     },
 
     /// Validate yatr.toml configuration
-    Check,
+    Check {
+        /// Also compile every task's Rhai script and report syntax errors
+        #[arg(long)]
+        scripts: bool,
+
+        /// Fail when a task's `sources` overlap another task's `outputs`
+        /// without depending on it
+        #[arg(long)]
+        strict_deps: bool,
+    },
 
     /// Print the JSON Schema for yatr.toml (for editor validation/autocomplete)
     Schema,
@@ -134,6 +258,30 @@ pub enum Commands {
     /// Run the yatr.toml language server (LSP over stdio)
     Lsp,
 
+    /// Print a shell completion script (install instructions at its top)
+    Completion {
+        /// Shell to complete for
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+
+    /// Inspect the Rhai scripting environment
+    Script {
+        /// List the built-in functions scripts can call
+        #[arg(long, required = true)]
+        list_functions: bool,
+    },
+
+    /// Print yatr.toml tasks converted from another tool's config
+    Import {
+        /// Format to import from
+        #[arg(long, default_value = "package-json")]
+        from: ImportFormat,
+
+        /// File to import (default: the format's usual file name)
+        path: Option<PathBuf>,
+    },
+
     /// List tasks affected by changes since a git ref
     Affected {
         /// Git ref to compare against (e.g. `main`, `HEAD~1`, `origin/main...HEAD`)
@@ -158,6 +306,31 @@ pub enum CacheCommands {
 
     /// Show cache directory location
     Path,
+
+    /// Remove corrupt, incomplete and orphaned cache files
+    Repair,
+
+    /// Explain why a task would miss the cache, naming changed source files
+    Debug {
+        /// Task to check
+        task: String,
+    },
+
+    /// Share this cache with other machines over HTTP (set
+    /// `YATR_CACHE_TOKEN` to require a bearer token)
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+
+        /// Port to listen on
+        #[arg(long, default_value = "8080")]
+        port: u16,
+
+        /// Reject uploads
+        #[arg(long)]
+        read_only: bool,
+    },
 }
 
 #[derive(ValueEnum, Clone, Debug, Default)]
@@ -168,12 +341,34 @@ pub enum ListFormat {
     Plain,
 }
 
+#[derive(ValueEnum, Clone, Debug, Default)]
+pub enum ImportFormat {
+    /// npm/yarn `package.json` scripts
+    #[default]
+    PackageJson,
+    /// `Makefile` rules (`.PHONY` targets, or every explicit target)
+    Makefile,
+}
+
 #[derive(ValueEnum, Clone, Debug, Default)]
 pub enum GraphFormat {
     #[default]
     Text,
     Dot,
     Json,
+    /// Mermaid flowchart (renders in GitHub/GitLab Markdown)
+    Mermaid,
+    /// `stdenv.mkDerivation`s for a task and its dependencies (needs a task)
+    Nix,
+}
+
+#[derive(ValueEnum, Clone, Debug, Default)]
+pub enum DiffFormat {
+    #[default]
+    Unified,
+    SideBySide,
+    Stats,
+    Json,
 }
 
 impl Cli {
@@ -189,6 +384,70 @@ impl Cli {
     }
 }
 
+/// Write the completion script for `shell` to `out`: install instructions,
+/// clap's completions for commands and flags, and (bash, zsh and fish) task
+/// names, fetched with `yatr list --format plain` as you type.
+pub fn write_completion(shell: Shell, out: &mut dyn std::io::Write) -> Result<()> {
+    let (install, tasks) = match shell {
+        Shell::Bash => (
+            "#   source <(yatr completion bash)     # in ~/.bashrc, or:\n\
+             #   yatr completion bash > ~/.local/share/bash-completion/completions/yatr",
+            BASH_TASKS,
+        ),
+        Shell::Zsh => (
+            "#   source <(yatr completion zsh)      # in ~/.zshrc",
+            ZSH_TASKS,
+        ),
+        Shell::Fish => (
+            "#   yatr completion fish > ~/.config/fish/completions/yatr.fish",
+            FISH_TASKS,
+        ),
+        Shell::Elvish => (
+            "#   eval (yatr completion elvish | slurp)   # in rc.elv",
+            "",
+        ),
+        Shell::PowerShell => (
+            "#   yatr completion powershell | Out-String | Invoke-Expression   # in $PROFILE",
+            "",
+        ),
+        _ => ("", ""),
+    };
+    writeln!(out, "# yatr {shell} completion. Install with:\n{install}\n")?;
+    // `generate` panics on a write error (e.g. piped into `head`), so build
+    // the script first.
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut Cli::command(), "yatr", &mut script);
+    out.write_all(&script)?;
+    if !tasks.is_empty() {
+        writeln!(out, "\n{tasks}")?;
+    }
+    Ok(())
+}
+
+const BASH_TASKS: &str = r#"# Task names, from the config in the current directory
+_yatr_with_tasks() {
+    _yatr "$@"
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    if [[ "$cur" != -* ]]; then
+        COMPREPLY+=($(compgen -W "$(yatr list --format plain 2>/dev/null)" -- "$cur"))
+    fi
+}
+complete -F _yatr_with_tasks -o bashdefault -o default yatr"#;
+
+const ZSH_TASKS: &str = r#"# Task names, from the config in the current directory
+_yatr_with_tasks() {
+    _yatr "$@"
+    if [[ "${words[CURRENT]}" != -* ]]; then
+        local -a tasks
+        tasks=(${(f)"$(yatr list --format plain 2>/dev/null)"})
+        compadd -a tasks
+    fi
+}
+compdef _yatr_with_tasks yatr"#;
+
+const FISH_TASKS: &str = r"# Task names, from the config in the current directory
+complete -c yatr -f -n '__fish_use_subcommand; or __fish_seen_subcommand_from run watch' -a '(yatr list --format plain 2>/dev/null)' -d task";
+
 pub enum EffectiveCommand<'a> {
     Subcommand(&'a Commands),
     RunTasks(&'a Vec<String>),
@@ -4,13 +4,38 @@
 
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use crate::error::{Result, YatrError};
+use crate::script::{ScriptEngine, ScriptLimits};
+use crate::ssh::SshTarget;
 
 /// Default config file names to search for
-pub const CONFIG_FILES: &[&str] = &["yatr.toml", "Yatr.toml"];
+pub const CONFIG_FILES: &[&str] = &["yatr.toml", "Yatr.toml", "yatr.yaml", "Yatr.yaml"];
+
+/// Environment variable holding a whole TOML config, used instead of
+/// searching for a file (see [`Config::load`])
+pub const CONFIG_CONTENT_ENV: &str = "YATR_CONFIG_CONTENT";
+
+/// Path [`Config::load`] reports for a config read from [`CONFIG_CONTENT_ENV`]
+pub const CONFIG_CONTENT_PATH: &str = "<env:YATR_CONFIG_CONTENT>";
+
+/// Environment variable overriding how many parent directories the config
+/// search checks (see [`Config::load_with_search_limit`])
+pub const CONFIG_SEARCH_LIMIT_ENV: &str = "YATR_CONFIG_SEARCH_LIMIT";
+
+/// Parent directories the config search checks when no limit is given
+pub const DEFAULT_CONFIG_SEARCH_LIMIT: usize = 20;
+
+/// Marker file that ends the config search: the directory holding it is
+/// searched, its parents are not (like `.git` for git)
+pub const SEARCH_BOUNDARY_FILE: &str = ".yatrignore";
+
+/// Prefix of environment variables that define tasks at load time (see
+/// [`Config::apply_env_tasks`])
+pub const TASKS_ENV_PREFIX: &str = "YATR_TASKS_";
 
 /// Root configuration structure
 #[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
@@ -18,7 +43,9 @@ pub const CONFIG_FILES: &[&str] = &["yatr.toml", "Yatr.toml"];
 #[derive(Default)]
 pub struct Config {
     /// Other yatr.toml files to merge in (paths relative to this file).
-    /// Their tasks and env are composed into this config; their settings are
+    /// Their tasks and env are composed into this config, later includes
+    /// winning over earlier ones and this file over all of them; a task
+    /// defined more than once is merged field by field. Their settings are
     /// ignored (the root file's settings are authoritative).
     #[serde(default)]
     pub include: Vec<PathBuf>,
@@ -27,10 +54,20 @@ pub struct Config {
     #[serde(default)]
     pub env: HashMap<String, String>,
 
+    /// Per-platform rewrites of command prefixes, e.g.
+    /// `"rm -rf" = { windows = "cmd /C rmdir /S /Q" }`, so `run` commands
+    /// work everywhere without `shell = true`.
+    #[serde(default)]
+    pub command_aliases: HashMap<String, CommandAlias>,
+
     /// Task definitions
     #[serde(default)]
     pub tasks: HashMap<String, TaskConfig>,
 
+    /// Defaults applied to every task in this file; task values win.
+    #[serde(default)]
+    pub task_defaults: TaskDefaults,
+
     /// Pinned language toolchains, auto-downloaded and put on task `PATH`.
     #[serde(default)]
     pub toolchain: HashMap<String, ToolchainConfig>,
@@ -38,8 +75,117 @@ pub struct Config {
     /// Global settings
     #[serde(default)]
     pub settings: Settings,
+
+    /// Named overrides of `env` and `settings`, activated with `--config-profile`
+    /// (see [`Config::with_profile`])
+    #[serde(default)]
+    pub profiles: HashMap<String, ProfileConfig>,
+
+    /// Per task, the env keys [`Config::interpolate`] substituted into its
+    /// `run` commands, which [`Config::lint`] counts as referenced.
+    #[serde(skip)]
+    pub interpolated_env: HashMap<String, HashSet<String>>,
+
+    /// Per task, the `[task_defaults]` of each file that defines it (the
+    /// including file first), applied once `extends` is resolved
+    #[serde(skip)]
+    pub file_defaults: HashMap<String, Vec<Arc<TaskDefaults>>>,
+}
+
+/// Per-platform replacements for a command prefix (see
+/// [`Config::command_aliases`]). A platform left unset keeps the command as
+/// written.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CommandAlias {
+    /// Used on Linux, macOS and other Unix-likes
+    #[serde(default)]
+    pub unix: Option<String>,
+
+    /// Used on Windows
+    #[serde(default)]
+    pub windows: Option<String>,
+}
+
+/// A named block of `env` and `settings` overrides, e.g. `[profiles.ci]`
+/// (see [`Config::with_profile`]).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileConfig {
+    /// Variables set over the global `env`; task `env` still wins
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+
+    /// `[settings]` keys to override; tables merge key by key
+    #[serde(default)]
+    pub settings: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A Windows stand-in for a common Unix command (see
+/// [`BUILTIN_WINDOWS_ALIASES`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuiltinAlias {
+    /// Leading words of the Unix command, e.g. `rm -rf`
+    pub key: &'static str,
+    /// The command the operands are appended to
+    pub command: &'static str,
+    /// Joins the operands, since `Remove-Item a b` would take `b` for a
+    /// second positional parameter rather than a path
+    pub separator: &'static str,
+    /// Flag the last operand is passed with instead, e.g. `cp -r`'s
+    /// destination
+    pub destination: Option<&'static str>,
+}
+
+impl BuiltinAlias {
+    /// The stand-in for a command whose operands (after the key) are `rest`.
+    #[must_use]
+    pub fn expand(&self, rest: &str) -> String {
+        let mut operands = split_operands(rest);
+        let destination = match self.destination {
+            Some(flag) if operands.len() > 1 => operands.pop().map(|last| (flag, last)),
+            _ => None,
+        };
+        let mut out = self.command.to_string();
+        if !operands.is_empty() {
+            out = format!("{out} {}", operands.join(self.separator));
+        }
+        if let Some((flag, last)) = destination {
+            out = format!("{out} {flag} {last}");
+        }
+        out
+    }
 }
 
+/// Windows stand-ins for common Unix commands, applied when no
+/// `command_aliases` entry matches. Unix platforms run the originals.
+pub const BUILTIN_WINDOWS_ALIASES: &[BuiltinAlias] = &[
+    BuiltinAlias {
+        key: "rm -rf",
+        command: "powershell -NoProfile -Command Remove-Item -Recurse -Force -ErrorAction SilentlyContinue",
+        separator: ",",
+        destination: None,
+    },
+    BuiltinAlias {
+        key: "mkdir -p",
+        command: "powershell -NoProfile -Command New-Item -ItemType Directory -Force -Path",
+        separator: ",",
+        destination: None,
+    },
+    BuiltinAlias {
+        key: "cp -r",
+        command: "powershell -NoProfile -Command Copy-Item -Recurse -Force -Path",
+        separator: ",",
+        destination: Some("-Destination"),
+    },
+    BuiltinAlias {
+        key: "touch",
+        command: "cmd /C type nul >>",
+        separator: " & type nul >> ",
+        destination: None,
+    },
+];
+
 /// A pinned, auto-downloaded language toolchain.
 ///
 /// The `url` (and optional `bin`) are templates where `{version}`, `{os}`, and
@@ -82,6 +228,11 @@ pub struct Settings {
     #[serde(default)]
     pub cache_dir: Option<PathBuf>,
 
+    /// How often cache-hit access times are written back to disk, in seconds
+    /// (0 = on every hit)
+    #[serde(default = "default_access_flush_interval")]
+    pub cache_access_flush_interval_secs: u64,
+
     /// Default parallelism level (0 = number of CPUs)
     #[serde(default)]
     pub parallelism: usize,
@@ -90,9 +241,48 @@ pub struct Settings {
     #[serde(default = "default_debounce")]
     pub watch_debounce_ms: u64,
 
+    /// URL that receives a JSON `POST` after each watch-mode run (e.g. for live reload)
+    #[serde(default)]
+    pub watch_webhook: Option<String>,
+
     /// Optional shared/remote cache backend
     #[serde(default)]
     pub remote_cache: Option<RemoteCacheConfig>,
+
+    /// Sandbox limits for Rhai `script` tasks
+    #[serde(default)]
+    pub script_limits: ScriptLimits,
+
+    /// Reject task names outside `[a-zA-Z0-9_.-]` or over the length limit
+    /// (otherwise they only produce warnings)
+    #[serde(default)]
+    pub strict_task_names: bool,
+
+    /// Longest allowed task name
+    #[serde(default = "default_task_name_max_length")]
+    pub task_name_max_length: usize,
+
+    /// Start commands with the host environment. When false, they only see
+    /// `[env]`, the task's `env`, and the host `PATH` (unless `env` sets one).
+    #[serde(default = "default_true")]
+    pub inherit_env: bool,
+
+    /// Strip variables that can inject code into child processes
+    /// (`LD_PRELOAD`, `DYLD_INSERT_LIBRARIES`, ...) before running commands
+    #[serde(default)]
+    pub sanitize_env: bool,
+
+    /// Only use this config when yatr starts in its own directory; found
+    /// from a subdirectory, the search stops with no config instead (so a
+    /// monorepo root's config isn't picked up deep inside a member)
+    #[serde(default)]
+    pub stop_at_root: bool,
+
+    /// Fail loading when a `${VAR}` in a task names a variable that is set
+    /// neither in `env` nor in yatr's environment (otherwise it's a warning
+    /// and the reference is left as written)
+    #[serde(default)]
+    pub strict_interpolation: bool,
 }
 
 impl Default for Settings {
@@ -104,9 +294,18 @@ impl Default for Settings {
             shell: None,
             cache: default_true(),
             cache_dir: None,
+            cache_access_flush_interval_secs: default_access_flush_interval(),
             parallelism: 0,
             watch_debounce_ms: default_debounce(),
+            watch_webhook: None,
             remote_cache: None,
+            script_limits: ScriptLimits::default(),
+            strict_task_names: false,
+            task_name_max_length: default_task_name_max_length(),
+            inherit_env: default_true(),
+            sanitize_env: false,
+            stop_at_root: false,
+            strict_interpolation: false,
         }
     }
 }
@@ -148,6 +347,23 @@ pub struct RemoteCacheConfig {
     pub protocol: CacheProtocol,
 }
 
+/// Connection options for tasks whose `cwd` is an `ssh://` URL.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SshExecutorConfig {
+    /// Private key passed to `ssh -i`
+    #[serde(default)]
+    pub key_file: Option<PathBuf>,
+
+    /// SSH port (default: the client's, usually 22)
+    #[serde(default)]
+    pub port: Option<u16>,
+
+    /// Connection timeout in seconds
+    #[serde(default)]
+    pub timeout: Option<u64>,
+}
+
 /// Remote cache wire protocol.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
@@ -167,17 +383,147 @@ const fn default_debounce() -> u64 {
     300
 }
 
+const fn default_access_flush_interval() -> u64 {
+    60
+}
+
+const fn default_task_name_max_length() -> usize {
+    64
+}
+
+/// A non-fatal problem found in a config (see [`Config::warnings`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigWarning {
+    pub task: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for ConfigWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "task '{}': {}", self.task, self.message)
+    }
+}
+
+/// A task reading another task's `outputs` without depending on it (see
+/// [`Config::validate_implicit_deps`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImplicitDepWarning {
+    /// Task whose `outputs` pattern overlaps
+    pub producer: String,
+    /// Task whose `sources` pattern overlaps, without depending on `producer`
+    pub consumer: String,
+    pub output: String,
+    pub source: String,
+}
+
+impl std::fmt::Display for ImplicitDepWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "task '{}': source '{}' overlaps output '{}' of task '{}', which it doesn't depend on",
+            self.consumer, self.source, self.output, self.producer
+        )
+    }
+}
+
+/// Outcome of compiling one task's Rhai script (see [`Config::validate_scripts`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptValidationResult {
+    pub task: String,
+    pub valid: bool,
+    /// Parse error, when the script doesn't compile
+    pub error: Option<String>,
+    pub warnings: Vec<String>,
+}
+
+/// One entry of a task's `run` array.
+///
+/// Either a command line or a table whose `stdin` is written to that command
+/// alone, e.g. `{ cmd = "psql app", stdin = "select 1;" }`. A command's own
+/// `stdin` takes precedence over the task's `stdin`/`stdin_file`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(untagged, deny_unknown_fields)]
+pub enum RunCommand {
+    /// A plain command line
+    Plain(String),
+    /// A command line with its own stdin
+    WithStdin {
+        /// The command line
+        cmd: String,
+        /// Text written to the command's stdin
+        #[serde(default)]
+        stdin: Option<String>,
+    },
+}
+
+impl RunCommand {
+    /// The command line
+    #[must_use]
+    pub fn cmd(&self) -> &str {
+        match self {
+            Self::Plain(cmd) | Self::WithStdin { cmd, .. } => cmd,
+        }
+    }
+
+    /// Text for this command's stdin, if it sets its own
+    #[must_use]
+    pub fn stdin(&self) -> Option<&str> {
+        match self {
+            Self::Plain(_) => None,
+            Self::WithStdin { stdin, .. } => stdin.as_deref(),
+        }
+    }
+}
+
+impl From<String> for RunCommand {
+    fn from(cmd: String) -> Self {
+        Self::Plain(cmd)
+    }
+}
+
+impl From<&str> for RunCommand {
+    fn from(cmd: &str) -> Self {
+        Self::Plain(cmd.to_string())
+    }
+}
+
+impl std::fmt::Display for RunCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.cmd())
+    }
+}
+
 /// Configuration for a single task
-#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct TaskConfig {
     /// Human-readable description
     #[serde(default)]
     pub desc: Option<String>,
 
-    /// Commands to run (simple string list mode)
+    /// Labels for selecting tasks as a group: `yatr run --tag ci`,
+    /// `yatr list --tag ci`
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// File holding this task's fields (a bare task table, TOML or YAML by
+    /// extension), relative to the config file. Fields set here win over the
+    /// file's; several tasks may share one file as a template. The file's
+    /// modification time is part of the cache key.
+    #[serde(default)]
+    pub task_file: Option<PathBuf>,
+
+    /// Another task to inherit fields from, in this file or an included one.
+    /// Fields set here (`false` included) win over the parent's as with
+    /// `task_file`, except `depends`, which are appended to the parent's.
+    #[serde(default)]
+    pub extends: Option<String>,
+
+    /// Commands to run (simple string list mode). An entry may also be a
+    /// `{ cmd = "...", stdin = "..." }` table to feed that command its own
+    /// stdin.
     #[serde(default)]
-    pub run: Vec<String>,
+    pub run: Vec<RunCommand>,
 
     /// Rhai script to execute (alternative to `run`)
     #[serde(default)]
@@ -194,23 +540,58 @@ pub struct TaskConfig {
 
     /// Run commands in parallel
     #[serde(default)]
-    pub parallel: bool,
+    pub parallel: Option<bool>,
 
-    /// Task-specific environment variables
+    /// Task-specific environment variables. yatr also sets `YATR_TASK_NAME`,
+    /// `YATR_RUN_ID` (a UUID per run) and `YATR_VERBOSE` (`1`/`0`) for every
+    /// command and script.
     #[serde(default)]
     pub env: HashMap<String, String>,
 
-    /// Working directory for this task
+    /// Rhai expression evaluated (with the task's `env`, `cwd` and `task`
+    /// map) before the task runs; a falsy result skips the task
+    #[serde(default)]
+    pub condition_script: Option<String>,
+
+    /// Fast guard command run through the shell before the task; when it
+    /// exits 0 the task is skipped, otherwise it runs as usual. `--force`
+    /// skips the check.
+    #[serde(default)]
+    pub pre_exec_check: Option<String>,
+
+    /// Rhai script deciding what `yatr watch` runs on a change, with
+    /// `changed_files` and `task` in scope; it queues tasks with
+    /// `run_task(name)`, and nothing runs if it queues none
+    #[serde(default)]
+    pub on_change_script: Option<String>,
+
+    /// Text written to the stdin of each `run` command (unless the command
+    /// sets its own). Keeps secrets out of command arguments, which show up
+    /// in process listings.
+    #[serde(default)]
+    pub stdin: Option<String>,
+
+    /// File whose contents are written to the stdin of each `run` command
+    /// (relative to the task's working directory)
+    #[serde(default)]
+    pub stdin_file: Option<PathBuf>,
+
+    /// Working directory for this task. An `ssh://[user@]host:/path` URL runs
+    /// the task's commands on that host (requires `shell = true`).
     #[serde(default)]
     pub cwd: Option<PathBuf>,
 
+    /// SSH options for a remote (`ssh://`) working directory
+    #[serde(default)]
+    pub ssh: Option<SshExecutorConfig>,
+
     /// Use shell to execute commands
     #[serde(default)]
     pub shell: Option<bool>,
 
     /// Run in foreground with inherited stdio (for long-running processes like dev servers)
     #[serde(default)]
-    pub foreground: bool,
+    pub foreground: Option<bool>,
 
     /// Files to watch for this task (glob patterns)
     #[serde(default)]
@@ -220,26 +601,329 @@ pub struct TaskConfig {
     #[serde(default)]
     pub sources: Vec<String>,
 
+    /// Skip the task when the files matching these globs are unchanged since
+    /// its last successful run
+    #[serde(default)]
+    pub run_if_changed: Vec<String>,
+
     /// Output files/directories produced by this task
     #[serde(default)]
     pub outputs: Vec<String>,
 
+    /// Delete the declared `outputs` before the task runs, so a failed run
+    /// can't leave a stale artifact from an earlier success behind
+    #[serde(default)]
+    pub outputs_cleanup: Option<bool>,
+
     /// Skip caching for this task
     #[serde(default)]
-    pub no_cache: bool,
+    pub no_cache: Option<bool>,
 
     /// Continue even if this task fails
     #[serde(default)]
-    pub allow_failure: bool,
+    pub allow_failure: Option<bool>,
+
+    /// Timeout in seconds
+    #[serde(default)]
+    pub timeout: Option<u64>,
+
+    /// Extra attempts after a failed run (default 0)
+    #[serde(default)]
+    pub retries: Option<u32>,
+
+    /// Milliseconds to wait before each retry (default 0)
+    #[serde(default)]
+    pub retry_delay_ms: Option<u64>,
+
+    /// Only retry when the failure's stderr matches one of these regexes
+    /// (empty = retry on any failure)
+    #[serde(default)]
+    pub retry_on: Vec<String>,
+
+    /// Never retry when the failure's stderr matches one of these regexes
+    #[serde(default)]
+    pub retry_abort_on: Vec<String>,
+
+    /// Arguments the task accepts, with their default values. Pass them as
+    /// `yatr run <task> -- --name value`; `run` commands refer to them as
+    /// `${args.name}`.
+    #[serde(default)]
+    pub args: HashMap<String, String>,
+}
+
+/// Shared defaults for every task (`[task_defaults]`). Only settings-like
+/// fields are covered — what a task runs and depends on is always its own.
+///
+/// Unset task fields take the default: `Option`s when `None` (so a task's
+/// own `no_cache = false` beats a default of `true`), lists when empty; `env`
+/// entries are merged with the task's own taking precedence.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TaskDefaults {
+    /// Environment variables for every task
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+
+    /// Working directory
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+
+    /// SSH options for remote (`ssh://`) working directories
+    #[serde(default)]
+    pub ssh: Option<SshExecutorConfig>,
+
+    /// Use shell to execute commands
+    #[serde(default)]
+    pub shell: Option<bool>,
+
+    /// Files to watch (glob patterns)
+    #[serde(default)]
+    pub watch: Vec<String>,
+
+    /// Files that affect the cache key (glob patterns)
+    #[serde(default)]
+    pub sources: Vec<String>,
+
+    /// Delete declared `outputs` before each run
+    #[serde(default)]
+    pub outputs_cleanup: Option<bool>,
+
+    /// Skip caching
+    #[serde(default)]
+    pub no_cache: Option<bool>,
+
+    /// Continue even if a task fails
+    #[serde(default)]
+    pub allow_failure: Option<bool>,
 
     /// Timeout in seconds
     #[serde(default)]
     pub timeout: Option<u64>,
+
+    /// Extra attempts after a failed run
+    #[serde(default)]
+    pub retries: Option<u32>,
+
+    /// Milliseconds to wait before each retry
+    #[serde(default)]
+    pub retry_delay_ms: Option<u64>,
+
+    /// Only retry when stderr matches one of these regexes
+    #[serde(default)]
+    pub retry_on: Vec<String>,
+
+    /// Never retry when stderr matches one of these regexes
+    #[serde(default)]
+    pub retry_abort_on: Vec<String>,
+}
+
+impl TaskConfig {
+    /// Substitute `${args.<name>}` references in `cmd` with this task's arg
+    /// values. On a reference to an undeclared arg, returns its name.
+    pub fn expand_args(&self, cmd: &str) -> std::result::Result<String, String> {
+        const OPEN: &str = "${args.";
+        let mut out = String::with_capacity(cmd.len());
+        let mut rest = cmd;
+        while let Some(start) = rest.find(OPEN) {
+            let after = &rest[start + OPEN.len()..];
+            let Some(end) = after.find('}') else {
+                break;
+            };
+            let name = &after[..end];
+            let value = self.args.get(name).ok_or_else(|| name.to_string())?;
+            out.push_str(&rest[..start]);
+            out.push_str(value);
+            rest = &after[end + 1..];
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+
+    /// Whether any of this task's `tags` is in `tags`
+    #[must_use]
+    pub fn has_any_tag(&self, tags: &[String]) -> bool {
+        self.tags.iter().any(|tag| tags.contains(tag))
+    }
+
+    /// Fill in unset fields from `defaults` (see [`TaskDefaults`] for the
+    /// precedence rules). Applying the same defaults twice is a no-op.
+    pub fn merge_defaults(&mut self, defaults: &TaskDefaults) {
+        self.fill_unset(defaults.into());
+    }
+
+    /// Fill in unset fields from `template` (a `task_file`, `[task_defaults]`
+    /// or an `extends` parent): `Option`s (booleans included, so an explicit
+    /// `false` sticks) when `None`, lists when empty; `env` and `args` entries
+    /// are merged with the task's own taking precedence.
+    fn fill_unset(&mut self, template: Self) {
+        fn fill<T>(value: &mut Vec<T>, template: Vec<T>) {
+            if value.is_empty() {
+                *value = template;
+            }
+        }
+        fn fill_map(value: &mut HashMap<String, String>, template: HashMap<String, String>) {
+            for (key, v) in template {
+                value.entry(key).or_insert(v);
+            }
+        }
+
+        let Self {
+            desc,
+            tags,
+            task_file: _,
+            extends,
+            run,
+            script,
+            wasm,
+            depends,
+            parallel,
+            env,
+            condition_script,
+            pre_exec_check,
+            on_change_script,
+            stdin,
+            stdin_file,
+            cwd,
+            ssh,
+            shell,
+            foreground,
+            watch,
+            sources,
+            run_if_changed,
+            outputs,
+            outputs_cleanup,
+            no_cache,
+            allow_failure,
+            timeout,
+            retries,
+            retry_delay_ms,
+            retry_on,
+            retry_abort_on,
+            args,
+        } = template;
+
+        self.desc = self.desc.take().or(desc);
+        fill(&mut self.tags, tags);
+        self.extends = self.extends.take().or(extends);
+        fill(&mut self.run, run);
+        self.script = self.script.take().or(script);
+        self.wasm = self.wasm.take().or(wasm);
+        fill(&mut self.depends, depends);
+        self.parallel = self.parallel.or(parallel);
+        fill_map(&mut self.env, env);
+        self.condition_script = self.condition_script.take().or(condition_script);
+        self.pre_exec_check = self.pre_exec_check.take().or(pre_exec_check);
+        self.on_change_script = self.on_change_script.take().or(on_change_script);
+        self.stdin = self.stdin.take().or(stdin);
+        self.stdin_file = self.stdin_file.take().or(stdin_file);
+        self.cwd = self.cwd.take().or(cwd);
+        self.ssh = self.ssh.take().or(ssh);
+        self.shell = self.shell.or(shell);
+        self.foreground = self.foreground.or(foreground);
+        fill(&mut self.watch, watch);
+        fill(&mut self.sources, sources);
+        fill(&mut self.run_if_changed, run_if_changed);
+        fill(&mut self.outputs, outputs);
+        self.outputs_cleanup = self.outputs_cleanup.or(outputs_cleanup);
+        self.no_cache = self.no_cache.or(no_cache);
+        self.allow_failure = self.allow_failure.or(allow_failure);
+        self.timeout = self.timeout.or(timeout);
+        self.retries = self.retries.or(retries);
+        self.retry_delay_ms = self.retry_delay_ms.or(retry_delay_ms);
+        fill(&mut self.retry_on, retry_on);
+        fill(&mut self.retry_abort_on, retry_abort_on);
+        fill_map(&mut self.args, args);
+    }
+
+    /// Fill in unset fields from the `extends` parent, or an earlier
+    /// definition of the same task in an included file (see
+    /// [`fill_unset`](Self::fill_unset)), putting the parent's
+    /// `depends` before this task's. A parent's `task_file` is inherited too,
+    /// so editing it still invalidates the child's cache.
+    fn inherit(&mut self, mut parent: Self) {
+        let mut depends = std::mem::take(&mut parent.depends);
+        for dep in std::mem::take(&mut self.depends) {
+            if !depends.contains(&dep) {
+                depends.push(dep);
+            }
+        }
+        if self.task_file.is_none() {
+            self.task_file = parent.task_file.take();
+        }
+        self.fill_unset(parent);
+        self.depends = depends;
+    }
+}
+
+impl From<&TaskDefaults> for TaskConfig {
+    /// A task with only the fields `defaults` covers set
+    fn from(defaults: &TaskDefaults) -> Self {
+        let TaskDefaults {
+            env,
+            cwd,
+            ssh,
+            shell,
+            watch,
+            sources,
+            outputs_cleanup,
+            no_cache,
+            allow_failure,
+            timeout,
+            retries,
+            retry_delay_ms,
+            retry_on,
+            retry_abort_on,
+        } = defaults.clone();
+        Self {
+            env,
+            cwd,
+            ssh,
+            shell,
+            watch,
+            sources,
+            outputs_cleanup,
+            no_cache,
+            allow_failure,
+            timeout,
+            retries,
+            retry_delay_ms,
+            retry_on,
+            retry_abort_on,
+            ..Self::default()
+        }
+    }
 }
 
 impl Config {
-    /// Load configuration from the specified path or search for it
+    /// Load configuration from the specified path or search for it.
+    ///
+    /// Without a path, a non-empty [`CONFIG_CONTENT_ENV`] is parsed as TOML
+    /// instead of searching, and the returned path is [`CONFIG_CONTENT_PATH`];
+    /// its `include`s resolve against the current directory. An explicit path
+    /// always wins over the variable.
     pub fn load(path: Option<&Path>) -> Result<(Self, PathBuf)> {
+        Self::load_with_search_limit(path, None)
+    }
+
+    /// [`load`](Self::load), checking at most `search_limit` parent
+    /// directories above the current one when searching for a config file.
+    /// `None` uses [`CONFIG_SEARCH_LIMIT_ENV`], else
+    /// [`DEFAULT_CONFIG_SEARCH_LIMIT`].
+    pub fn load_with_search_limit(
+        path: Option<&Path>,
+        search_limit: Option<usize>,
+    ) -> Result<(Self, PathBuf)> {
+        Self::load_with(path, search_limit, |_| Ok(()))
+    }
+
+    /// [`load_with_search_limit`](Self::load_with_search_limit), calling
+    /// `prepare` on the merged config before `${VAR}` interpolation and
+    /// validation, e.g. to activate a profile whose `env` commands refer to.
+    pub fn load_with(
+        path: Option<&Path>,
+        search_limit: Option<usize>,
+        prepare: impl FnOnce(&mut Self) -> Result<()>,
+    ) -> Result<(Self, PathBuf)> {
         let config_path = match path {
             Some(p) => {
                 if p.exists() {
@@ -250,79 +934,463 @@ impl Config {
                     });
                 }
             }
-            None => Self::find_config()?,
+            None => match std::env::var(CONFIG_CONTENT_ENV) {
+                Ok(content) if !content.is_empty() => {
+                    let path = PathBuf::from(CONFIG_CONTENT_PATH);
+                    let config =
+                        Self::parse_with_includes(&content, &path, false, &mut Vec::new())?;
+                    return Self::finish_root(config, path, prepare);
+                }
+                _ => Self::find_config(search_limit)?,
+            },
+        };
+
+        let yaml = Self::is_yaml(&config_path);
+        Self::load_root(config_path, yaml, prepare)
+    }
+
+    /// Load a YAML config file (whatever its extension). Its `include`s are
+    /// parsed by their own extension, so YAML and TOML files can be mixed.
+    pub fn load_yaml(path: &Path) -> Result<(Self, PathBuf)> {
+        if !path.exists() {
+            return Err(YatrError::ConfigNotFound {
+                searched: vec![path.to_path_buf()],
+            });
+        }
+        Self::load_root(path.to_path_buf(), true, |_| Ok(()))
+    }
+
+    /// Build tasks from the `scripts` of an npm/yarn `package.json`. A plain
+    /// script (`"test": "vitest"`) becomes `run = ["npx vitest"]`; one using
+    /// shell syntax (`&&`, pipes, env assignments, ...) is delegated to
+    /// `npm run --ignore-scripts <name>`. A `pre<name>` script becomes a
+    /// dependency of `<name>`; `post<name>` scripts are imported as-is.
+    pub fn from_package_json(path: &Path) -> Result<Self> {
+        let invalid = |message: String| YatrError::InvalidConfig { message };
+        let content = std::fs::read_to_string(path)?;
+        let json: serde_json::Value = serde_json::from_str(&content)
+            .map_err(|e| invalid(format!("{}: {e}", path.display())))?;
+        let scripts = json
+            .get("scripts")
+            .and_then(serde_json::Value::as_object)
+            .ok_or_else(|| invalid(format!("{} has no \"scripts\" section", path.display())))?;
+
+        let mut tasks = HashMap::new();
+        for (name, script) in scripts {
+            let Some(script) = script.as_str() else {
+                continue;
+            };
+            let plain = !script.contains(['&', '|', ';', '>', '<', '$', '`', '='])
+                && !script.trim().is_empty();
+            let run = if plain {
+                format!("npx {script}")
+            } else {
+                format!("npm run --ignore-scripts {name}")
+            };
+            let pre = format!("pre{name}");
+            let task = TaskConfig {
+                desc: Some(format!("npm script: {script}")),
+                depends: scripts
+                    .contains_key(&pre)
+                    .then_some(pre)
+                    .into_iter()
+                    .collect(),
+                run: vec![run.into()],
+                ..TaskConfig::default()
+            };
+            tasks.insert(name.clone(), task);
+        }
+
+        let config = Self {
+            tasks,
+            ..Self::default()
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Build tasks from a Makefile's rules (see [`crate::makefile`]). Returns
+    /// the config and notes on the Make features that weren't converted.
+    pub fn from_makefile(path: &Path) -> Result<(Self, Vec<String>)> {
+        let content = std::fs::read_to_string(path)?;
+        let import = crate::makefile::import(&content);
+        let config = Self {
+            env: import.env,
+            tasks: import.tasks,
+            ..Self::default()
         };
+        config.validate()?;
+        Ok((config, import.notes))
+    }
+
+    fn load_root(
+        config_path: PathBuf,
+        yaml: bool,
+        prepare: impl FnOnce(&mut Self) -> Result<()>,
+    ) -> Result<(Self, PathBuf)> {
+        let config = Self::load_with_includes(&config_path, yaml, &mut Vec::new())?;
+        Self::finish_root(config, config_path, prepare)
+    }
 
-        let mut visited = std::collections::HashSet::new();
-        let config = Self::load_with_includes(&config_path, &mut visited)?;
+    /// Resolve `extends` in a fully merged root config, apply
+    /// [`TASKS_ENV_PREFIX`] tasks and `prepare` to it, interpolate it, then
+    /// validate it and log its warnings.
+    fn finish_root(
+        mut config: Self,
+        config_path: PathBuf,
+        prepare: impl FnOnce(&mut Self) -> Result<()>,
+    ) -> Result<(Self, PathBuf)> {
+        config.resolve_extends()?;
+        config.apply_env_tasks(std::env::vars())?;
+        prepare(&mut config)?;
+        config.interpolate()?;
         config.validate()?;
+        for warning in config.warnings() {
+            tracing::warn!("{warning}");
+        }
 
         Ok((config, config_path))
     }
 
+    /// Whether `path` names a YAML file (`.yaml` / `.yml`); anything else is TOML.
+    fn is_yaml(path: &Path) -> bool {
+        matches!(
+            path.extension().and_then(std::ffi::OsStr::to_str),
+            Some("yaml" | "yml")
+        )
+    }
+
     /// Load a config file and recursively merge any files it `include`s.
-    fn load_with_includes(
-        path: &Path,
-        visited: &mut std::collections::HashSet<PathBuf>,
-    ) -> Result<Self> {
+    /// `stack` holds the files being loaded, outermost first, so a file that
+    /// includes itself (directly or not) is reported with the whole chain.
+    fn load_with_includes(path: &Path, yaml: bool, stack: &mut Vec<PathBuf>) -> Result<Self> {
         let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
-        if !visited.insert(canonical) {
-            return Err(YatrError::InvalidConfig {
-                message: format!("include cycle detected at {}", path.display()),
+        if let Some(start) = stack.iter().position(|p| *p == canonical) {
+            let chain: Vec<_> = stack[start..]
+                .iter()
+                .chain([&canonical])
+                .map(|p| p.display().to_string())
+                .collect();
+            let message = format!("include cycle: {}", chain.join(" -> "));
+            return Err(YatrError::ConfigParse {
+                source: Box::new(<toml::de::Error as serde::de::Error>::custom(message)),
+                path: path.to_path_buf(),
+                help: Some("remove one of the `include` entries in the chain".to_string()),
             });
         }
 
         let content = std::fs::read_to_string(path).map_err(|e| YatrError::InvalidConfig {
             message: format!("failed to read included config {}: {e}", path.display()),
         })?;
-        let mut config: Self = toml::from_str(&content).map_err(|e| YatrError::ConfigParse {
-            source: e,
-            path: path.to_path_buf(),
-        })?;
+        stack.push(canonical);
+        let config = Self::parse_with_includes(&content, path, yaml, stack);
+        stack.pop();
+        config
+    }
+
+    /// Parse config `content` (read from `path`) and recursively merge any
+    /// files it `include`s, relative to `path`'s directory.
+    fn parse_with_includes(
+        content: &str,
+        path: &Path,
+        yaml: bool,
+        stack: &mut Vec<PathBuf>,
+    ) -> Result<Self> {
+        let mut config: Self = if yaml {
+            serde_yaml::from_str(content).map_err(|e| YatrError::ConfigParseYaml {
+                source: e,
+                path: path.to_path_buf(),
+            })?
+        } else {
+            toml::from_str(content).map_err(|e| YatrError::ConfigParse {
+                help: dotted_task_name_hint(content, &e),
+                source: Box::new(e),
+                path: path.to_path_buf(),
+            })?
+        };
 
         let base = path.parent().unwrap_or_else(|| Path::new("."));
+        for (name, task) in &mut config.tasks {
+            Self::load_task_file(name, task, base)?;
+        }
+
+        // A file's defaults apply to its own tasks only, not to included
+        // ones, and only once `extends` has been resolved across all files.
+        let defaults = Arc::new(config.task_defaults.clone());
+        config.file_defaults = config
+            .tasks
+            .keys()
+            .map(|name| (name.clone(), vec![Arc::clone(&defaults)]))
+            .collect();
+
+        let mut included = Vec::new();
         for inc in std::mem::take(&mut config.include) {
             let inc_path = base.join(&inc);
-            let included = Self::load_with_includes(&inc_path, visited)?;
-            config.merge_from(included)?;
+            included.push(Self::load_with_includes(
+                &inc_path,
+                Self::is_yaml(&inc_path),
+                stack,
+            )?);
+        }
+        // Merged last to first under what's already there, so later includes
+        // win over earlier ones and this file over every include.
+        for other in included.into_iter().rev() {
+            config.merge_under(other);
         }
 
         Ok(config)
     }
 
-    /// Merge another config's tasks and env into this one. Duplicate task names
-    /// are an error; existing (root) env entries win over included ones; the
-    /// other config's settings are ignored.
-    fn merge_from(&mut self, other: Self) -> Result<()> {
-        for (name, task) in other.tasks {
-            if self.tasks.contains_key(&name) {
-                return Err(YatrError::InvalidConfig {
-                    message: format!("task '{name}' is defined in more than one config file"),
-                });
-            }
-            self.tasks.insert(name, task);
+    /// Resolve every `extends` across the merged tasks, parents first, then
+    /// fill each task's unset fields from its files' `[task_defaults]`. A
+    /// parent gets its defaults before a child inherits from it, so fields a
+    /// child inherits beat the defaults of the child's own file.
+    fn resolve_extends(&mut self) -> Result<()> {
+        let mut names: Vec<_> = self
+            .tasks
+            .iter()
+            .filter(|(_, task)| task.extends.is_some())
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        let mut resolved = HashSet::new();
+        for name in names {
+            self.resolve_task_extends(&name, &mut resolved, &mut Vec::new())?;
         }
-        for (key, value) in other.env {
-            self.env.entry(key).or_insert(value);
+        let mut names: Vec<_> = self.file_defaults.keys().cloned().collect();
+        names.sort();
+        for name in names {
+            self.apply_file_defaults(&name);
         }
         Ok(())
     }
 
-    /// Search for config file starting from current directory
-    fn find_config() -> Result<PathBuf> {
-        let mut current = std::env::current_dir()?;
-        let mut searched = Vec::new();
+    /// Merge `name`'s parent into it, after resolving the parent's own
+    /// `extends`. `chain` holds the tasks being resolved, to catch cycles.
+    fn resolve_task_extends(
+        &mut self,
+        name: &str,
+        resolved: &mut HashSet<String>,
+        chain: &mut Vec<String>,
+    ) -> Result<()> {
+        let invalid = |reason: String| YatrError::InvalidTask {
+            task: name.to_string(),
+            reason,
+        };
+        if resolved.contains(name) {
+            return Ok(());
+        }
+        if chain.iter().any(|n| n == name) {
+            return Err(invalid(format!(
+                "extends cycle: {} -> {name}",
+                chain.join(" -> ")
+            )));
+        }
+        let Some(parent_name) = self.tasks.get(name).and_then(|t| t.extends.clone()) else {
+            return Ok(());
+        };
+        if !self.tasks.contains_key(&parent_name) {
+            return Err(invalid(format!("extends unknown task '{parent_name}'")));
+        }
 
-        loop {
-            for name in CONFIG_FILES {
-                let candidate = current.join(name);
-                searched.push(candidate.clone());
+        chain.push(name.to_string());
+        self.resolve_task_extends(&parent_name, resolved, chain)?;
+        chain.pop();
+
+        self.apply_file_defaults(&parent_name);
+        let parent = self.tasks[&parent_name].clone();
+        if let Some(task) = self.tasks.get_mut(name) {
+            task.inherit(parent);
+        }
+        resolved.insert(name.to_string());
+        Ok(())
+    }
+
+    /// Fill `name`'s unset fields from its files' `[task_defaults]`, the
+    /// including file's first. Only the first call per task does anything.
+    fn apply_file_defaults(&mut self, name: &str) {
+        let Some(defaults) = self.file_defaults.remove(name) else {
+            return;
+        };
+        if let Some(task) = self.tasks.get_mut(name) {
+            for defaults in defaults {
+                task.merge_defaults(&defaults);
+            }
+        }
+    }
+
+    /// Merge `task`'s `task_file` (relative to `base`) into it, and resolve
+    /// `task_file` to the path that was read so the cache can find it.
+    fn load_task_file(name: &str, task: &mut TaskConfig, base: &Path) -> Result<()> {
+        let Some(rel) = &task.task_file else {
+            return Ok(());
+        };
+        let path = base.join(rel);
+        let invalid = |message: String| YatrError::InvalidConfig { message };
+        let content = std::fs::read_to_string(&path).map_err(|e| {
+            invalid(format!(
+                "task '{name}': failed to read task_file {}: {e}",
+                path.display()
+            ))
+        })?;
+        let template: TaskConfig = if Self::is_yaml(&path) {
+            serde_yaml::from_str(&content).map_err(|e| YatrError::ConfigParseYaml {
+                source: e,
+                path: path.clone(),
+            })?
+        } else {
+            toml::from_str(&content).map_err(|e| YatrError::ConfigParse {
+                help: None,
+                source: Box::new(e),
+                path: path.clone(),
+            })?
+        };
+        if template.task_file.is_some() {
+            return Err(invalid(format!(
+                "{}: a task_file can't set task_file itself",
+                path.display()
+            )));
+        }
+        task.fill_unset(template);
+        task.task_file = Some(path);
+        Ok(())
+    }
+
+    /// Create or override tasks from `YATR_TASKS_<NAME>` (a JSON array of
+    /// `run` commands) and `YATR_TASKS_<NAME>_DEPENDS` (a JSON array of task
+    /// names) among `vars`. `<NAME>` matches an existing task whose name,
+    /// uppercased with other characters as `_`, is the same (so
+    /// `YATR_TASKS_BUILD_RELEASE` names `build-release`); otherwise a task
+    /// named `<NAME>` lowercased is created.
+    ///
+    /// A `run` variable replaces the whole task, so it wins over one defined
+    /// in a file; a `_DEPENDS` variable alone only replaces `depends`, and is
+    /// an error for a task that neither the config nor a `run` variable
+    /// defines.
+    pub fn apply_env_tasks(
+        &mut self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<()> {
+        fn parse<T: serde::de::DeserializeOwned>(key: &str, value: &str) -> Result<Vec<T>> {
+            serde_json::from_str(value).map_err(|e| YatrError::InvalidConfig {
+                message: format!("{key} must be a JSON array of strings: {e}"),
+            })
+        }
+
+        let mut runs: Vec<(String, Vec<RunCommand>)> = Vec::new();
+        let mut depends: HashMap<String, (String, Vec<String>)> = HashMap::new();
+        for (key, value) in vars {
+            let Some(name) = key.strip_prefix(TASKS_ENV_PREFIX) else {
+                continue;
+            };
+            if let Some(name) = name.strip_suffix("_DEPENDS") {
+                let deps = parse(&key, &value)?;
+                depends.insert(self.env_task_name(name), (key, deps));
+            } else if !name.is_empty() {
+                runs.push((self.env_task_name(name), parse(&key, &value)?));
+            }
+        }
+
+        for (name, run) in runs {
+            let mut task = TaskConfig {
+                run,
+                depends: depends.remove(&name).unwrap_or_default().1,
+                ..TaskConfig::default()
+            };
+            task.merge_defaults(&self.task_defaults);
+            self.tasks.insert(name, task);
+        }
+        for (name, (key, deps)) in depends {
+            let task = self
+                .tasks
+                .get_mut(&name)
+                .ok_or_else(|| YatrError::InvalidConfig {
+                    message: format!(
+                        "{key} sets the dependencies of task '{name}', which isn't defined; \
+                         define it in the config or with {}",
+                        key.trim_end_matches("_DEPENDS")
+                    ),
+                })?;
+            task.depends = deps;
+        }
+        Ok(())
+    }
+
+    /// The task a `YATR_TASKS_<NAME>` variable refers to (see
+    /// [`apply_env_tasks`](Self::apply_env_tasks))
+    fn env_task_name(&self, name: &str) -> String {
+        let env_form = |task: &str| -> String {
+            task.chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() {
+                        c.to_ascii_uppercase()
+                    } else {
+                        '_'
+                    }
+                })
+                .collect()
+        };
+        self.task_names_sorted()
+            .into_iter()
+            .find(|task| env_form(task) == name)
+            .map_or_else(|| name.to_lowercase(), str::to_string)
+    }
+
+    /// Merge a lower-priority config's tasks, env, command aliases and
+    /// profiles into this one; entries already here win. A task defined in
+    /// both fills its unset fields from the other one's, like `extends`. The
+    /// other config's settings are ignored.
+    fn merge_under(&mut self, other: Self) {
+        for (name, task) in other.tasks {
+            match self.tasks.get_mut(&name) {
+                Some(existing) => existing.inherit(task),
+                None => {
+                    self.tasks.insert(name, task);
+                }
+            }
+        }
+        for (name, defaults) in other.file_defaults {
+            self.file_defaults.entry(name).or_default().extend(defaults);
+        }
+        for (key, value) in other.env {
+            self.env.entry(key).or_insert(value);
+        }
+        for (key, alias) in other.command_aliases {
+            self.command_aliases.entry(key).or_insert(alias);
+        }
+        for (name, profile) in other.profiles {
+            self.profiles.entry(name).or_insert(profile);
+        }
+    }
+
+    /// Search for config file starting from current directory, then up to
+    /// `limit` of its parents (see [`load_with_search_limit`](Self::load_with_search_limit)).
+    fn find_config(limit: Option<usize>) -> Result<PathBuf> {
+        let limit = limit
+            .or_else(|| std::env::var(CONFIG_SEARCH_LIMIT_ENV).ok()?.parse().ok())
+            .unwrap_or(DEFAULT_CONFIG_SEARCH_LIMIT);
+        Self::find_config_from(std::env::current_dir()?, limit)
+    }
+
+    /// Search `current` and up to `limit` of its parents, stopping early at a
+    /// directory with a [`SEARCH_BOUNDARY_FILE`] or above the start at a
+    /// config with `stop_at_root` set.
+    fn find_config_from(mut current: PathBuf, limit: usize) -> Result<PathBuf> {
+        let mut searched = Vec::new();
+
+        for depth in 0..=limit {
+            for name in CONFIG_FILES {
+                let candidate = current.join(name);
+                searched.push(candidate.clone());
                 if candidate.exists() {
+                    if depth > 0 && Self::stops_at_root(&candidate) {
+                        return Err(YatrError::ConfigNotFound { searched });
+                    }
                     return Ok(candidate);
                 }
             }
 
-            if !current.pop() {
+            if current.join(SEARCH_BOUNDARY_FILE).exists() || !current.pop() {
                 break;
             }
         }
@@ -330,8 +1398,43 @@ impl Config {
         Err(YatrError::ConfigNotFound { searched })
     }
 
+    /// Whether the config file at `path` sets `settings.stop_at_root`. Only
+    /// that key is read; a file that doesn't parse is left for the real load
+    /// to report.
+    fn stops_at_root(path: &Path) -> bool {
+        #[derive(Deserialize)]
+        struct Root {
+            #[serde(default)]
+            settings: RootSettings,
+        }
+        #[derive(Deserialize, Default)]
+        struct RootSettings {
+            #[serde(default)]
+            stop_at_root: bool,
+        }
+
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return false;
+        };
+        let root: Option<Root> = if Self::is_yaml(path) {
+            serde_yaml::from_str(&content).ok()
+        } else {
+            toml::from_str(&content).ok()
+        };
+        root.is_some_and(|root| root.settings.stop_at_root)
+    }
+
     /// Validate the configuration
     pub(crate) fn validate(&self) -> Result<()> {
+        if self.settings.strict_task_names {
+            if let Some(problem) = self.task_name_problems().into_iter().next() {
+                return Err(YatrError::InvalidTask {
+                    task: problem.task,
+                    reason: problem.message,
+                });
+            }
+        }
+
         for (name, task) in &self.tasks {
             // Task must have one of `run`, `script`, `wasm`, or dependencies
             let has_run = !task.run.is_empty();
@@ -354,6 +1457,23 @@ impl Config {
                 });
             }
 
+            // stdin only feeds `run` commands whose output yatr captures
+            let has_stdin = task.stdin.is_some()
+                || task.stdin_file.is_some()
+                || task.run.iter().any(|cmd| cmd.stdin().is_some());
+            if task.stdin.is_some() && task.stdin_file.is_some() {
+                return Err(YatrError::InvalidTask {
+                    task: name.clone(),
+                    reason: "Task can only have one of 'stdin' or 'stdin_file'".to_string(),
+                });
+            }
+            if has_stdin && (!has_run || task.foreground.unwrap_or(false)) {
+                return Err(YatrError::InvalidTask {
+                    task: name.clone(),
+                    reason: "'stdin' requires non-foreground 'run' commands".to_string(),
+                });
+            }
+
             // Check for self-dependency
             if task.depends.contains(name) {
                 return Err(YatrError::InvalidTask {
@@ -361,11 +1481,303 @@ impl Config {
                     reason: "Task cannot depend on itself".to_string(),
                 });
             }
+
+            // A remote (ssh://) cwd must parse, and only works for shell commands
+            if let Some(target) = task.cwd.as_deref().and_then(SshTarget::from_cwd) {
+                target?;
+                if has_script || has_wasm || !has_run {
+                    return Err(YatrError::InvalidTask {
+                        task: name.clone(),
+                        reason: "An ssh:// cwd requires 'run' commands".to_string(),
+                    });
+                }
+                if task.shell != Some(true) {
+                    return Err(YatrError::InvalidTask {
+                        task: name.clone(),
+                        reason: "An ssh:// cwd requires 'shell = true'".to_string(),
+                    });
+                }
+            }
+
+            // `${args.x}` references must name a declared arg
+            for cmd in &task.run {
+                if let Err(arg) = task.expand_args(cmd.cmd()) {
+                    return Err(YatrError::InvalidTask {
+                        task: name.clone(),
+                        reason: format!("'${{args.{arg}}}' refers to an undeclared arg"),
+                    });
+                }
+            }
+
+            // Retry patterns must be valid regexes
+            for pattern in task.retry_on.iter().chain(&task.retry_abort_on) {
+                if let Err(e) = regex::Regex::new(pattern) {
+                    return Err(YatrError::InvalidTask {
+                        task: name.clone(),
+                        reason: format!("Invalid retry pattern '{pattern}': {e}"),
+                    });
+                }
+            }
         }
 
         Ok(())
     }
 
+    /// Names of the tasks with any of `tags`, sorted
+    #[must_use]
+    pub fn tasks_tagged(&self, tags: &[String]) -> Vec<String> {
+        let mut names: Vec<_> = self
+            .tasks
+            .iter()
+            .filter(|(_, task)| task.has_any_tag(tags))
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Override the `args` of the `targets` tasks from command-line tokens
+    /// (`--name value` or `--name=value`). Each arg is set on every target
+    /// that declares it; an arg no target declares is an error.
+    pub fn set_task_args(&mut self, targets: &[String], tokens: &[String]) -> Result<()> {
+        let invalid = |message: String| YatrError::InvalidConfig { message };
+
+        let mut tokens = tokens.iter();
+        while let Some(token) = tokens.next() {
+            let flag = token
+                .strip_prefix("--")
+                .ok_or_else(|| invalid(format!("unexpected task argument '{token}'")))?;
+            let (name, value) = if let Some((name, value)) = flag.split_once('=') {
+                (name, value.to_string())
+            } else {
+                let value = tokens
+                    .next()
+                    .ok_or_else(|| invalid(format!("missing value for --{flag}")))?;
+                (flag, value.clone())
+            };
+
+            let mut accepted = false;
+            for target in targets {
+                if let Some(task) = self.tasks.get_mut(target) {
+                    if let Some(slot) = task.args.get_mut(name) {
+                        slot.clone_from(&value);
+                        accepted = true;
+                    }
+                }
+            }
+            if !accepted {
+                return Err(invalid(format!(
+                    "no task in [{}] accepts an arg named '{name}'",
+                    targets.join(", ")
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Non-fatal problems with the config. Task-name problems only appear
+    /// here in lenient mode; with `strict_task_names` they fail validation.
+    #[must_use]
+    pub fn warnings(&self) -> Vec<ConfigWarning> {
+        if self.settings.strict_task_names {
+            Vec::new()
+        } else {
+            self.task_name_problems()
+        }
+    }
+
+    /// Best-effort smells that are too noisy to report on every load (`yatr
+    /// check` shows them): task `env` keys that none of the task's `run`
+    /// commands mention as `$KEY`, `${KEY}` (also once interpolated, see
+    /// [`interpolated_env`](Self::interpolated_env)) or `%KEY%`. Tasks with a `script`
+    /// or `wasm` body are skipped, as are keys from `[task_defaults]`. A
+    /// program may still read such a variable itself, hence only a warning.
+    #[must_use]
+    pub fn lint(&self) -> Vec<ConfigWarning> {
+        let mut warnings = Vec::new();
+        for name in self.task_names_sorted() {
+            let task = &self.tasks[name];
+            if task.script.is_some() || task.wasm.is_some() {
+                continue;
+            }
+            let mut keys: Vec<&String> = task
+                .env
+                .keys()
+                .filter(|key| !self.task_defaults.env.contains_key(*key))
+                .filter(|key| {
+                    !self
+                        .interpolated_env
+                        .get(name)
+                        .is_some_and(|used| used.contains(*key))
+                })
+                .filter(|key| {
+                    !task
+                        .run
+                        .iter()
+                        .any(|cmd| references_env_var(cmd.cmd(), key))
+                })
+                .collect();
+            keys.sort();
+            warnings.extend(keys.into_iter().map(|key| ConfigWarning {
+                task: name.to_string(),
+                message: format!("env var '{key}' is never referenced by its `run` commands"),
+            }));
+        }
+        warnings
+    }
+
+    /// Compile every task's `script` (with `settings.script_limits`) without
+    /// running it, so syntax errors surface before a run. Sorted by task name.
+    #[must_use]
+    pub fn validate_scripts(&self) -> Vec<ScriptValidationResult> {
+        let engine = ScriptEngine::new_with_limits(self.settings.script_limits);
+        self.task_names_sorted()
+            .into_iter()
+            .filter_map(|name| {
+                let script = self.tasks[name].script.as_deref()?;
+                let (valid, error) = match engine.compile(script) {
+                    Ok(_) => (true, None),
+                    Err(e) => (false, Some(e.to_string())),
+                };
+                let mut warnings = Vec::new();
+                if valid && script.trim().is_empty() {
+                    warnings.push("script is empty".to_string());
+                }
+                if script.contains("${args.") {
+                    warnings.push(
+                        "`${args.*}` is only substituted in `run` commands, not scripts"
+                            .to_string(),
+                    );
+                }
+                Some(ScriptValidationResult {
+                    task: name.to_string(),
+                    valid,
+                    error,
+                    warnings,
+                })
+            })
+            .collect()
+    }
+
+    /// Find implicit ordering: task B's `sources` overlap task A's `outputs`,
+    /// but A is not among B's (transitive) `depends`, so B may run before A
+    /// or read a half-written output. Only tasks sharing a `cwd` are compared,
+    /// and patterns are compared textually (a literal path against a glob, or
+    /// two globs by their leading directories), so treat the result as a hint.
+    /// Sorted by consumer, then producer.
+    #[must_use]
+    pub fn validate_implicit_deps(&self) -> Vec<ImplicitDepWarning> {
+        let mut warnings = Vec::new();
+        for consumer in self.task_names_sorted() {
+            let task = &self.tasks[consumer];
+            if task.sources.is_empty() {
+                continue;
+            }
+            let upstream = self.transitive_depends(consumer);
+            for producer in self.task_names_sorted() {
+                let other = &self.tasks[producer];
+                if producer == consumer || other.cwd != task.cwd || upstream.contains(producer) {
+                    continue;
+                }
+                let overlap = other.outputs.iter().find_map(|output| {
+                    task.sources
+                        .iter()
+                        .find(|source| patterns_overlap(output, source))
+                        .map(|source| (output, source))
+                });
+                if let Some((output, source)) = overlap {
+                    warnings.push(ImplicitDepWarning {
+                        producer: producer.to_string(),
+                        consumer: consumer.to_string(),
+                        output: output.clone(),
+                        source: source.clone(),
+                    });
+                }
+            }
+        }
+        warnings
+    }
+
+    /// Every task `name` depends on, directly or through other tasks.
+    fn transitive_depends(&self, name: &str) -> std::collections::HashSet<&str> {
+        let mut seen = std::collections::HashSet::new();
+        let mut stack: Vec<&str> = vec![name];
+        while let Some(current) = stack.pop() {
+            let Some(task) = self.tasks.get(current) else {
+                continue;
+            };
+            for dep in &task.depends {
+                if seen.insert(dep.as_str()) {
+                    stack.push(dep);
+                }
+            }
+        }
+        seen
+    }
+
+    /// Task names with characters outside `[a-zA-Z0-9_.-]` (awkward in shells
+    /// and glob patterns) or longer than `task_name_max_length`.
+    fn task_name_problems(&self) -> Vec<ConfigWarning> {
+        let max = self.settings.task_name_max_length;
+        let mut problems = Vec::new();
+        for name in self.task_names_sorted() {
+            if name.is_empty()
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+            {
+                problems.push(ConfigWarning {
+                    task: name.to_string(),
+                    message: "task names should only use letters, digits, '_', '-' and '.'"
+                        .to_string(),
+                });
+            }
+            if name.chars().count() > max {
+                problems.push(ConfigWarning {
+                    task: name.to_string(),
+                    message: format!("task name is longer than {max} characters"),
+                });
+            }
+        }
+        problems
+    }
+
+    /// Rewrite `cmd` for `os` (as in [`std::env::consts::OS`]) when it starts
+    /// with (or is) a [`command_aliases`](Self::command_aliases) key, matched
+    /// at a word boundary; the longest matching key wins. On Windows,
+    /// [`BUILTIN_WINDOWS_ALIASES`] fill in for keys the config doesn't define.
+    #[must_use]
+    pub fn resolve_command_alias(&self, cmd: &str, os: &str) -> String {
+        enum Rewrite<'a> {
+            Prefix(&'a str),
+            Builtin(&'a BuiltinAlias),
+        }
+
+        let windows = os == "windows";
+        let configured = self.command_aliases.iter().filter_map(|(key, alias)| {
+            let value = if windows { &alias.windows } else { &alias.unix };
+            Some((key.as_str(), Rewrite::Prefix(value.as_deref()?)))
+        });
+        let builtins = BUILTIN_WINDOWS_ALIASES
+            .iter()
+            .filter(|_| windows)
+            .filter(|builtin| !self.command_aliases.contains_key(builtin.key))
+            .map(|builtin| (builtin.key, Rewrite::Builtin(builtin)));
+
+        let matched = configured
+            .chain(builtins)
+            .filter_map(|(key, replacement)| {
+                let rest = cmd.strip_prefix(key)?;
+                (rest.is_empty() || rest.starts_with(' ')).then_some((key.len(), replacement, rest))
+            })
+            .max_by_key(|(len, _, _)| *len);
+        match matched {
+            None => cmd.to_string(),
+            Some((_, Rewrite::Prefix(value), rest)) => format!("{value}{rest}"),
+            Some((_, Rewrite::Builtin(builtin), rest)) => builtin.expand(rest),
+        }
+    }
+
     /// Get a task by name
     #[must_use]
     pub fn get_task(&self, name: &str) -> Option<&TaskConfig> {
@@ -378,6 +1790,168 @@ impl Config {
         self.tasks.keys().map(std::string::String::as_str).collect()
     }
 
+    /// List all task names in alphabetical order
+    #[must_use]
+    pub fn task_names_sorted(&self) -> Vec<&str> {
+        let mut names = self.task_names();
+        names.sort_unstable();
+        names
+    }
+
+    /// Substitute `${VAR}` in `[env]` values and in each task's `run`,
+    /// `cwd`, `desc` and `env` values. `run` sees only the task's merged env
+    /// (task over global): a name set just in yatr's own environment is left
+    /// for the shell, or for the executor to fill in from the command's
+    /// environment as it starts, so host secrets never end up in printed
+    /// plans. `cwd` and `desc` see the merged env and then yatr's
+    /// environment. An `env` value sees only the scope around it — the other
+    /// `[env]` entries for `[env]`, the global env for a task's — and then
+    /// yatr's environment, so `PATH = "${PATH}:bin"` extends the outer
+    /// `PATH`. Other forms (`${args.x}`, `${VAR:-default}`, `$VAR`) are left
+    /// to later stages and the shell. The keys substituted into `run` are
+    /// kept in [`interpolated_env`](Self::interpolated_env).
+    ///
+    /// Names set nowhere are left as written with a warning, or fail with
+    /// [`Settings::strict_interpolation`]. `YATR_*` names are exempt, since
+    /// yatr sets those per run.
+    pub fn interpolate(&mut self) -> Result<()> {
+        let mut unknown: Vec<(Option<String>, String)> = Vec::new();
+
+        let global = self.env.clone();
+        for (key, value) in &mut self.env {
+            let lookup = |name: &str| {
+                (name != key)
+                    .then(|| global.get(name).cloned())
+                    .flatten()
+                    .or_else(|| std::env::var(name).ok())
+            };
+            *value = interpolate_vars(value, lookup, &mut |name| unknown.push((None, name)));
+        }
+
+        let mut names: Vec<_> = self.tasks.keys().cloned().collect();
+        names.sort();
+        for name in names {
+            let Some(task) = self.tasks.get_mut(&name) else {
+                continue;
+            };
+            let mut report = |var: String| unknown.push((Some(name.clone()), var));
+
+            let outer = |var: &str| {
+                self.env
+                    .get(var)
+                    .cloned()
+                    .or_else(|| std::env::var(var).ok())
+            };
+            for value in task.env.values_mut() {
+                *value = interpolate_vars(value, outer, &mut report);
+            }
+
+            let mut merged = self.env.clone();
+            merged.extend(task.env.clone());
+            let lookup = |var: &str| merged.get(var).cloned();
+            let host = |var: &str| lookup(var).or_else(|| std::env::var(var).ok());
+            if let Some(desc) = &mut task.desc {
+                *desc = interpolate_vars(desc, host, &mut report);
+            }
+            if let Some(cwd) = task
+                .cwd
+                .as_mut()
+                .and_then(|cwd| cwd.to_str().map(str::to_string))
+            {
+                task.cwd = Some(interpolate_vars(&cwd, host, &mut report).into());
+            }
+
+            let mut report = |var: String| {
+                if std::env::var_os(&var).is_none() {
+                    report(var);
+                }
+            };
+            for cmd in &mut task.run {
+                let (RunCommand::Plain(cmd) | RunCommand::WithStdin { cmd, .. }) = cmd;
+                let used = merged
+                    .keys()
+                    .filter(|key| cmd.contains(&format!("${{{key}}}")))
+                    .cloned();
+                self.interpolated_env
+                    .entry(name.clone())
+                    .or_default()
+                    .extend(used);
+                *cmd = interpolate_vars(cmd, lookup, &mut report);
+            }
+        }
+
+        for (task, var) in unknown {
+            let reason = format!("`${{{var}}}` is not set in env or the environment");
+            match task {
+                Some(task) if self.settings.strict_interpolation => {
+                    return Err(YatrError::InvalidTask { task, reason });
+                }
+                None if self.settings.strict_interpolation => {
+                    return Err(YatrError::InvalidConfig {
+                        message: format!("[env]: {reason}"),
+                    });
+                }
+                Some(task) => tracing::warn!("task '{task}': {reason}"),
+                None => tracing::warn!("[env]: {reason}"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Activate the `[profiles.<name>]` block: its `env` is merged over the
+    /// global `env` and its `settings` over `[settings]`. Errors on an unknown
+    /// profile or a setting that doesn't fit.
+    pub fn with_profile(mut self, name: &str) -> Result<Self> {
+        let invalid = |message: String| YatrError::InvalidConfig { message };
+        let Some(profile) = self.profiles.get(name).cloned() else {
+            let mut names: Vec<_> = self.profiles.keys().map(String::as_str).collect();
+            names.sort_unstable();
+            return Err(invalid(if names.is_empty() {
+                format!("unknown profile '{name}' (the config defines no [profiles])")
+            } else {
+                format!("unknown profile '{name}' (available: {})", names.join(", "))
+            }));
+        };
+
+        self.env.extend(profile.env);
+        if !profile.settings.is_empty() {
+            let mut settings =
+                serde_json::to_value(&self.settings).map_err(|e| invalid(e.to_string()))?;
+            merge_json(&mut settings, serde_json::Value::Object(profile.settings));
+            self.settings = serde_json::from_value(settings)
+                .map_err(|e| invalid(format!("profile '{name}': invalid settings: {e}")))?;
+        }
+        Ok(self)
+    }
+
+    /// Set each `KEY=VALUE` of `--env` on every task, winning over both
+    /// global and task `env`. The values join the tasks' own `env`, so they
+    /// take part in cache keys like any other variable.
+    pub fn apply_env_overrides(&mut self, overrides: &[String]) -> Result<()> {
+        let mut env = HashMap::new();
+        for arg in overrides {
+            match arg.split_once('=') {
+                Some((key, value)) if !key.is_empty() => {
+                    env.insert(key.to_string(), value.to_string());
+                }
+                _ => {
+                    return Err(YatrError::InvalidTask {
+                        task: "--env".to_string(),
+                        reason: format!("invalid --env '{arg}' (expected KEY=VALUE)"),
+                    })
+                }
+            }
+        }
+        if env.is_empty() {
+            return Ok(());
+        }
+        self.env.extend(env.clone());
+        for task in self.tasks.values_mut() {
+            task.env.extend(env.clone());
+        }
+        Ok(())
+    }
+
     /// Merge environment variables for a task (global + task-specific)
     #[must_use]
     pub fn task_env(&self, task: &TaskConfig) -> HashMap<String, String> {
@@ -387,10 +1961,172 @@ impl Config {
     }
 }
 
+/// Replace each `${NAME}` in `text` (`NAME` a plain identifier) with
+/// `lookup(NAME)`, passing names it doesn't know, other than `YATR_*`, to
+/// `unknown` and leaving them as written.
+pub fn interpolate_vars(
+    text: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+    unknown: &mut impl FnMut(String),
+) -> String {
+    let is_ident = |name: &str| {
+        name.chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find('}') else {
+            break;
+        };
+        let name = &after[..end];
+        let token = &rest[start..=start + 2 + end];
+        out.push_str(&rest[..start]);
+        if !is_ident(name) {
+            out.push_str(token);
+        } else if let Some(value) = lookup(name) {
+            out.push_str(&value);
+        } else {
+            if !name.starts_with("YATR_") {
+                unknown(name.to_string());
+            }
+            out.push_str(token);
+        }
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Split a command's operands on unquoted spaces, keeping any quotes so a
+/// quoted path survives being joined back into one command.
+fn split_operands(rest: &str) -> Vec<&str> {
+    let mut operands = Vec::new();
+    let mut quote = None;
+    let mut start = None;
+    for (i, c) in rest.char_indices() {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            (' ', None) => {
+                if let Some(from) = start.take() {
+                    operands.push(&rest[from..i]);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        start.get_or_insert(i);
+    }
+    if let Some(from) = start {
+        operands.push(&rest[from..]);
+    }
+    operands
+}
+
+/// Merge `overlay` into `base`, recursing into objects; anything else in
+/// `overlay` replaces what `base` had.
+fn merge_json(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Whether `cmd` mentions `$key`, `${key}` (incl. `${key:-default}`) or `%key%`.
+fn references_env_var(cmd: &str, key: &str) -> bool {
+    let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let dollar = cmd.match_indices('$').any(|(i, _)| {
+        let after = &cmd[i + 1..];
+        after
+            .strip_prefix('{')
+            .unwrap_or(after)
+            .strip_prefix(key)
+            .is_some_and(|rest| !rest.starts_with(is_ident))
+    });
+    dollar || cmd.contains(&format!("%{key}%"))
+}
+
+/// Whether an `outputs` pattern and a `sources` pattern can name the same
+/// files. A literal path overlaps a glob it matches, and a directory overlaps
+/// anything beneath it; two globs overlap when one's literal prefix (the part
+/// before the first wildcard) is a directory prefix of the other's. Negated
+/// (`!`) patterns never overlap.
+fn patterns_overlap(output: &str, source: &str) -> bool {
+    fn normalize(pattern: &str) -> Option<&str> {
+        (!pattern.starts_with('!')).then(|| pattern.trim_start_matches("./").trim_end_matches('/'))
+    }
+    fn literal_prefix(pattern: &str) -> &str {
+        pattern
+            .find(['*', '?', '[', '{'])
+            .map_or(pattern, |i| &pattern[..i])
+    }
+    fn contains(dir: &str, path: &str) -> bool {
+        path.strip_prefix(dir)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            || (dir.is_empty() && !path.is_empty())
+    }
+    fn matches(glob: &str, path: &str) -> bool {
+        globset::Glob::new(glob).is_ok_and(|g| g.compile_matcher().is_match(path))
+    }
+
+    let (Some(output), Some(source)) = (normalize(output), normalize(source)) else {
+        return false;
+    };
+    let (output_prefix, source_prefix) = (literal_prefix(output), literal_prefix(source));
+    match (output_prefix == output, source_prefix == source) {
+        (true, true) => contains(output, source) || contains(source, output),
+        (true, false) => matches(source, output) || contains(output, source_prefix),
+        (false, true) => matches(output, source) || contains(source, output_prefix),
+        (false, false) => {
+            let (a, b) = (
+                output_prefix.trim_end_matches('/'),
+                source_prefix.trim_end_matches('/'),
+            );
+            !a.is_empty() && !b.is_empty() && (contains(a, b) || contains(b, a))
+        }
+    }
+}
+
+/// When a parse fails on an unknown task field that is really the tail of a
+/// dotted header like `[tasks.test.unit]`, suggest quoting the task name:
+/// TOML reads that header as a `unit` table inside a task named `test`.
+fn dotted_task_name_hint(content: &str, error: &toml::de::Error) -> Option<String> {
+    let field = error
+        .message()
+        .strip_prefix("unknown field `")?
+        .split('`')
+        .next()?;
+    content.lines().find_map(|line| {
+        let header = line.trim().strip_prefix("[tasks.")?.strip_suffix(']')?;
+        let task = header.strip_suffix(field)?.strip_suffix('.')?;
+        (!task.is_empty() && !task.contains(['"', '\''])).then(|| {
+            format!("`[tasks.{header}]` is read as a table inside task '{task}'; quote task names containing dots: `[tasks.\"{header}\"]`")
+        })
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// The command lines of a `run` list
+    fn commands(run: &[RunCommand]) -> Vec<&str> {
+        run.iter().map(RunCommand::cmd).collect()
+    }
+
     #[test]
     fn test_parse_simple_config() {
         let toml = r#"
@@ -423,7 +2159,30 @@ mod tests {
         "#;
 
         let config: Config = toml::from_str(toml).unwrap();
-        assert!(config.tasks["lint"].parallel);
+        assert_eq!(config.tasks["lint"].parallel, Some(true));
+    }
+
+    #[test]
+    fn test_parse_run_command_with_stdin() {
+        let toml = r#"
+            [tasks.db]
+            run = ["createdb app", { cmd = "psql app", stdin = "select 1;" }]
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        config.validate().unwrap();
+        let run = &config.tasks["db"].run;
+        assert_eq!(run[0].cmd(), "createdb app");
+        assert_eq!(run[0].stdin(), None);
+        assert_eq!(run[1].cmd(), "psql app");
+        assert_eq!(run[1].stdin(), Some("select 1;"));
+
+        let typo = "[tasks.db]\nrun = [{ cmd = \"psql\", stdn = \"x\" }]\n";
+        assert!(toml::from_str::<Config>(typo).is_err());
+
+        let foreground =
+            "[tasks.db]\nforeground = true\nrun = [{ cmd = \"psql\", stdin = \"x\" }]\n";
+        let config: Config = toml::from_str(foreground).unwrap();
+        assert!(config.validate().is_err());
     }
 
     #[test]
@@ -453,6 +2212,192 @@ mod tests {
         assert_eq!(config.settings.watch_debounce_ms, 300);
     }
 
+    #[test]
+    fn test_parse_script_limits() {
+        let toml = r#"
+            [settings.script_limits]
+            max_operations = 500
+
+            [tasks.t]
+            script = "print(1);"
+        "#;
+
+        let config: Config = toml::from_str(toml).unwrap();
+        let limits = config.settings.script_limits;
+        assert_eq!(limits.max_operations, 500);
+        assert_eq!(
+            limits.max_expr_depth,
+            ScriptLimits::default().max_expr_depth
+        );
+    }
+
+    #[test]
+    fn test_validate_ssh_cwd() {
+        let parse =
+            |task: &str| -> Config { toml::from_str(&format!("[tasks.t]\n{task}")).unwrap() };
+
+        let ok = parse(
+            "cwd = \"ssh://ci@build:/srv/app\"\nshell = true\nrun = [\"make\"]\nssh = { port = 2222 }",
+        );
+        assert!(ok.validate().is_ok());
+        assert_eq!(ok.tasks["t"].ssh.as_ref().unwrap().port, Some(2222));
+
+        let no_shell = parse("cwd = \"ssh://build:/srv/app\"\nrun = [\"make\"]");
+        assert!(no_shell.validate().is_err());
+
+        let bad_url = parse("cwd = \"ssh://build\"\nshell = true\nrun = [\"make\"]");
+        assert!(bad_url.validate().is_err());
+    }
+
+    #[test]
+    fn test_task_name_warnings_and_strict_mode() {
+        let toml = r#"
+            [tasks."build:release"]
+            run = ["true"]
+            [tasks.ok-name_1]
+            run = ["true"]
+            [tasks.abcdefghijk]
+            run = ["true"]
+        "#;
+        let mut config: Config = toml::from_str(toml).unwrap();
+        config.settings.task_name_max_length = 10;
+
+        let warnings = config.warnings();
+        let tasks: Vec<&str> = warnings.iter().map(|w| w.task.as_str()).collect();
+        assert_eq!(tasks, vec!["abcdefghijk", "build:release", "build:release"]);
+        assert!(config.validate().is_ok());
+
+        config.settings.strict_task_names = true;
+        assert!(config.warnings().is_empty());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_task_names_sorted() {
+        let toml = r#"
+            [tasks.zeta]
+            run = ["true"]
+            [tasks.alpha]
+            run = ["true"]
+            [tasks.mid]
+            run = ["true"]
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.task_names_sorted(), vec!["alpha", "mid", "zeta"]);
+    }
+
+    #[test]
+    fn test_extends_inherits_parent_fields() {
+        let toml = r#"
+            [task_defaults]
+            timeout = 60
+
+            [tasks.base]
+            cwd = "crates/app"
+            env = { RUST_LOG = "info", MODE = "base" }
+            sources = ["src/**/*.rs"]
+            depends = ["fmt"]
+            run = ["cargo build"]
+
+            [tasks.mid]
+            extends = "base"
+            env = { MODE = "mid" }
+
+            [tasks.child]
+            extends = "mid"
+            desc = "only the description changes"
+            depends = ["lint", "fmt"]
+
+            [tasks.fmt]
+            run = ["cargo fmt"]
+            [tasks.lint]
+            run = ["cargo clippy"]
+        "#;
+        let mut stack = Vec::new();
+        let mut config =
+            Config::parse_with_includes(toml, Path::new("yatr.toml"), false, &mut stack).unwrap();
+        config.resolve_extends().unwrap();
+        config.validate().unwrap();
+
+        let child = &config.tasks["child"];
+        assert_eq!(child.desc.as_deref(), Some("only the description changes"));
+        assert_eq!(child.sources, ["src/**/*.rs"]);
+        assert_eq!(child.cwd, Some(PathBuf::from("crates/app")));
+        assert_eq!(child.run, config.tasks["base"].run);
+        assert_eq!(child.env["RUST_LOG"], "info");
+        assert_eq!(child.env["MODE"], "mid");
+        assert_eq!(child.depends, ["fmt", "lint"]);
+        assert_eq!(child.timeout, Some(60));
+
+        for (toml, reason) in [
+            (
+                "[tasks.a]\nextends = \"b\"\n[tasks.b]\nextends = \"a\"\n",
+                "extends cycle: a -> b -> a",
+            ),
+            ("[tasks.a]\nextends = \"nope\"\n", "unknown task 'nope'"),
+        ] {
+            let mut config =
+                Config::parse_with_includes(toml, Path::new("yatr.toml"), false, &mut stack)
+                    .unwrap();
+            let err = config.resolve_extends().unwrap_err();
+            let YatrError::InvalidTask { reason: got, .. } = err else {
+                panic!("expected InvalidTask, got {err}");
+            };
+            assert!(got.contains(reason), "{got}");
+        }
+    }
+
+    #[test]
+    fn test_extends_a_parent_from_an_include() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("shared.toml"),
+            r#"
+                [task_defaults]
+                no_cache = true
+
+                [tasks.base]
+                run = ["cargo test"]
+                allow_failure = true
+                parallel = true
+                timeout = 10
+            "#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("yatr.toml"),
+            r#"
+                include = ["shared.toml"]
+
+                [task_defaults]
+                timeout = 60
+
+                [tasks.child]
+                extends = "base"
+                allow_failure = false
+                parallel = false
+            "#,
+        )
+        .unwrap();
+
+        let (config, _) = Config::load(Some(&dir.path().join("yatr.toml"))).unwrap();
+        let child = &config.tasks["child"];
+        assert_eq!(commands(&child.run), ["cargo test"]);
+        assert_eq!(child.allow_failure, Some(false));
+        assert_eq!(child.parallel, Some(false));
+        assert_eq!(
+            child.no_cache,
+            Some(true),
+            "the parent's defaults come along"
+        );
+        assert_eq!(
+            child.timeout,
+            Some(10),
+            "the parent's field beats our default"
+        );
+        assert_eq!(config.tasks["base"].allow_failure, Some(true));
+    }
+
     #[test]
     fn test_include_merges_tasks() {
         let dir = tempfile::tempdir().unwrap();
@@ -473,20 +2418,154 @@ mod tests {
     }
 
     #[test]
-    fn test_include_duplicate_task_errors() {
+    fn test_task_defaults_fill_unset_fields() {
         let dir = tempfile::tempdir().unwrap();
         std::fs::write(
             dir.path().join("shared.toml"),
-            "[tasks.build]\nrun=[\"echo a\"]\n",
+            "[tasks.lint]\nrun=[\"echo lint\"]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("yatr.toml"),
+            r#"
+                include = ["shared.toml"]
+
+                [task_defaults]
+                env = { RUST_LOG = "info", PROFILE = "dev" }
+                sources = ["src/**/*.rs"]
+                timeout = 60
+                no_cache = true
+
+                [tasks.build]
+                run = ["cargo build"]
+
+                [tasks.release]
+                run = ["cargo build --release"]
+                env = { PROFILE = "release" }
+                sources = ["Cargo.toml"]
+                timeout = 600
+                no_cache = false
+            "#,
+        )
+        .unwrap();
+
+        let (config, _) = Config::load(Some(&dir.path().join("yatr.toml"))).unwrap();
+        let build = &config.tasks["build"];
+        assert_eq!(build.env["PROFILE"], "dev");
+        assert_eq!(build.sources, ["src/**/*.rs"]);
+        assert_eq!(build.timeout, Some(60));
+        assert_eq!(build.no_cache, Some(true));
+
+        let release = &config.tasks["release"];
+        assert_eq!(release.env["PROFILE"], "release");
+        assert_eq!(release.env["RUST_LOG"], "info");
+        assert_eq!(release.sources, ["Cargo.toml"]);
+        assert_eq!(release.timeout, Some(600));
+        assert_eq!(release.no_cache, Some(false));
+
+        // Included files don't inherit the root file's defaults.
+        let lint = &config.tasks["lint"];
+        assert!(lint.env.is_empty());
+        assert_eq!(lint.timeout, None);
+    }
+
+    #[test]
+    fn test_task_file_is_a_shared_template() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("tasks")).unwrap();
+        std::fs::write(
+            dir.path().join("tasks/cargo.toml"),
+            "run = [\"cargo build\"]\ntimeout = 60\nenv = { PROFILE = \"dev\" }\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("yatr.toml"),
+            r#"
+                [tasks.build]
+                task_file = "tasks/cargo.toml"
+
+                [tasks.release]
+                task_file = "tasks/cargo.toml"
+                run = ["cargo build --release"]
+                env = { PROFILE = "release" }
+            "#,
+        )
+        .unwrap();
+
+        let (config, _) = Config::load(Some(&dir.path().join("yatr.toml"))).unwrap();
+        let build = &config.tasks["build"];
+        assert_eq!(commands(&build.run), ["cargo build"]);
+        assert_eq!(build.env["PROFILE"], "dev");
+        assert_eq!(build.task_file, Some(dir.path().join("tasks/cargo.toml")));
+
+        let release = &config.tasks["release"];
+        assert_eq!(commands(&release.run), ["cargo build --release"]);
+        assert_eq!(release.env["PROFILE"], "release");
+        assert_eq!(release.timeout, Some(60));
+
+        std::fs::write(
+            dir.path().join("yatr.toml"),
+            "[tasks.build]\ntask_file = \"tasks/missing.toml\"\n",
+        )
+        .unwrap();
+        let err = Config::load(Some(&dir.path().join("yatr.toml"))).unwrap_err();
+        assert!(err.to_string().contains("tasks/missing.toml"), "{err}");
+    }
+
+    #[test]
+    fn test_dotted_task_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("yatr.toml");
+        std::fs::write(
+            &path,
+            "[tasks.\"test.unit\"]\nrun=[\"true\"]\n[tasks.ci]\ndepends=[\"test.unit\"]\n",
+        )
+        .unwrap();
+        let (config, _) = Config::load(Some(&path)).unwrap();
+        assert_eq!(config.task_names_sorted(), vec!["ci", "test.unit"]);
+        assert!(config.warnings().is_empty());
+
+        std::fs::write(&path, "[tasks.test.unit]\nrun=[\"true\"]\n").unwrap();
+        let Err(YatrError::ConfigParse { help, .. }) = Config::load(Some(&path)) else {
+            panic!("expected a parse error");
+        };
+        assert!(help.unwrap().contains(r#"[tasks."test.unit"]"#));
+    }
+
+    #[test]
+    fn test_include_duplicate_tasks_merge() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("first.toml"),
+            "[env]\nLEVEL=\"first\"\nONLY_FIRST=\"1\"\n\
+             [tasks.gen]\nrun=[\"echo gen\"]\n\
+             [tasks.build]\ndesc=\"first\"\nrun=[\"echo first\"]\ndepends=[\"gen\"]\n\
+             env={A=\"1\", B=\"first\"}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("second.toml"),
+            "[env]\nLEVEL=\"second\"\n\
+             [tasks.lint]\nrun=[\"echo lint\"]\n\
+             [tasks.build]\ndesc=\"second\"\ndepends=[\"lint\"]\nenv={B=\"second\"}\n",
         )
         .unwrap();
         std::fs::write(
             dir.path().join("yatr.toml"),
-            "include=[\"shared.toml\"]\n[tasks.build]\nrun=[\"echo b\"]\n",
+            "include=[\"first.toml\", \"second.toml\"]\n[tasks.build]\nrun=[\"echo root\"]\n",
         )
         .unwrap();
 
-        assert!(Config::load(Some(&dir.path().join("yatr.toml"))).is_err());
+        let (config, _) = Config::load(Some(&dir.path().join("yatr.toml"))).unwrap();
+        assert_eq!(config.env["LEVEL"], "second");
+        assert_eq!(config.env["ONLY_FIRST"], "1");
+
+        let build = &config.tasks["build"];
+        assert_eq!(build.run, vec![RunCommand::from("echo root")]);
+        assert_eq!(build.desc.as_deref(), Some("second"));
+        assert_eq!(build.depends, vec!["gen", "lint"]);
+        assert_eq!(build.env["A"], "1");
+        assert_eq!(build.env["B"], "second");
     }
 
     #[test]
@@ -503,6 +2582,576 @@ mod tests {
         )
         .unwrap();
 
-        assert!(Config::load(Some(&dir.path().join("a.toml"))).is_err());
+        let err = Config::load(Some(&dir.path().join("a.toml"))).unwrap_err();
+        let YatrError::ConfigParse { source, .. } = err else {
+            panic!("expected ConfigParse, got {err:?}");
+        };
+        let message = source.to_string();
+        assert!(message.contains("include cycle"), "{message}");
+        assert!(
+            message.contains("a.toml -> ") && message.contains("b.toml -> "),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn test_include_chains_and_shared_includes() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("ci")).unwrap();
+        std::fs::write(
+            dir.path().join("yatr.toml"),
+            "include=[\"ci/yatr.toml\", \"lint.toml\"]\n[env]\nLEVEL=\"root\"\n",
+        )
+        .unwrap();
+        // Nested includes resolve relative to the file that names them.
+        std::fs::write(
+            dir.path().join("ci/yatr.toml"),
+            "include=[\"../common.toml\", \"../env.toml\"]\n[env]\nLEVEL=\"ci\"\n[tasks.ci]\nrun=[\"echo ci\"]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("common.toml"),
+            "[env]\nCOMMON=\"1\"\n[tasks.fmt]\nrun=[\"echo fmt\"]\n",
+        )
+        .unwrap();
+        // Including the same file from two branches isn't a cycle.
+        std::fs::write(dir.path().join("lint.toml"), "include=[\"env.toml\"]\n").unwrap();
+        std::fs::write(dir.path().join("env.toml"), "[env]\nSHARED=\"1\"\n").unwrap();
+
+        let (config, _) = Config::load(Some(&dir.path().join("yatr.toml"))).unwrap();
+        assert_eq!(config.task_names_sorted(), vec!["ci", "fmt"]);
+        assert_eq!(config.env["LEVEL"], "root");
+        assert_eq!(config.env["COMMON"], "1");
+        assert_eq!(config.env["SHARED"], "1");
+    }
+
+    #[test]
+    fn test_task_args() {
+        let mut config: Config = toml::from_str(
+            r#"
+[tasks.test]
+args = { filter = "", jobs = "4" }
+run = ["cargo test ${args.filter} -j ${args.jobs}"]
+"#,
+        )
+        .unwrap();
+        config.validate().unwrap();
+
+        let targets = vec!["test".to_string()];
+        let tokens = ["--filter", "my_test", "--jobs=2"].map(String::from);
+        config.set_task_args(&targets, &tokens).unwrap();
+        let task = &config.tasks["test"];
+        assert_eq!(
+            task.expand_args(task.run[0].cmd()).unwrap(),
+            "cargo test my_test -j 2"
+        );
+        assert_eq!(task.expand_args("${args.nope}"), Err("nope".to_string()));
+
+        assert!(config
+            .set_task_args(&targets, &["--unknown".into(), "x".into()])
+            .is_err());
+        assert!(config
+            .set_task_args(&targets, &["--filter".into()])
+            .is_err());
+    }
+
+    #[test]
+    fn test_undeclared_arg_reference_fails_validation() {
+        let config: Config =
+            toml::from_str("[tasks.t]\nrun = [\"echo ${args.missing}\"]\n").unwrap();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_load_yaml_config() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("shared.toml"),
+            "[tasks.fmt]\nrun = [\"cargo fmt\"]\n",
+        )
+        .unwrap();
+        let yaml = r"
+include: [shared.toml]
+env:
+  RUST_LOG: debug
+settings:
+  cache: false
+tasks:
+  build:
+    desc: Build it
+    depends: [fmt]
+    run:
+      - cargo build
+";
+        std::fs::write(dir.path().join("yatr.yaml"), yaml).unwrap();
+
+        let (config, _) = Config::load(Some(&dir.path().join("yatr.yaml"))).unwrap();
+        assert_eq!(config.tasks["build"].depends, ["fmt"]);
+        assert_eq!(commands(&config.tasks["fmt"].run), ["cargo fmt"]);
+        assert_eq!(config.env["RUST_LOG"], "debug");
+        assert!(!config.settings.cache);
+
+        // `load_yaml` parses YAML whatever the extension.
+        std::fs::write(dir.path().join("ci.conf"), yaml).unwrap();
+        let (config, _) = Config::load_yaml(&dir.path().join("ci.conf")).unwrap();
+        assert_eq!(config.tasks.len(), 2);
+
+        std::fs::write(dir.path().join("bad.yml"), "tasks: [oops").unwrap();
+        assert!(matches!(
+            Config::load(Some(&dir.path().join("bad.yml"))),
+            Err(YatrError::ConfigParseYaml { .. })
+        ));
+    }
+
+    #[test]
+    fn test_from_package_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("package.json");
+        std::fs::write(
+            &path,
+            r#"{
+                "name": "web",
+                "scripts": {
+                    "test": "vitest",
+                    "pretest": "tsc --noEmit",
+                    "build": "tsc && vite build",
+                    "odd": 42
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let config = Config::from_package_json(&path).unwrap();
+        let mut names: Vec<&str> = config.tasks.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        assert_eq!(names, ["build", "pretest", "test"]);
+        assert_eq!(commands(&config.tasks["test"].run), ["npx vitest"]);
+        assert_eq!(config.tasks["test"].depends, ["pretest"]);
+        assert_eq!(
+            commands(&config.tasks["build"].run),
+            ["npm run --ignore-scripts build"]
+        );
+
+        std::fs::write(&path, r#"{"name": "web"}"#).unwrap();
+        assert!(Config::from_package_json(&path).is_err());
+    }
+
+    #[test]
+    fn test_validate_scripts() {
+        let config: Config = toml::from_str(
+            r#"
+[tasks.good]
+script = "let x = 1; print(x);"
+
+[tasks.broken]
+script = "let x = ;"
+
+[tasks.templated]
+script = "print(\"${args.name}\");"
+
+[tasks.cmd]
+run = ["echo hi"]
+"#,
+        )
+        .unwrap();
+
+        let results = config.validate_scripts();
+        let tasks: Vec<&str> = results.iter().map(|r| r.task.as_str()).collect();
+        assert_eq!(tasks, ["broken", "good", "templated"]);
+        assert!(!results[0].valid);
+        assert!(results[0].error.is_some());
+        assert!(results[1].valid && results[1].warnings.is_empty());
+        assert!(results[2].valid);
+        assert_eq!(results[2].warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_lint_unused_task_env() {
+        let config: Config = toml::from_str(
+            r#"
+[task_defaults]
+env = { RUST_LOG = "info" }
+
+[tasks.deploy]
+env = { API_KEY = "k", API_KEY_ID = "i", REGION = "eu", STAGE = "prod", HOST = "h" }
+run = ["deploy --key ${API_KEY_ID} --region ${REGION:-us} --stage=${STAGE}", "ping %HOST%"]
+
+[tasks.scripted]
+env = { UNUSED = "x" }
+script = "print(1);"
+"#,
+        )
+        .unwrap();
+
+        let lint = config.lint();
+        assert_eq!(lint.len(), 1, "{lint:?}");
+        assert_eq!(lint[0].task, "deploy");
+        assert!(lint[0].message.contains("'API_KEY'"));
+
+        assert!(references_env_var("echo $A;", "A"));
+        assert!(!references_env_var("echo $AB", "A"));
+        assert!(!references_env_var("echo A", "A"));
+        assert!(references_env_var("echo ${A-x}", "A"));
+        assert!(!references_env_var("echo ${AB}", "A"));
+    }
+
+    #[test]
+    fn test_lint_counts_interpolated_env() {
+        let mut config: Config = toml::from_str(
+            r#"
+[tasks.build]
+env = { TARGET = "x86_64", UNUSED = "x" }
+run = ["cargo build --target ${TARGET}"]
+"#,
+        )
+        .unwrap();
+        config.interpolate().unwrap();
+        assert_eq!(
+            config.tasks["build"].run[0].cmd(),
+            "cargo build --target x86_64"
+        );
+
+        let lint = config.lint();
+        assert_eq!(lint.len(), 1, "{lint:?}");
+        assert!(lint[0].message.contains("'UNUSED'"));
+    }
+
+    #[test]
+    fn test_validate_implicit_deps() {
+        let config: Config = toml::from_str(
+            r#"
+[tasks.gen]
+run = ["gen"]
+outputs = ["src/generated/schema.rs"]
+
+[tasks.build]
+run = ["cargo build"]
+sources = ["src/**/*.rs"]
+outputs = ["target/release/app"]
+
+[tasks.package]
+depends = ["build"]
+run = ["tar czf app.tgz target/release/app"]
+sources = ["target/release/app"]
+
+[tasks.bundle]
+depends = ["package"]
+run = ["bundle"]
+sources = ["target/release"]
+"#,
+        )
+        .unwrap();
+
+        let warnings = config.validate_implicit_deps();
+        assert_eq!(warnings.len(), 1, "{warnings:?}");
+        assert_eq!(warnings[0].consumer, "build");
+        assert_eq!(warnings[0].producer, "gen");
+        assert_eq!(warnings[0].source, "src/**/*.rs");
+
+        assert!(patterns_overlap("dist", "dist/**/*.js"));
+        assert!(patterns_overlap("./dist/", "dist/app.js"));
+        assert!(patterns_overlap("target/**", "target/debug/*.d"));
+        assert!(!patterns_overlap("dist", "distribution/**"));
+        assert!(!patterns_overlap("src/gen.txt", "src/**/*.rs"));
+        assert!(!patterns_overlap("**/*.o", "src/**/*.c"));
+        assert!(!patterns_overlap("out", "!out/**"));
+    }
+
+    #[test]
+    fn test_resolve_command_alias() {
+        let config: Config = toml::from_str(
+            r#"
+[command_aliases]
+"rm -rf" = { unix = "rm -rfv", windows = "cmd /C rmdir /S /Q" }
+"rm" = { unix = "echo rm" }
+"open" = { windows = "cmd /C start" }
+"#,
+        )
+        .unwrap();
+        let unix = |cmd| config.resolve_command_alias(cmd, "linux");
+        let windows = |cmd| config.resolve_command_alias(cmd, "windows");
+
+        assert_eq!(unix("rm -rf dist"), "rm -rfv dist");
+        assert_eq!(unix("rm -f a"), "echo rm -f a");
+        assert_eq!(unix("rm"), "echo rm");
+        assert_eq!(unix("rmdir x"), "rmdir x");
+        assert_eq!(unix("open x"), "open x");
+        // The Windows built-ins stay out of the way on Unix.
+        assert_eq!(unix("mkdir -p a/b"), "mkdir -p a/b");
+
+        assert_eq!(windows("rm -rf dist"), "cmd /C rmdir /S /Q dist");
+        assert_eq!(windows("rm -f a"), "rm -f a");
+        assert_eq!(windows("open x"), "cmd /C start x");
+        // Built-ins take every operand, not just the first.
+        assert_eq!(
+            windows("mkdir -p a \"b c\"  d"),
+            "powershell -NoProfile -Command New-Item -ItemType Directory -Force -Path a,\"b c\",d"
+        );
+        // ...except `cp -r`'s last, which is the destination.
+        assert_eq!(
+            windows("cp -r src dist"),
+            "powershell -NoProfile -Command Copy-Item -Recurse -Force -Path src -Destination dist"
+        );
+        assert_eq!(
+            windows("cp -r a b out"),
+            "powershell -NoProfile -Command Copy-Item -Recurse -Force -Path a,b -Destination out"
+        );
+        assert_eq!(windows("touch a b"), "cmd /C type nul >> a & type nul >> b");
+    }
+
+    #[test]
+    fn test_apply_env_tasks() {
+        let toml = r#"
+            [task_defaults]
+            timeout = 60
+            [tasks.build-release]
+            desc = "from the file"
+            run = ["cargo build --release"]
+            [tasks.test]
+            run = ["cargo test"]
+        "#;
+        let mut config: Config = toml::from_str(toml).unwrap();
+        let vars = [
+            ("YATR_TASKS_BUILD_RELEASE", r#"["make release"]"#),
+            ("YATR_TASKS_BUILD_RELEASE_DEPENDS", r#"["test"]"#),
+            ("YATR_TASKS_LINT", r#"["cargo clippy"]"#),
+            ("YATR_TASKS_TEST_DEPENDS", r#"["lint"]"#),
+            ("YATR_CONFIG_SEARCH_LIMIT", "3"),
+        ]
+        .map(|(k, v)| (k.to_string(), v.to_string()));
+        config.apply_env_tasks(vars).unwrap();
+        config.validate().unwrap();
+
+        let release = &config.tasks["build-release"];
+        assert_eq!(commands(&release.run), ["make release"]);
+        assert_eq!(release.depends, ["test"]);
+        assert_eq!(release.desc, None);
+        assert_eq!(release.timeout, Some(60));
+        assert_eq!(commands(&config.tasks["lint"].run), ["cargo clippy"]);
+        assert_eq!(commands(&config.tasks["test"].run), ["cargo test"]);
+        assert_eq!(config.tasks["test"].depends, ["lint"]);
+
+        let bad = [("YATR_TASKS_X".to_string(), "cargo build".to_string())];
+        let err = config.apply_env_tasks(bad).unwrap_err();
+        assert!(err.to_string().contains("YATR_TASKS_X"), "{err}");
+
+        let orphan = [(
+            "YATR_TASKS_DOCS_DEPENDS".to_string(),
+            r#"["test"]"#.to_string(),
+        )];
+        let err = config.apply_env_tasks(orphan).unwrap_err();
+        assert!(matches!(err, YatrError::InvalidConfig { .. }), "{err}");
+        assert!(err.to_string().contains("task 'docs'"), "{err}");
+        assert!(!config.tasks.contains_key("docs"));
+    }
+
+    #[test]
+    fn test_tasks_tagged() {
+        let config: Config = toml::from_str(
+            r#"
+                [tasks.test]
+                tags = ["ci"]
+                [tasks.lint]
+                tags = ["ci", "fast"]
+                [tasks.publish]
+                tags = ["release"]
+                [tasks.dev]
+            "#,
+        )
+        .unwrap();
+        let tags = |tags: &[&str]| tags.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        assert_eq!(config.tasks_tagged(&tags(&["ci"])), ["lint", "test"]);
+        assert_eq!(
+            config.tasks_tagged(&tags(&["fast", "release"])),
+            ["lint", "publish"]
+        );
+        assert!(config.tasks_tagged(&tags(&["nightly"])).is_empty());
+        assert!(config.tasks_tagged(&[]).is_empty());
+        assert!(!config.tasks["dev"].has_any_tag(&tags(&["ci"])));
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let toml = r#"
+            [env]
+            CARGO_TERM_COLOR = "always"
+            TARGET = "x86_64-unknown-linux-gnu"
+            [tasks.build]
+            desc = "Build for ${TARGET}"
+            cwd = "target/${TARGET}"
+            env = { OUT = "dist/${TARGET}", TARGET = "${TARGET}-musl" }
+            run = [
+                "cargo build --color ${CARGO_TERM_COLOR} --target ${TARGET}",
+                "cp app ${OUT} ${args.dir} ${HOME:-/root} $CARGO_TERM_COLOR ${YATR_RUN_ID}",
+                "echo ${UNSET_BY_ANYONE}",
+            ]
+            args = { dir = "." }
+        "#;
+        let mut config: Config = toml::from_str(toml).unwrap();
+        config.interpolate().unwrap();
+
+        let build = &config.tasks["build"];
+        assert_eq!(
+            build.run[0].cmd(),
+            "cargo build --color always --target x86_64-unknown-linux-gnu-musl"
+        );
+        assert_eq!(
+            build.run[1].cmd(),
+            "cp app dist/x86_64-unknown-linux-gnu ${args.dir} ${HOME:-/root} $CARGO_TERM_COLOR ${YATR_RUN_ID}"
+        );
+        assert_eq!(build.run[2].cmd(), "echo ${UNSET_BY_ANYONE}");
+        assert_eq!(build.env["TARGET"], "x86_64-unknown-linux-gnu-musl");
+        assert_eq!(
+            build.desc.as_deref(),
+            Some("Build for x86_64-unknown-linux-gnu-musl")
+        );
+        assert_eq!(
+            build.cwd,
+            Some(PathBuf::from("target/x86_64-unknown-linux-gnu-musl"))
+        );
+
+        let mut strict: Config = toml::from_str(toml).unwrap();
+        strict.settings.strict_interpolation = true;
+        let err = strict.interpolate().unwrap_err();
+        let YatrError::InvalidTask { task, reason } = err else {
+            panic!("expected InvalidTask, got {err}");
+        };
+        assert_eq!(task, "build");
+        assert!(reason.contains("UNSET_BY_ANYONE"), "{reason}");
+    }
+
+    #[test]
+    fn test_interpolate_leaves_host_variables_to_the_shell() {
+        std::env::set_var("INTERPOLATE_TEST_HOST_SECRET", "supersecret");
+        let mut config: Config = toml::from_str(
+            r#"
+            [tasks.deploy]
+            desc = "Deploy as ${INTERPOLATE_TEST_HOST_SECRET}"
+            env = { AUTH = "${INTERPOLATE_TEST_HOST_SECRET}" }
+            run = ["curl -H \"Authorization: ${INTERPOLATE_TEST_HOST_SECRET}\" example.com"]
+            "#,
+        )
+        .unwrap();
+        config.settings.strict_interpolation = true;
+        config.interpolate().unwrap();
+
+        let deploy = &config.tasks["deploy"];
+        assert_eq!(
+            deploy.run[0].cmd(),
+            "curl -H \"Authorization: ${INTERPOLATE_TEST_HOST_SECRET}\" example.com"
+        );
+        // `desc` and `env` values never reach a printed plan or a command
+        // line, so they may use the host.
+        assert_eq!(deploy.desc.as_deref(), Some("Deploy as supersecret"));
+        assert_eq!(deploy.env["AUTH"], "supersecret");
+    }
+
+    #[test]
+    fn test_with_profile() {
+        let toml = r#"
+            [env]
+            RUST_TEST_THREADS = "4"
+            RUST_LOG = "info"
+            [settings]
+            parallelism = 8
+            [profiles.ci]
+            env = { RUST_TEST_THREADS = "1" }
+            settings = { parallelism = 2, script_limits = { max_operations = 10 } }
+            [tasks.test]
+            run = ["cargo test"]
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let limits = config.settings.script_limits;
+
+        let ci = config.clone().with_profile("ci").unwrap();
+        assert_eq!(ci.env["RUST_TEST_THREADS"], "1");
+        assert_eq!(ci.env["RUST_LOG"], "info");
+        assert_eq!(ci.settings.parallelism, 2);
+        assert_eq!(ci.settings.script_limits.max_operations, 10);
+        assert_eq!(
+            ci.settings.script_limits.max_string_size,
+            limits.max_string_size
+        );
+
+        let err = config.with_profile("release").unwrap_err();
+        assert!(err.to_string().contains("available: ci"), "{err}");
+    }
+
+    #[test]
+    fn test_apply_env_overrides() {
+        let toml = r#"
+            [env]
+            MODE = "global"
+            LOG = "info"
+            [tasks.build]
+            run = ["cargo build"]
+            env = { MODE = "task" }
+        "#;
+        let mut config: Config = toml::from_str(toml).unwrap();
+        config
+            .apply_env_overrides(&["MODE=cli".to_string(), "URL=a=b".to_string()])
+            .unwrap();
+
+        let env = config.task_env(&config.tasks["build"]);
+        assert_eq!(env["MODE"], "cli");
+        assert_eq!(env["URL"], "a=b");
+        assert_eq!(env["LOG"], "info");
+
+        for bad in ["MODE", "=cli"] {
+            let err = config.apply_env_overrides(&[bad.to_string()]).unwrap_err();
+            assert!(matches!(err, YatrError::InvalidTask { .. }), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_find_config_stops_at_search_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("yatr.toml"), "").unwrap();
+        let nested = dir.path().join("a/b/c");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        let found = Config::find_config_from(nested.clone(), 3).unwrap();
+        assert_eq!(found, dir.path().join("yatr.toml"));
+
+        let Err(YatrError::ConfigNotFound { searched }) = Config::find_config_from(nested, 2)
+        else {
+            panic!("expected ConfigNotFound");
+        };
+        assert_eq!(searched.len(), 3 * CONFIG_FILES.len());
+        assert!(searched.last().unwrap().starts_with(dir.path().join("a")));
+    }
+
+    #[test]
+    fn test_find_config_stops_at_boundaries() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("yatr.toml"), "").unwrap();
+        let member = dir.path().join("member");
+        let nested = member.join("src");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        // A `.yatrignore` directory is searched, its parents are not.
+        std::fs::write(member.join(SEARCH_BOUNDARY_FILE), "").unwrap();
+        let Err(YatrError::ConfigNotFound { searched }) =
+            Config::find_config_from(nested.clone(), 10)
+        else {
+            panic!("expected ConfigNotFound");
+        };
+        assert_eq!(searched.len(), 2 * CONFIG_FILES.len());
+        std::fs::write(member.join("yatr.toml"), "").unwrap();
+        let found = Config::find_config_from(nested.clone(), 10).unwrap();
+        assert_eq!(found, member.join("yatr.toml"));
+
+        // `stop_at_root` hides a config from its subdirectories only.
+        std::fs::remove_file(member.join(SEARCH_BOUNDARY_FILE)).unwrap();
+        std::fs::remove_file(member.join("yatr.toml")).unwrap();
+        std::fs::write(
+            dir.path().join("yatr.toml"),
+            "[settings]\nstop_at_root = true\n",
+        )
+        .unwrap();
+        assert!(Config::find_config_from(nested, 10).is_err());
+        let found = Config::find_config_from(dir.path().to_path_buf(), 10).unwrap();
+        assert_eq!(found, dir.path().join("yatr.toml"));
     }
 }
@@ -0,0 +1,204 @@
+//! Output diffing for `yatr diff`.
+//!
+//! Compares a task's last cached stdout against the output of a fresh run,
+//! line by line. Rendering is split from the CLI so each format can be tested
+//! without a terminal: the unified and side-by-side views come back as
+//! (optionally coloured) text, the stats and JSON views as plain data.
+
+use std::fmt::Write;
+
+use console::style;
+use similar::{ChangeTag, TextDiff};
+
+/// Line counts for a diff.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiffStats {
+    pub inserted: usize,
+    pub deleted: usize,
+    pub unchanged: usize,
+}
+
+impl DiffStats {
+    /// Whether the two outputs are identical.
+    #[must_use]
+    pub const fn is_unchanged(&self) -> bool {
+        self.inserted == 0 && self.deleted == 0
+    }
+}
+
+/// Count inserted, deleted, and unchanged lines between `old` and `new`.
+#[must_use]
+pub fn stats(old: &str, new: &str) -> DiffStats {
+    let diff = TextDiff::from_lines(old, new);
+    let mut stats = DiffStats::default();
+    for change in diff.iter_all_changes() {
+        match change.tag() {
+            ChangeTag::Insert => stats.inserted += 1,
+            ChangeTag::Delete => stats.deleted += 1,
+            ChangeTag::Equal => stats.unchanged += 1,
+        }
+    }
+    stats
+}
+
+/// Render a coloured unified diff (`-` cached, `+` current) with three lines
+/// of context around each hunk.
+#[must_use]
+pub fn unified(old: &str, new: &str) -> String {
+    let diff = TextDiff::from_lines(old, new);
+    let mut out = String::new();
+    for (i, group) in diff.grouped_ops(3).iter().enumerate() {
+        if i > 0 {
+            let _ = writeln!(out, "{}", style("···").dim());
+        }
+        for op in group {
+            for change in diff.iter_changes(op) {
+                let line = change.to_string_lossy();
+                let line = line.trim_end_matches('\n');
+                let rendered = match change.tag() {
+                    ChangeTag::Delete => style(format!("-{line}")).red().to_string(),
+                    ChangeTag::Insert => style(format!("+{line}")).green().to_string(),
+                    ChangeTag::Equal => format!(" {line}"),
+                };
+                out.push_str(&rendered);
+                out.push('\n');
+            }
+        }
+    }
+    out
+}
+
+/// Render a two-column diff, cached output on the left and current output on
+/// the right, each column `width` characters wide.
+#[must_use]
+pub fn side_by_side(old: &str, new: &str, width: usize) -> String {
+    let diff = TextDiff::from_lines(old, new);
+    let mut out = String::new();
+
+    for op in diff.ops() {
+        let mut left: Vec<String> = Vec::new();
+        let mut right: Vec<String> = Vec::new();
+        let mut equal = true;
+        for change in diff.iter_changes(op) {
+            let line = change.to_string_lossy().trim_end_matches('\n').to_string();
+            match change.tag() {
+                ChangeTag::Equal => {
+                    left.push(line.clone());
+                    right.push(line);
+                }
+                ChangeTag::Delete => {
+                    equal = false;
+                    left.push(line);
+                }
+                ChangeTag::Insert => {
+                    equal = false;
+                    right.push(line);
+                }
+            }
+        }
+
+        for i in 0..left.len().max(right.len()) {
+            let l = fit(left.get(i).map_or("", String::as_str), width);
+            let r = fit(right.get(i).map_or("", String::as_str), width);
+            if equal {
+                let _ = writeln!(out, "{l} │ {r}");
+            } else {
+                let marker = match (i < left.len(), i < right.len()) {
+                    (true, true) => "~",
+                    (true, false) => "<",
+                    _ => ">",
+                };
+                let _ = writeln!(
+                    out,
+                    "{}{}{}",
+                    style(l).red(),
+                    style(format!(" {marker} ")).yellow(),
+                    style(r).green()
+                );
+            }
+        }
+    }
+    out
+}
+
+/// Structured description of the diff, for tooling.
+#[must_use]
+pub fn to_json(old: &str, new: &str) -> serde_json::Value {
+    let diff = TextDiff::from_lines(old, new);
+    let changes: Vec<_> = diff
+        .iter_all_changes()
+        .filter(|c| c.tag() != ChangeTag::Equal)
+        .map(|c| {
+            serde_json::json!({
+                "tag": match c.tag() {
+                    ChangeTag::Insert => "insert",
+                    ChangeTag::Delete => "delete",
+                    ChangeTag::Equal => "equal",
+                },
+                "old_line": c.old_index().map(|i| i + 1),
+                "new_line": c.new_index().map(|i| i + 1),
+                "value": c.to_string_lossy().trim_end_matches('\n'),
+            })
+        })
+        .collect();
+    let s = stats(old, new);
+    serde_json::json!({
+        "changes": changes,
+        "stats": {
+            "inserted": s.inserted,
+            "deleted": s.deleted,
+            "unchanged": s.unchanged,
+        },
+    })
+}
+
+/// Pad or truncate `s` to exactly `width` characters.
+fn fit(s: &str, width: usize) -> String {
+    let count = s.chars().count();
+    if count > width {
+        let mut t: String = s.chars().take(width.saturating_sub(1)).collect();
+        t.push('…');
+        t
+    } else {
+        format!("{s}{}", " ".repeat(width - count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_counts_lines() {
+        let s = stats("a\nb\nc\n", "a\nB\nc\nd\n");
+        assert_eq!(
+            s,
+            DiffStats {
+                inserted: 2,
+                deleted: 1,
+                unchanged: 2,
+            }
+        );
+        assert!(stats("x\n", "x\n").is_unchanged());
+    }
+
+    #[test]
+    fn test_unified_marks_changes() {
+        console::set_colors_enabled(false);
+        let out = unified("a\nb\n", "a\nc\n");
+        assert!(out.contains("-b"));
+        assert!(out.contains("+c"));
+        assert!(out.contains(" a"));
+    }
+
+    #[test]
+    fn test_json_reports_line_numbers() {
+        let json = to_json("a\nb\n", "a\nc\n");
+        let changes = json["changes"].as_array().unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0]["tag"], "delete");
+        assert_eq!(changes[0]["old_line"], 2);
+        assert_eq!(changes[1]["tag"], "insert");
+        assert_eq!(changes[1]["new_line"], 2);
+    }
+}
@@ -3,6 +3,7 @@
 //! Uses `miette` for pretty error reporting with source spans and help text.
 
 use miette::Diagnostic;
+use std::fmt::Write as _;
 use std::path::PathBuf;
 use thiserror::Error;
 
@@ -20,7 +21,17 @@ pub enum YatrError {
     #[diagnostic(code(yatr::config::parse))]
     ConfigParse {
         #[source]
-        source: toml::de::Error,
+        source: Box<toml::de::Error>,
+        path: PathBuf,
+        #[help]
+        help: Option<String>,
+    },
+
+    #[error("Failed to parse configuration")]
+    #[diagnostic(code(yatr::config::parse))]
+    ConfigParseYaml {
+        #[source]
+        source: serde_yaml::Error,
         path: PathBuf,
     },
 
@@ -34,6 +45,14 @@ pub enum YatrError {
         available: Vec<String>,
     },
 
+    #[error("{} errors found", .errors.len())]
+    #[diagnostic(code(yatr::multiple))]
+    MultipleErrors {
+        /// The independent errors, each reported in full
+        #[related]
+        errors: Vec<Self>,
+    },
+
     #[error("Circular dependency detected: {cycle}")]
     #[diagnostic(
         code(yatr::task::cycle),
@@ -41,21 +60,38 @@ pub enum YatrError {
     )]
     CyclicDependency { cycle: String },
 
-    #[error("Task '{task}' failed with exit code {code}")]
+    #[error(
+        "Task '{task}' failed with exit code {code}{context}",
+        context = failure_context(.command.as_deref(), *.group_index)
+    )]
     #[diagnostic(code(yatr::exec::failed))]
     TaskFailed {
         task: String,
         code: i32,
+        /// The `run` command that failed, when the failure is a single command's
+        command: Option<String>,
+        /// Position of the failing command in `run` for a `parallel` task
+        /// (shown 1-based as its stage)
+        group_index: Option<usize>,
         #[help]
         stderr: Option<String>,
     },
 
-    #[error("Command not found: {command}")]
+    #[error("Task '{task}' timed out after {seconds}s")]
     #[diagnostic(
-        code(yatr::exec::command_not_found),
-        help("Ensure the command is installed and in your PATH")
+        code(yatr::exec::timeout),
+        help("Raise the task's `timeout` if it legitimately needs longer")
     )]
-    CommandNotFound { command: String },
+    TaskTimeout { task: String, seconds: u64 },
+
+    #[error("Command not found: {command}")]
+    #[diagnostic(code(yatr::exec::command_not_found))]
+    CommandNotFound {
+        command: String,
+        /// The `PATH` that was searched, and where else the command exists
+        #[help]
+        help: String,
+    },
 
     #[error("Script execution failed in task '{task}'")]
     #[diagnostic(code(yatr::script::failed))]
@@ -85,6 +121,10 @@ pub enum YatrError {
     #[diagnostic(code(yatr::affected))]
     Affected { message: String },
 
+    #[error("Interrupted after {completed} of {total} tasks completed")]
+    #[diagnostic(code(yatr::interrupted))]
+    Interrupted { completed: usize, total: usize },
+
     #[error("I/O error")]
     #[diagnostic(code(yatr::io))]
     Io(#[from] std::io::Error),
@@ -101,5 +141,30 @@ pub enum YatrError {
     },
 }
 
+impl YatrError {
+    /// Process exit status for this error: 130 (128 + SIGINT) for
+    /// [`YatrError::Interrupted`], 1 otherwise.
+    #[must_use]
+    pub const fn exit_code(&self) -> u8 {
+        match self {
+            Self::Interrupted { .. } => 130,
+            _ => 1,
+        }
+    }
+}
+
+/// The lines [`YatrError::TaskFailed`] adds after its message: the failing
+/// command and, for parallel commands, its stage.
+fn failure_context(command: Option<&str>, group_index: Option<usize>) -> String {
+    let mut context = String::new();
+    if let Some(command) = command {
+        let _ = write!(context, "\nCommand: `{command}`");
+    }
+    if let Some(index) = group_index {
+        let _ = write!(context, "\nStage: {}", index + 1);
+    }
+    context
+}
+
 /// Result type alias for YATR operations
 pub type Result<T> = std::result::Result<T, YatrError>;
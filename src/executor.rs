@@ -4,25 +4,59 @@
 //! with the cache and scripting systems.
 
 use std::collections::{HashMap, HashSet};
-use std::path::Path;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 use console::style;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
 use tokio::process::Command;
-use tokio::sync::Semaphore;
+use tokio::sync::{Notify, Semaphore};
 use tokio::task::JoinSet;
 
-use crate::cache::Cache;
-use crate::config::Config;
+use crate::cache::{Cache, CacheStats};
+use crate::config::{interpolate_vars, Config, ConfigWarning, TaskConfig};
 use crate::error::{Result, YatrError};
 use crate::graph::{ExecutionPlan, TaskGraph, TaskNode};
-use crate::script::ScriptEngine;
+use crate::last_run::LastRun;
+use crate::report::{self, ReportFormat};
+use crate::script::{ScriptContext, ScriptEngine, ScriptLimits};
+use crate::timing_history::{TimingHistory, TimingStats};
+
+/// Variables removed from every child environment when
+/// [`ExecutorConfig::sanitize_env`] is on: each can make the dynamic loader
+/// pull arbitrary code into the process.
+pub const DANGEROUS_ENV_VARS: &[&str] = &[
+    "LD_PRELOAD",
+    "LD_LIBRARY_PATH",
+    "LD_AUDIT",
+    "DYLD_INSERT_LIBRARIES",
+    "DYLD_LIBRARY_PATH",
+    "DYLD_FRAMEWORK_PATH",
+];
+
+/// Substrings that mark an environment variable as a secret when
+/// [`ExecutorConfig::mask_secrets`] is on (matched case-insensitively).
+pub const SECRET_KEY_PATTERNS: &[&str] = &["SECRET", "TOKEN", "PASSWORD", "API_KEY"];
+
+/// Variables yatr sets for every task's commands and scripts, with what they
+/// hold.
+pub const CONTEXT_ENV_VARS: &[(&str, &str)] = &[
+    ("YATR_TASK_NAME", "the task's name"),
+    ("YATR_RUN_ID", "a UUID shared by every task of one run"),
+    ("YATR_VERBOSE", "1 with --verbose, else 0"),
+];
+
+/// Shortest secret value that gets masked; shorter values (`1`, `on`) would
+/// garble unrelated output.
+const MIN_SECRET_LEN: usize = 4;
 
 /// Result of executing a single task
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TaskResult {
     pub name: String,
     pub success: bool,
@@ -30,10 +64,170 @@ pub struct TaskResult {
     /// Offset from the start of the run until this task began (for profiling)
     pub start_offset: Duration,
     pub cached: bool,
+    /// The task's `condition_script` was falsy, so nothing ran
+    pub skipped: bool,
     pub output: Option<String>,
+    /// Captured stderr (only for commands run with piped output)
+    pub stderr: Option<String>,
+    /// Process exit code, when the task ran a command that exited
+    pub exit_code: Option<i32>,
     pub error: Option<String>,
+    /// For a cache hit, when the cached run happened
+    pub cache_created_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// For a cache hit, how long the cached run took, in milliseconds
+    pub cache_duration_ms: Option<u64>,
+}
+
+/// Captured stdout/stderr of one or more commands. Stdout is kept as raw
+/// bytes so binary output reaches the cache unchanged.
+#[derive(Debug, Default)]
+struct CommandOutput {
+    stdout: Vec<u8>,
+    stderr: String,
+}
+
+impl From<String> for CommandOutput {
+    fn from(stdout: String) -> Self {
+        Self {
+            stdout: stdout.into_bytes(),
+            stderr: String::new(),
+        }
+    }
+}
+
+/// How long before `now` `then` was, e.g. `3m ago`.
+fn time_ago(then: chrono::DateTime<chrono::Utc>, now: chrono::DateTime<chrono::Utc>) -> String {
+    let secs = (now - then).num_seconds();
+    match secs {
+        ..=0 => "just now".to_string(),
+        1..=59 => format!("{secs}s ago"),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86_399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86_400),
+    }
+}
+
+/// Replace every occurrence of each of `secrets` in `text` with `****`.
+fn mask(text: &mut String, secrets: &[String]) {
+    for secret in secrets {
+        if text.contains(secret.as_str()) {
+            *text = text.replace(secret.as_str(), "****");
+        }
+    }
+}
+
+/// [`mask`] for raw bytes, such as stdout that isn't valid UTF-8.
+fn mask_bytes(bytes: &mut Vec<u8>, secrets: &[String]) {
+    for secret in secrets.iter().map(String::as_bytes) {
+        let mut masked = Vec::with_capacity(bytes.len());
+        let mut rest = bytes.as_slice();
+        while let Some(at) = rest.windows(secret.len()).position(|w| w == secret) {
+            masked.extend_from_slice(&rest[..at]);
+            masked.extend_from_slice(b"****");
+            rest = &rest[at + secret.len()..];
+        }
+        if masked.is_empty() {
+            continue;
+        }
+        masked.extend_from_slice(rest);
+        *bytes = masked;
+    }
+}
+
+/// An id for one run, formatted as a version 4 UUID. The bits come from
+/// hashing the process id, a per-process counter and the time, which keeps
+/// runs apart without a random number generator.
+fn new_run_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&std::process::id().to_le_bytes());
+    hasher.update(&COUNTER.fetch_add(1, Ordering::Relaxed).to_le_bytes());
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    hasher.update(&now.as_nanos().to_le_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&hasher.finalize().as_bytes()[..16]);
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = bytes.iter().fold(String::new(), |mut hex, b| {
+        let _ = write!(hex, "{b:02x}");
+        hex
+    });
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// Running `foreground` processes, each with the name of its task.
+pub type ForegroundProcs = Mutex<Vec<(String, tokio::process::Child)>>;
+
+/// Lock the foreground process list, recovering from a poisoned lock.
+fn lock_procs(
+    procs: &ForegroundProcs,
+) -> std::sync::MutexGuard<'_, Vec<(String, tokio::process::Child)>> {
+    procs
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Lock the results of the current run, recovering from a poisoned lock.
+fn lock_results(results: &Mutex<Vec<TaskResult>>) -> std::sync::MutexGuard<'_, Vec<TaskResult>> {
+    results
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Kills any still-tracked foreground processes when dropped, so a run that
+/// fails (or errors out) doesn't leave dev servers behind.
+struct ForegroundGuard<'a>(&'a ForegroundProcs);
+
+impl Drop for ForegroundGuard<'_> {
+    fn drop(&mut self) {
+        for (_, mut child) in lock_procs(self.0).drain(..) {
+            let _ = child.start_kill();
+        }
+    }
 }
 
+/// Listens for Ctrl-C during a run: sets the executor's `interrupted` flag
+/// and wakes the scheduler. Stops listening when dropped.
+struct InterruptListener {
+    task: tokio::task::JoinHandle<()>,
+    notify: Arc<Notify>,
+}
+
+impl InterruptListener {
+    fn spawn(flag: &Arc<AtomicBool>) -> Self {
+        flag.store(false, Ordering::SeqCst);
+        let notify = Arc::new(Notify::new());
+        let (flag, wake) = (Arc::clone(flag), Arc::clone(&notify));
+        let task = tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                flag.store(true, Ordering::SeqCst);
+                // `notify_one` keeps a permit, so a wake-up can't be missed.
+                wake.notify_one();
+            }
+        });
+        Self { task, notify }
+    }
+}
+
+impl Drop for InterruptListener {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Callback receiving fresh cache statistics after each cache read or write
+/// (see [`Executor::with_cache_stats_callback`]).
+pub type CacheStatsCallback = Arc<dyn Fn(CacheStats) + Send + Sync>;
+
 /// Executor configuration
 #[derive(Debug, Clone)]
 pub struct ExecutorConfig {
@@ -53,8 +247,67 @@ pub struct ExecutorConfig {
     pub json: bool,
     /// Warn when a task writes files outside its declared `outputs`
     pub trace_io: bool,
+    /// Show an estimated timeline in dry-run output
+    pub timing: bool,
+    /// Write each task's stdout, stderr and metadata into this directory
+    pub output_dir: Option<PathBuf>,
+    /// Prune tasks whose cache is valid from the graph before scheduling
+    pub incremental: bool,
+    /// Expert mode: start every task at once, ignoring `depends` ordering
+    /// (see [`TaskGraph::unconstrained_plan`])
+    pub unconstrained: bool,
+    /// Prefix each line of task output with a colored `[task]`, so output of
+    /// tasks run in parallel stays attributable. Only applies when more than
+    /// one task may run at a time.
+    pub prefix_output: bool,
+    /// Write a JUnit/HTML report of every task run so far to this path
+    /// after each [`Executor::execute`], including runs that fail
+    pub report: Option<(ReportFormat, PathBuf)>,
+    /// Watch mode: stop after the first fully successful run
+    pub watch_until_success: bool,
+    /// Start commands with the host environment. [`Executor::new`] clears it
+    /// when the config sets `settings.inherit_env = false`.
+    pub inherit_env: bool,
+    /// Remove [`DANGEROUS_ENV_VARS`] from child environments. [`Executor::new`]
+    /// sets it when the config has `settings.sanitize_env = true`.
+    pub sanitize_env: bool,
+    /// Suppress warnings (`--quiet`)
+    pub quiet: bool,
+    /// Replace the values of secret-looking environment variables (see
+    /// [`SECRET_KEY_PATTERNS`]) with `****` in captured output and errors
+    pub mask_secrets: bool,
+    /// Extra key substrings that mark a variable as secret
+    pub secret_keys: Vec<String>,
     /// Reference instant for the whole run, used to compute task start offsets
     pub run_start: Instant,
+    /// Running `foreground` processes (dev servers). They are started without
+    /// blocking the schedule, awaited once every other task is done, and
+    /// killed on Ctrl-C or when the run fails.
+    pub foreground_procs: Arc<ForegroundProcs>,
+    /// Per-task Rhai state. Clones of this config share it, so scripts keep
+    /// their variables from one watch-mode run to the next.
+    pub script_contexts: Arc<Mutex<HashMap<String, ScriptContext>>>,
+}
+
+/// What one task of a run gets on top of the shared [`ExecutorConfig`]
+#[derive(Debug, Clone, Default)]
+struct TaskRun {
+    /// Identifier of the current [`Executor::execute`], exported to commands
+    /// as `YATR_RUN_ID`
+    run_id: String,
+    /// Print each line of command output as it arrives, after this lead (the
+    /// task's `[task] ` prefix or an indent); output is printed once the task
+    /// finishes when `None`
+    live_output: Option<String>,
+}
+
+impl TaskRun {
+    fn new(run_id: &str, live_output: Option<String>) -> Self {
+        Self {
+            run_id: run_id.to_string(),
+            live_output,
+        }
+    }
 }
 
 impl Default for ExecutorConfig {
@@ -68,56 +321,322 @@ impl Default for ExecutorConfig {
             verbose: false,
             json: false,
             trace_io: false,
+            timing: false,
+            output_dir: None,
+            incremental: false,
+            unconstrained: false,
+            prefix_output: true,
+            report: None,
+            watch_until_success: false,
+            inherit_env: true,
+            sanitize_env: false,
+            quiet: false,
+            mask_secrets: true,
+            secret_keys: Vec::new(),
             run_start: Instant::now(),
+            foreground_procs: Arc::default(),
+            script_contexts: Arc::default(),
         }
     }
 }
 
+impl ExecutorConfig {
+    /// Start building a config from the defaults, e.g.
+    /// `ExecutorConfig::builder().verbose(true).dry_run(true).build()`.
+    #[must_use]
+    pub fn builder() -> ExecutorConfigBuilder {
+        ExecutorConfigBuilder::default()
+    }
+}
+
+/// Builder for [`ExecutorConfig`]; unset fields keep their defaults.
+#[derive(Debug, Clone, Default)]
+pub struct ExecutorConfigBuilder {
+    config: ExecutorConfig,
+}
+
+impl ExecutorConfigBuilder {
+    /// Number of parallel tasks (0 = number of CPUs)
+    #[must_use]
+    pub const fn parallelism(mut self, parallelism: usize) -> Self {
+        self.config.parallelism = parallelism;
+        self
+    }
+
+    /// Show the plan without executing it
+    #[must_use]
+    pub const fn dry_run(mut self, dry_run: bool) -> Self {
+        self.config.dry_run = dry_run;
+        self
+    }
+
+    /// Run tasks even when cached
+    #[must_use]
+    pub const fn force(mut self, force: bool) -> Self {
+        self.config.force = force;
+        self
+    }
+
+    /// Run commands through the shell
+    #[must_use]
+    pub const fn shell(mut self, shell: bool) -> Self {
+        self.config.shell = shell;
+        self
+    }
+
+    /// Print commands and output as tasks run
+    #[must_use]
+    pub const fn verbose(mut self, verbose: bool) -> Self {
+        self.config.verbose = verbose;
+        self
+    }
+
+    /// Suppress human output (the caller emits JSON)
+    #[must_use]
+    pub const fn json(mut self, json: bool) -> Self {
+        self.config.json = json;
+        self
+    }
+
+    /// Warn about writes outside declared `outputs`
+    #[must_use]
+    pub const fn trace_io(mut self, trace_io: bool) -> Self {
+        self.config.trace_io = trace_io;
+        self
+    }
+
+    /// Show an estimated timeline in dry-run output
+    #[must_use]
+    pub const fn timing(mut self, timing: bool) -> Self {
+        self.config.timing = timing;
+        self
+    }
+
+    /// Prune tasks with a valid cache before scheduling
+    #[must_use]
+    pub const fn incremental(mut self, incremental: bool) -> Self {
+        self.config.incremental = incremental;
+        self
+    }
+
+    /// Start every task at once, ignoring dependency ordering
+    #[must_use]
+    pub const fn unconstrained(mut self, unconstrained: bool) -> Self {
+        self.config.unconstrained = unconstrained;
+        self
+    }
+
+    /// Prefix task output lines with `[task]` when running in parallel
+    #[must_use]
+    pub const fn prefix_output(mut self, prefix_output: bool) -> Self {
+        self.config.prefix_output = prefix_output;
+        self
+    }
+
+    /// Watch mode: stop after the first fully successful run
+    #[must_use]
+    pub const fn watch_until_success(mut self, watch_until_success: bool) -> Self {
+        self.config.watch_until_success = watch_until_success;
+        self
+    }
+
+    /// Start commands with the host environment
+    #[must_use]
+    pub const fn inherit_env(mut self, inherit_env: bool) -> Self {
+        self.config.inherit_env = inherit_env;
+        self
+    }
+
+    /// Remove [`DANGEROUS_ENV_VARS`] from child environments
+    #[must_use]
+    pub const fn sanitize_env(mut self, sanitize_env: bool) -> Self {
+        self.config.sanitize_env = sanitize_env;
+        self
+    }
+
+    /// Suppress warnings
+    #[must_use]
+    pub const fn quiet(mut self, quiet: bool) -> Self {
+        self.config.quiet = quiet;
+        self
+    }
+
+    /// Mask secret environment variable values in captured output
+    #[must_use]
+    pub const fn mask_secrets(mut self, mask_secrets: bool) -> Self {
+        self.config.mask_secrets = mask_secrets;
+        self
+    }
+
+    /// Extra key substrings that mark a variable as secret
+    #[must_use]
+    pub fn secret_keys(mut self, keys: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.config.secret_keys = keys.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Working directory tasks run from
+    #[must_use]
+    pub fn cwd(mut self, cwd: impl Into<PathBuf>) -> Self {
+        self.config.cwd = cwd.into();
+        self
+    }
+
+    /// Write each task's stdout, stderr and metadata into this directory
+    #[must_use]
+    pub fn output_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.output_dir = Some(dir.into());
+        self
+    }
+
+    /// Write a run report in `format` to `path` after each execution
+    #[must_use]
+    pub fn report(mut self, format: ReportFormat, path: impl Into<PathBuf>) -> Self {
+        self.config.report = Some((format, path.into()));
+        self
+    }
+
+    /// Finish building.
+    #[must_use]
+    pub fn build(self) -> ExecutorConfig {
+        self.config
+    }
+}
+
 /// Task executor
 pub struct Executor {
     config: Arc<Config>,
     exec_config: ExecutorConfig,
     cache: Option<Cache>,
     script_engine: ScriptEngine,
+    cache_stats_callback: Option<CacheStatsCallback>,
+    /// Results of every `execute` call, for `ExecutorConfig::report`
+    reported: Mutex<Vec<TaskResult>>,
+    /// Earlier run times, loaded on first use in verbose mode
+    timing_history: Mutex<Option<TimingHistory>>,
+    /// Set when Ctrl-C arrives during [`Executor::execute`]
+    interrupted: Arc<AtomicBool>,
+    /// Results of the tasks completed so far in the current `execute`,
+    /// shown to Rhai scripts as `task_results`
+    task_results: Arc<Mutex<Vec<TaskResult>>>,
 }
 
 impl Executor {
     /// Create a new executor
     #[must_use]
-    pub fn new(config: Config, exec_config: ExecutorConfig, cache: Option<Cache>) -> Self {
+    pub fn new(config: Config, mut exec_config: ExecutorConfig, cache: Option<Cache>) -> Self {
+        exec_config.inherit_env &= config.settings.inherit_env;
+        exec_config.sanitize_env |= config.settings.sanitize_env;
+        let script_engine = Self::script_engine(&config, &exec_config);
+        if exec_config.sanitize_env && !exec_config.quiet && !exec_config.json {
+            for var in Self::sanitized_vars(&config) {
+                eprintln!(
+                    "{} removing {var} from task environments (sanitize_env)",
+                    style("warning:").yellow().bold()
+                );
+            }
+        }
         Self {
             config: Arc::new(config),
             exec_config,
             cache,
-            script_engine: ScriptEngine::new(),
+            script_engine,
+            cache_stats_callback: None,
+            reported: Mutex::default(),
+            timing_history: Mutex::default(),
+            interrupted: Arc::default(),
+            task_results: Arc::default(),
+        }
+    }
+
+    /// Call `callback` with up-to-date [`CacheStats`] after every cache lookup
+    /// and store, e.g. to drive a live dashboard (builder style). Stats are
+    /// only gathered when a callback is set.
+    #[must_use]
+    pub fn with_cache_stats_callback(
+        mut self,
+        callback: impl Fn(CacheStats) + Send + Sync + 'static,
+    ) -> Self {
+        self.cache_stats_callback = Some(Arc::new(callback));
+        self
+    }
+
+    /// Dry run: build the execution plan for `tasks` (the order of
+    /// `task_names`) and print it.
+    fn show_plan(
+        &self,
+        tasks: Vec<&TaskNode>,
+        task_names: &[&str],
+        graph: &TaskGraph,
+    ) -> Result<()> {
+        let plan = self
+            .execution_plan(tasks, task_names, graph)?
+            .with_estimated_durations(self.recorded_durations(graph));
+        self.print_dry_run(&plan);
+        Ok(())
+    }
+
+    /// The [`ExecutionPlan`] for `tasks` (the order of `task_names`), with a
+    /// single stage when the run is `unconstrained`
+    fn execution_plan<'g>(
+        &self,
+        tasks: Vec<&'g TaskNode>,
+        task_names: &[&str],
+        graph: &'g TaskGraph,
+    ) -> Result<ExecutionPlan<'g>> {
+        if self.exec_config.unconstrained {
+            return graph.unconstrained_plan(task_names);
+        }
+        let plan = ExecutionPlan::from_tasks(tasks, graph);
+        #[cfg(debug_assertions)]
+        graph.validate_execution_plan(&plan)?;
+        Ok(plan)
+    }
+
+    /// Maximum number of concurrent tasks; `0` means one per available CPU.
+    fn parallelism(&self) -> usize {
+        if self.exec_config.parallelism == 0 {
+            std::thread::available_parallelism().map_or(4, std::num::NonZero::get)
+        } else {
+            self.exec_config.parallelism
         }
     }
 
     /// Execute tasks according to the execution plan
     pub async fn execute(&self, graph: &TaskGraph, task_name: &str) -> Result<Vec<TaskResult>> {
-        let tasks = graph.execution_order(task_name)?;
+        self.execute_all(graph, &[task_name]).await
+    }
+
+    /// Execute several targets as one run: their combined plan is scheduled
+    /// together, so shared dependencies run once, every task gets its own
+    /// [`TaskResult`], and a single summary covers them all.
+    pub async fn execute_all(
+        &self,
+        graph: &TaskGraph,
+        task_names: &[&str],
+    ) -> Result<Vec<TaskResult>> {
+        let pruned = self.incremental_graph(graph)?;
+        let graph = pruned.as_ref().unwrap_or(graph);
+
+        let tasks = graph.combined_execution_order(task_names)?;
 
         if self.exec_config.dry_run {
             if !self.exec_config.json {
-                let plan = ExecutionPlan::from_tasks(tasks, graph);
-                self.print_dry_run(&plan);
+                self.show_plan(tasks, task_names, graph)?;
             }
             return Ok(Vec::new());
         }
 
-        let parallelism = if self.exec_config.parallelism == 0 {
-            std::thread::available_parallelism().map_or(4, std::num::NonZero::get)
-        } else {
-            self.exec_config.parallelism
-        };
-
-        let semaphore = Arc::new(Semaphore::new(parallelism));
+        let semaphore = Arc::new(Semaphore::new(self.parallelism()));
         let multi_progress = MultiProgress::new();
+        let run_id = new_run_id();
 
         // Ready-queue scheduling: a task starts the moment all of its
         // dependencies have completed, rather than waiting for its whole
         // dependency "level" — better wall-clock when task durations are uneven.
-        let (node_map, mut in_degree, dependents) = Self::build_dag(&tasks, graph);
+        let (node_map, mut in_degree, dependents) = self.build_dag(&tasks, graph);
+        let prefixes = self.output_prefixes(&tasks);
+        let concurrent = self.parallelism() > 1 && tasks.len() > 1;
 
         // Build the future that runs one task, gated by the concurrency semaphore.
         let spawn_one = |name: &str| {
@@ -125,7 +644,10 @@ impl Executor {
             let config = Arc::clone(&self.config);
             let sem = Arc::clone(&semaphore);
             let exec_config = self.exec_config.clone();
+            let run = TaskRun::new(&run_id, self.live_output(&task, &prefixes, concurrent));
             let cache = self.cache.clone();
+            let stats_callback = self.cache_stats_callback.clone();
+            let completed = Arc::clone(&self.task_results);
             let mp = multi_progress.clone();
             async move {
                 let _permit = sem.acquire().await.map_err(|e| {
@@ -134,20 +656,21 @@ impl Executor {
                     )))
                 })?;
 
-                // Progress spinner is human-only; suppress it in JSON mode.
-                let pb = (!exec_config.json).then(|| {
-                    let pb = mp.add(ProgressBar::new_spinner());
-                    let style = ProgressStyle::default_spinner()
-                        .template("{spinner:.cyan} {msg}")
-                        .unwrap_or_else(|_| ProgressStyle::default_spinner());
-                    pb.set_style(style);
-                    pb.set_message(format!("Running {}", task.name));
-                    pb.enable_steady_tick(Duration::from_millis(100));
-                    pb
-                });
-
-                let result =
-                    Self::execute_single_task(&task, &config, &exec_config, cache.as_ref()).await;
+                // Progress spinner is human-only; suppress it in JSON mode, and
+                // when the task's output is streamed it would clobber the lines.
+                let pb = (!exec_config.json && run.live_output.is_none())
+                    .then(|| Self::task_spinner(&mp, &task.name));
+
+                let result = Self::execute_single_task(
+                    &task,
+                    &config,
+                    &exec_config,
+                    cache.as_ref(),
+                    stats_callback.as_ref(),
+                    &completed,
+                    &run,
+                )
+                .await;
 
                 if let Some(pb) = pb {
                     pb.finish_and_clear();
@@ -156,6 +679,13 @@ impl Executor {
             }
         };
 
+        // Kill foreground processes if the run bails out early.
+        let _foreground = ForegroundGuard(&self.exec_config.foreground_procs);
+        let interrupt = InterruptListener::spawn(&self.interrupted);
+        // Cleared before anything starts, so a root task's script never sees
+        // the previous run's `task_results`.
+        let completed = &self.task_results;
+        lock_results(completed).clear();
         let mut running = JoinSet::new();
         for name in node_map.keys() {
             if in_degree[name] == 0 {
@@ -163,28 +693,32 @@ impl Executor {
             }
         }
 
-        let mut all_results = Vec::new();
-        while let Some(joined) = running.join_next().await {
+        let mut failed = None;
+        loop {
+            let joined = tokio::select! {
+                joined = running.join_next() => joined,
+                () = interrupt.notify.notified() => None,
+            };
+            // A task ending because of Ctrl-C isn't a task failure.
+            let Some(joined) = joined.filter(|_| !self.interrupted.load(Ordering::SeqCst)) else {
+                break;
+            };
             let result =
                 joined.map_err(|e| YatrError::Io(std::io::Error::other(e.to_string())))??;
 
             let success = result.success;
             let finished = result.name.clone();
-            let allow_failure = graph
-                .get_task(&finished)
-                .is_some_and(|t| t.config.allow_failure);
+            let task = graph.get_task(&finished);
+            let allow_failure = task.is_some_and(|t| t.config.allow_failure.unwrap_or(false));
+            let streamed = !result.cached
+                && task.is_some_and(|t| self.live_output(t, &prefixes, concurrent).is_some());
 
-            if !self.exec_config.json {
-                Self::print_task_result(&result);
-            }
-            all_results.push(result);
+            self.record_task_result(&result, &prefixes, streamed)?;
+            lock_results(completed).push(result);
 
             if !success && !allow_failure {
-                return Err(YatrError::TaskFailed {
-                    task: finished,
-                    code: 1,
-                    stderr: None,
-                });
+                failed = Some(finished);
+                break;
             }
 
             // Unblock dependents whose final dependency just completed.
@@ -200,17 +734,288 @@ impl Executor {
             }
         }
 
+        // Dropping the unfinished tasks kills their commands.
+        running.abort_all();
+        let results = std::mem::take(&mut *lock_results(completed));
+        let plan = self.execution_plan(tasks, task_names, graph)?;
+        self.finish_run(results, failed, plan).await
+    }
+
+    /// A running-task spinner added to `mp`
+    fn task_spinner(mp: &MultiProgress, task_name: &str) -> ProgressBar {
+        let pb = mp.add(ProgressBar::new_spinner());
+        let style = ProgressStyle::default_spinner()
+            .template("{spinner:.cyan} {msg}")
+            .unwrap_or_else(|_| ProgressStyle::default_spinner());
+        pb.set_style(style);
+        pb.set_message(format!("Running {task_name}"));
+        pb.enable_steady_tick(Duration::from_millis(100));
+        pb
+    }
+
+    /// The lead for `task`'s output lines when they're printed as they
+    /// arrive (see [`TaskRun::live_output`]), or `None` to print its
+    /// output once it finishes. Commands of a `parallel` task would
+    /// interleave, so they're only streamed in verbose mode; scripts, WASM
+    /// plugins and foreground tasks don't run through a piped command. When
+    /// other tasks may be running alongside (`concurrent`), only prefixed
+    /// output is streamed, so every line stays attributable.
+    fn live_output(
+        &self,
+        task: &TaskNode,
+        prefixes: &HashMap<String, String>,
+        concurrent: bool,
+    ) -> Option<String> {
+        let config = &task.config;
+        let streams = !self.exec_config.json
+            && (self.exec_config.verbose || !config.parallel.unwrap_or(false))
+            && config.script.is_none()
+            && config.wasm.is_none()
+            && !config.foreground.unwrap_or(false);
+        if !streams {
+            return None;
+        }
+        prefixes.get(&task.name).map_or_else(
+            || (!concurrent).then(|| "  ".to_string()),
+            |prefix| Some(format!("{prefix} ")),
+        )
+    }
+
+    /// The `[task]` prefix for each task's output lines, colored by its
+    /// position in the run and padded to the longest name so output lines
+    /// up. Empty unless [`ExecutorConfig::prefix_output`] is set and several
+    /// tasks can run in parallel.
+    fn output_prefixes(&self, tasks: &[&TaskNode]) -> HashMap<String, String> {
+        const COLORS: [console::Color; 5] = [
+            console::Color::Cyan,
+            console::Color::Magenta,
+            console::Color::Yellow,
+            console::Color::Blue,
+            console::Color::Green,
+        ];
+        if !self.exec_config.prefix_output || self.parallelism() <= 1 || tasks.len() <= 1 {
+            return HashMap::new();
+        }
+        let width = tasks
+            .iter()
+            .map(|t| t.name.chars().count())
+            .max()
+            .unwrap_or(0);
+        tasks
+            .iter()
+            .zip(COLORS.iter().cycle())
+            .map(|(task, &color)| {
+                let label = format!("[{:>width$}]", task.name);
+                let prefix = console::Style::new().fg(color).apply_to(label);
+                (task.name.clone(), prefix.to_string())
+            })
+            .collect()
+    }
+
+    /// Print a finished task's result and write its `--output-dir` files.
+    /// Output lines get the task's entry in `prefixes`, if any; `streamed`
+    /// output was already printed as it arrived.
+    fn record_task_result(
+        &self,
+        result: &TaskResult,
+        prefixes: &HashMap<String, String>,
+        streamed: bool,
+    ) -> Result<()> {
+        if !self.exec_config.json {
+            let timing = self.record_timing(result);
+            let prefix = prefixes.get(&result.name).map(String::as_str);
+            Self::print_task_result(result, prefix, timing, streamed);
+        }
+        if let Some(dir) = &self.exec_config.output_dir {
+            Self::write_task_output(dir, result)?;
+        }
+        Ok(())
+    }
+
+    /// In verbose mode, add a successful run's duration to the timing history
+    /// and return the stats of the task's runs before it. Cached and skipped
+    /// results aren't recorded.
+    fn record_timing(&self, result: &TaskResult) -> Option<TimingStats> {
+        if !self.exec_config.verbose || !result.success || result.cached || result.skipped {
+            return None;
+        }
+        let mut guard = self
+            .timing_history
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let history = guard.get_or_insert_with(|| {
+            TimingHistory::load(
+                self.config.settings.cache_dir.as_deref(),
+                &self.exec_config.cwd,
+            )
+        });
+        let stats = history.stats(&result.name);
+        history.record(&result.name, result.duration);
+        if let Err(e) = history.save() {
+            tracing::warn!("failed to save timing history: {e}");
+        }
+        drop(guard);
+        stats
+    }
+
+    /// Report the run, then fail it if it was interrupted or `failed` names a
+    /// task, or print the summary and wait for foreground processes.
+    async fn finish_run(
+        &self,
+        results: Vec<TaskResult>,
+        failed: Option<String>,
+        plan: ExecutionPlan<'_>,
+    ) -> Result<Vec<TaskResult>> {
+        self.write_report(&results)?;
+        if self.interrupted.load(Ordering::SeqCst) {
+            return Err(YatrError::Interrupted {
+                completed: results.len(),
+                total: plan.tasks.len(),
+            });
+        }
+        if let Some(task) = failed {
+            return Err(YatrError::TaskFailed {
+                task,
+                code: 1,
+                command: None,
+                group_index: None,
+                stderr: None,
+            });
+        }
+
+        if !self.exec_config.json {
+            self.print_summary(&results, plan);
+        }
+        self.wait_foreground(results.len()).await?;
+        Ok(results)
+    }
+
+    /// Rewrite the `--report` file with every result seen by this executor,
+    /// so running several targets yields one combined report.
+    fn write_report(&self, results: &[TaskResult]) -> Result<()> {
+        let Some((format, path)) = &self.exec_config.report else {
+            return Ok(());
+        };
+        let reported = {
+            let mut reported = self
+                .reported
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            reported.extend_from_slice(results);
+            reported.clone()
+        };
+        report::write_report(*format, &reported, path)
+    }
+
+    /// Wait for the foreground processes started during the run to exit, or
+    /// kill them all on Ctrl-C, which fails the run as interrupted after the
+    /// `completed` tasks. A process exiting non-zero fails the run.
+    async fn wait_foreground(&self, completed: usize) -> Result<()> {
+        let children = std::mem::take(&mut *lock_procs(&self.exec_config.foreground_procs));
+        if children.is_empty() {
+            return Ok(());
+        }
+        let interrupted = Err(YatrError::Interrupted {
+            completed,
+            total: completed,
+        });
+        // Listen before announcing the wait, so a Ctrl-C right after the
+        // announcement can't slip past.
+        let ctrl_c = tokio::signal::ctrl_c();
+        tokio::pin!(ctrl_c);
+        if futures::poll!(&mut ctrl_c).is_ready() {
+            return interrupted;
+        }
         if !self.exec_config.json {
-            self.print_summary(&all_results);
+            let names: Vec<_> = children.iter().map(|(name, _)| name.as_str()).collect();
+            println!(
+                "{} Waiting for {} (Ctrl-C to stop)",
+                style("⏳").cyan(),
+                names.join(", ")
+            );
+        }
+
+        let wait_all =
+            futures::future::join_all(children.into_iter().map(|(name, mut child)| async move {
+                let status = child.wait().await;
+                (name, status, child)
+            }));
+        tokio::pin!(wait_all);
+
+        let finished = tokio::select! {
+            finished = &mut wait_all => finished,
+            _ = &mut ctrl_c => {
+                // Dropping the pending waits drops (and so kills) the children.
+                return interrupted;
+            }
+        };
+
+        for (task, status, _child) in finished {
+            let status = status?;
+            if !status.success() {
+                return Err(YatrError::TaskFailed {
+                    task,
+                    code: status.code().unwrap_or(1),
+                    command: None,
+                    group_index: None,
+                    stderr: None,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Run ad-hoc commands in order, outside of any task definition, using
+    /// this executor's shell and output settings. Useful for commands generated
+    /// at runtime. The result is named `(raw)`; a failing command is reported
+    /// through `success: false` rather than an error.
+    pub async fn execute_raw(
+        &self,
+        commands: &[String],
+        env: &HashMap<String, String>,
+        cwd: &Path,
+    ) -> Result<TaskResult> {
+        const NAME: &str = "(raw)";
+        let start = Instant::now();
+        let start_offset = self.exec_config.run_start.elapsed();
+        let result = Self::execute_commands_sequential(
+            NAME,
+            commands,
+            &[],
+            None,
+            env,
+            cwd,
+            &self.exec_config,
+        )
+        .await;
+        Ok(Self::task_result(
+            NAME,
+            result,
+            start.elapsed(),
+            start_offset,
+        ))
+    }
+
+    /// Incremental mode: a copy of `graph` whose up-to-date tasks are no-op
+    /// sentinels, or `None` when not running incrementally (or without a cache,
+    /// or under `--force`, which runs every task).
+    fn incremental_graph(&self, graph: &TaskGraph) -> Result<Option<TaskGraph>> {
+        match &self.cache {
+            Some(cache) if self.exec_config.incremental && !self.exec_config.force => graph
+                .prune_unchanged(cache, &self.exec_config.cwd)
+                .map(Some),
+            _ => Ok(None),
         }
-        Ok(all_results)
     }
 
     /// Build the dependency bookkeeping for the ready-queue scheduler:
     /// name → task node, name → outstanding dependency count, and
     /// name → tasks that depend on it (all scoped to the tasks being run).
+    /// An `unconstrained` run drops every dependency, so all tasks are ready
+    /// at once.
     #[allow(clippy::type_complexity)]
     fn build_dag(
+        &self,
         tasks: &[&TaskNode],
         graph: &TaskGraph,
     ) -> (
@@ -218,6 +1023,7 @@ impl Executor {
         HashMap<String, usize>,
         HashMap<String, Vec<String>>,
     ) {
+        let unconstrained = self.exec_config.unconstrained;
         let in_set: HashSet<&str> = tasks.iter().map(|t| t.name.as_str()).collect();
         let node_map: HashMap<String, TaskNode> = tasks
             .iter()
@@ -230,7 +1036,7 @@ impl Executor {
                 .dependencies(&task.name)
                 .unwrap_or_default()
                 .into_iter()
-                .filter(|d| in_set.contains(d))
+                .filter(|d| !unconstrained && in_set.contains(d))
                 .map(String::from)
                 .collect();
             in_degree.insert(task.name.clone(), deps.len());
@@ -241,16 +1047,27 @@ impl Executor {
         (node_map, in_degree, dependents)
     }
 
-    /// Execute a single task
+    /// Execute a single task; `completed` holds the run's results so far.
     async fn execute_single_task(
         task: &TaskNode,
         config: &Config,
         exec_config: &ExecutorConfig,
         cache: Option<&Cache>,
+        stats_callback: Option<&CacheStatsCallback>,
+        completed: &Mutex<Vec<TaskResult>>,
+        run: &TaskRun,
     ) -> Result<TaskResult> {
         let start = Instant::now();
         let start_offset = exec_config.run_start.elapsed();
-        let env = config.task_env(&task.config);
+
+        // Pruned by incremental mode: the cache already vouches for this task.
+        if task.pruned {
+            let elapsed = start.elapsed();
+            return Ok(Self::cached_result(&task.name, None, elapsed, start_offset));
+        }
+
+        let mut env = config.task_env(&task.config);
+        Self::add_context_env(&mut env, task, &run.run_id, exec_config);
 
         // Determine working directory (needed for cache key + output restore)
         let cwd = task
@@ -259,66 +1076,71 @@ impl Executor {
             .clone()
             .unwrap_or_else(|| exec_config.cwd.clone());
 
+        if let Some(condition) = &task.config.condition_script {
+            let (name, elapsed) = (&task.name, start.elapsed());
+            let engine = Self::script_engine(config, exec_config);
+            match Self::evaluate_condition(task, condition, &engine, &env, &cwd).await {
+                Ok(true) => {}
+                Ok(false) => return Ok(Self::skipped_result(name, elapsed, start_offset)),
+                Err(e) => return Ok(Self::task_result(name, Err(e), elapsed, start_offset)),
+            }
+        }
+        if let Some(check) = task
+            .config
+            .pre_exec_check
+            .as_ref()
+            .filter(|_| !exec_config.force)
+        {
+            if Self::pre_exec_check_passes(check, &env, &cwd, exec_config).await {
+                return Ok(Self::skipped_result(
+                    &task.name,
+                    start.elapsed(),
+                    start_offset,
+                ));
+            }
+        }
+
+        // run_if_changed: skip when the matched files are as they were at the
+        // last successful run.
+        let last_run = Self::last_run_fingerprint(task, config, &cwd);
+        if let Some((store, fingerprint)) = &last_run {
+            if !exec_config.force && store.is_unchanged(&task.name, &cwd, fingerprint) {
+                let elapsed = start.elapsed();
+                return Ok(Self::skipped_result(&task.name, elapsed, start_offset));
+            }
+        }
+
         // Check cache
         if !exec_config.force {
             if let Some(cache) = cache {
-                if !task.config.no_cache {
-                    if let Some(cached) = cache.get(&task.name, &task.config, &cwd).await? {
-                        return Ok(TaskResult {
-                            name: task.name.clone(),
-                            success: true,
-                            duration: start.elapsed(),
-                            start_offset,
-                            cached: true,
-                            output: Some(cached),
-                            error: None,
-                        });
+                if !task.config.no_cache.unwrap_or(false) {
+                    let hit = cache
+                        .get_with_metadata(&task.name, &task.config, &cwd)
+                        .await?;
+                    Self::report_cache_stats(cache, stats_callback);
+                    if let Some((cached, entry)) = hit {
+                        let elapsed = start.elapsed();
+                        let mut result =
+                            Self::cached_result(&task.name, Some(cached), elapsed, start_offset);
+                        result.cache_created_at = Some(entry.created_at);
+                        result.cache_duration_ms = Some(entry.duration_ms);
+                        return Ok(result);
                     }
                 }
             }
         }
 
-        // Use task-level shell setting if specified, otherwise use exec_config
-        let mut task_exec_config = exec_config.clone();
-        if task.config.shell.unwrap_or(false) {
-            task_exec_config.shell = true;
+        if task.config.outputs_cleanup.unwrap_or(false) {
+            Self::clean_outputs(&cwd, &task.config.outputs)?;
         }
 
         // IO tracing: snapshot the tree before a (non-foreground) run.
-        let trace_before =
-            (exec_config.trace_io && !task.config.foreground).then(|| crate::trace::snapshot(&cwd));
+        let trace_before = (exec_config.trace_io && !task.config.foreground.unwrap_or(false))
+            .then(|| crate::trace::snapshot(&cwd));
 
-        let result = if task.config.foreground {
-            // Execute in foreground with inherited stdio (for long-running processes)
-            Self::execute_foreground(&task.name, &task.config.run, &env, &cwd, &task_exec_config)
-                .await
-        } else if let Some(wasm) = &task.config.wasm {
-            // Execute a sandboxed WASM plugin (local path or downloaded URL)
-            Self::execute_wasm(&task.name, wasm, &cwd, &env).await
-        } else if let Some(script) = &task.config.script {
-            // Execute Rhai script
-            Self::execute_script(&task.name, script, &env, &cwd)
-        } else if task.config.parallel {
-            // Execute commands in parallel
-            Self::execute_commands_parallel(
-                &task.name,
-                &task.config.run,
-                &env,
-                &cwd,
-                &task_exec_config,
-            )
-            .await
-        } else {
-            // Execute commands sequentially
-            Self::execute_commands_sequential(
-                &task.name,
-                &task.config.run,
-                &env,
-                &cwd,
-                &task_exec_config,
-            )
-            .await
-        };
+        let result =
+            Self::run_with_retries(task, config, &env, &cwd, exec_config, completed, run).await;
+        let result = Self::mask_secrets(result, &env, exec_config);
 
         let duration = start.elapsed();
 
@@ -327,39 +1149,536 @@ impl Executor {
             Self::report_io_trace(&task.name, &cwd, &task.config.outputs, &before);
         }
 
-        match result {
-            Ok(output) => {
-                // Store in cache (skip foreground tasks: their output isn't captured)
-                if let Some(cache) = cache {
-                    if !task.config.no_cache && !task.config.foreground {
-                        let _ = cache
-                            .put(&task.name, &task.config, &cwd, &output, duration)
-                            .await;
-                    }
+        // Store in cache (skip foreground tasks: their output isn't captured)
+        if let (Ok(output), Some(cache)) = (&result, cache) {
+            if !task.config.no_cache.unwrap_or(false) && !task.config.foreground.unwrap_or(false) {
+                let _ = cache
+                    .put(&task.name, &task.config, &cwd, &output.stdout, duration)
+                    .await;
+                Self::report_cache_stats(cache, stats_callback);
+            }
+        }
+
+        // Fingerprint again, so files the task rewrote itself count as seen.
+        if result.is_ok() && last_run.is_some() {
+            if let Some((store, fingerprint)) = Self::last_run_fingerprint(task, config, &cwd) {
+                if let Err(e) = store.record(&task.name, &cwd, &fingerprint) {
+                    tracing::warn!("could not record last run of '{}': {e}", task.name);
                 }
+            }
+        }
 
-                Ok(TaskResult {
-                    name: task.name.clone(),
-                    success: true,
-                    duration,
-                    start_offset,
-                    cached: false,
-                    output: Some(output),
-                    error: None,
+        Ok(Self::task_result(
+            &task.name,
+            result,
+            duration,
+            start_offset,
+        ))
+    }
+
+    /// Tell commands and scripts about their run through [`CONTEXT_ENV_VARS`],
+    /// overriding any same-named task `env`.
+    fn add_context_env(
+        env: &mut HashMap<String, String>,
+        task: &TaskNode,
+        run_id: &str,
+        exec_config: &ExecutorConfig,
+    ) {
+        let verbose = if exec_config.verbose { "1" } else { "0" };
+        env.extend([
+            ("YATR_TASK_NAME".to_string(), task.name.clone()),
+            ("YATR_RUN_ID".to_string(), run_id.to_string()),
+            ("YATR_VERBOSE".to_string(), verbose.to_string()),
+        ]);
+    }
+
+    /// Replace secret values (see [`ExecutorConfig::mask_secrets`]) in a
+    /// run's captured output, before it is cached or reported.
+    fn mask_secrets(
+        result: Result<CommandOutput>,
+        env: &HashMap<String, String>,
+        exec_config: &ExecutorConfig,
+    ) -> Result<CommandOutput> {
+        if !exec_config.mask_secrets {
+            return result;
+        }
+        let secrets = Self::secret_values(env, exec_config);
+        if secrets.is_empty() {
+            return result;
+        }
+        match result {
+            Ok(mut output) => {
+                mask_bytes(&mut output.stdout, &secrets);
+                mask(&mut output.stderr, &secrets);
+                Ok(output)
+            }
+            Err(YatrError::TaskFailed {
+                task,
+                code,
+                mut command,
+                group_index,
+                mut stderr,
+            }) => {
+                for text in [&mut command, &mut stderr].into_iter().flatten() {
+                    mask(text, &secrets);
+                }
+                Err(YatrError::TaskFailed {
+                    task,
+                    code,
+                    command,
+                    group_index,
+                    stderr,
                 })
             }
-            Err(e) => Ok(TaskResult {
-                name: task.name.clone(),
-                success: false,
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Values of the secret-looking variables a task's commands can see: its
+    /// own env, plus the host's when it is inherited. Longest first, so a
+    /// secret containing another is masked whole.
+    fn secret_values(env: &HashMap<String, String>, exec_config: &ExecutorConfig) -> Vec<String> {
+        let patterns: Vec<String> = SECRET_KEY_PATTERNS
+            .iter()
+            .map(|p| (*p).to_string())
+            .chain(exec_config.secret_keys.iter().map(|k| k.to_uppercase()))
+            .collect();
+        let is_secret = |key: &str| {
+            let key = key.to_uppercase();
+            patterns.iter().any(|p| key.contains(p.as_str()))
+        };
+        let host = exec_config
+            .inherit_env
+            .then(std::env::vars)
+            .into_iter()
+            .flatten();
+        let mut secrets: Vec<String> = env
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .chain(host)
+            .filter(|(k, v)| v.len() >= MIN_SECRET_LEN && is_secret(k))
+            .map(|(_, v)| v)
+            .collect();
+        secrets.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        secrets.dedup();
+        secrets
+    }
+
+    /// Fingerprint of a task's `run_if_changed` files and commands (see
+    /// [`LastRun::fingerprint`]), with the store it is checked against. `None` when the task has no `run_if_changed` globs or
+    /// they can't be hashed, in which case the task always runs.
+    fn last_run_fingerprint(
+        task: &TaskNode,
+        config: &Config,
+        cwd: &Path,
+    ) -> Option<(LastRun, String)> {
+        if task.config.run_if_changed.is_empty() {
+            return None;
+        }
+        match LastRun::fingerprint(cwd, &task.config, &config.task_env(&task.config)) {
+            Ok(fingerprint) => {
+                let store = LastRun::new(config.settings.cache_dir.as_deref());
+                Some((store, fingerprint))
+            }
+            Err(e) => {
+                tracing::warn!("run_if_changed of '{}' ignored: {e}", task.name);
+                None
+            }
+        }
+    }
+
+    /// A script engine with the configured sandbox limits, whose `exec`
+    /// commands get the same environment filtering as task commands.
+    const fn script_engine(config: &Config, exec_config: &ExecutorConfig) -> ScriptEngine {
+        ScriptEngine::new_with_limits(config.settings.script_limits)
+            .with_host_env(exec_config.inherit_env, exec_config.sanitize_env)
+    }
+
+    /// Evaluate a task's `condition_script`; `false` means skip the task.
+    async fn evaluate_condition(
+        task: &TaskNode,
+        condition: &str,
+        engine: &ScriptEngine,
+        env: &HashMap<String, String>,
+        cwd: &Path,
+    ) -> Result<bool> {
+        engine
+            .evaluate_condition_async(condition, &task.name, &task.config, env, cwd)
+            .await
+            .map_err(|e| YatrError::ScriptFailed {
+                task: task.name.clone(),
+                source: e,
+            })
+    }
+
+    /// Run a task's `pre_exec_check` through the shell with its output
+    /// discarded. Only a zero exit passes; a check that can't be started
+    /// counts as failed, so the task still runs.
+    async fn pre_exec_check_passes(
+        check: &str,
+        env: &HashMap<String, String>,
+        cwd: &Path,
+        exec_config: &ExecutorConfig,
+    ) -> bool {
+        let (shell, flag) = if cfg!(windows) {
+            ("cmd", "/C")
+        } else {
+            ("sh", "-c")
+        };
+        let mut command = Command::new(shell);
+        command.arg(flag).arg(check);
+        Self::set_env(&mut command, env, exec_config);
+        let status = command
+            .current_dir(cwd)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .status()
+            .await;
+        match status {
+            Ok(status) => status.success(),
+            Err(e) => {
+                tracing::warn!("pre_exec_check '{check}' could not be started: {e}");
+                false
+            }
+        }
+    }
+
+    /// Result of a task skipped by its `condition_script`, `pre_exec_check` or
+    /// unchanged `run_if_changed` files.
+    fn skipped_result(name: &str, duration: Duration, start_offset: Duration) -> TaskResult {
+        TaskResult {
+            name: name.to_string(),
+            success: true,
+            duration,
+            start_offset,
+            cached: false,
+            skipped: true,
+            output: None,
+            stderr: None,
+            exit_code: None,
+            error: None,
+            cache_created_at: None,
+            cache_duration_ms: None,
+        }
+    }
+
+    /// Result for a task served from the cache (`output` is `None` when it was
+    /// pruned by incremental mode).
+    fn cached_result(
+        name: &str,
+        output: Option<String>,
+        duration: Duration,
+        start_offset: Duration,
+    ) -> TaskResult {
+        TaskResult {
+            name: name.to_string(),
+            success: true,
+            duration,
+            start_offset,
+            cached: true,
+            skipped: false,
+            output,
+            stderr: None,
+            exit_code: None,
+            error: None,
+            cache_created_at: None,
+            cache_duration_ms: None,
+        }
+    }
+
+    /// Hand fresh cache statistics to the stats callback, if one is set.
+    fn report_cache_stats(cache: &Cache, callback: Option<&CacheStatsCallback>) {
+        if let Some(callback) = callback {
+            if let Ok(stats) = cache.stats() {
+                callback(stats);
+            }
+        }
+    }
+
+    /// Turn the outcome of running a task's commands into a [`TaskResult`].
+    /// A failure is recorded on the result, not returned as an error.
+    fn task_result(
+        name: &str,
+        result: Result<CommandOutput>,
+        duration: Duration,
+        start_offset: Duration,
+    ) -> TaskResult {
+        match result {
+            Ok(output) => TaskResult {
+                name: name.to_string(),
+                success: true,
                 duration,
                 start_offset,
                 cached: false,
-                output: None,
-                error: Some(e.to_string()),
-            }),
+                skipped: false,
+                output: Some(String::from_utf8_lossy(&output.stdout).into_owned()),
+                stderr: Some(output.stderr),
+                exit_code: Some(0),
+                error: None,
+                cache_created_at: None,
+                cache_duration_ms: None,
+            },
+            Err(e) => {
+                let (exit_code, stderr) = match &e {
+                    YatrError::TaskFailed { code, stderr, .. } => (Some(*code), stderr.clone()),
+                    _ => (None, None),
+                };
+                // The help is the useful part of a missing command's error.
+                let error = match &e {
+                    YatrError::CommandNotFound { help, .. } => format!("{e}\n{help}"),
+                    _ => e.to_string(),
+                };
+                TaskResult {
+                    name: name.to_string(),
+                    success: false,
+                    duration,
+                    start_offset,
+                    cached: false,
+                    skipped: false,
+                    output: None,
+                    stderr,
+                    exit_code,
+                    error: Some(error),
+                    cache_created_at: None,
+                    cache_duration_ms: None,
+                }
+            }
+        }
+    }
+
+    /// Delete every file or directory matched by the declared output patterns.
+    fn clean_outputs(cwd: &Path, outputs: &[String]) -> Result<()> {
+        for pattern in outputs {
+            let full = cwd.join(pattern);
+            let Ok(paths) = glob::glob(&full.to_string_lossy()) else {
+                continue;
+            };
+            for path in paths.filter_map(std::result::Result::ok) {
+                if path.is_dir() {
+                    std::fs::remove_dir_all(&path)?;
+                } else {
+                    std::fs::remove_file(&path)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Run a task, retrying failures up to `retries` times. A failure is only
+    /// retried when its stderr matches `retry_on` (if set) and does not match
+    /// `retry_abort_on`, and after `retry_delay_ms`. Each attempt is limited
+    /// to the task's `timeout`.
+    async fn run_with_retries(
+        task: &TaskNode,
+        config: &Config,
+        env: &HashMap<String, String>,
+        cwd: &Path,
+        exec_config: &ExecutorConfig,
+        completed: &Mutex<Vec<TaskResult>>,
+        run: &TaskRun,
+    ) -> Result<CommandOutput> {
+        let retries = task.config.retries.unwrap_or(0);
+        let mut attempt = 0;
+        loop {
+            let body = Self::run_task_body(task, config, env, cwd, exec_config, completed, run);
+            // Dropping the body on timeout kills its commands (`kill_on_drop`).
+            let result = match task.config.timeout {
+                Some(seconds) => tokio::time::timeout(Duration::from_secs(seconds), body)
+                    .await
+                    .unwrap_or_else(|_| {
+                        Err(YatrError::TaskTimeout {
+                            task: task.name.clone(),
+                            seconds,
+                        })
+                    }),
+                None => body.await,
+            };
+            match &result {
+                Err(e) if attempt < retries && Self::should_retry(&task.config, e) => {
+                    attempt += 1;
+                    tracing::warn!("Retrying task '{}' ({attempt}/{retries})", task.name);
+                    if let Some(ms) = task.config.retry_delay_ms {
+                        tokio::time::sleep(Duration::from_millis(ms)).await;
+                    }
+                }
+                _ => return result,
+            }
+        }
+    }
+
+    /// Whether a failure qualifies for a retry under the task's
+    /// `retry_on` / `retry_abort_on` patterns. Failures without captured
+    /// stderr are matched against the error message instead.
+    fn should_retry(task: &TaskConfig, error: &YatrError) -> bool {
+        let message = error.to_string();
+        let text = if let YatrError::TaskFailed {
+            stderr: Some(stderr),
+            ..
+        } = error
+        {
+            stderr.as_str()
+        } else {
+            message.as_str()
+        };
+        let matches = |patterns: &[String]| {
+            patterns
+                .iter()
+                .any(|p| regex::Regex::new(p).is_ok_and(|re| re.is_match(text)))
+        };
+
+        if matches(&task.retry_abort_on) {
+            return false;
+        }
+        task.retry_on.is_empty() || matches(&task.retry_on)
+    }
+
+    /// Dispatch a task to the runner matching its kind (foreground, WASM,
+    /// script, or parallel/sequential commands).
+    async fn run_task_body(
+        task: &TaskNode,
+        config: &Config,
+        env: &HashMap<String, String>,
+        cwd: &Path,
+        exec_config: &ExecutorConfig,
+        completed: &Mutex<Vec<TaskResult>>,
+        run: &TaskRun,
+    ) -> Result<CommandOutput> {
+        // Use task-level shell setting if specified, otherwise use exec_config
+        let mut task_exec_config = exec_config.clone();
+        if task.config.shell.unwrap_or(false) {
+            task_exec_config.shell = true;
+        }
+
+        // Substitute `${args.x}` with the task's (possibly overridden) args.
+        let mut commands = task
+            .config
+            .run
+            .iter()
+            .map(|cmd| {
+                task.config
+                    .expand_args(cmd.cmd())
+                    .map_err(|arg| YatrError::InvalidTask {
+                        task: task.name.clone(),
+                        reason: format!("'${{args.{arg}}}' refers to an undeclared arg"),
+                    })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // Remote (ssh://) cwd: wrap each command in `ssh … 'cd <path> && cmd'`
+        // and run it through the local shell from the run's own directory.
+        // Local commands get their platform's `command_aliases` instead.
+        let cwd = if let Some(target) = crate::ssh::SshTarget::from_cwd(cwd) {
+            let target = target?;
+            for cmd in &mut commands {
+                *cmd = target.wrap_command(cmd, task.config.ssh.as_ref());
+            }
+            task_exec_config.shell = true;
+            exec_config.cwd.as_path()
+        } else {
+            for cmd in &mut commands {
+                *cmd = config.resolve_command_alias(cmd, std::env::consts::OS);
+            }
+            cwd
+        };
+        let commands = commands.as_slice();
+
+        // A command's own `stdin` wins over the task's `stdin` / `stdin_file`.
+        let task_stdin: Option<Arc<[u8]>> = if let Some(stdin) = &task.config.stdin {
+            Some(stdin.as_bytes().into())
+        } else if let Some(path) = &task.config.stdin_file {
+            let bytes = std::fs::read(cwd.join(path)).map_err(|e| YatrError::InvalidTask {
+                task: task.name.clone(),
+                reason: format!("Failed to read stdin_file '{}': {e}", path.display()),
+            })?;
+            Some(bytes.into())
+        } else {
+            None
+        };
+        let stdins: Vec<Option<Arc<[u8]>>> = task
+            .config
+            .run
+            .iter()
+            .map(|cmd| {
+                cmd.stdin()
+                    .map(|stdin| stdin.as_bytes().into())
+                    .or_else(|| task_stdin.clone())
+            })
+            .collect();
+        let live_output = run.live_output.as_deref();
+
+        if task.config.foreground.unwrap_or(false) {
+            // Execute in foreground with inherited stdio (for long-running processes)
+            Self::execute_foreground(&task.name, commands, env, cwd, &task_exec_config)
+                .map(CommandOutput::from)
+        } else if let Some(wasm) = &task.config.wasm {
+            // Execute a sandboxed WASM plugin (local path or downloaded URL)
+            Self::execute_wasm(&task.name, wasm, cwd, env)
+                .await
+                .map(CommandOutput::from)
+        } else if let Some(script) = &task.config.script {
+            // Execute Rhai script
+            let engine = Self::script_engine(config, exec_config);
+            let completed = lock_results(completed).clone();
+            let contexts = &exec_config.script_contexts;
+            Self::execute_script(task, script, env, cwd, &engine, contexts, &completed)
+                .await
+                .map(CommandOutput::from)
+        } else if task.config.parallel.unwrap_or(false) {
+            // Execute commands in parallel
+            Self::execute_commands_parallel(
+                &task.name,
+                commands,
+                &stdins,
+                live_output,
+                env,
+                cwd,
+                &task_exec_config,
+            )
+            .await
+        } else {
+            // Execute commands sequentially
+            Self::execute_commands_sequential(
+                &task.name,
+                commands,
+                &stdins,
+                live_output,
+                env,
+                cwd,
+                &task_exec_config,
+            )
+            .await
         }
     }
 
+    /// Write `<task>.stdout`, `<task>.stderr` and `<task>.json` into `dir`.
+    /// Path separators in task names are replaced so each task gets a flat file.
+    fn write_task_output(dir: &Path, result: &TaskResult) -> Result<()> {
+        std::fs::create_dir_all(dir)?;
+        let stem = result.name.replace(['/', '\\'], "_");
+        std::fs::write(
+            dir.join(format!("{stem}.stdout")),
+            result.output.as_deref().unwrap_or_default(),
+        )?;
+        std::fs::write(
+            dir.join(format!("{stem}.stderr")),
+            result.stderr.as_deref().unwrap_or_default(),
+        )?;
+        let meta = serde_json::json!({
+            "name": result.name,
+            "success": result.success,
+            "exit_code": result.exit_code,
+            "duration_ms": result.duration.as_millis(),
+            "cached": result.cached,
+            "skipped": result.skipped,
+            "error": result.error,
+        });
+        let meta = serde_json::to_string_pretty(&meta).unwrap_or_default();
+        std::fs::write(dir.join(format!("{stem}.json")), meta)?;
+        Ok(())
+    }
+
     /// Warn about files a task wrote outside its declared `outputs` (IO trace).
     fn report_io_trace(
         task_name: &str,
@@ -378,20 +1697,65 @@ impl Executor {
         }
     }
 
-    /// Execute a Rhai script
-    fn execute_script(
-        task_name: &str,
+    /// Execute a Rhai script under the configured sandbox limits, on the
+    /// blocking pool so long scripts don't starve other tasks. The task's
+    /// [`ScriptContext`] is taken from `contexts` and put back afterwards, and
+    /// `completed` is exposed to the script as `task_results`.
+    async fn execute_script(
+        task: &TaskNode,
         script: &str,
         env: &HashMap<String, String>,
         cwd: &Path,
+        engine: &ScriptEngine,
+        contexts: &Mutex<HashMap<String, ScriptContext>>,
+        completed: &[TaskResult],
     ) -> Result<String> {
-        let engine = ScriptEngine::new();
-        engine
-            .execute(script, env, cwd)
-            .map_err(|e| YatrError::ScriptFailed {
-                task: task_name.to_string(),
-                source: e,
+        let lock = || {
+            contexts
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+        };
+        let mut ctx = lock().remove(&task.name).unwrap_or_default();
+        ctx.set("task_results", Self::task_results_dynamic(completed));
+        ctx.set("task_outputs", Self::task_outputs_dynamic(completed));
+        let (ctx, result) = engine
+            .execute_task_with_context_async(script, &task.name, &task.config, ctx, env, cwd)
+            .await;
+        lock().insert(task.name.clone(), ctx);
+        result.map_err(|e| YatrError::ScriptFailed {
+            task: task.name.clone(),
+            source: e,
+        })
+    }
+
+    /// `task_results` for scripts: one `{name, success, duration_ms, cached,
+    /// output}` map per completed task, with `output` unit when not captured.
+    fn task_results_dynamic(results: &[TaskResult]) -> rhai::Dynamic {
+        let results: rhai::Array = results
+            .iter()
+            .map(|r| {
+                let mut map = rhai::Map::new();
+                map.insert("name".into(), r.name.clone().into());
+                map.insert("success".into(), r.success.into());
+                let duration_ms = i64::try_from(r.duration.as_millis()).unwrap_or(i64::MAX);
+                map.insert("duration_ms".into(), duration_ms.into());
+                map.insert("cached".into(), r.cached.into());
+                let output = r.output.clone().map_or(rhai::Dynamic::UNIT, Into::into);
+                map.insert("output".into(), output);
+                map.into()
             })
+            .collect();
+        results.into()
+    }
+
+    /// `task_outputs` for scripts: completed task name → captured stdout.
+    /// Tasks without captured output (failed, skipped, foreground) are left out.
+    fn task_outputs_dynamic(results: &[TaskResult]) -> rhai::Dynamic {
+        let outputs: rhai::Map = results
+            .iter()
+            .filter_map(|r| Some((r.name.as_str().into(), r.output.clone()?.into())))
+            .collect();
+        outputs.into()
     }
 
     /// Execute a sandboxed WASM plugin. The reference is a local path (resolved
@@ -410,41 +1774,50 @@ impl Executor {
         crate::wasm::run_plugin(&path, task_name, &input_bytes)
     }
 
-    /// Execute commands sequentially
+    /// Execute commands sequentially. `stdins[i]`, when set, is written to
+    /// the stdin of `commands[i]`; output is streamed after `live_output`
+    /// (see [`TaskRun::live_output`]) when set.
     async fn execute_commands_sequential(
-        _task_name: &str,
+        task_name: &str,
         commands: &[String],
+        stdins: &[Option<Arc<[u8]>>],
+        live_output: Option<&str>,
         env: &HashMap<String, String>,
         cwd: &Path,
         exec_config: &ExecutorConfig,
-    ) -> Result<String> {
-        let mut all_output = String::new();
-
-        for cmd in commands {
-            let output = Self::execute_command(cmd, env, cwd, exec_config).await?;
-            all_output.push_str(&output);
-            all_output.push('\n');
+    ) -> Result<CommandOutput> {
+        let mut all_output = CommandOutput::default();
+
+        for (index, cmd) in commands.iter().enumerate() {
+            let stdin = stdins.get(index).and_then(Option::as_ref);
+            let output =
+                Self::execute_command(task_name, cmd, stdin, live_output, env, cwd, exec_config)
+                    .await?;
+            all_output.stdout.extend_from_slice(&output.stdout);
+            all_output.stdout.push(b'\n');
+            all_output.stderr.push_str(&output.stderr);
         }
 
         Ok(all_output)
     }
 
-    /// Execute commands in foreground with inherited stdio
-    async fn execute_foreground(
+    /// Start a foreground command with inherited stdio. The process is
+    /// tracked in [`ExecutorConfig::foreground_procs`] and the task completes
+    /// as soon as it has spawned, so dependents aren't blocked behind it.
+    fn execute_foreground(
         task_name: &str,
         commands: &[String],
         env: &HashMap<String, String>,
         cwd: &Path,
         exec_config: &ExecutorConfig,
     ) -> Result<String> {
-        // Foreground tasks run with inherited stdio and block until completion
         // Only the first command is executed (foreground doesn't make sense for multiple commands)
         let cmd = commands.first().ok_or_else(|| YatrError::InvalidTask {
             task: task_name.to_string(),
             reason: "Foreground task must have at least one command".to_string(),
         })?;
 
-        let parts = Self::parse_command(cmd, exec_config.shell);
+        let parts = Self::command_args(task_name, cmd, env, cwd, exec_config);
 
         let mut command = if exec_config.shell {
             let shell = if cfg!(windows) { "cmd" } else { "sh" };
@@ -460,67 +1833,97 @@ impl Executor {
             c
         };
 
+        Self::set_env(&mut command, env, exec_config);
         command
             .current_dir(cwd)
-            .envs(env)
             .stdin(Stdio::inherit())
             .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit());
-
-        let status = command.status().await?;
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true);
 
-        if !status.success() {
-            return Err(YatrError::TaskFailed {
-                task: cmd.clone(),
-                code: status.code().unwrap_or(1),
-                stderr: None,
-            });
-        }
+        let child = command.spawn()?;
+        lock_procs(&exec_config.foreground_procs).push((task_name.to_string(), child));
 
-        Ok(String::from("(foreground task completed)"))
+        Ok(String::from("(foreground task started)"))
     }
 
-    /// Execute commands in parallel
+    /// Execute commands in parallel, with per-command `stdins` and
+    /// `live_output` as in [`execute_commands_sequential`](Self::execute_commands_sequential)
     async fn execute_commands_parallel(
-        _task_name: &str,
+        task_name: &str,
         commands: &[String],
+        stdins: &[Option<Arc<[u8]>>],
+        live_output: Option<&str>,
         env: &HashMap<String, String>,
         cwd: &Path,
         exec_config: &ExecutorConfig,
-    ) -> Result<String> {
+    ) -> Result<CommandOutput> {
         let mut handles = Vec::new();
 
-        for cmd in commands {
+        for (index, cmd) in commands.iter().enumerate() {
+            let task_name = task_name.to_string();
             let cmd = cmd.clone();
+            let stdin = stdins.get(index).cloned().flatten();
+            let live_output = live_output.map(str::to_string);
             let env = env.clone();
             let cwd = cwd.to_path_buf();
             let exec_config = exec_config.clone();
 
             handles.push(tokio::spawn(async move {
-                Self::execute_command(&cmd, &env, &cwd, &exec_config).await
+                Self::execute_command(
+                    &task_name,
+                    &cmd,
+                    stdin.as_ref(),
+                    live_output.as_deref(),
+                    &env,
+                    &cwd,
+                    &exec_config,
+                )
+                .await
             }));
         }
 
-        let mut all_output = String::new();
-        for handle in handles {
+        let mut all_output = CommandOutput::default();
+        for (index, handle) in handles.into_iter().enumerate() {
             let output = handle
                 .await
-                .map_err(|e| YatrError::Io(std::io::Error::other(e.to_string())))??;
-            all_output.push_str(&output);
-            all_output.push('\n');
+                .map_err(|e| YatrError::Io(std::io::Error::other(e.to_string())))?
+                .map_err(|e| match e {
+                    YatrError::TaskFailed {
+                        task,
+                        code,
+                        command,
+                        stderr,
+                        ..
+                    } => YatrError::TaskFailed {
+                        task,
+                        code,
+                        command,
+                        group_index: Some(index),
+                        stderr,
+                    },
+                    e => e,
+                })?;
+            all_output.stdout.extend_from_slice(&output.stdout);
+            all_output.stdout.push(b'\n');
+            all_output.stderr.push_str(&output.stderr);
         }
 
         Ok(all_output)
     }
 
-    /// Execute a single command
+    /// Execute a single command of `task_name`, feeding it `stdin` if set
+    /// and streaming its output after `live_output` if set
     async fn execute_command(
+        task_name: &str,
         cmd: &str,
+        stdin: Option<&Arc<[u8]>>,
+        live_output: Option<&str>,
         env: &HashMap<String, String>,
         cwd: &Path,
         exec_config: &ExecutorConfig,
-    ) -> Result<String> {
-        let parts = Self::parse_command(cmd, exec_config.shell);
+    ) -> Result<CommandOutput> {
+        let parts = Self::command_args(task_name, cmd, env, cwd, exec_config);
 
         let mut command = if exec_config.shell {
             let shell = if cfg!(windows) { "cmd" } else { "sh" };
@@ -536,25 +1939,232 @@ impl Executor {
             c
         };
 
+        Self::set_env(&mut command, env, exec_config);
         command
             .current_dir(cwd)
-            .envs(env)
             .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
-
-        let output = command.output().await?;
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        let waited = match live_output {
+            Some(lead) => {
+                let secrets = if exec_config.mask_secrets {
+                    Self::secret_values(env, exec_config)
+                } else {
+                    Vec::new()
+                };
+                Self::wait_streaming(command, stdin, lead, &secrets).await
+            }
+            None => Self::wait_for_output(command, stdin).await,
+        };
+        let output = match waited {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && !exec_config.shell => {
+                // A missing `cwd` fails the spawn the same way.
+                if !cwd.is_dir() {
+                    return Err(e.into());
+                }
+                return Err(Self::command_not_found(&parts[0], env));
+            }
+            result => result?,
+        };
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
+            if exec_config.shell && output.status.code() == Some(127) {
+                if let Some(missing) = missing_shell_command(&stderr) {
+                    return Err(Self::command_not_found(missing, env));
+                }
+            }
             return Err(YatrError::TaskFailed {
-                task: cmd.to_string(),
+                task: task_name.to_string(),
                 code: output.status.code().unwrap_or(1),
+                command: Some(cmd.to_string()),
+                group_index: None,
                 stderr: Some(stderr.to_string()),
             });
         }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        Ok(stdout.to_string())
+        Ok(CommandOutput {
+            stdout: output.stdout,
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+
+    /// Spawn `command` and collect its output, feeding it `stdin` if set.
+    async fn wait_for_output(
+        mut command: Command,
+        stdin: Option<&Arc<[u8]>>,
+    ) -> std::io::Result<std::process::Output> {
+        let Some(input) = stdin else {
+            return command.output().await;
+        };
+        command.stdin(Stdio::piped());
+        let mut child = command.spawn()?;
+        Self::feed_stdin(&mut child, input);
+        child.wait_with_output().await
+    }
+
+    /// Write `input` to `child`'s stdin from a separate task, so a command
+    /// that fills its stdout pipe before reading all of stdin can't deadlock.
+    /// A command that exits without reading stdin closes the pipe early; that
+    /// write error is not the command's failure.
+    fn feed_stdin(child: &mut tokio::process::Child, input: &Arc<[u8]>) {
+        if let Some(mut pipe) = child.stdin.take() {
+            let input = Arc::clone(input);
+            tokio::spawn(async move {
+                let _ = pipe.write_all(&input).await;
+            });
+        }
+    }
+
+    /// Like [`wait_for_output`](Self::wait_for_output), but print each line
+    /// of stdout and stderr after `lead` as it arrives, with `secrets` masked.
+    /// The returned output is the complete, unmasked bytes.
+    async fn wait_streaming(
+        mut command: Command,
+        stdin: Option<&Arc<[u8]>>,
+        lead: &str,
+        secrets: &[String],
+    ) -> std::io::Result<std::process::Output> {
+        if stdin.is_some() {
+            command.stdin(Stdio::piped());
+        }
+        let mut child = command.spawn()?;
+        if let Some(input) = stdin {
+            Self::feed_stdin(&mut child, input);
+        }
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let (stdout, stderr, status) = tokio::try_join!(
+            Self::stream_lines(stdout, lead, secrets, false),
+            Self::stream_lines(stderr, lead, secrets, true),
+            child.wait(),
+        )?;
+        Ok(std::process::Output {
+            status,
+            stdout,
+            stderr,
+        })
+    }
+
+    /// Read `pipe` to the end, printing each line after `lead` (to stderr
+    /// when `to_stderr`) and returning everything read. Lines are split on
+    /// bytes, so output that isn't valid UTF-8 is printed lossily instead of
+    /// ending the read.
+    async fn stream_lines(
+        pipe: Option<impl AsyncRead + Unpin>,
+        lead: &str,
+        secrets: &[String],
+        to_stderr: bool,
+    ) -> std::io::Result<Vec<u8>> {
+        let mut all = Vec::new();
+        let Some(pipe) = pipe else {
+            return Ok(all);
+        };
+        let mut reader = BufReader::new(pipe);
+        let mut line = Vec::new();
+        while reader.read_until(b'\n', &mut line).await? > 0 {
+            let mut text = String::from_utf8_lossy(&line)
+                .trim_end_matches(['\n', '\r'])
+                .to_string();
+            mask(&mut text, secrets);
+            if to_stderr {
+                eprintln!("{lead}{text}");
+            } else {
+                println!("{lead}{text}");
+            }
+            all.append(&mut line);
+        }
+        Ok(all)
+    }
+
+    /// A [`YatrError::CommandNotFound`] whose help lists the `PATH` the
+    /// command was looked up in (the task's own, if its `env` sets one) and,
+    /// when the command exists in a well-known directory outside it, the
+    /// `PATH` entry that would find it.
+    fn command_not_found(command: &str, env: &HashMap<String, String>) -> YatrError {
+        let path = env
+            .get("PATH")
+            .cloned()
+            .or_else(|| std::env::var("PATH").ok())
+            .unwrap_or_default();
+        let searched: Vec<PathBuf> = std::env::split_paths(&path)
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .collect();
+
+        let mut help = String::from("Ensure the command is installed and in your PATH.");
+        if searched.is_empty() {
+            help.push_str(" PATH is empty.");
+        } else {
+            help.push_str(" PATH searched:");
+            for dir in &searched {
+                let _ = write!(help, "\n  {}", dir.display());
+            }
+        }
+
+        let elsewhere = well_known_bin_dirs()
+            .into_iter()
+            .filter(|dir| !searched.contains(dir));
+        if let Some(found) = find_executable(command, elsewhere) {
+            let dir = found.parent().unwrap_or(&found).display();
+            let _ = write!(
+                help,
+                "\nFound {} outside PATH; add its directory in the task env:\n  \
+                 PATH = \"${{PATH}}:{dir}\"",
+                found.display()
+            );
+        }
+
+        YatrError::CommandNotFound {
+            command: command.to_string(),
+            help,
+        }
+    }
+
+    /// Give `command` the task's environment. Without `inherit_env`, the
+    /// host environment is dropped except for `PATH`, so programs can still
+    /// be found unless the task sets its own.
+    fn set_env(command: &mut Command, env: &HashMap<String, String>, exec_config: &ExecutorConfig) {
+        if !exec_config.inherit_env {
+            command.env_clear();
+            if let (false, Some(path)) = (env.contains_key("PATH"), std::env::var_os("PATH")) {
+                command.env("PATH", path);
+            }
+        }
+        command.envs(env);
+        if exec_config.sanitize_env {
+            for var in DANGEROUS_ENV_VARS {
+                command.env_remove(var);
+            }
+        }
+    }
+
+    /// The value `name` has in a command's environment as
+    /// [`set_env`](Self::set_env) builds it.
+    fn child_env_var(
+        name: &str,
+        env: &HashMap<String, String>,
+        exec_config: &ExecutorConfig,
+    ) -> Option<String> {
+        if exec_config.sanitize_env && DANGEROUS_ENV_VARS.contains(&name) {
+            return None;
+        }
+        env.get(name).cloned().or_else(|| {
+            (exec_config.inherit_env || name == "PATH").then(|| std::env::var(name).ok())?
+        })
+    }
+
+    /// Dangerous variables that are actually set, on the host or in the config.
+    fn sanitized_vars(config: &Config) -> Vec<&'static str> {
+        DANGEROUS_ENV_VARS
+            .iter()
+            .copied()
+            .filter(|var| {
+                std::env::var_os(var).is_some()
+                    || config.env.contains_key(*var)
+                    || config.tasks.values().any(|t| t.env.contains_key(*var))
+            })
+            .collect()
     }
 
     /// Parse a command string into parts
@@ -562,10 +2172,19 @@ impl Executor {
         if use_shell {
             return vec![cmd.to_string()];
         }
+        Self::parse_words(cmd)
+            .into_iter()
+            .map(|(word, _)| word)
+            .collect()
+    }
 
+    /// Split a command on unquoted spaces, noting for each word whether it
+    /// has an unquoted `*` or `?` (and so is a glob to expand).
+    fn parse_words(cmd: &str) -> Vec<(String, bool)> {
         // Simple shell-like parsing (handles quotes)
         let mut parts = Vec::new();
         let mut current = String::new();
+        let mut is_glob = false;
         let mut in_quotes = false;
         let mut quote_char = '"';
 
@@ -580,38 +2199,113 @@ impl Executor {
                 }
                 ' ' if !in_quotes => {
                     if !current.is_empty() {
-                        parts.push(std::mem::take(&mut current));
+                        parts.push((std::mem::take(&mut current), is_glob));
                     }
+                    is_glob = false;
                 }
                 _ => {
+                    is_glob |= !in_quotes && matches!(c, '*' | '?');
                     current.push(c);
                 }
             }
         }
 
         if !current.is_empty() {
-            parts.push(current);
+            parts.push((current, is_glob));
         }
 
         parts
     }
 
-    /// Print dry-run execution plan
-    #[allow(clippy::unused_self)]
-    fn print_dry_run(&self, plan: &ExecutionPlan) {
-        println!("{}", style("Execution plan (dry run):").bold().cyan());
-        println!();
-
-        for (i, group) in plan.parallel_groups.iter().enumerate() {
-            let parallel_note = if group.len() > 1 { " (parallel)" } else { "" };
-            println!(
-                "{} {}{}",
-                style(format!("Stage {}:", i + 1)).bold(),
-                group
-                    .iter()
-                    .map(|t| t.name.as_str())
-                    .collect::<Vec<_>>()
-                    .join(", "),
+    /// Split a command into the program and its arguments, filling in each
+    /// `${VAR}` left by [`Config::interpolate`] from the command's
+    /// environment and expanding unquoted glob arguments against `cwd`
+    /// (sorted, relative when the pattern is). A pattern matching nothing is
+    /// passed through as-is, with a warning. Shell commands are left whole
+    /// for the shell to expand.
+    fn command_args(
+        task_name: &str,
+        cmd: &str,
+        env: &HashMap<String, String>,
+        cwd: &Path,
+        exec_config: &ExecutorConfig,
+    ) -> Vec<String> {
+        if exec_config.shell {
+            return Self::parse_command(cmd, true);
+        }
+        let lookup = |name: &str| Self::child_env_var(name, env, exec_config);
+        let mut words = Self::parse_words(cmd)
+            .into_iter()
+            .map(|(word, is_glob)| (interpolate_vars(&word, lookup, &mut |_| {}), is_glob));
+        let mut args: Vec<String> = words
+            .next()
+            .map(|(program, _)| program)
+            .into_iter()
+            .collect();
+        for (word, is_glob) in words {
+            if !is_glob {
+                args.push(word);
+                continue;
+            }
+            let mut matches: Vec<String> = glob::glob(&cwd.join(&word).to_string_lossy())
+                .into_iter()
+                .flatten()
+                .filter_map(std::result::Result::ok)
+                .map(|path| {
+                    let path = if Path::new(&word).is_absolute() {
+                        &path
+                    } else {
+                        path.strip_prefix(cwd).unwrap_or(&path)
+                    };
+                    path.to_string_lossy().into_owned()
+                })
+                .collect();
+            if matches.is_empty() {
+                if !exec_config.quiet && !exec_config.json {
+                    let warning = ConfigWarning {
+                        task: task_name.to_string(),
+                        message: format!("'{word}' matched no files; passing it on unexpanded"),
+                    };
+                    eprintln!("{} {warning}", style("warning:").yellow().bold());
+                }
+                args.push(word);
+            } else {
+                matches.sort();
+                args.append(&mut matches);
+            }
+        }
+        args
+    }
+
+    /// Durations of each task's last cached run, used as timeline estimates.
+    fn recorded_durations(&self, graph: &TaskGraph) -> HashMap<String, Duration> {
+        let Some(cache) = &self.cache else {
+            return HashMap::new();
+        };
+        graph
+            .task_names()
+            .filter_map(|name| {
+                let last = cache.last_result(name).ok().flatten()?;
+                Some((name.to_string(), Duration::from_millis(last.duration_ms)))
+            })
+            .collect()
+    }
+
+    /// Print dry-run execution plan
+    fn print_dry_run(&self, plan: &ExecutionPlan) {
+        println!("{}", style("Execution plan (dry run):").bold().cyan());
+        println!();
+
+        for (i, group) in plan.parallel_groups.iter().enumerate() {
+            let parallel_note = if group.len() > 1 { " (parallel)" } else { "" };
+            println!(
+                "{} {}{}",
+                style(format!("Stage {}:", i + 1)).bold(),
+                group
+                    .iter()
+                    .map(|t| t.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
                 style(parallel_note).dim()
             );
 
@@ -629,12 +2323,117 @@ impl Executor {
                 }
             }
         }
+
+        if self.exec_config.timing {
+            println!();
+            Self::print_timeline(plan);
+        }
+    }
+
+    /// Print a Gantt-style timeline of the plan's estimated start/end times.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    fn print_timeline(plan: &ExecutionPlan) {
+        const WIDTH: usize = 40;
+
+        let base = Instant::now();
+        let starts = plan.assign_start_times(base);
+        let spans: Vec<(&str, Duration, Duration)> = plan
+            .tasks
+            .iter()
+            .map(|t| {
+                let start = starts.get(&t.name).map_or(Duration::ZERO, |s| *s - base);
+                (t.name.as_str(), start, plan.estimated_duration(&t.name))
+            })
+            .collect();
+        let total = spans
+            .iter()
+            .map(|(_, start, dur)| *start + *dur)
+            .max()
+            .unwrap_or_default();
+        if total.is_zero() {
+            return;
+        }
+
+        let scale = WIDTH as f64 / total.as_secs_f64();
+        let col = |d: Duration| ((d.as_secs_f64() * scale).round() as usize).min(WIDTH);
+        let name_width = spans.iter().map(|(n, _, _)| n.len()).max().unwrap_or(0);
+
+        println!("{}", style("Estimated timeline:").bold());
+        for (name, start, dur) in &spans {
+            let len = (col(*start + *dur) - col(*start)).max(1);
+            let from = col(*start).min(WIDTH - len);
+            let hinted = plan.estimated_durations.contains_key(*name);
+            println!(
+                "  {name:<name_width$}  {}{}{}  {}",
+                " ".repeat(from),
+                style("█".repeat(len)).cyan(),
+                " ".repeat(WIDTH.saturating_sub(from + len)),
+                style(format!(
+                    "{:.1}s{}",
+                    dur.as_secs_f64(),
+                    if hinted { "" } else { " (est.)" }
+                ))
+                .dim()
+            );
+        }
+        println!(
+            "  {:name_width$}  {}",
+            "",
+            style(format!(
+                "0s{:>width$}",
+                format!("{:.1}s", total.as_secs_f64()),
+                width = WIDTH - 2
+            ))
+            .dim()
+        );
+
+        Self::print_efficiency(plan);
+    }
+
+    /// Print the plan's parallel efficiency and its least balanced stage, by
+    /// the plan's estimated durations
+    fn print_efficiency(plan: &ExecutionPlan) {
+        if let Some(efficiency) = plan.parallel_efficiency(&plan.estimated_durations) {
+            println!(
+                "{} {:.0}%",
+                style("Parallel efficiency:").bold(),
+                efficiency * 100.0
+            );
+        }
+        if let Some(group) = plan.bottleneck_group() {
+            let names: Vec<&str> = plan.parallel_groups[group]
+                .iter()
+                .map(|t| t.name.as_str())
+                .collect();
+            println!(
+                "  {}",
+                style(format!(
+                    "Stage {} is the least balanced: {}",
+                    group + 1,
+                    names.join(", ")
+                ))
+                .dim()
+            );
+        }
     }
 
-    /// Print result of a single task
-    fn print_task_result(result: &TaskResult) {
+    /// Print result of a single task, with `prefix` before each output line
+    /// (indented instead when `None`) and `timing` (its earlier runs) after
+    /// the duration. Output that was `streamed` isn't repeated.
+    fn print_task_result(
+        result: &TaskResult,
+        prefix: Option<&str>,
+        timing: Option<TimingStats>,
+        streamed: bool,
+    ) {
         let status = if result.success {
-            if result.cached {
+            if result.skipped {
+                style("- skipped").dim()
+            } else if result.cached {
                 style("✓ cached").green()
             } else {
                 style("✓").green()
@@ -643,7 +2442,18 @@ impl Executor {
             style("✗").red()
         };
 
-        let duration = format!("{:.2}s", result.duration.as_secs_f64());
+        // A cache hit takes no time worth showing; say how fresh it is instead
+        let mut duration = match (result.cache_created_at, result.cache_duration_ms) {
+            (Some(created), Some(ms)) => format!(
+                "({}, originally took {:.1}s)",
+                time_ago(created, chrono::Utc::now()),
+                Duration::from_millis(ms).as_secs_f64()
+            ),
+            _ => format!("{:.2}s", result.duration.as_secs_f64()),
+        };
+        if let Some(timing) = timing {
+            let _ = write!(duration, " ({timing})");
+        }
 
         println!(
             "{} {} {}",
@@ -653,33 +2463,41 @@ impl Executor {
         );
 
         if let Some(error) = &result.error {
-            eprintln!("  {}", style(error).red());
+            for line in error.lines() {
+                eprintln!("  {}", style(line).red());
+            }
         }
 
         // Print command output if present
-        if let Some(output) = &result.output {
+        if let Some(output) = result.output.as_ref().filter(|_| !streamed) {
             let trimmed = output.trim();
             if !trimmed.is_empty() {
                 for line in trimmed.lines() {
-                    println!("  {line}");
+                    match prefix {
+                        Some(prefix) => println!("{prefix} {line}"),
+                        None => println!("  {line}"),
+                    }
                 }
             }
         }
     }
 
-    /// Print execution summary
-    #[allow(clippy::unused_self)]
-    fn print_summary(&self, results: &[TaskResult]) {
+    /// Print execution summary, plus the run's parallel efficiency with `--timing`
+    fn print_summary(&self, results: &[TaskResult], plan: ExecutionPlan) {
         println!();
 
         let total: Duration = results.iter().map(|r| r.duration).sum();
         let succeeded = results.iter().filter(|r| r.success).count();
         let failed = results.iter().filter(|r| !r.success).count();
         let cached = results.iter().filter(|r| r.cached).count();
+        let skipped = match results.iter().filter(|r| r.skipped).count() {
+            0 => String::new(),
+            n => format!(", {n} skipped"),
+        };
 
         if failed == 0 {
             println!(
-                "{} {} tasks completed in {:.2}s ({} cached)",
+                "{} {} tasks completed in {:.2}s ({} cached{skipped})",
                 style("✓").green().bold(),
                 succeeded,
                 total.as_secs_f64(),
@@ -694,9 +2512,95 @@ impl Executor {
                 total.as_secs_f64()
             );
         }
+
+        if let Some(cache) = self.cache.as_ref().filter(|c| c.is_enabled()) {
+            let stats = cache.session_stats();
+            if stats.hits + stats.misses > 0 {
+                println!("  Cache hit rate: {stats}");
+            }
+        }
+
+        // With `--timing`, rate the run by how long its tasks actually took.
+        if self.exec_config.timing {
+            let durations = results
+                .iter()
+                .map(|r| (r.name.clone(), r.duration))
+                .collect();
+            Self::print_efficiency(&plan.with_estimated_durations(durations));
+        }
     }
 }
 
+/// The command a shell reported as missing, from `sh: 1: foo: not found`
+/// (dash), `bash: line 1: foo: command not found` or `zsh:1: command not
+/// found: foo`.
+fn missing_shell_command(stderr: &str) -> Option<&str> {
+    stderr.lines().find_map(|line| {
+        let line = line.trim();
+        if let Some((_, name)) = line.split_once("command not found: ") {
+            return Some(name.trim());
+        }
+        line.strip_suffix(": command not found")
+            .or_else(|| line.strip_suffix(": not found"))
+            .and_then(|rest| rest.rsplit(": ").next())
+            .map(str::trim)
+    })
+}
+
+/// Directories that commonly hold installed programs but are often missing
+/// from `PATH` (e.g. in CI images or non-login shells).
+fn well_known_bin_dirs() -> Vec<PathBuf> {
+    let home = directories::BaseDirs::new().map(|d| d.home_dir().to_path_buf());
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    if cfg!(windows) {
+        dirs.push(PathBuf::from(r"C:\ProgramData\chocolatey\bin"));
+        if let Some(home) = &home {
+            dirs.extend([".cargo\\bin", "scoop\\shims"].map(|d| home.join(d)));
+        }
+    } else {
+        dirs.extend(
+            [
+                "/usr/local/bin",
+                "/usr/local/sbin",
+                "/usr/bin",
+                "/usr/sbin",
+                "/bin",
+                "/sbin",
+                "/opt/homebrew/bin",
+                "/opt/local/bin",
+                "/snap/bin",
+            ]
+            .map(PathBuf::from),
+        );
+        if let Some(home) = &home {
+            dirs.extend([".cargo/bin", ".local/bin", "go/bin", "bin"].map(|d| home.join(d)));
+        }
+        if let Ok(opt) = glob::glob("/opt/*/bin") {
+            dirs.extend(opt.filter_map(std::result::Result::ok));
+        }
+    }
+    dirs
+}
+
+/// The first `dirs` entry holding a file named `command` (or, on Windows,
+/// `command.exe` / `.cmd` / `.bat`). Commands given as a path aren't searched.
+fn find_executable(command: &str, dirs: impl IntoIterator<Item = PathBuf>) -> Option<PathBuf> {
+    if command.contains(['/', '\\']) {
+        return None;
+    }
+    let names: Vec<String> = if cfg!(windows) {
+        ["", ".exe", ".cmd", ".bat"]
+            .iter()
+            .map(|ext| format!("{command}{ext}"))
+            .collect()
+    } else {
+        vec![command.to_string()]
+    };
+    dirs.into_iter()
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|candidate| candidate.is_file())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -713,6 +2617,60 @@ mod tests {
         assert_eq!(parts, vec!["echo", "hello world"]);
     }
 
+    #[test]
+    fn command_args_expand_unquoted_globs() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        for file in ["src/b.rs", "src/a.rs", "src/c.txt"] {
+            std::fs::write(dir.path().join(file), "").unwrap();
+        }
+        let exec_config = ExecutorConfig {
+            quiet: true,
+            ..Default::default()
+        };
+        let env = HashMap::new();
+        let args = |cmd: &str| Executor::command_args("t", cmd, &env, dir.path(), &exec_config);
+
+        assert_eq!(
+            args("rustfmt src/*.rs"),
+            ["rustfmt", "src/a.rs", "src/b.rs"]
+        );
+        assert_eq!(args("ls **/?.txt"), ["ls", "src/c.txt"]);
+        assert_eq!(args("grep 'a*' src/*.md"), ["grep", "a*", "src/*.md"]);
+        let shell = ExecutorConfig {
+            shell: true,
+            ..exec_config.clone()
+        };
+        assert_eq!(
+            Executor::command_args("t", "ls *.rs", &HashMap::new(), dir.path(), &shell),
+            ["ls *.rs"]
+        );
+    }
+
+    #[test]
+    fn command_args_fill_in_the_command_env() {
+        std::env::set_var("COMMAND_ARGS_TEST_HOST", "host");
+        let env = HashMap::from([("NAME".to_string(), "a b".to_string())]);
+        let inherit = ExecutorConfig::default();
+        let args = |cmd: &str, exec_config: &ExecutorConfig| {
+            Executor::command_args("t", cmd, &env, Path::new("."), exec_config)
+        };
+
+        assert_eq!(args("echo ${NAME}", &inherit), ["echo", "a b"]);
+        assert_eq!(
+            args("echo x=${COMMAND_ARGS_TEST_HOST} ${UNSET_VAR}", &inherit),
+            ["echo", "x=host", "${UNSET_VAR}"]
+        );
+        let hermetic = ExecutorConfig {
+            inherit_env: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            args("echo ${COMMAND_ARGS_TEST_HOST}", &hermetic),
+            ["echo", "${COMMAND_ARGS_TEST_HOST}"]
+        );
+    }
+
     #[tokio::test]
     async fn ready_queue_runs_full_diamond_dag() {
         // a → {b, c} → d. All four must run, each after its dependencies.
@@ -747,4 +2705,1114 @@ mod tests {
         let latest = results.iter().max_by_key(|r| r.start_offset).unwrap();
         assert_eq!(latest.name, "d");
     }
+
+    #[test]
+    fn test_should_retry_honours_patterns() {
+        let task: TaskConfig = toml::from_str(
+            r#"
+            run = ["true"]
+            retry_on = ["connection refused", "timed? ?out"]
+            retry_abort_on = ["assertion failed"]
+            "#,
+        )
+        .unwrap();
+        let failed = |stderr: &str| YatrError::TaskFailed {
+            task: "t".to_string(),
+            code: 1,
+            command: None,
+            group_index: None,
+            stderr: Some(stderr.to_string()),
+        };
+
+        assert!(Executor::should_retry(
+            &task,
+            &failed("error: connection refused")
+        ));
+        assert!(Executor::should_retry(&task, &failed("request timed out")));
+        assert!(!Executor::should_retry(
+            &task,
+            &failed("assertion failed: x == y")
+        ));
+        assert!(!Executor::should_retry(
+            &task,
+            &failed("connection refused; assertion failed")
+        ));
+        assert!(!Executor::should_retry(&task, &failed("syntax error")));
+    }
+
+    #[tokio::test]
+    async fn retries_only_matching_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let toml = r#"
+            [tasks.flaky]
+            shell = true
+            retries = 2
+            retry_on = ["connection refused"]
+            run = ["echo x >> flaky.log; echo 'connection refused' >&2; exit 1"]
+            [tasks.broken]
+            shell = true
+            retries = 2
+            retry_on = ["connection refused"]
+            run = ["echo x >> broken.log; echo 'assertion failed' >&2; exit 1"]
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let exec_config = ExecutorConfig {
+            json: true,
+            cwd: dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let executor = Executor::new(config, exec_config, None);
+
+        assert!(executor.execute(&graph, "flaky").await.is_err());
+        assert!(executor.execute(&graph, "broken").await.is_err());
+
+        let attempts = |file: &str| {
+            std::fs::read_to_string(dir.path().join(file))
+                .unwrap()
+                .lines()
+                .count()
+        };
+        assert_eq!(attempts("flaky.log"), 3);
+        assert_eq!(attempts("broken.log"), 1);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn retried_task_succeeds_after_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let toml = r#"
+            [tasks.fetch]
+            shell = true
+            retries = 3
+            retry_delay_ms = 50
+            run = ["echo x >> attempts.log; test $(wc -l < attempts.log) -ge 3"]
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let exec_config = ExecutorConfig {
+            json: true,
+            cwd: dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let executor = Executor::new(config, exec_config, None);
+
+        let start = Instant::now();
+        let results = executor.execute(&graph, "fetch").await.unwrap();
+        assert!(results[0].success);
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        let log = std::fs::read_to_string(dir.path().join("attempts.log")).unwrap();
+        assert_eq!(log.lines().count(), 3);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn timeout_stops_a_hung_task() {
+        let config: Config =
+            toml::from_str("[tasks.hang]\nrun = [\"sleep 10\"]\ntimeout = 1\n").unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let task = graph.get_task("hang").unwrap();
+        let exec_config = ExecutorConfig::default();
+
+        let start = Instant::now();
+        let result = Executor::run_with_retries(
+            task,
+            &config,
+            &HashMap::new(),
+            &exec_config.cwd,
+            &exec_config,
+            &Mutex::default(),
+            &TaskRun::default(),
+        )
+        .await;
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert!(
+            matches!(&result, Err(YatrError::TaskTimeout { task, seconds: 1 }) if task == "hang"),
+            "{:?}",
+            result.err()
+        );
+    }
+
+    #[tokio::test]
+    async fn outputs_cleanup_removes_stale_artifacts() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("dist/sub")).unwrap();
+        std::fs::write(dir.path().join("dist/sub/old.bin"), b"stale").unwrap();
+        std::fs::write(dir.path().join("app.bin"), b"stale").unwrap();
+        std::fs::write(dir.path().join("keep.txt"), b"keep").unwrap();
+
+        let toml = r#"
+            [tasks.build]
+            outputs = ["dist", "*.bin"]
+            outputs_cleanup = true
+            run = ["false"]
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let exec_config = ExecutorConfig {
+            json: true,
+            cwd: dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let executor = Executor::new(config, exec_config, None);
+
+        // The run fails, but the old artifacts must already be gone.
+        assert!(executor.execute(&graph, "build").await.is_err());
+        assert!(!dir.path().join("dist").exists());
+        assert!(!dir.path().join("app.bin").exists());
+        assert!(dir.path().join("keep.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn execute_raw_runs_ad_hoc_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let exec_config = ExecutorConfig {
+            json: true,
+            shell: true,
+            ..Default::default()
+        };
+        let config: Config = toml::from_str("").unwrap();
+        let executor = Executor::new(config, exec_config, None);
+        let env = HashMap::from([("GREETING".to_string(), "hi".to_string())]);
+
+        let ok = executor
+            .execute_raw(
+                &["echo $GREETING".to_string(), "pwd".to_string()],
+                &env,
+                dir.path(),
+            )
+            .await
+            .unwrap();
+        assert!(ok.success);
+        let output = ok.output.unwrap();
+        assert!(output.starts_with("hi\n"));
+        assert!(output.contains(&*dir.path().file_name().unwrap().to_string_lossy()));
+
+        let failed = executor
+            .execute_raw(&["exit 3".to_string()], &env, dir.path())
+            .await
+            .unwrap();
+        assert!(!failed.success);
+        assert_eq!(failed.exit_code, Some(3));
+    }
+
+    #[tokio::test]
+    async fn inherit_env_false_keeps_only_declared_vars_and_path() {
+        std::env::set_var("YATR_TEST_HOST_ONLY", "leaked");
+        let toml = r#"
+            [settings]
+            inherit_env = false
+            [env]
+            GLOBAL = "g"
+            [tasks.show]
+            shell = true
+            env = { LOCAL = "l" }
+            run = ["echo \"$GLOBAL $LOCAL [$YATR_TEST_HOST_ONLY]\"; command -v sh"]
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let exec_config = ExecutorConfig {
+            json: true,
+            ..Default::default()
+        };
+        let executor = Executor::new(config, exec_config, None);
+
+        let results = executor.execute(&graph, "show").await.unwrap();
+        let output = results[0].output.as_deref().unwrap();
+        assert!(output.starts_with("g l []\n"), "{output}");
+        assert!(output.contains("/sh"), "PATH should still be inherited");
+    }
+
+    #[tokio::test]
+    async fn commands_see_context_env_vars() {
+        let toml = r#"
+            [tasks.a]
+            shell = true
+            env = { YATR_TASK_NAME = "overridden" }
+            run = ["echo $YATR_TASK_NAME $YATR_RUN_ID $YATR_VERBOSE${YATR_DRY_RUN-}${YATR_CACHE_HIT-}"]
+            [tasks.b]
+            shell = true
+            depends = ["a"]
+            run = ["echo $YATR_TASK_NAME $YATR_RUN_ID"]
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let exec_config = ExecutorConfig {
+            json: true,
+            verbose: true,
+            ..Default::default()
+        };
+        let executor = Executor::new(config, exec_config, None);
+
+        let run = |results: &[TaskResult]| -> Vec<Vec<String>> {
+            results
+                .iter()
+                .map(|r| {
+                    let output = r.output.as_deref().unwrap();
+                    output.split_whitespace().map(String::from).collect()
+                })
+                .collect()
+        };
+        let first = run(&executor.execute(&graph, "b").await.unwrap());
+        assert_eq!(first[0][0], "a");
+        assert_eq!(first[0][2], "1", "only YATR_VERBOSE is a flag");
+        assert_eq!(first[1][0], "b");
+        let run_id = &first[0][1];
+        assert_eq!(run_id.len(), 36);
+        assert_eq!(&run_id[14..15], "4");
+        assert_eq!(&first[1][1], run_id, "one id per run");
+
+        let second = run(&executor.execute(&graph, "a").await.unwrap());
+        assert_ne!(&second[0][1], run_id);
+    }
+
+    #[tokio::test]
+    async fn condition_script_skips_task_when_falsy() {
+        let toml = r#"
+            [env]
+            DEPLOY = "no"
+            [tasks.deploy]
+            condition_script = 'env.DEPLOY == "yes"'
+            run = ["false"]
+            [tasks.notify]
+            depends = ["deploy"]
+            condition_script = "task.depends.len()"
+            run = ["echo sent"]
+            [tasks.broken]
+            condition_script = "undefined_var"
+            run = ["echo never"]
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let exec_config = ExecutorConfig {
+            json: true,
+            ..Default::default()
+        };
+        let executor = Executor::new(config, exec_config, None);
+
+        let results = executor.execute(&graph, "notify").await.unwrap();
+        assert!(results[0].skipped && results[0].success);
+        assert!(!results[1].skipped);
+        assert_eq!(results[1].output.as_deref(), Some("sent\n\n"));
+
+        let results = executor.execute(&graph, "broken").await;
+        assert!(results.is_err(), "a failing condition fails the task");
+    }
+
+    #[tokio::test]
+    async fn execute_all_runs_shared_dependencies_once() {
+        let toml = r#"
+            [tasks.build]
+            run = ["echo built"]
+            [tasks.test]
+            depends = ["build"]
+            run = ["echo tested"]
+            [tasks.clippy]
+            depends = ["build"]
+            run = ["echo linted"]
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let exec_config = ExecutorConfig {
+            json: true,
+            ..Default::default()
+        };
+        let executor = Executor::new(config, exec_config, None);
+
+        let results = executor
+            .execute_all(&graph, &["test", "clippy"])
+            .await
+            .unwrap();
+        let mut names: Vec<&str> = results.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names[0], "build");
+        names.sort_unstable();
+        assert_eq!(names, vec!["build", "clippy", "test"]);
+    }
+
+    #[tokio::test]
+    async fn pre_exec_check_skips_task_when_it_succeeds() {
+        let toml = r#"
+            [tasks.fresh]
+            pre_exec_check = "exit 0"
+            run = ["false"]
+            [tasks.stale]
+            pre_exec_check = "exit 3"
+            run = ["echo built"]
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let exec_config = ExecutorConfig {
+            json: true,
+            ..Default::default()
+        };
+        let executor = Executor::new(config, exec_config, None);
+
+        let results = executor.execute(&graph, "fresh").await.unwrap();
+        assert!(results[0].skipped && results[0].success);
+        let results = executor.execute(&graph, "stale").await.unwrap();
+        assert!(!results[0].skipped);
+        assert_eq!(results[0].output.as_deref(), Some("built\n\n"));
+    }
+
+    #[tokio::test]
+    async fn force_runs_tasks_despite_pre_exec_check() {
+        let config: Config =
+            toml::from_str("[tasks.fresh]\npre_exec_check = \"exit 0\"\nrun = [\"echo built\"]\n")
+                .unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let exec_config = ExecutorConfig {
+            json: true,
+            force: true,
+            ..Default::default()
+        };
+        let executor = Executor::new(config, exec_config, None);
+
+        let results = executor.execute(&graph, "fresh").await.unwrap();
+        assert!(!results[0].skipped);
+        assert_eq!(results[0].output.as_deref(), Some("built\n\n"));
+    }
+
+    #[tokio::test]
+    async fn run_if_changed_skips_until_inputs_change() {
+        let store = tempfile::tempdir().unwrap();
+        let work = tempfile::tempdir().unwrap();
+        std::fs::write(work.path().join("schema.sql"), "v1").unwrap();
+        let mut config: Config = toml::from_str(
+            r#"
+            [tasks.migrate]
+            run_if_changed = ["*.sql"]
+            run = ["echo migrated"]
+        "#,
+        )
+        .unwrap();
+        config.settings.cache_dir = Some(store.path().to_path_buf());
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let exec_config = ExecutorConfig {
+            json: true,
+            cwd: work.path().to_path_buf(),
+            ..Default::default()
+        };
+        let executor = Executor::new(config, exec_config, None);
+
+        let results = executor.execute(&graph, "migrate").await.unwrap();
+        assert!(!results[0].skipped && results[0].success);
+        let results = executor.execute(&graph, "migrate").await.unwrap();
+        assert!(results[0].skipped && results[0].success);
+
+        std::fs::write(work.path().join("schema.sql"), "v2").unwrap();
+        let results = executor.execute(&graph, "migrate").await.unwrap();
+        assert!(!results[0].skipped);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn run_if_changed_tracks_files_the_task_writes() {
+        let store = tempfile::tempdir().unwrap();
+        let work = tempfile::tempdir().unwrap();
+        let mut config: Config = toml::from_str(
+            r#"
+            [tasks.gen]
+            run_if_changed = ["*.gen"]
+            run = ["sh -c 'echo line >> out.gen'"]
+        "#,
+        )
+        .unwrap();
+        config.settings.cache_dir = Some(store.path().to_path_buf());
+        let exec_config = ExecutorConfig {
+            json: true,
+            cwd: work.path().to_path_buf(),
+            ..Default::default()
+        };
+        let run = |config: Config| {
+            let graph = TaskGraph::from_config(&config).unwrap();
+            let executor = Executor::new(config, exec_config.clone(), None);
+            async move { executor.execute(&graph, "gen").await.unwrap() }
+        };
+
+        assert!(!run(config.clone()).await[0].skipped);
+        assert!(run(config.clone()).await[0].skipped);
+
+        // Editing the command runs it again.
+        config.tasks.get_mut("gen").unwrap().run = vec!["sh -c 'echo new >> out.gen'".into()];
+        assert!(!run(config.clone()).await[0].skipped);
+        assert!(run(config).await[0].skipped);
+    }
+
+    #[tokio::test]
+    async fn verbose_runs_record_timing_history() {
+        let store = tempfile::tempdir().unwrap();
+        let work = tempfile::tempdir().unwrap();
+        let mut config: Config = toml::from_str("[tasks.build]\nrun = [\"true\"]\n").unwrap();
+        config.settings.cache_dir = Some(store.path().to_path_buf());
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let exec_config = ExecutorConfig {
+            verbose: true,
+            cwd: work.path().to_path_buf(),
+            ..Default::default()
+        };
+        let executor = Executor::new(config, exec_config, None);
+
+        executor.execute(&graph, "build").await.unwrap();
+        executor.execute(&graph, "build").await.unwrap();
+
+        let history = TimingHistory::load(Some(store.path()), work.path());
+        assert_eq!(history.stats("build").unwrap().runs, 2);
+    }
+
+    #[tokio::test]
+    async fn secret_env_values_are_masked_in_output() {
+        let toml = r#"
+            [tasks.deploy]
+            env = { DEPLOY_TOKEN = "s3cr3t-value", VAULT_ROLE = "deployer" }
+            run = ["echo token=s3cr3t-value role=deployer"]
+        "#;
+        let run = |exec_config: ExecutorConfig| async {
+            let config: Config = toml::from_str(toml).unwrap();
+            let graph = TaskGraph::from_config(&config).unwrap();
+            let executor = Executor::new(config, exec_config, None);
+            let results = executor.execute(&graph, "deploy").await.unwrap();
+            results[0].output.clone().unwrap()
+        };
+
+        let masked = run(ExecutorConfig::builder().json(true).build()).await;
+        assert_eq!(masked.trim(), "token=**** role=deployer");
+
+        let custom = ExecutorConfig::builder()
+            .json(true)
+            .secret_keys(["vault"])
+            .build();
+        assert_eq!(run(custom).await.trim(), "token=**** role=****");
+
+        let raw = ExecutorConfig::builder()
+            .json(true)
+            .mask_secrets(false)
+            .build();
+        assert_eq!(run(raw).await.trim(), "token=s3cr3t-value role=deployer");
+    }
+
+    #[tokio::test]
+    async fn failures_name_the_failing_command_and_stage() {
+        let toml = r#"
+            [tasks.seq]
+            allow_failure = true
+            run = ["true", "false"]
+            [tasks.par]
+            allow_failure = true
+            parallel = true
+            run = ["true", "false"]
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let exec_config = ExecutorConfig {
+            json: true,
+            ..Default::default()
+        };
+        let executor = Executor::new(config, exec_config, None);
+
+        let results = executor.execute(&graph, "seq").await.unwrap();
+        assert_eq!(
+            results[0].error.as_deref(),
+            Some("Task 'seq' failed with exit code 1\nCommand: `false`")
+        );
+        let results = executor.execute(&graph, "par").await.unwrap();
+        assert_eq!(
+            results[0].error.as_deref(),
+            Some("Task 'par' failed with exit code 1\nCommand: `false`\nStage: 2")
+        );
+    }
+
+    #[tokio::test]
+    async fn scripts_see_completed_task_results() {
+        let toml = r#"
+            [tasks.build]
+            run = ["echo built"]
+            [tasks.summary]
+            depends = ["build"]
+            script = '''
+                for r in task_results {
+                    let output = r.output;
+                    output.trim();
+                    print(`${r.name} ${r.success} ${r.cached} ${output}`);
+                }
+            '''
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let exec_config = ExecutorConfig {
+            json: true,
+            ..Default::default()
+        };
+        let executor = Executor::new(config, exec_config, None);
+
+        let results = executor.execute(&graph, "summary").await.unwrap();
+        assert_eq!(
+            results[1].output.as_deref(),
+            Some("build true false built\n")
+        );
+        // Each run starts with an empty list.
+        let results = executor.execute(&graph, "summary").await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            results[1].output.as_deref(),
+            Some("build true false built\n")
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn root_scripts_never_see_the_previous_run() {
+        let toml = r#"
+            [tasks.build]
+            run = ["echo built"]
+            [tasks.count]
+            script = "print(task_results.len());"
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let exec_config = ExecutorConfig {
+            json: true,
+            ..Default::default()
+        };
+        let executor = Executor::new(config, exec_config, None);
+
+        executor.execute(&graph, "build").await.unwrap();
+        let results = executor.execute(&graph, "count").await.unwrap();
+        assert_eq!(results[0].output.as_deref(), Some("0\n"));
+    }
+
+    #[tokio::test]
+    async fn scripts_see_completed_task_outputs() {
+        let toml = r#"
+            [tasks.version]
+            run = ["echo 1.2.3"]
+            [tasks.lint]
+            condition_script = "false"
+            run = ["echo linted"]
+            [tasks.release]
+            depends = ["version", "lint"]
+            script = '''
+                let version = task_outputs.version;
+                version.trim();
+                print(`releasing ${version}, lint output: ${"lint" in task_outputs}`);
+            '''
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let exec_config = ExecutorConfig {
+            json: true,
+            ..Default::default()
+        };
+        let executor = Executor::new(config, exec_config, None);
+
+        let results = executor.execute(&graph, "release").await.unwrap();
+        let release = results.iter().find(|r| r.name == "release").unwrap();
+        assert_eq!(
+            release.output.as_deref(),
+            Some("releasing 1.2.3, lint output: false\n")
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn streamed_commands_still_capture_all_output() {
+        let cwd = std::env::current_dir().unwrap();
+        let exec_config = ExecutorConfig {
+            shell: true,
+            ..Default::default()
+        };
+        let output = Executor::execute_command(
+            "t",
+            "printf 'one\\ntwo\\n'; printf 'warn\\n' >&2; printf 'no newline'",
+            None,
+            Some("  "),
+            &HashMap::new(),
+            &cwd,
+            &exec_config,
+        )
+        .await
+        .unwrap();
+        assert_eq!(output.stdout, b"one\ntwo\nno newline");
+        assert_eq!(output.stderr, "warn\n");
+
+        let stdin: Arc<[u8]> = b"piped\n".as_slice().into();
+        let output = Executor::execute_command(
+            "t",
+            "cat",
+            Some(&stdin),
+            Some("  "),
+            &HashMap::new(),
+            &cwd,
+            &exec_config,
+        )
+        .await
+        .unwrap();
+        assert_eq!(output.stdout, b"piped\n");
+
+        let err = Executor::execute_command(
+            "t",
+            "echo partial; exit 3",
+            None,
+            Some("  "),
+            &HashMap::new(),
+            &cwd,
+            &exec_config,
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            matches!(err, YatrError::TaskFailed { code: 3, .. }),
+            "{err:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_commands_list_the_searched_path() {
+        let cwd = std::env::current_dir().unwrap();
+        let env = HashMap::from([("PATH".to_string(), "/no/such/a:/no/such/b".to_string())]);
+        let err = Executor::execute_command(
+            "t",
+            "yatr-no-such-cmd x",
+            None,
+            None,
+            &env,
+            &cwd,
+            &ExecutorConfig::default(),
+        )
+        .await
+        .unwrap_err();
+        let YatrError::CommandNotFound { command, help } = err else {
+            panic!("expected CommandNotFound, got {err:?}");
+        };
+        assert_eq!(command, "yatr-no-such-cmd");
+        assert!(
+            help.contains("PATH searched:\n  /no/such/a\n  /no/such/b"),
+            "{help}"
+        );
+
+        if cfg!(unix) {
+            let shell = ExecutorConfig {
+                shell: true,
+                ..Default::default()
+            };
+            let err = Executor::execute_command(
+                "t",
+                "true && yatr-no-such-cmd",
+                None,
+                None,
+                &HashMap::new(),
+                &cwd,
+                &shell,
+            )
+            .await
+            .unwrap_err();
+            assert!(
+                matches!(&err, YatrError::CommandNotFound { command, .. } if command == "yatr-no-such-cmd"),
+                "{err:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn missing_command_detection_helpers() {
+        assert_eq!(
+            missing_shell_command("sh: 1: foo: not found\n"),
+            Some("foo")
+        );
+        assert_eq!(
+            missing_shell_command("bash: line 1: foo: command not found"),
+            Some("foo")
+        );
+        assert_eq!(
+            missing_shell_command("zsh:1: command not found: foo"),
+            Some("foo")
+        );
+        assert_eq!(missing_shell_command("error: boom"), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("tool"), "").unwrap();
+        let dirs = || vec![PathBuf::from("/no/such/dir"), dir.path().to_path_buf()];
+        assert_eq!(
+            find_executable("tool", dirs()),
+            Some(dir.path().join("tool"))
+        );
+        assert_eq!(find_executable("other", dirs()), None);
+        assert_eq!(find_executable("./tool", dirs()), None);
+    }
+
+    #[test]
+    fn builder_sets_fields_and_keeps_defaults() {
+        let config = ExecutorConfig::builder()
+            .verbose(true)
+            .dry_run(true)
+            .parallelism(4)
+            .cwd("/tmp/project")
+            .report(ReportFormat::Junit, "report.xml")
+            .build();
+        assert!(config.verbose && config.dry_run);
+        assert_eq!(config.parallelism, 4);
+        assert_eq!(config.cwd, PathBuf::from("/tmp/project"));
+        assert_eq!(
+            config.report,
+            Some((ReportFormat::Junit, PathBuf::from("report.xml")))
+        );
+        assert!(config.inherit_env && !config.force && config.output_dir.is_none());
+    }
+
+    #[tokio::test]
+    async fn stdin_and_stdin_file_feed_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("token.txt"), "from-file").unwrap();
+        let toml = r#"
+            [tasks.inline]
+            stdin = "secret\n"
+            run = ["cat", "cat"]
+            [tasks.file]
+            stdin_file = "token.txt"
+            parallel = true
+            run = ["cat"]
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let exec_config = ExecutorConfig {
+            json: true,
+            cwd: dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let executor = Executor::new(config, exec_config, None);
+
+        let output = |task: &str| {
+            let executor = &executor;
+            let graph = &graph;
+            let task = task.to_string();
+            async move {
+                let results = executor.execute(graph, &task).await.unwrap();
+                results[0].output.clone().unwrap()
+            }
+        };
+        assert_eq!(output("inline").await, "secret\n\nsecret\n\n");
+        assert_eq!(output("file").await, "from-file\n");
+    }
+
+    #[test]
+    fn output_prefixes_align_task_names() {
+        let config: Config =
+            toml::from_str("[tasks.a]\nrun = [\"true\"]\n[tasks.build]\nrun = [\"true\"]\n")
+                .unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let tasks = graph.all_tasks_ordered().unwrap();
+        let prefixes = |exec_config: ExecutorConfig| {
+            Executor::new(config.clone(), exec_config, None).output_prefixes(&tasks)
+        };
+
+        let parallel = prefixes(ExecutorConfig::builder().parallelism(2).build());
+        let plain = |name: &str| console::strip_ansi_codes(&parallel[name]).into_owned();
+        assert_eq!(plain("a"), "[    a]");
+        assert_eq!(plain("build"), "[build]");
+
+        let serial = prefixes(ExecutorConfig::builder().parallelism(1).build());
+        assert!(serial.is_empty());
+        let off = ExecutorConfig::builder()
+            .parallelism(2)
+            .prefix_output(false)
+            .build();
+        assert!(prefixes(off).is_empty());
+    }
+
+    #[test]
+    fn streamed_output_stays_attributable() {
+        let config: Config = toml::from_str(
+            "[tasks.a]\nrun = [\"true\"]\n[tasks.b]\nparallel = true\nrun = [\"true\"]\n",
+        )
+        .unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let tasks = graph.all_tasks_ordered().unwrap();
+        let live = |exec_config: ExecutorConfig, name: &str| {
+            let executor = Executor::new(config.clone(), exec_config, None);
+            let prefixes = executor.output_prefixes(&tasks);
+            let concurrent = executor.parallelism() > 1;
+            let task = graph.get_task(name).unwrap();
+            executor
+                .live_output(task, &prefixes, concurrent)
+                .map(|lead| console::strip_ansi_codes(&lead).into_owned())
+        };
+
+        let serial = ExecutorConfig::builder().parallelism(1).build();
+        assert_eq!(live(serial.clone(), "a").as_deref(), Some("  "));
+        // A `parallel` task's own commands only stream in verbose mode.
+        assert_eq!(live(serial, "b"), None);
+        let verbose = ExecutorConfig::builder()
+            .parallelism(1)
+            .verbose(true)
+            .build();
+        assert_eq!(live(verbose, "b").as_deref(), Some("  "));
+
+        let prefixed = ExecutorConfig::builder().parallelism(2).build();
+        assert_eq!(live(prefixed, "a").as_deref(), Some("[a] "));
+        // Tasks side by side without prefixes would interleave anonymously.
+        let unprefixed = ExecutorConfig::builder()
+            .parallelism(2)
+            .prefix_output(false)
+            .build();
+        assert_eq!(live(unprefixed, "a"), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unconstrained_runs_ignore_dependency_order() {
+        // `slow` holds out until `app` has run; the timeout turns a
+        // regression into a failure instead of a hang.
+        let toml = r#"
+            [tasks.slow]
+            shell = true
+            timeout = 10
+            run = ["while [ ! -f app-ran ]; do sleep 0.01; done"]
+            [tasks.app]
+            depends = ["slow"]
+            run = ["touch app-ran"]
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let order = |unconstrained: bool| {
+            let exec_config = ExecutorConfig::builder()
+                .parallelism(2)
+                .json(true)
+                .cwd(dir.path())
+                .unconstrained(unconstrained)
+                .build();
+            let executor = Executor::new(config.clone(), exec_config, None);
+            let graph = &graph;
+            async move {
+                let results = executor.execute(graph, "app").await.unwrap();
+                assert!(results.iter().all(|r| r.success), "{results:?}");
+                results.into_iter().map(|r| r.name).collect::<Vec<_>>()
+            }
+        };
+        // With the marker already there, `slow` finishes at once and `app`
+        // still waits for it.
+        std::fs::write(dir.path().join("app-ran"), "").unwrap();
+        assert_eq!(order(false).await, ["slow", "app"]);
+        // `app` no longer waits for `slow`, so it finishes first.
+        std::fs::remove_file(dir.path().join("app-ran")).unwrap();
+        assert_eq!(order(true).await, ["app", "slow"]);
+    }
+
+    #[tokio::test]
+    async fn run_entries_feed_their_own_stdin() {
+        let toml = r#"
+            [tasks.seq]
+            stdin = "task\n"
+            run = [{ cmd = "cat", stdin = "first\n" }, "cat"]
+            [tasks.par]
+            parallel = true
+            run = [{ cmd = "cat", stdin = "a" }, { cmd = "cat", stdin = "b" }]
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let exec_config = ExecutorConfig {
+            json: true,
+            ..Default::default()
+        };
+        let executor = Executor::new(config, exec_config, None);
+
+        let results = executor.execute(&graph, "seq").await.unwrap();
+        assert_eq!(results[0].output.as_deref(), Some("first\n\ntask\n\n"));
+        let results = executor.execute(&graph, "par").await.unwrap();
+        assert_eq!(results[0].output.as_deref(), Some("a\nb\n"));
+    }
+
+    #[tokio::test]
+    async fn sanitize_env_strips_loader_variables() {
+        let toml = r#"
+            [settings]
+            sanitize_env = true
+            [tasks.show]
+            shell = true
+            env = { LD_PRELOAD = "/tmp/evil.so", KEEP = "yes" }
+            run = ["echo \"[$LD_PRELOAD] $KEEP\""]
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let exec_config = ExecutorConfig {
+            json: true,
+            ..Default::default()
+        };
+        let executor = Executor::new(config, exec_config, None);
+
+        let results = executor.execute(&graph, "show").await.unwrap();
+        assert_eq!(results[0].output.as_deref().unwrap().trim(), "[] yes");
+    }
+
+    #[tokio::test]
+    async fn script_state_survives_between_executors_sharing_a_config() {
+        let toml = r#"
+            [tasks.count]
+            script = "state.n = (state.n ?? 0) + 1; print(state.n);"
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let exec_config = ExecutorConfig {
+            json: true,
+            ..Default::default()
+        };
+
+        // Watch mode builds a new executor per run from a clone of the config.
+        for expected in ["1", "2"] {
+            let executor = Executor::new(config.clone(), exec_config.clone(), None);
+            let results = executor.execute(&graph, "count").await.unwrap();
+            assert_eq!(results[0].output.as_deref().unwrap().trim(), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn cache_stats_callback_fires_on_get_and_put() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache_dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("input.txt"), b"data").unwrap();
+
+        let toml = r#"
+            [tasks.build]
+            sources = ["input.txt"]
+            run = ["echo built"]
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let exec_config = ExecutorConfig {
+            json: true,
+            cwd: dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let cache = Cache::new(Some(cache_dir.path().to_path_buf())).unwrap();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let entries = Arc::new(AtomicUsize::new(0));
+        let executor = Executor::new(config, exec_config, Some(cache)).with_cache_stats_callback({
+            let calls = Arc::clone(&calls);
+            let entries = Arc::clone(&entries);
+            move |stats| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                entries.store(stats.entries, Ordering::SeqCst);
+            }
+        });
+
+        // First run: lookup misses, then the result is stored.
+        executor.execute(&graph, "build").await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(entries.load(Ordering::SeqCst) > 0);
+
+        // Second run: a single lookup that hits.
+        let results = executor.execute(&graph, "build").await.unwrap();
+        assert!(results[0].cached);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert!(results[0].cache_created_at.is_some());
+        assert!(results[0].cache_duration_ms.is_some());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn binary_stdout_is_cached_byte_for_byte() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_dir = tempfile::tempdir().unwrap();
+        let toml = r#"
+            [tasks.bin]
+            run = ["printf '\\377\\376bin'"]
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let exec_config = ExecutorConfig {
+            json: true,
+            shell: true,
+            cwd: dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let cache = Cache::new(Some(cache_dir.path().to_path_buf())).unwrap();
+        let task = config.tasks["bin"].clone();
+        let executor = Executor::new(config, exec_config, Some(cache.clone()));
+
+        executor.execute(&graph, "bin").await.unwrap();
+        let cached = cache.get("bin", &task, dir.path()).await.unwrap();
+        assert_eq!(cached.as_deref(), Some(b"\xff\xfebin\n".as_slice()));
+
+        let mut bytes = b"\xff token=hunter22 \xfe".to_vec();
+        mask_bytes(&mut bytes, &["hunter22".to_string()]);
+        assert_eq!(bytes, b"\xff token=**** \xfe");
+    }
+
+    #[tokio::test]
+    async fn force_skips_incremental_pruning() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_dir = tempfile::tempdir().unwrap();
+        let config: Config = toml::from_str("[tasks.build]\nrun = [\"echo built\"]\n").unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let cache = Cache::new(Some(cache_dir.path().to_path_buf())).unwrap();
+        let run = |force| {
+            let exec_config = ExecutorConfig {
+                json: true,
+                incremental: true,
+                force,
+                cwd: dir.path().to_path_buf(),
+                ..Default::default()
+            };
+            Executor::new(config.clone(), exec_config, Some(cache.clone()))
+        };
+
+        run(false).execute(&graph, "build").await.unwrap();
+        let results = run(false).execute(&graph, "build").await.unwrap();
+        assert!(results[0].cached);
+        let results = run(true).execute(&graph, "build").await.unwrap();
+        assert!(!results[0].cached);
+        assert_eq!(results[0].output.as_deref(), Some("built\n\n"));
+    }
+
+    #[test]
+    fn time_ago_uses_the_largest_whole_unit() {
+        let now = chrono::Utc::now();
+        let ago = |secs| time_ago(now - chrono::Duration::seconds(secs), now);
+        assert_eq!(ago(0), "just now");
+        assert_eq!(ago(42), "42s ago");
+        assert_eq!(ago(200), "3m ago");
+        assert_eq!(ago(7300), "2h ago");
+        assert_eq!(ago(3 * 86_400 + 5), "3d ago");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn foreground_tasks_do_not_block_dependents() {
+        let dir = tempfile::tempdir().unwrap();
+        let toml = r#"
+            [tasks.serve]
+            foreground = true
+            shell = true
+            run = ["sleep 0.5 && test -f client-ran && touch serve-saw-client"]
+
+            [tasks.client]
+            depends = ["serve"]
+            run = ["touch client-ran"]
+
+            [tasks.crash]
+            foreground = true
+            shell = true
+            run = ["exit 4"]
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let exec_config = ExecutorConfig {
+            json: true,
+            cwd: dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let executor = Executor::new(config, exec_config, None);
+
+        // `client` runs while `serve` is still up; the run waits for `serve`.
+        executor.execute(&graph, "client").await.unwrap();
+        assert!(dir.path().join("serve-saw-client").exists());
+
+        // A foreground process exiting non-zero still fails the run, under
+        // its task's name.
+        let err = executor.execute(&graph, "crash").await.unwrap_err();
+        assert!(
+            matches!(&err, YatrError::TaskFailed { task, code: 4, .. } if task == "crash"),
+            "{err:?}"
+        );
+    }
 }
@@ -1,24 +1,55 @@
 //! Task graph construction and dependency resolution
 //!
 //! Uses petgraph to build a DAG of tasks and perform topological sorting
-//! to determine execution order.
+//! to determine execution order. Where several orders are valid, ties are
+//! broken alphabetically so plans are stable from run to run.
 
-use petgraph::algo::{is_cyclic_directed, toposort};
+use petgraph::algo::is_cyclic_directed;
 use petgraph::graph::{DiGraph, NodeIndex};
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt::Write as _;
+use std::path::Path;
+use std::time::{Duration, Instant};
 
+use crate::cache::{Cache, CacheStatus};
 use crate::config::{Config, TaskConfig};
 use crate::error::{Result, YatrError};
+use crate::executor::TaskResult;
 
 /// A node in the task graph
 #[derive(Debug, Clone)]
 pub struct TaskNode {
     pub name: String,
     pub config: TaskConfig,
+    /// Set by [`TaskGraph::prune_unchanged`]: the task's cache is valid, so the
+    /// node is a no-op sentinel that succeeds immediately.
+    pub pruned: bool,
+}
+
+/// Aggregate metrics about a [`TaskGraph`] (see [`TaskGraph::stats`]).
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct GraphStats {
+    pub total_tasks: usize,
+    /// Tasks with no dependencies
+    pub root_tasks: usize,
+    /// Tasks nothing depends on
+    pub leaf_tasks: usize,
+    /// Dependency edges on the longest chain (0 when no task has dependencies)
+    pub max_depth: usize,
+    /// Direct dependencies per task
+    pub avg_dependencies: f64,
+    /// Task with the most direct dependents (empty when there are no edges)
+    pub most_depended_upon: String,
+    /// Task with the most direct dependencies (empty when there are no edges)
+    pub most_dependencies: String,
+    /// Always `false` for a graph built by [`TaskGraph::from_config`], which
+    /// rejects cycles
+    pub has_cycles: bool,
 }
 
 /// The task dependency graph
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TaskGraph {
     graph: DiGraph<TaskNode, ()>,
     name_to_index: HashMap<String, NodeIndex>,
@@ -30,36 +61,49 @@ impl TaskGraph {
         let mut graph = DiGraph::new();
         let mut name_to_index = HashMap::new();
 
-        // Add all tasks as nodes
-        for (name, task_config) in &config.tasks {
+        // Add all tasks as nodes (in name order, so node indices are stable)
+        let names = config.task_names_sorted();
+        for name in &names {
             let node = TaskNode {
-                name: name.clone(),
-                config: task_config.clone(),
+                name: (*name).to_string(),
+                config: config.tasks[*name].clone(),
+                pruned: false,
             };
             let idx = graph.add_node(node);
-            name_to_index.insert(name.clone(), idx);
+            name_to_index.insert((*name).to_string(), idx);
         }
 
-        // Add dependency edges
-        for (name, task_config) in &config.tasks {
-            let task_idx = name_to_index[name];
+        // Add dependency edges, collecting every unknown dependency (once per
+        // name) rather than stopping at the first
+        let mut missing: Vec<&str> = Vec::new();
+        for name in &names {
+            let task_config = &config.tasks[*name];
+            let task_idx = name_to_index[*name];
 
             for dep in &task_config.depends {
-                let dep_idx = name_to_index
-                    .get(dep)
-                    .ok_or_else(|| YatrError::TaskNotFound {
-                        name: dep.clone(),
-                        available: config
-                            .task_names()
-                            .iter()
-                            .map(std::string::ToString::to_string)
-                            .collect(),
-                    })?;
+                let Some(dep_idx) = name_to_index.get(dep) else {
+                    if !missing.contains(&dep.as_str()) {
+                        missing.push(dep);
+                    }
+                    continue;
+                };
 
                 // Edge goes from dependency TO dependent (dep must run first)
                 graph.add_edge(*dep_idx, task_idx, ());
             }
         }
+        let mut errors: Vec<YatrError> = missing
+            .into_iter()
+            .map(|dep| YatrError::TaskNotFound {
+                name: dep.to_string(),
+                available: names.iter().map(ToString::to_string).collect(),
+            })
+            .collect();
+        match errors.len() {
+            0 => {}
+            1 => return Err(errors.remove(0)),
+            _ => return Err(YatrError::MultipleErrors { errors }),
+        }
 
         // Check for cycles
         if is_cyclic_directed(&graph) {
@@ -75,21 +119,21 @@ impl TaskGraph {
 
     /// Get execution order for a specific task (including dependencies)
     pub fn execution_order(&self, task_name: &str) -> Result<Vec<&TaskNode>> {
-        let target_idx =
-            self.name_to_index
-                .get(task_name)
-                .ok_or_else(|| YatrError::TaskNotFound {
-                    name: task_name.to_string(),
-                    available: self.name_to_index.keys().cloned().collect(),
-                })?;
+        self.combined_execution_order(&[task_name])
+    }
 
-        // Get all ancestors (dependencies) of the target task
-        let required_nodes = self.get_ancestors(*target_idx);
+    /// One execution order covering several targets: every target and its
+    /// dependencies, each task once, in dependency order.
+    pub fn combined_execution_order(&self, task_names: &[&str]) -> Result<Vec<&TaskNode>> {
+        // Get all ancestors (dependencies) of the target tasks
+        let mut required_nodes = std::collections::HashSet::new();
+        for task_name in task_names {
+            let target_idx = self.index_of(task_name)?;
+            required_nodes.extend(self.get_ancestors(target_idx));
+        }
 
         // Topological sort of the subgraph
-        let sorted = toposort(&self.graph, None).map_err(|_| YatrError::CyclicDependency {
-            cycle: "Unknown cycle detected".to_string(),
-        })?;
+        let sorted = self.sorted_toposort()?;
 
         // Filter to only include required nodes, maintaining order
         let execution_order: Vec<&TaskNode> = sorted
@@ -101,15 +145,160 @@ impl TaskGraph {
         Ok(execution_order)
     }
 
+    /// An [`ExecutionPlan`] for `task_names` and their dependencies that puts
+    /// every task in one parallel group, with no task waiting on another.
+    ///
+    /// This bypasses dependency ordering entirely (`yatr run
+    /// --unconstrained`): a task may start before, or alongside, the tasks it
+    /// `depends` on, so it can read their outputs half-written or stale. Only
+    /// use it when the declared dependencies don't matter for ordering.
+    pub fn unconstrained_plan(&self, task_names: &[&str]) -> Result<ExecutionPlan<'_>> {
+        let tasks = self.combined_execution_order(task_names)?;
+        let parallel_groups = if tasks.is_empty() {
+            Vec::new()
+        } else {
+            vec![tasks.clone()]
+        };
+        let dependencies = tasks
+            .iter()
+            .map(|t| (t.name.as_str(), Vec::new()))
+            .collect();
+        Ok(ExecutionPlan {
+            tasks,
+            parallel_groups,
+            estimated_durations: HashMap::new(),
+            dependencies,
+        })
+    }
+
     /// Get all tasks in dependency order
     pub fn all_tasks_ordered(&self) -> Result<Vec<&TaskNode>> {
-        let sorted = toposort(&self.graph, None).map_err(|_| YatrError::CyclicDependency {
-            cycle: "Unknown cycle detected".to_string(),
-        })?;
+        let sorted = self.sorted_toposort()?;
 
         Ok(sorted.into_iter().map(|idx| &self.graph[idx]).collect())
     }
 
+    /// Every task grouped by topological generation: generation 0 holds the
+    /// tasks without dependencies, and each later generation the tasks whose
+    /// deepest dependency sits in the one before. Names within a generation
+    /// are sorted.
+    pub fn topological_generations(&self) -> Result<Vec<Vec<&str>>> {
+        let mut generation: HashMap<NodeIndex, usize> = HashMap::new();
+        let mut generations: Vec<Vec<&str>> = Vec::new();
+        for idx in self.sorted_toposort()? {
+            let level = self
+                .graph
+                .neighbors_directed(idx, petgraph::Direction::Incoming)
+                .map(|dep| generation[&dep] + 1)
+                .max()
+                .unwrap_or(0);
+            generation.insert(idx, level);
+            if generations.len() <= level {
+                generations.resize_with(level + 1, Vec::new);
+            }
+            generations[level].push(self.graph[idx].name.as_str());
+        }
+        for names in &mut generations {
+            names.sort_unstable();
+        }
+        Ok(generations)
+    }
+
+    /// Return a copy of the graph in which every task whose cache entry is
+    /// still valid — and whose dependencies are all pruned too — is replaced by
+    /// a no-op sentinel. The dependency structure is preserved, so the sentinel
+    /// still orders its dependents; a task downstream of anything that must run
+    /// is kept, since that run may change its inputs.
+    ///
+    /// Pruned tasks don't restore their cached outputs: use this when the
+    /// working tree already holds them (e.g. repeated local builds).
+    pub fn prune_unchanged(&self, cache: &Cache, cwd: &Path) -> Result<Self> {
+        let order = self.sorted_toposort()?;
+        let entries: Vec<(&str, &TaskConfig)> = order
+            .iter()
+            .map(|&idx| (self.graph[idx].name.as_str(), &self.graph[idx].config))
+            .collect();
+        let statuses = cache.precompute(&entries, cwd)?;
+
+        let mut pruned = self.clone();
+        for (&idx, (_, status)) in order.iter().zip(statuses) {
+            let deps_pruned = self
+                .graph
+                .neighbors_directed(idx, petgraph::Direction::Incoming)
+                .all(|dep| pruned.graph[dep].pruned);
+            if status == CacheStatus::Hit && deps_pruned {
+                let node = &mut pruned.graph[idx];
+                node.config = TaskConfig {
+                    desc: node.config.desc.take(),
+                    depends: std::mem::take(&mut node.config.depends),
+                    no_cache: Some(true),
+                    ..TaskConfig::default()
+                };
+                node.pruned = true;
+            }
+        }
+        Ok(pruned)
+    }
+
+    /// Topological sort (Kahn's algorithm) that always picks the
+    /// alphabetically smallest ready task next.
+    fn sorted_toposort(&self) -> Result<Vec<NodeIndex>> {
+        let mut in_degree: HashMap<NodeIndex, usize> = self
+            .graph
+            .node_indices()
+            .map(|idx| {
+                let deps = self
+                    .graph
+                    .neighbors_directed(idx, petgraph::Direction::Incoming);
+                (idx, deps.count())
+            })
+            .collect();
+
+        let mut ready: BinaryHeap<Reverse<(&str, NodeIndex)>> = in_degree
+            .iter()
+            .filter(|(_, &d)| d == 0)
+            .map(|(&idx, _)| Reverse((self.graph[idx].name.as_str(), idx)))
+            .collect();
+
+        let mut sorted = Vec::with_capacity(self.graph.node_count());
+        while let Some(Reverse((_, idx))) = ready.pop() {
+            sorted.push(idx);
+            for next in self
+                .graph
+                .neighbors_directed(idx, petgraph::Direction::Outgoing)
+            {
+                if let Some(remaining) = in_degree.get_mut(&next) {
+                    *remaining -= 1;
+                    if *remaining == 0 {
+                        ready.push(Reverse((self.graph[next].name.as_str(), next)));
+                    }
+                }
+            }
+        }
+
+        if sorted.len() < self.graph.node_count() {
+            return Err(YatrError::CyclicDependency {
+                cycle: "Unknown cycle detected".to_string(),
+            });
+        }
+        Ok(sorted)
+    }
+
+    /// Node index of a task, or a `TaskNotFound` error listing the tasks.
+    fn index_of(&self, task_name: &str) -> Result<NodeIndex> {
+        self.name_to_index
+            .get(task_name)
+            .copied()
+            .ok_or_else(|| YatrError::TaskNotFound {
+                name: task_name.to_string(),
+                available: self
+                    .task_names_sorted()
+                    .into_iter()
+                    .map(String::from)
+                    .collect(),
+            })
+    }
+
     /// Get ancestors (all dependencies, transitive) of a node
     fn get_ancestors(&self, target: NodeIndex) -> Vec<NodeIndex> {
         use petgraph::visit::Bfs;
@@ -191,6 +380,43 @@ impl TaskGraph {
         self.name_to_index.keys().map(std::string::String::as_str)
     }
 
+    /// Get all task names in alphabetical order
+    #[must_use]
+    pub fn task_names_sorted(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.task_names().collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Group tasks into independent clusters: two tasks share a group when
+    /// they are connected by any chain of dependencies, in either direction.
+    /// Names within a group and the groups themselves are sorted.
+    #[must_use]
+    pub fn connected_components(&self) -> Vec<Vec<String>> {
+        let mut sets = petgraph::unionfind::UnionFind::<usize>::new(self.graph.node_count());
+        for edge in self.graph.raw_edges() {
+            sets.union(edge.source().index(), edge.target().index());
+        }
+
+        let mut groups: HashMap<usize, Vec<String>> = HashMap::new();
+        for idx in self.graph.node_indices() {
+            groups
+                .entry(sets.find(idx.index()))
+                .or_default()
+                .push(self.graph[idx].name.clone());
+        }
+
+        let mut components: Vec<Vec<String>> = groups
+            .into_values()
+            .map(|mut names| {
+                names.sort_unstable();
+                names
+            })
+            .collect();
+        components.sort_unstable();
+        components
+    }
+
     /// Get direct dependencies of a task
     #[must_use]
     pub fn dependencies(&self, name: &str) -> Option<Vec<&str>> {
@@ -202,6 +428,374 @@ impl TaskGraph {
         })
     }
 
+    /// Shortest dependency chain from `from` to a task that (transitively)
+    /// depends on it, e.g. `["a", "b", "d"]` when `d` depends on `b` which
+    /// depends on `a`. `Ok(None)` when `to` doesn't depend on `from` at all.
+    pub fn shortest_dependency_path(&self, from: &str, to: &str) -> Result<Option<Vec<&str>>> {
+        let start = self.index_of(from)?;
+        let goal = self.index_of(to)?;
+
+        // BFS along dependency → dependent edges, remembering each node's parent.
+        let mut parent: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+        let mut queue = std::collections::VecDeque::from([start]);
+        while let Some(idx) = queue.pop_front() {
+            if idx == goal {
+                let mut path = vec![self.graph[goal].name.as_str()];
+                let mut current = goal;
+                while let Some(&prev) = parent.get(&current) {
+                    path.push(self.graph[prev].name.as_str());
+                    current = prev;
+                }
+                path.reverse();
+                return Ok(Some(path));
+            }
+            let mut next: Vec<_> = self
+                .graph
+                .neighbors_directed(idx, petgraph::Direction::Outgoing)
+                .collect();
+            // Visit in name order so ties resolve the same way every time.
+            next.sort_by_key(|&n| self.graph[n].name.as_str());
+            for n in next {
+                if n != start && !parent.contains_key(&n) {
+                    parent.insert(n, idx);
+                    queue.push_back(n);
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Number of transitive dependencies (all ancestors) of every task. High
+    /// counts point at tasks that can only start late in a run.
+    #[must_use]
+    pub fn transitive_dependency_counts(&self) -> HashMap<String, usize> {
+        self.graph
+            .node_indices()
+            .map(|idx| {
+                // `get_ancestors` includes the task itself.
+                let count = self.get_ancestors(idx).len() - 1;
+                (self.graph[idx].name.clone(), count)
+            })
+            .collect()
+    }
+
+    /// The task with the most transitive dependencies (ties go to the
+    /// alphabetically first name), or `None` for an empty graph.
+    #[must_use]
+    pub fn deepest_task(&self) -> Option<(&str, usize)> {
+        self.graph
+            .node_indices()
+            .map(|idx| {
+                (
+                    self.graph[idx].name.as_str(),
+                    self.get_ancestors(idx).len() - 1,
+                )
+            })
+            .max_by(|(a_name, a), (b_name, b)| a.cmp(b).then_with(|| b_name.cmp(a_name)))
+    }
+
+    /// Aggregate metrics about the graph's structure. Ties for
+    /// `most_depended_upon` / `most_dependencies` go to the alphabetically
+    /// first name.
+    #[must_use]
+    pub fn stats(&self) -> GraphStats {
+        let degree = |idx: NodeIndex, direction| self.graph.edges_directed(idx, direction).count();
+        let busiest = |direction| {
+            self.graph
+                .node_indices()
+                .map(|idx| (self.graph[idx].name.as_str(), degree(idx, direction)))
+                .filter(|&(_, count)| count > 0)
+                .max_by(|(a_name, a), (b_name, b)| a.cmp(b).then_with(|| b_name.cmp(a_name)))
+                .map(|(name, _)| name.to_string())
+                .unwrap_or_default()
+        };
+        let count_where = |direction| {
+            self.graph
+                .node_indices()
+                .filter(|&idx| degree(idx, direction) == 0)
+                .count()
+        };
+
+        let total_tasks = self.graph.node_count();
+        #[allow(clippy::cast_precision_loss)]
+        let avg_dependencies = if total_tasks == 0 {
+            0.0
+        } else {
+            self.graph.edge_count() as f64 / total_tasks as f64
+        };
+        GraphStats {
+            total_tasks,
+            root_tasks: count_where(petgraph::Direction::Incoming),
+            leaf_tasks: count_where(petgraph::Direction::Outgoing),
+            max_depth: self
+                .topological_generations()
+                .map_or(0, |generations| generations.len().saturating_sub(1)),
+            avg_dependencies,
+            most_depended_upon: busiest(petgraph::Direction::Outgoing),
+            most_dependencies: busiest(petgraph::Direction::Incoming),
+            has_cycles: is_cyclic_directed(&self.graph),
+        }
+    }
+
+    /// Check that every task in `plan` is grouped strictly after all of its
+    /// (planned) dependencies. A violation means the grouping went wrong —
+    /// typically because the plan was built from tasks not in topological
+    /// order — and is reported as a [`YatrError::CyclicDependency`].
+    pub fn validate_execution_plan(&self, plan: &ExecutionPlan<'_>) -> Result<()> {
+        let group_of: HashMap<&str, usize> = plan
+            .parallel_groups
+            .iter()
+            .enumerate()
+            .flat_map(|(i, group)| group.iter().map(move |t| (t.name.as_str(), i)))
+            .collect();
+
+        for (i, group) in plan.parallel_groups.iter().enumerate() {
+            for task in group {
+                for dep in self.dependencies(&task.name).unwrap_or_default() {
+                    if let Some(&dep_group) = group_of.get(dep) {
+                        if dep_group >= i {
+                            return Err(YatrError::CyclicDependency {
+                                cycle: format!(
+                                    "execution plan bug: '{}' is in group {i} but its \
+                                     dependency '{dep}' is in group {dep_group}",
+                                    task.name
+                                ),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check a hand-built execution order, the inverse of
+    /// [`execution_order`](Self::execution_order): every name must be a known
+    /// task, appear only once, and come after all of its dependencies. The
+    /// first violation is reported as [`YatrError::TaskNotFound`] or
+    /// [`YatrError::InvalidTask`].
+    pub fn validate_execution_order(&self, order: &[&str]) -> Result<()> {
+        let mut seen = std::collections::HashSet::new();
+        for &name in order {
+            self.index_of(name)?;
+            if !seen.insert(name) {
+                return Err(YatrError::InvalidTask {
+                    task: name.to_string(),
+                    reason: "listed more than once in the execution order".to_string(),
+                });
+            }
+            let mut deps = self.dependencies(name).unwrap_or_default();
+            deps.sort_unstable();
+            if let Some(dep) = deps.into_iter().find(|dep| !seen.contains(dep)) {
+                return Err(YatrError::InvalidTask {
+                    task: name.to_string(),
+                    reason: format!("ordered before its dependency '{dep}'"),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `order` passes [`validate_execution_order`](Self::validate_execution_order).
+    #[must_use]
+    pub fn is_valid_execution_order(&self, order: &[&str]) -> bool {
+        self.validate_execution_order(order).is_ok()
+    }
+
+    /// Reconstruct a [`Config`] holding every task in the graph, with each
+    /// task's `depends` rebuilt from its incoming edges (declared order first,
+    /// then any other edges by name). `[env]`, `[settings]` and the rest of
+    /// the root config aren't part of the graph, so they come back as defaults.
+    #[must_use]
+    pub fn to_config(&self) -> Config {
+        let tasks = self
+            .graph
+            .node_indices()
+            .map(|idx| {
+                let node = &self.graph[idx];
+                let mut deps: Vec<&str> = self
+                    .graph
+                    .neighbors_directed(idx, petgraph::Direction::Incoming)
+                    .map(|dep_idx| self.graph[dep_idx].name.as_str())
+                    .collect();
+                deps.sort_by_key(|dep| {
+                    let declared = node.config.depends.iter().position(|d| d == dep);
+                    (declared.unwrap_or(usize::MAX), *dep)
+                });
+                deps.dedup();
+
+                let mut config = node.config.clone();
+                config.depends = deps.into_iter().map(String::from).collect();
+                (node.name.clone(), config)
+            })
+            .collect();
+
+        Config {
+            tasks,
+            ..Config::default()
+        }
+    }
+
+    /// Render the whole graph as Graphviz DOT (see [`Self::to_dot_with_results`]).
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        self.to_dot_with_results(None)
+    }
+
+    /// Render the whole graph as Graphviz DOT, styled by task properties:
+    /// `script` tasks are ellipses, `parallel` tasks get a double border and
+    /// `no_cache` tasks a dashed one. With `results`, cached tasks are filled
+    /// green and failed tasks red. Nodes are listed alphabetically.
+    #[must_use]
+    pub fn to_dot_with_results(&self, results: Option<&[TaskResult]>) -> String {
+        let tasks: Vec<&TaskNode> = self
+            .task_names_sorted()
+            .into_iter()
+            .filter_map(|name| self.get_task(name))
+            .collect();
+        self.dot_for(&tasks, results)
+    }
+
+    /// DOT for `tasks` and the edges between them.
+    pub(crate) fn dot_for(&self, tasks: &[&TaskNode], results: Option<&[TaskResult]>) -> String {
+        let quote = |name: &str| format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""));
+        let result_of = |name: &str| results?.iter().rev().find(|r| r.name == name);
+
+        let mut dot = String::from("digraph yatr {\n  rankdir=LR;\n  node [shape=box];\n");
+        for task in tasks {
+            let fill = match result_of(&task.name) {
+                Some(r) if !r.success => Some("#f4a6a6"),
+                Some(r) if r.cached => Some("#a8e6a1"),
+                _ => None,
+            };
+            let mut attrs = Vec::new();
+            if task.config.script.is_some() {
+                attrs.push("shape=ellipse".to_string());
+            }
+            if task.config.parallel.unwrap_or(false) {
+                attrs.push("peripheries=2".to_string());
+            }
+            if let Some(color) = fill {
+                attrs.push(format!("fillcolor=\"{color}\""));
+            }
+            let styles: Vec<&str> = [
+                task.config.no_cache.unwrap_or(false).then_some("dashed"),
+                fill.map(|_| "filled"),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+            if !styles.is_empty() {
+                attrs.push(format!("style=\"{}\"", styles.join(",")));
+            }
+            let _ = if attrs.is_empty() {
+                writeln!(dot, "  {};", quote(&task.name))
+            } else {
+                writeln!(dot, "  {} [{}];", quote(&task.name), attrs.join(", "))
+            };
+        }
+        for task in tasks {
+            for dep in self.dependencies(&task.name).unwrap_or_default() {
+                if tasks.iter().any(|t| t.name == dep) {
+                    let _ = writeln!(dot, "  {} -> {};", quote(dep), quote(&task.name));
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Render the whole graph as a Mermaid flowchart, which GitHub and GitLab
+    /// render inline in Markdown. Nodes are listed alphabetically.
+    #[must_use]
+    pub fn to_mermaid(&self) -> String {
+        let tasks: Vec<&TaskNode> = self
+            .task_names_sorted()
+            .into_iter()
+            .filter_map(|name| self.get_task(name))
+            .collect();
+        self.mermaid_for(&tasks)
+    }
+
+    /// Mermaid `flowchart` for `tasks` and the edges between them. Node IDs
+    /// are the tasks' positions (`n0`, `n1`, …), so no name can collide with
+    /// another or with a Mermaid keyword like `end`; the label carries the
+    /// real name, with the `desc` on a second line. `allow_failure` tasks are
+    /// drawn with rounded corners.
+    pub(crate) fn mermaid_for(&self, tasks: &[&TaskNode]) -> String {
+        let ids: HashMap<&str, String> = tasks
+            .iter()
+            .enumerate()
+            .map(|(i, task)| (task.name.as_str(), format!("n{i}")))
+            .collect();
+
+        let escape = |text: &str| {
+            text.replace('"', "#quot;")
+                .replace('<', "#lt;")
+                .replace('>', "#gt;")
+        };
+
+        let mut mermaid = String::from("flowchart LR\n");
+        for task in tasks {
+            let mut label = escape(&task.name);
+            if let Some(desc) = &task.config.desc {
+                let _ = write!(label, "<br/>{}", escape(desc));
+            }
+            let (open, close) = if task.config.allow_failure.unwrap_or(false) {
+                ("(", ")")
+            } else {
+                ("[", "]")
+            };
+            let _ = writeln!(
+                mermaid,
+                "    {}{open}\"{label}\"{close}",
+                ids[task.name.as_str()]
+            );
+        }
+        for task in tasks {
+            for dep in self.dependencies(&task.name).unwrap_or_default() {
+                if let Some(dep_id) = ids.get(dep) {
+                    let _ = writeln!(mermaid, "    {dep_id} --> {}", ids[task.name.as_str()]);
+                }
+            }
+        }
+        mermaid
+    }
+
+    /// Render `task_name` and its dependencies as a Nix expression: one
+    /// `stdenv.mkDerivation` per task, bound in a `let` in execution order,
+    /// with the task's `run` commands as `buildPhase`, its dependencies as
+    /// `propagatedBuildInputs`, a `src` filtered by its `sources` globs and an
+    /// `installPhase` copying its `outputs` into `$out`. A starting point for
+    /// a hand-maintained expression, not a complete one: the tools the
+    /// commands need still have to be added, and script/WASM tasks only get a
+    /// placeholder.
+    pub fn to_nix_derivation(&self, task_name: &str) -> Result<String> {
+        let tasks = self.execution_order(task_name)?;
+
+        let mut nix = format!(
+            "# Generated by `yatr graph --format nix {task_name}` as a starting point:\n\
+             # add the tools the commands need to nativeBuildInputs.\n\
+             {{ pkgs ? import <nixpkgs> {{ }} }}:\n\nlet\n"
+        );
+        let idents: HashMap<&str, String> = tasks
+            .iter()
+            .enumerate()
+            .map(|(i, task)| (task.name.as_str(), nix_ident(&task.name, i)))
+            .collect();
+        for task in &tasks {
+            let deps: Vec<&str> = self
+                .dependencies(&task.name)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|dep| idents[dep].as_str())
+                .collect();
+            nix_derivation(&mut nix, task, &idents[task.name.as_str()], &deps);
+        }
+        let _ = writeln!(nix, "in\n{}", idents[task_name]);
+        Ok(nix)
+    }
+
     /// Get tasks that depend on the given task
     #[must_use]
     pub fn dependents(&self, name: &str) -> Option<Vec<&str>> {
@@ -214,6 +808,9 @@ impl TaskGraph {
     }
 }
 
+/// Duration assumed for a task with no timing hint.
+pub const DEFAULT_DURATION_ESTIMATE: Duration = Duration::from_secs(1);
+
 /// Execution plan for a set of tasks
 #[derive(Debug)]
 pub struct ExecutionPlan<'a> {
@@ -221,52 +818,309 @@ pub struct ExecutionPlan<'a> {
     pub tasks: Vec<&'a TaskNode>,
     /// Groups of tasks that can run in parallel (respecting dependencies)
     pub parallel_groups: Vec<Vec<&'a TaskNode>>,
+    /// Expected run time per task (e.g. from the last cached run). Tasks
+    /// without an entry are assumed to take [`DEFAULT_DURATION_ESTIMATE`].
+    pub estimated_durations: HashMap<String, Duration>,
+    /// Direct dependencies of each planned task, restricted to the plan
+    dependencies: HashMap<&'a str, Vec<&'a str>>,
 }
 
 impl<'a> ExecutionPlan<'a> {
     /// Create an execution plan from a list of tasks
     #[must_use]
     pub fn from_tasks(tasks: Vec<&'a TaskNode>, graph: &'a TaskGraph) -> Self {
-        // Group tasks by "depth" in the dependency graph for parallel execution
+        // Group tasks by "depth" in the dependency graph for parallel execution:
+        // each task goes in the group after its latest already-placed
+        // dependency (the same levels as `TaskGraph::topological_generations`
+        // when `tasks` is in topological order).
         let mut parallel_groups: Vec<Vec<&'a TaskNode>> = Vec::new();
-        let mut placed: std::collections::HashSet<&'a str> = std::collections::HashSet::new();
+        let mut group_of: HashMap<&'a str, usize> = HashMap::new();
 
         for task in &tasks {
-            // Find the earliest group this task can be placed in
-            // (all dependencies must be in earlier groups)
-            let deps: Vec<_> = graph
+            let target_group = graph
                 .dependencies(&task.name)
                 .unwrap_or_default()
                 .into_iter()
-                .collect();
-
-            let mut target_group = 0;
-            for (i, group) in parallel_groups.iter().enumerate() {
-                for t in group {
-                    if deps.contains(&t.name.as_str()) {
-                        target_group = i + 1;
-                    }
-                }
-            }
+                .filter_map(|dep| group_of.get(dep).map(|group| group + 1))
+                .max()
+                .unwrap_or(0);
 
             // Ensure we have enough groups
             while parallel_groups.len() <= target_group {
                 parallel_groups.push(Vec::new());
             }
 
-            if !placed.insert(task.name.as_str()) {
+            if group_of.contains_key(task.name.as_str()) {
                 continue;
             }
+            group_of.insert(task.name.as_str(), target_group);
             parallel_groups[target_group].push(task);
         }
 
+        let dependencies = tasks
+            .iter()
+            .map(|t| {
+                let deps = graph
+                    .dependencies(&t.name)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|d| group_of.contains_key(d))
+                    .collect();
+                (t.name.as_str(), deps)
+            })
+            .collect();
+
         Self {
             tasks,
             parallel_groups,
+            estimated_durations: HashMap::new(),
+            dependencies,
+        }
+    }
+
+    /// Attach per-task duration hints (builder style).
+    #[must_use]
+    pub fn with_estimated_durations(mut self, durations: HashMap<String, Duration>) -> Self {
+        self.estimated_durations = durations;
+        self
+    }
+
+    /// Expected run time of a task, falling back to [`DEFAULT_DURATION_ESTIMATE`].
+    #[must_use]
+    pub fn estimated_duration(&self, name: &str) -> Duration {
+        self.estimated_durations
+            .get(name)
+            .copied()
+            .unwrap_or(DEFAULT_DURATION_ESTIMATE)
+    }
+
+    /// Compute each task's earliest start time, assuming unlimited parallelism:
+    /// a task starts as soon as its slowest dependency is estimated to finish.
+    #[must_use]
+    pub fn assign_start_times(&self, base: Instant) -> HashMap<String, Instant> {
+        let mut starts: HashMap<String, Instant> = HashMap::new();
+        // `tasks` is topologically ordered, so dependencies are assigned first.
+        for task in &self.tasks {
+            let start = self
+                .dependencies
+                .get(task.name.as_str())
+                .into_iter()
+                .flatten()
+                .filter_map(|dep| starts.get(*dep).map(|s| *s + self.estimated_duration(dep)))
+                .max()
+                .unwrap_or(base);
+            starts.insert(task.name.clone(), start);
         }
+        starts
+    }
+
+    /// `sum(task_durations) / (groups * max_group_duration)`, where a
+    /// parallel group (a stage of the plan) lasts as long as its slowest
+    /// task. 1.0 means every stage takes as long as the longest one; stages
+    /// that finish early pull it down, and several tasks sharing a stage push
+    /// it up.
+    ///
+    /// Tasks missing from `durations` use their estimate. `None` for an empty
+    /// plan or one whose tasks all take no time.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn parallel_efficiency(&self, durations: &HashMap<String, Duration>) -> Option<f64> {
+        let groups = self.group_durations(durations);
+        let total: Duration = groups.iter().flatten().sum();
+        let longest = groups
+            .iter()
+            .filter_map(|times| times.iter().max())
+            .max()
+            .copied()
+            .unwrap_or_default();
+        let reserved = longest.as_secs_f64() * groups.len() as f64;
+        (reserved > 0.0).then(|| total.as_secs_f64() / reserved)
+    }
+
+    /// Index of the parallel group whose tasks are the most unevenly
+    /// distributed (by estimated durations): the one whose tasks spend the
+    /// most time in total waiting on its slowest task. `None` when every
+    /// group is balanced.
+    #[must_use]
+    pub fn bottleneck_group(&self) -> Option<usize> {
+        self.group_durations(&self.estimated_durations)
+            .into_iter()
+            .map(|times| {
+                let slowest = times.iter().max().copied().unwrap_or_default();
+                times
+                    .iter()
+                    .map(|t| slowest.saturating_sub(*t))
+                    .sum::<Duration>()
+            })
+            .enumerate()
+            .filter(|(_, idle)| !idle.is_zero())
+            .max_by_key(|(_, idle)| *idle)
+            .map(|(i, _)| i)
+    }
+
+    /// The duration of each task, per parallel group. Tasks missing from
+    /// `durations` use their estimate.
+    fn group_durations(&self, durations: &HashMap<String, Duration>) -> Vec<Vec<Duration>> {
+        self.parallel_groups
+            .iter()
+            .map(|group| {
+                group
+                    .iter()
+                    .map(|t| {
+                        durations
+                            .get(&t.name)
+                            .copied()
+                            .unwrap_or_else(|| self.estimated_duration(&t.name))
+                    })
+                    .collect()
+            })
+            .collect()
     }
 }
 
+/// Append one task's `mkDerivation` binding to `ident` (see
+/// [`TaskGraph::to_nix_derivation`]); `deps` are the identifiers of its
+/// dependencies.
+fn nix_derivation(nix: &mut String, task: &TaskNode, ident: &str, deps: &[&str]) {
+    let config = &task.config;
+    let _ = writeln!(nix, "  {ident} = pkgs.stdenv.mkDerivation {{");
+    let _ = writeln!(nix, "    name = {};", nix_string(&task.name));
+
+    if config.sources.is_empty() {
+        nix.push_str("    src = ./.;\n");
+    } else {
+        let patterns: Vec<String> = config
+            .sources
+            .iter()
+            .map(|glob| nix_string(&glob_to_regex(glob)))
+            .collect();
+        let _ = write!(
+            nix,
+            "    src = builtins.path {{\n      \
+             path = ./.;\n      \
+             name = {};\n      \
+             # sources: {}\n      \
+             filter = path: type:\n        \
+             let rel = pkgs.lib.removePrefix (toString ./. + \"/\") (toString path);\n        \
+             in type == \"directory\" || builtins.any (re: builtins.match re rel != null) [ {} ];\n    \
+             }};\n",
+            nix_string(&format!("{}-src", task.name)),
+            config.sources.join(", "),
+            patterns.join(" ")
+        );
+    }
+
+    if !deps.is_empty() {
+        let _ = writeln!(nix, "    propagatedBuildInputs = [ {} ];", deps.join(" "));
+    }
+    if !config.env.is_empty() {
+        let mut env: Vec<_> = config.env.iter().collect();
+        env.sort();
+        nix.push_str("    env = {\n");
+        for (key, value) in env {
+            let _ = writeln!(nix, "      {} = {};", nix_string(key), nix_string(value));
+        }
+        nix.push_str("    };\n");
+    }
+
+    let mut build = Vec::new();
+    if let Some(cwd) = &config.cwd {
+        build.push(format!("cd {}", cwd.display()));
+    }
+    if config.run.is_empty() {
+        build.push("# a Rhai script or WASM task: replace with equivalent commands".to_string());
+    }
+    build.extend(config.run.iter().map(|cmd| {
+        config
+            .expand_args(cmd.cmd())
+            .unwrap_or_else(|_| cmd.cmd().to_string())
+    }));
+    let install: Vec<String> = std::iter::once("mkdir -p $out".to_string())
+        .chain(
+            config
+                .outputs
+                .iter()
+                .map(|o| format!("cp -r --parents {o} $out/")),
+        )
+        .collect();
+    for (phase, hook, lines) in [
+        ("buildPhase", "Build", build),
+        ("installPhase", "Install", install),
+    ] {
+        let _ = writeln!(nix, "    {phase} = ''\n      runHook pre{hook}");
+        for line in lines {
+            let _ = writeln!(nix, "      {}", nix_indented_escape(&line));
+        }
+        let _ = writeln!(nix, "      runHook post{hook}\n    '';");
+    }
+    nix.push_str("  };\n\n");
+}
+
+/// The Nix identifier of the task `name` at position `index`: characters
+/// outside `[A-Za-z0-9_'-]` become `_`, a name not starting with a letter or
+/// `_` is prefixed, and `_<index>` is appended, so sanitized names can't
+/// collide with each other or with keywords like `in`.
+fn nix_ident(name: &str, index: usize) -> String {
+    let ident: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | '\'' | '-') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if ident.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        format!("{ident}_{index}")
+    } else {
+        format!("_{ident}_{index}")
+    }
+}
+
+/// A double-quoted Nix string.
+fn nix_string(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace("${", "\\${");
+    format!("\"{escaped}\"")
+}
+
+/// Escape a line for a Nix indented (`''`) string.
+fn nix_indented_escape(line: &str) -> String {
+    line.replace("''", "'''").replace("${", "''${")
+}
+
+/// Translate a `sources` glob into the POSIX regex `builtins.match` expects
+/// (matched against the whole path relative to the project root).
+fn glob_to_regex(glob: &str) -> String {
+    let glob = glob.trim_start_matches("./");
+    let mut regex = String::new();
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '.' | '+' | '(' | ')' | '|' | '^' | '$' | '\\' | '{' | '}' | '[' | ']' => {
+                regex.push('\\');
+                regex.push(c);
+            }
+            c => regex.push(c),
+        }
+    }
+    regex
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -319,6 +1173,227 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_mermaid_uses_indexed_node_ids() {
+        let config: Config = toml::from_str(
+            r#"
+            [tasks.gen-code]
+            run = ["echo gen"]
+            [tasks.gen_code]
+            run = ["echo gen"]
+            [tasks.end]
+            depends = ["gen_code"]
+            run = ["echo end"]
+            [tasks."unit tests"]
+            depends = ["gen-code"]
+            run = ["echo test"]
+            [tasks.lint]
+            desc = "Lint <all> \"the\" code"
+            depends = ["gen-code"]
+            allow_failure = true
+            run = ["echo lint"]
+            "#,
+        )
+        .unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+
+        assert_eq!(
+            graph.to_mermaid(),
+            "flowchart LR\n    \
+             n0[\"end\"]\n    \
+             n1[\"gen-code\"]\n    \
+             n2[\"gen_code\"]\n    \
+             n3(\"lint<br/>Lint #lt;all#gt; #quot;the#quot; code\")\n    \
+             n4[\"unit tests\"]\n    \
+             n2 --> n0\n    \
+             n1 --> n3\n    \
+             n1 --> n4\n"
+        );
+    }
+
+    #[test]
+    fn test_dot_styles_nodes_by_properties_and_results() {
+        let config: Config = toml::from_str(
+            r#"
+            [tasks.gen]
+            script = "print(1);"
+            [tasks.build]
+            depends = ["gen"]
+            parallel = true
+            no_cache = true
+            run = ["echo a", "echo b"]
+            [tasks.test]
+            depends = ["build"]
+            run = ["echo \"t\""]
+            "#,
+        )
+        .unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+
+        let plain = graph.to_dot();
+        assert!(plain.contains("  \"gen\" [shape=ellipse];\n"));
+        assert!(plain.contains("  \"build\" [peripheries=2, style=\"dashed\"];\n"));
+        assert!(plain.contains("  \"test\";\n"));
+        assert!(plain.contains("  \"build\" -> \"test\";\n"));
+        assert!(!plain.contains("fillcolor"));
+
+        let result = |name: &str, success: bool, cached: bool| TaskResult {
+            name: name.to_string(),
+            success,
+            cached,
+            skipped: false,
+            duration: Duration::ZERO,
+            start_offset: Duration::ZERO,
+            output: None,
+            stderr: None,
+            exit_code: None,
+            error: None,
+            cache_created_at: None,
+            cache_duration_ms: None,
+        };
+        let results = [
+            result("gen", true, true),
+            result("build", true, false),
+            result("test", false, false),
+        ];
+        let styled = graph.to_dot_with_results(Some(&results));
+        assert!(styled
+            .contains("  \"gen\" [shape=ellipse, fillcolor=\"#a8e6a1\", style=\"filled\"];\n"));
+        assert!(styled.contains("  \"build\" [peripheries=2, style=\"dashed\"];\n"));
+        assert!(styled.contains("  \"test\" [fillcolor=\"#f4a6a6\", style=\"filled\"];\n"));
+    }
+
+    #[test]
+    fn test_execution_order_breaks_ties_alphabetically() {
+        let toml = r#"
+            [tasks.zlib]
+            run = ["true"]
+            [tasks.app]
+            depends = ["zlib", "core", "mid"]
+            run = ["true"]
+            [tasks.mid]
+            run = ["true"]
+            [tasks.core]
+            run = ["true"]
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+
+        let names: Vec<_> = graph
+            .execution_order("app")
+            .unwrap()
+            .iter()
+            .map(|t| t.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["core", "mid", "zlib", "app"]);
+        assert_eq!(
+            graph.task_names_sorted(),
+            vec!["app", "core", "mid", "zlib"]
+        );
+    }
+
+    #[test]
+    fn test_connected_components() {
+        let toml = r#"
+            [tasks.web-build]
+            run = ["true"]
+            [tasks.web-test]
+            depends = ["web-build"]
+            run = ["true"]
+            [tasks.api-build]
+            run = ["true"]
+            [tasks.api-test]
+            depends = ["api-build"]
+            run = ["true"]
+            [tasks.lint]
+            run = ["true"]
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+
+        assert_eq!(
+            graph.connected_components(),
+            vec![
+                vec!["api-build".to_string(), "api-test".to_string()],
+                vec!["lint".to_string()],
+                vec!["web-build".to_string(), "web-test".to_string()],
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prune_unchanged_keeps_tasks_downstream_of_misses() {
+        // a → b are both cached; c is cached but depends on d, which isn't.
+        let toml = r#"
+            [tasks.a]
+            run = ["echo a"]
+            [tasks.b]
+            depends = ["a"]
+            run = ["echo b"]
+            [tasks.c]
+            depends = ["d"]
+            run = ["echo c"]
+            [tasks.d]
+            run = ["echo d"]
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+
+        let cache_dir = tempfile::tempdir().unwrap();
+        let work = tempfile::tempdir().unwrap();
+        let cache = Cache::new(Some(cache_dir.path().to_path_buf())).unwrap();
+        for name in ["a", "b", "c"] {
+            cache
+                .put_text(name, &config.tasks[name], work.path(), "", Duration::ZERO)
+                .await
+                .unwrap();
+        }
+
+        let pruned = graph.prune_unchanged(&cache, work.path()).unwrap();
+        let is_pruned = |name: &str| pruned.get_task(name).unwrap().pruned;
+        assert!(is_pruned("a"));
+        assert!(is_pruned("b"));
+        assert!(!is_pruned("c"));
+        assert!(!is_pruned("d"));
+
+        // Sentinels keep the dependency structure and drop the commands.
+        assert_eq!(pruned.dependencies("b"), Some(vec!["a"]));
+        assert!(pruned.get_task("b").unwrap().config.run.is_empty());
+        assert!(!graph.get_task("b").unwrap().pruned);
+    }
+
+    #[test]
+    fn test_missing_dependencies_are_all_reported() {
+        let toml = r#"
+            [tasks.a]
+            depends = ["nope"]
+            run = ["true"]
+            [tasks.b]
+            depends = ["a", "gone", "nope"]
+            run = ["true"]
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let Err(YatrError::MultipleErrors { errors }) = TaskGraph::from_config(&config) else {
+            panic!("expected MultipleErrors");
+        };
+        let names: Vec<&str> = errors
+            .iter()
+            .map(|e| match e {
+                YatrError::TaskNotFound { name, .. } => name.as_str(),
+                other => panic!("unexpected {other:?}"),
+            })
+            .collect();
+        assert_eq!(names, ["nope", "gone"]);
+
+        // A single missing dependency is reported on its own.
+        let config: Config =
+            toml::from_str("[tasks.a]\ndepends = [\"nope\"]\nrun = [\"true\"]\n").unwrap();
+        assert!(matches!(
+            TaskGraph::from_config(&config),
+            Err(YatrError::TaskNotFound { .. })
+        ));
+    }
+
     #[test]
     fn test_cycle_detection() {
         let toml = r#"
@@ -336,4 +1411,306 @@ mod tests {
 
         assert!(matches!(result, Err(YatrError::CyclicDependency { .. })));
     }
+
+    #[test]
+    fn test_assign_start_times_follows_slowest_dependency() {
+        let config = make_test_config();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let order = graph.execution_order("d").unwrap();
+        let plan =
+            ExecutionPlan::from_tasks(order, &graph).with_estimated_durations(HashMap::from([
+                ("a".to_string(), Duration::from_secs(2)),
+                ("b".to_string(), Duration::from_secs(5)),
+                ("c".to_string(), Duration::from_secs(1)),
+            ]));
+
+        let base = Instant::now();
+        let starts = plan.assign_start_times(base);
+        assert_eq!(starts["a"], base);
+        assert_eq!(starts["b"], base + Duration::from_secs(2));
+        assert_eq!(starts["c"], base + Duration::from_secs(2));
+        // d waits for b (the slower branch): 2s + 5s.
+        assert_eq!(starts["d"], base + Duration::from_secs(7));
+    }
+
+    #[test]
+    fn test_parallel_efficiency_and_bottleneck() {
+        let config = make_test_config();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let order = graph.execution_order("d").unwrap();
+        // Groups: [a], [b, c], [d]. b and c are uneven: c idles for 3s.
+        let durations = HashMap::from([
+            ("a".to_string(), Duration::from_secs(2)),
+            ("b".to_string(), Duration::from_secs(4)),
+            ("c".to_string(), Duration::from_secs(1)),
+            ("d".to_string(), Duration::from_secs(1)),
+        ]);
+        let plan = ExecutionPlan::from_tasks(order, &graph).with_estimated_durations(durations);
+
+        // 8s of tasks over 3 groups, the longest of which takes 4s.
+        let efficiency = plan.parallel_efficiency(&plan.estimated_durations).unwrap();
+        assert!((efficiency - 8.0 / 12.0).abs() < 1e-9);
+        assert_eq!(plan.bottleneck_group(), Some(1));
+
+        // Measured durations win over the estimates.
+        let measured = HashMap::from([
+            ("a".to_string(), Duration::from_secs(4)),
+            ("c".to_string(), Duration::from_secs(4)),
+            ("d".to_string(), Duration::from_secs(4)),
+        ]);
+        let efficiency = plan.parallel_efficiency(&measured).unwrap();
+        assert!((efficiency - 16.0 / 12.0).abs() < 1e-9);
+
+        let balanced = ["a", "b", "c", "d"].map(|t| (t.to_string(), Duration::from_secs(1)));
+        let plan = plan.with_estimated_durations(HashMap::from(balanced));
+        assert_eq!(plan.bottleneck_group(), None);
+        // A lone task keeps its only stage busy for the whole run.
+        let single = ExecutionPlan::from_tasks(graph.execution_order("a").unwrap(), &graph);
+        assert!((single.parallel_efficiency(&HashMap::new()).unwrap() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_to_config_round_trip() {
+        let toml = r#"
+            [env]
+            FOO = "bar"
+
+            [tasks.fmt]
+            run = ["cargo fmt"]
+
+            [tasks.build]
+            depends = ["fmt"]
+            run = ["cargo build"]
+            sources = ["src/**/*.rs"]
+
+            [tasks.release]
+            depends = ["test", "build"]
+            run = ["cargo publish"]
+
+            [tasks.test]
+            depends = ["build"]
+            script = "print(1);"
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let rebuilt = graph.to_config();
+
+        assert!(rebuilt.env.is_empty());
+        assert_eq!(rebuilt.tasks.len(), config.tasks.len());
+        for (name, task) in &config.tasks {
+            assert_eq!(
+                serde_json::to_value(&rebuilt.tasks[name]).unwrap(),
+                serde_json::to_value(task).unwrap(),
+                "task '{name}' did not round-trip"
+            );
+        }
+        assert_eq!(rebuilt.tasks["release"].depends, ["test", "build"]);
+
+        // The reconstructed config serializes and rebuilds the same graph.
+        let reparsed: Config = toml::from_str(&toml::to_string(&rebuilt).unwrap()).unwrap();
+        let regraph = TaskGraph::from_config(&reparsed).unwrap();
+        assert_eq!(regraph.task_names_sorted(), graph.task_names_sorted());
+        assert_eq!(regraph.dependencies("test"), graph.dependencies("test"));
+    }
+
+    #[test]
+    fn test_validate_execution_plan() {
+        let toml = r#"
+            [tasks.a]
+            run = ["echo a"]
+
+            [tasks.b]
+            depends = ["a"]
+            run = ["echo b"]
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+
+        let order = graph.execution_order("b").unwrap();
+        let plan = ExecutionPlan::from_tasks(order, &graph);
+        assert!(graph.validate_execution_plan(&plan).is_ok());
+
+        // Out of topological order, both tasks land in the same group.
+        let mut order = graph.execution_order("b").unwrap();
+        order.reverse();
+        let plan = ExecutionPlan::from_tasks(order, &graph);
+        let err = graph.validate_execution_plan(&plan).unwrap_err();
+        assert!(matches!(err, YatrError::CyclicDependency { .. }));
+        assert!(err.to_string().contains("'b' is in group 0"));
+    }
+
+    #[test]
+    fn test_topological_generations() {
+        let config = make_test_config();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        assert_eq!(
+            graph.topological_generations().unwrap(),
+            vec![vec!["a"], vec!["b", "c"], vec!["d"]]
+        );
+
+        let order = graph.all_tasks_ordered().unwrap();
+        let plan = ExecutionPlan::from_tasks(order, &graph);
+        let groups: Vec<Vec<&str>> = plan
+            .parallel_groups
+            .iter()
+            .map(|g| g.iter().map(|t| t.name.as_str()).collect())
+            .collect();
+        assert_eq!(groups, graph.topological_generations().unwrap());
+
+        let empty = TaskGraph::from_config(&Config::default()).unwrap();
+        assert!(empty.topological_generations().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_unconstrained_plan() {
+        let config = make_test_config();
+        let graph = TaskGraph::from_config(&config).unwrap();
+
+        let plan = graph.unconstrained_plan(&["d"]).unwrap();
+        assert_eq!(plan.parallel_groups.len(), 1);
+        let mut names: Vec<&str> = plan.parallel_groups[0]
+            .iter()
+            .map(|t| t.name.as_str())
+            .collect();
+        names.sort_unstable();
+        assert_eq!(names, ["a", "b", "c", "d"]);
+
+        // Nothing waits on anything: every task starts at once.
+        let base = Instant::now();
+        assert!(plan.assign_start_times(base).values().all(|s| *s == base));
+
+        assert!(graph.unconstrained_plan(&["nope"]).is_err());
+    }
+
+    #[test]
+    fn test_validate_execution_order() {
+        let config = make_test_config();
+        let graph = TaskGraph::from_config(&config).unwrap();
+
+        let order: Vec<&str> = graph
+            .execution_order("d")
+            .unwrap()
+            .iter()
+            .map(|t| t.name.as_str())
+            .collect();
+        assert!(graph.is_valid_execution_order(&order));
+        assert!(graph.is_valid_execution_order(&["a", "c", "b", "d"]));
+        assert!(graph.is_valid_execution_order(&[]));
+
+        let err = graph
+            .validate_execution_order(&["a", "d", "b", "c"])
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            YatrError::InvalidTask { task, reason }
+                if task == "d" && reason == "ordered before its dependency 'b'"
+        ));
+        assert!(matches!(
+            graph.validate_execution_order(&["a", "a"]),
+            Err(YatrError::InvalidTask { .. })
+        ));
+        assert!(matches!(
+            graph.validate_execution_order(&["a", "zzz"]),
+            Err(YatrError::TaskNotFound { .. })
+        ));
+        assert!(!graph.is_valid_execution_order(&["b"]));
+    }
+
+    #[test]
+    fn test_transitive_dependency_counts() {
+        let config = make_test_config();
+        let graph = TaskGraph::from_config(&config).unwrap();
+
+        let counts = graph.transitive_dependency_counts();
+        assert_eq!(counts["a"], 0);
+        assert_eq!(counts["b"], 1);
+        assert_eq!(counts["c"], 1);
+        assert_eq!(counts["d"], 3);
+        assert_eq!(graph.deepest_task(), Some(("d", 3)));
+
+        let empty = TaskGraph::from_config(&Config::default()).unwrap();
+        assert_eq!(empty.deepest_task(), None);
+    }
+
+    #[test]
+    fn test_graph_stats() {
+        let graph = TaskGraph::from_config(&make_test_config()).unwrap();
+        let stats = graph.stats();
+        assert_eq!(
+            (stats.total_tasks, stats.root_tasks, stats.leaf_tasks),
+            (4, 1, 1)
+        );
+        assert_eq!(stats.max_depth, 2);
+        assert!((stats.avg_dependencies - 1.0).abs() < f64::EPSILON);
+        assert_eq!(stats.most_depended_upon, "a");
+        assert_eq!(stats.most_dependencies, "d");
+        assert!(!stats.has_cycles);
+
+        let empty = TaskGraph::from_config(&Config::default()).unwrap().stats();
+        assert_eq!((empty.total_tasks, empty.max_depth), (0, 0));
+        assert_eq!(empty.most_depended_upon, "");
+    }
+
+    #[test]
+    fn test_shortest_dependency_path() {
+        let config = make_test_config();
+        let graph = TaskGraph::from_config(&config).unwrap();
+
+        assert_eq!(
+            graph.shortest_dependency_path("a", "d").unwrap(),
+            Some(vec!["a", "b", "d"])
+        );
+        assert_eq!(
+            graph.shortest_dependency_path("b", "b").unwrap(),
+            Some(vec!["b"])
+        );
+        // `b` and `c` are independent, and edges aren't followed backwards.
+        assert_eq!(graph.shortest_dependency_path("b", "c").unwrap(), None);
+        assert_eq!(graph.shortest_dependency_path("d", "a").unwrap(), None);
+        assert!(graph.shortest_dependency_path("a", "missing").is_err());
+    }
+
+    #[test]
+    fn test_to_nix_derivation() {
+        let config: Config = toml::from_str(
+            r#"
+            [tasks.gen]
+            run = ["gen --out ${args.dir}"]
+            args = { dir = "generated" }
+            outputs = ["generated"]
+
+            [tasks."build.release"]
+            depends = ["gen"]
+            sources = ["src/**/*.rs", "Cargo.toml"]
+            outputs = ["target/release/app"]
+            env = { PROFILE = "release" }
+            run = ["cargo build --profile ${PROFILE}"]
+
+            [tasks.unrelated]
+            run = ["true"]
+        "#,
+        )
+        .unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+
+        let nix = graph.to_nix_derivation("build.release").unwrap();
+        assert!(nix.contains("{ pkgs ? import <nixpkgs> { } }:"));
+        assert!(nix.find("  gen_0 = ").unwrap() < nix.find("  build_release_1 = ").unwrap());
+        assert!(!nix.contains("unrelated"));
+        assert!(nix.ends_with("in\nbuild_release_1\n"), "{nix}");
+        assert!(nix.contains("      gen --out generated\n"));
+        assert!(nix.contains("    propagatedBuildInputs = [ gen_0 ];\n"));
+        assert!(nix.contains("      cargo build --profile ''${PROFILE}\n"));
+        assert!(nix.contains("      \"PROFILE\" = \"release\";\n"));
+        assert!(nix.contains("cp -r --parents target/release/app $out/"));
+        assert!(
+            nix.contains(r#"[ "src/(.*/)?[^/]*\\.rs" "Cargo\\.toml" ]"#),
+            "{nix}"
+        );
+        assert!(graph.to_nix_derivation("missing").is_err());
+
+        assert_eq!(nix_ident("2fast", 0), "_2fast_0");
+        assert_eq!(nix_ident("in", 3), "in_3");
+        assert_ne!(nix_ident("gen.code", 0), nix_ident("gen_code", 1));
+    }
 }
@@ -0,0 +1,132 @@
+//! Last-run fingerprints for `run_if_changed` tasks.
+//!
+//! A task with `run_if_changed` globs is skipped when the files they match,
+//! its commands, env and args hash to the same fingerprint as at the end of
+//! its last successful run. This is
+//! a lighter alternative to the action cache for tasks with nothing worth
+//! restoring (deploys, notifications, code generators that write in place):
+//! nothing is captured, only a hash is remembered.
+//!
+//! Fingerprints live in a `last-run/` directory beside the action cache, one
+//! small file per task and working directory, so clearing or disabling the
+//! cache doesn't touch them.
+
+#![allow(clippy::missing_errors_doc)]
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::cache::Cache;
+use crate::config::TaskConfig;
+use crate::error::Result;
+
+/// Store of each task's fingerprint at its last successful run.
+#[derive(Debug, Clone)]
+pub struct LastRun {
+    dir: PathBuf,
+}
+
+impl LastRun {
+    /// The store beside the cache in `cache_dir` (or the default cache
+    /// directory when unset).
+    #[must_use]
+    pub fn new(cache_dir: Option<&Path>) -> Self {
+        let cache_dir = cache_dir.map_or_else(Cache::default_dir, Path::to_path_buf);
+        Self {
+            dir: cache_dir.join("last-run"),
+        }
+    }
+
+    /// Hash `task`'s commands ([`Cache::command_fingerprint`]), `env` and
+    /// args, and the contents of the files under `cwd` matching its
+    /// `run_if_changed` globs (honouring `.gitignore`, like cache `sources`).
+    pub fn fingerprint(
+        cwd: &Path,
+        task: &TaskConfig,
+        env: &HashMap<String, String>,
+    ) -> Result<String> {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(Cache::command_fingerprint(task).as_bytes());
+        let mut pairs: Vec<_> = env.iter().chain(&task.args).collect();
+        pairs.sort();
+        for (key, value) in pairs {
+            hasher.update(key.as_bytes());
+            hasher.update(&[0]);
+            hasher.update(value.as_bytes());
+            hasher.update(&[0]);
+        }
+        hasher.update(Cache::hash_sources(cwd, &task.run_if_changed)?.as_bytes());
+        Ok(hasher.finalize().to_hex().to_string())
+    }
+
+    /// Whether `fingerprint` is the one recorded at `task`'s last successful
+    /// run in `cwd`.
+    #[must_use]
+    pub fn is_unchanged(&self, task: &str, cwd: &Path, fingerprint: &str) -> bool {
+        std::fs::read_to_string(self.path(task, cwd)).is_ok_and(|last| last == fingerprint)
+    }
+
+    /// Remember `fingerprint` as `task`'s last successful run in `cwd`.
+    pub fn record(&self, task: &str, cwd: &Path, fingerprint: &str) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        Cache::write_atomic(&self.path(task, cwd), fingerprint.as_bytes())
+    }
+
+    /// One file per task and absolute working directory, so projects sharing
+    /// the default cache directory don't collide.
+    fn path(&self, task: &str, cwd: &Path) -> PathBuf {
+        let cwd = std::path::absolute(cwd).unwrap_or_else(|_| cwd.to_path_buf());
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(task.as_bytes());
+        hasher.update(&[0]);
+        hasher.update(cwd.to_string_lossy().as_bytes());
+        self.dir.join(&hasher.finalize().to_hex()[..32])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprints_follow_file_contents() {
+        let store_dir = tempfile::tempdir().unwrap();
+        let work = tempfile::tempdir().unwrap();
+        let store = LastRun::new(Some(store_dir.path()));
+        let task: TaskConfig =
+            toml::from_str("run = [\"gen\"]\nrun_if_changed = [\"*.txt\"]\n").unwrap();
+        let env = HashMap::new();
+        std::fs::write(work.path().join("a.txt"), "one").unwrap();
+
+        let first = LastRun::fingerprint(work.path(), &task, &env).unwrap();
+        assert!(!store.is_unchanged("gen", work.path(), &first));
+        store.record("gen", work.path(), &first).unwrap();
+        assert!(store.is_unchanged("gen", work.path(), &first));
+        assert!(!store.is_unchanged("other", work.path(), &first));
+
+        std::fs::write(work.path().join("a.txt"), "two").unwrap();
+        let second = LastRun::fingerprint(work.path(), &task, &env).unwrap();
+        assert!(!store.is_unchanged("gen", work.path(), &second));
+    }
+
+    #[test]
+    fn fingerprints_follow_commands_and_env() {
+        let work = tempfile::tempdir().unwrap();
+        let task: TaskConfig =
+            toml::from_str("run = [\"gen\"]\nrun_if_changed = [\"*.txt\"]\n").unwrap();
+        let env = HashMap::from([("MODE".to_string(), "dev".to_string())]);
+        let base = LastRun::fingerprint(work.path(), &task, &env).unwrap();
+
+        let mut edited = task.clone();
+        edited.run = vec!["gen --all".into()];
+        assert_ne!(
+            LastRun::fingerprint(work.path(), &edited, &env).unwrap(),
+            base
+        );
+        let prod = HashMap::from([("MODE".to_string(), "prod".to_string())]);
+        assert_ne!(
+            LastRun::fingerprint(work.path(), &task, &prod).unwrap(),
+            base
+        );
+    }
+}
@@ -57,24 +57,31 @@
 
 pub mod affected;
 pub mod cache;
+pub mod cache_server;
 pub mod config;
+pub mod diff;
 pub mod error;
 pub mod executor;
 pub mod graph;
+pub mod last_run;
 pub mod lsp;
+pub mod makefile;
 pub mod reapi;
 pub mod remote;
+pub mod report;
 pub mod script;
+pub mod ssh;
+pub mod timing_history;
 pub mod toolchain;
 pub mod trace;
 pub mod wasm;
 pub mod watch;
 
 // Re-export main types
-pub use cache::Cache;
+pub use cache::{Cache, CacheMiss, CacheStatus, SessionCacheStats};
 pub use config::Config;
 pub use error::{Result, YatrError};
-pub use executor::{Executor, ExecutorConfig, TaskResult};
+pub use executor::{Executor, ExecutorConfig, ExecutorConfigBuilder, TaskResult};
 pub use graph::{ExecutionPlan, TaskGraph, TaskNode};
 pub use remote::RemoteCache;
-pub use script::ScriptEngine;
+pub use script::{ScriptContext, ScriptEngine, ScriptLimits};
@@ -136,7 +136,14 @@ pub fn compute_diagnostics(text: &str) -> Vec<Diagnostic> {
     }
     if config.include.is_empty() {
         if let Err(e) = TaskGraph::from_config(&config) {
-            return vec![diagnostic(locate(text, &e), message_for(&e))];
+            let errors = match e {
+                YatrError::MultipleErrors { errors } => errors,
+                e => vec![e],
+            };
+            return errors
+                .iter()
+                .map(|e| diagnostic(locate(text, e), message_for(e)))
+                .collect();
         }
     }
     Vec::new()
@@ -148,14 +155,7 @@ pub fn compute_diagnostics(text: &str) -> Vec<Diagnostic> {
 pub fn document_symbols(text: &str) -> Vec<DocumentSymbol> {
     let mut symbols = Vec::new();
     for (i, line) in text.lines().enumerate() {
-        let trimmed = line.trim();
-        if let Some(name) = trimmed
-            .strip_prefix("[tasks.")
-            .and_then(|r| r.strip_suffix(']'))
-        {
-            if name.is_empty() || name.contains('.') {
-                continue; // skip subtables like [tasks.x.env]
-            }
+        if let Some(name) = task_header_name(line) {
             let range = line_range(i, line);
             symbols.push(DocumentSymbol {
                 name: name.to_string(),
@@ -193,9 +193,8 @@ fn locate(text: &str, e: &YatrError) -> Range {
         _ => None,
     };
     if let Some(name) = task {
-        let header = format!("[tasks.{name}]");
         for (i, line) in text.lines().enumerate() {
-            if line.trim() == header {
+            if task_header_name(line) == Some(name) {
                 return line_range(i, line);
             }
         }
@@ -203,6 +202,16 @@ fn locate(text: &str, e: &YatrError) -> Range {
     zero_range()
 }
 
+/// The task named by a `[tasks.<name>]` or `[tasks."<name>"]` header line.
+/// Subtables like `[tasks.x.env]` are not task headers.
+fn task_header_name(line: &str) -> Option<&str> {
+    let name = line.trim().strip_prefix("[tasks.")?.strip_suffix(']')?;
+    if let Some(quoted) = name.strip_prefix('"').and_then(|n| n.strip_suffix('"')) {
+        return (!quoted.contains('"')).then_some(quoted);
+    }
+    (!name.is_empty() && !name.contains(['.', '"'])).then_some(name)
+}
+
 fn diagnostic(range: Range, message: String) -> Diagnostic {
     Diagnostic {
         range,
@@ -282,6 +291,15 @@ mod tests {
         assert!(diags[0].message.contains("nope"));
     }
 
+    #[test]
+    fn every_missing_dependency_is_diagnosed() {
+        let text = "[tasks.build]\ndepends = [\"nope\", \"gone\"]\nrun = [\"x\"]\n";
+        let diags = compute_diagnostics(text);
+        assert_eq!(diags.len(), 2);
+        assert!(diags[0].message.contains("nope"));
+        assert!(diags[1].message.contains("gone"));
+    }
+
     #[test]
     fn symbols_list_top_level_tasks() {
         let text =
@@ -289,4 +307,13 @@ mod tests {
         let names: Vec<_> = document_symbols(text).into_iter().map(|s| s.name).collect();
         assert_eq!(names, vec!["build", "test"]); // subtable [tasks.test.env] excluded
     }
+
+    #[test]
+    fn quoted_task_names_may_contain_dots() {
+        let text =
+            "[tasks.\"test.unit\"]\ndepends = [\"nope\"]\n[tasks.\"test.unit\".env]\nA=\"1\"\n";
+        let names: Vec<_> = document_symbols(text).into_iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["test.unit"]);
+        assert_eq!(compute_diagnostics(text)[0].range.start.line, 0);
+    }
 }
@@ -18,6 +18,7 @@
     clippy::struct_excessive_bools
 )]
 
+use std::fmt::Write as _;
 use std::process::ExitCode;
 
 use clap::Parser;
@@ -26,25 +27,36 @@ use miette::IntoDiagnostic;
 
 mod affected;
 mod cache;
+mod cache_server;
 mod cli;
 mod config;
+mod diff;
 mod error;
 mod executor;
 mod graph;
+mod last_run;
 mod lsp;
+mod makefile;
 mod reapi;
 mod remote;
+mod report;
 mod script;
+mod ssh;
+mod timing_history;
 mod toolchain;
 mod trace;
 mod wasm;
 mod watch;
 
-use cli::{CacheCommands, Cli, Commands, EffectiveCommand, GraphFormat, ListFormat};
+use cache::CacheStatus;
+use cli::{
+    CacheCommands, Cli, Commands, DiffFormat, EffectiveCommand, GraphFormat, ImportFormat,
+    ListFormat,
+};
 use config::Config;
 use error::{Result, YatrError};
 use executor::{Executor, ExecutorConfig, TaskResult};
-use graph::TaskGraph;
+use graph::{TaskGraph, TaskNode};
 
 #[tokio::main]
 async fn main() -> ExitCode {
@@ -73,8 +85,9 @@ async fn main() -> ExitCode {
         Err(e) => {
             // Render via miette for a clean message + diagnostic code/help,
             // rather than the raw `{:?}` struct debug.
+            let code = e.exit_code();
             eprintln!("{:?}", miette::Report::new(e));
-            ExitCode::FAILURE
+            ExitCode::from(code)
         }
     }
 }
@@ -85,23 +98,55 @@ async fn run(cli: Cli) -> Result<()> {
         std::env::set_current_dir(cwd)?;
     }
 
+    if cli.print_config {
+        return print_config(&cli);
+    }
+
     match cli.effective_command() {
         EffectiveCommand::Subcommand(cmd) => run_command(cmd, &cli).await,
-        EffectiveCommand::RunTasks(tasks) => run_tasks(tasks, RunOpts::default(), &cli).await,
-        EffectiveCommand::None => {
-            // No command - show help or list tasks
-            let (config, _) = Config::load(cli.config.as_deref())?;
-            let graph = TaskGraph::from_config(&config)?;
-            print_task_list(&graph, &config, &ListFormat::Table, false);
-            Ok(())
+        EffectiveCommand::RunTasks(tokens) => {
+            // `yatr test -- --filter x`: task names, then task arguments.
+            let split = tokens
+                .iter()
+                .position(|t| t.starts_with('-'))
+                .unwrap_or(tokens.len());
+            let (tasks, rest) = tokens.split_at(split);
+            let opts = RunOpts {
+                args: rest.iter().skip_while(|t| *t == "--").cloned().collect(),
+                ..RunOpts::default()
+            };
+            run_tasks(tasks, opts, &cli).await
         }
+        // No command - list tasks
+        EffectiveCommand::None => list_all_tasks(&cli),
     }
 }
 
+/// Load the config named by `--config`, or search for one within
+/// `--config-search-limit` parent directories, applying the
+/// `--config-profile` and `--env` overrides before `${VAR}` interpolation.
+fn load_config(cli: &Cli) -> Result<(Config, std::path::PathBuf)> {
+    Config::load_with(cli.config.as_deref(), cli.config_search_limit, |config| {
+        if let Some(profile) = &cli.config_profile {
+            *config = std::mem::take(config).with_profile(profile)?;
+        }
+        config.apply_env_overrides(&cli.env)
+    })
+}
+
+/// Print every task as a table (`yatr` or `yatr run` with no tasks).
+fn list_all_tasks(cli: &Cli) -> Result<()> {
+    let (config, _) = load_config(cli)?;
+    let graph = TaskGraph::from_config(&config)?;
+    print_task_list(&graph, &config, &ListFormat::Table, false, &[]);
+    Ok(())
+}
+
 async fn run_command(cmd: &Commands, cli: &Cli) -> Result<()> {
     match cmd {
         Commands::Run {
             tasks,
+            tag,
             dry_run,
             force,
             parallel,
@@ -110,12 +155,17 @@ async fn run_command(cmd: &Commands, cli: &Cli) -> Result<()> {
             profile,
             affected,
             trace_io,
+            timing,
+            output_dir,
+            predict_cache,
+            incremental,
+            unconstrained,
+            report,
+            report_file,
+            args,
         } => {
-            if tasks.is_empty() {
-                let (config, _) = Config::load(cli.config.as_deref())?;
-                let graph = TaskGraph::from_config(&config)?;
-                print_task_list(&graph, &config, &ListFormat::Table, false);
-                Ok(())
+            if tasks.is_empty() && tag.is_empty() {
+                list_all_tasks(cli)
             } else {
                 let opts = RunOpts {
                     dry_run: *dry_run,
@@ -126,52 +176,79 @@ async fn run_command(cmd: &Commands, cli: &Cli) -> Result<()> {
                     profile: profile.clone(),
                     affected: affected.clone(),
                     trace_io: *trace_io,
+                    timing: *timing,
+                    output_dir: output_dir.clone(),
+                    predict_cache: *predict_cache,
+                    incremental: *incremental,
+                    unconstrained: *unconstrained,
+                    report: report.zip(report_file.clone()),
+                    tags: tag.clone(),
+                    args: args.clone(),
                 };
                 run_tasks(tasks, opts, cli).await
             }
         }
 
-        Commands::List { format, deps } => {
-            let (config, _) = Config::load(cli.config.as_deref())?;
+        Commands::List {
+            format,
+            deps,
+            groups,
+            tag,
+        } => {
+            let (config, _) = load_config(cli)?;
             let graph = TaskGraph::from_config(&config)?;
-            print_task_list(&graph, &config, format, *deps);
+            if *groups {
+                print_task_groups(&graph, format);
+            } else {
+                print_task_list(&graph, &config, format, *deps, tag);
+            }
             Ok(())
         }
 
-        Commands::Watch { task, clear } => {
-            let (config, _) = Config::load(cli.config.as_deref())?;
-            let graph = TaskGraph::from_config(&config)?;
+        Commands::Watch {
+            tasks,
+            clear,
+            watch_once,
+            on_change_script,
+        } => run_watch_command(tasks, *watch_once, on_change_script.as_deref(), cli).await,
 
-            let exec_config = ExecutorConfig {
-                verbose: cli.verbose,
-                cwd: std::env::current_dir()?,
-                ..Default::default()
-            };
+        Commands::Diff { task, format } => run_diff_command(task, format, cli).await,
 
-            watch::watch_and_run(&config, &graph, task, exec_config).await
-        }
+        Commands::Graph {
+            task,
+            format,
+            stats,
+            path,
+        } => run_graph_command(task.as_deref(), format, *stats, path, cli),
+
+        Commands::Cache { command } => run_cache_command(command, cli).await,
+
+        Commands::Init { force, from, path } => from.as_ref().map_or_else(
+            || init_config(*force),
+            |from| init_config_from(from, path.as_deref(), *force),
+        ),
 
-        Commands::Graph { task, format } => {
-            let (config, _) = Config::load(cli.config.as_deref())?;
+        Commands::Explain { task } => {
+            let (config, _) = load_config(cli)?;
             let graph = TaskGraph::from_config(&config)?;
-            print_graph(&graph, task.as_deref(), format)?;
-            Ok(())
+            explain_task(&graph, task)
         }
 
-        Commands::Cache { command } => run_cache_command(command, cli).await,
-
-        Commands::Init { force } => init_config(*force),
+        Commands::Check {
+            scripts,
+            strict_deps,
+        } => run_check_command(*scripts, *strict_deps, cli),
 
-        Commands::Check => run_check_command(cli),
+        Commands::Schema => print_schema(),
+        Commands::Completion { shell } => cli::write_completion(*shell, &mut std::io::stdout()),
 
-        Commands::Schema => {
-            let schema = schemars::schema_for!(Config);
-            let json = serde_json::to_string_pretty(&schema)
-                .map_err(|e| YatrError::Io(std::io::Error::other(e.to_string())))?;
-            println!("{json}");
+        Commands::Script { .. } => {
+            print_stdlib_functions();
             Ok(())
         }
 
+        Commands::Import { from, path } => import_config(from, path.as_deref()),
+
         Commands::Affected { git_ref, format } => run_affected_command(git_ref, format, cli),
 
         // The LSP runs a synchronous stdio loop; keep it off the async executor.
@@ -179,14 +256,28 @@ async fn run_command(cmd: &Commands, cli: &Cli) -> Result<()> {
     }
 }
 
-fn run_check_command(cli: &Cli) -> Result<()> {
-    let (config, path) = Config::load(cli.config.as_deref())?;
+/// Print the JSON Schema for yatr.toml.
+fn print_schema() -> Result<()> {
+    let schema = schemars::schema_for!(Config);
+    let json = serde_json::to_string_pretty(&schema)
+        .map_err(|e| YatrError::Io(std::io::Error::other(e.to_string())))?;
+    println!("{json}");
+    Ok(())
+}
+
+fn run_check_command(scripts: bool, strict_deps: bool, cli: &Cli) -> Result<()> {
+    let (config, path) = load_config(cli)?;
     let graph = TaskGraph::from_config(&config)?;
 
     let mut errors: Vec<String> = Vec::new();
-    let mut warnings: Vec<String> = Vec::new();
+    let mut warnings: Vec<String> = config
+        .warnings()
+        .iter()
+        .chain(&config.lint())
+        .map(ToString::to_string)
+        .collect();
 
-    for name in graph.task_names() {
+    for name in graph.task_names_sorted() {
         let Some(task) = config.get_task(name) else {
             continue;
         };
@@ -194,7 +285,7 @@ fn run_check_command(cli: &Cli) -> Result<()> {
 
         // Referenced paths must exist.
         if let Some(cwd) = &task.cwd {
-            if !cwd.is_dir() {
+            if ssh::SshTarget::from_cwd(cwd).is_none() && !cwd.is_dir() {
                 errors.push(format!(
                     "task '{name}': cwd '{}' does not exist",
                     cwd.display()
@@ -219,12 +310,12 @@ fn run_check_command(cli: &Cli) -> Result<()> {
         }
 
         // Config smells worth a nudge.
-        if !task.outputs.is_empty() && task.no_cache {
+        if !task.outputs.is_empty() && task.no_cache.unwrap_or(false) {
             warnings.push(format!(
                 "task '{name}': declares `outputs` but `no_cache = true` — outputs won't be cached"
             ));
         }
-        if task.foreground && task.run.len() > 1 {
+        if task.foreground.unwrap_or(false) && task.run.len() > 1 {
             warnings.push(format!(
                 "task '{name}': foreground tasks only run their first command ({} given)",
                 task.run.len()
@@ -232,6 +323,27 @@ fn run_check_command(cli: &Cli) -> Result<()> {
         }
     }
 
+    if scripts {
+        for result in config.validate_scripts() {
+            let task = &result.task;
+            if let Some(error) = &result.error {
+                errors.push(format!("task '{task}': script does not compile: {error}"));
+            }
+            for warning in &result.warnings {
+                warnings.push(format!("task '{task}': {warning}"));
+            }
+        }
+    }
+
+    if strict_deps {
+        errors.extend(
+            config
+                .validate_implicit_deps()
+                .iter()
+                .map(ToString::to_string),
+        );
+    }
+
     for w in &warnings {
         println!("{} {w}", style("warning:").yellow().bold());
     }
@@ -260,7 +372,7 @@ fn run_check_command(cli: &Cli) -> Result<()> {
 }
 
 fn run_affected_command(git_ref: &str, format: &ListFormat, cli: &Cli) -> Result<()> {
-    let (config, _) = Config::load(cli.config.as_deref())?;
+    let (config, _) = load_config(cli)?;
     let graph = TaskGraph::from_config(&config)?;
     let changed = affected::changed_files(git_ref)?;
     let affected = affected::affected_tasks(&graph, &changed);
@@ -308,26 +420,67 @@ struct RunOpts {
     profile: Option<std::path::PathBuf>,
     affected: Option<String>,
     trace_io: bool,
+    timing: bool,
+    output_dir: Option<std::path::PathBuf>,
+    predict_cache: bool,
+    incremental: bool,
+    unconstrained: bool,
+    report: Option<(report::ReportFormat, std::path::PathBuf)>,
+    /// Tags whose tasks run along with the named ones (`--tag`)
+    tags: Vec<String>,
+    /// Task arguments (`--name value`) for the requested tasks
+    args: Vec<String>,
+}
+
+/// Ensure pinned toolchains are installed and put them on the task PATH.
+async fn install_toolchains(config: &mut Config) -> Result<()> {
+    if config.toolchain.is_empty() {
+        return Ok(());
+    }
+    let bins = toolchain::ensure_all(&config.toolchain, &toolchain::toolchains_dir()).await?;
+    if !bins.is_empty() {
+        let mut paths = bins;
+        if let Some(existing) = std::env::var_os("PATH") {
+            paths.extend(std::env::split_paths(&existing));
+        }
+        if let Ok(joined) = std::env::join_paths(&paths) {
+            config
+                .env
+                .insert("PATH".to_string(), joined.to_string_lossy().into_owned());
+        }
+    }
+    Ok(())
+}
+
+/// `tasks` followed by the other tasks with any of `tags` (`run --tag`).
+fn with_tagged_tasks(config: &Config, tasks: &[String], tags: &[String]) -> Result<Vec<String>> {
+    let mut all = tasks.to_vec();
+    if tags.is_empty() {
+        return Ok(all);
+    }
+    let tagged = config.tasks_tagged(tags);
+    if tagged.is_empty() {
+        return Err(YatrError::InvalidConfig {
+            message: format!("no tasks tagged {}", tags.join(", ")),
+        });
+    }
+    for name in tagged {
+        if !all.contains(&name) {
+            all.push(name);
+        }
+    }
+    Ok(all)
 }
 
 async fn run_tasks(tasks: &[String], opts: RunOpts, cli: &Cli) -> Result<()> {
-    let (mut config, _) = Config::load(cli.config.as_deref())?;
+    let (mut config, _) = load_config(cli)?;
+
+    let tasks = &with_tagged_tasks(&config, tasks, &opts.tags)?;
+    config.set_task_args(tasks, &opts.args)?;
     let graph = TaskGraph::from_config(&config)?;
 
-    // Ensure pinned toolchains are installed and put them on the task PATH.
-    if !config.toolchain.is_empty() && !opts.dry_run {
-        let bins = toolchain::ensure_all(&config.toolchain, &toolchain::toolchains_dir()).await?;
-        if !bins.is_empty() {
-            let mut paths = bins;
-            if let Some(existing) = std::env::var_os("PATH") {
-                paths.extend(std::env::split_paths(&existing));
-            }
-            if let Ok(joined) = std::env::join_paths(&paths) {
-                config
-                    .env
-                    .insert("PATH".to_string(), joined.to_string_lossy().into_owned());
-            }
-        }
+    if !opts.dry_run {
+        install_toolchains(&mut config).await?;
     }
 
     // --affected: keep only the requested tasks that changes since the ref touch.
@@ -362,21 +515,20 @@ async fn run_tasks(tasks: &[String], opts: RunOpts, cli: &Cli) -> Result<()> {
         return Ok(());
     }
 
-    let cache = if config.settings.cache && !opts.dry_run {
-        let remote_cfg = config.settings.remote_cache.as_ref();
-        let remote = match remote_cfg {
-            Some(rc) => Some(remote::RemoteCache::from_config(rc)?),
-            None => None,
-        };
-        let signing_key = remote_cfg
-            .and_then(|rc| rc.sign_key_env.as_ref())
-            .and_then(|var| std::env::var(var).ok())
-            .map(|secret| cache::Cache::derive_key(&secret));
-        Some(
-            cache::Cache::new(config.settings.cache_dir.clone())?
-                .with_remote(remote)
-                .with_signing_key(signing_key),
-        )
+    if opts.predict_cache {
+        return print_cache_prediction(&config, &graph, tasks, opts.json);
+    }
+
+    if opts.unconstrained && !opts.json && !cli.quiet {
+        eprintln!(
+            "{} --unconstrained ignores `depends`: tasks may run before their dependencies finish",
+            style("warning:").yellow().bold()
+        );
+    }
+
+    // A timed dry run reads previous durations from the cache, but never writes.
+    let cache = if config.settings.cache && (!opts.dry_run || opts.timing) {
+        Some(open_cache(&config)?)
     } else {
         None
     };
@@ -390,7 +542,21 @@ async fn run_tasks(tasks: &[String], opts: RunOpts, cli: &Cli) -> Result<()> {
         verbose: cli.verbose,
         json: opts.json,
         trace_io: opts.trace_io,
+        timing: opts.timing,
+        output_dir: opts.output_dir,
+        incremental: opts.incremental,
+        unconstrained: opts.unconstrained,
+        prefix_output: true,
+        report: opts.report,
+        watch_until_success: false,
+        inherit_env: true,
+        sanitize_env: false,
+        quiet: cli.quiet,
+        mask_secrets: true,
+        secret_keys: Vec::new(),
         run_start: std::time::Instant::now(),
+        foreground_procs: std::sync::Arc::default(),
+        script_contexts: std::sync::Arc::default(),
     };
 
     let executor = Executor::new(config, exec_config, cache);
@@ -414,6 +580,216 @@ async fn run_tasks(tasks: &[String], opts: RunOpts, cli: &Cli) -> Result<()> {
     Ok(())
 }
 
+/// Open the task cache described by `[settings]`, wiring up the remote
+/// backend and signing key when configured.
+fn open_cache(config: &Config) -> Result<cache::Cache> {
+    let remote_cfg = config.settings.remote_cache.as_ref();
+    let remote = match remote_cfg {
+        Some(rc) => Some(remote::RemoteCache::from_config(rc)?),
+        None => None,
+    };
+    let signing_key = remote_cfg
+        .and_then(|rc| rc.sign_key_env.as_ref())
+        .and_then(|var| std::env::var(var).ok())
+        .map(|secret| cache::Cache::derive_key(&secret));
+    let flush_interval =
+        std::time::Duration::from_secs(config.settings.cache_access_flush_interval_secs);
+    Ok(cache::Cache::new(config.settings.cache_dir.clone())?
+        .with_remote(remote)
+        .with_signing_key(signing_key)
+        .with_access_flush_interval(flush_interval))
+}
+
+/// `run --predict-cache`: report whether each task in the plan would be a
+/// cache hit, a miss, or stale, without running anything.
+fn print_cache_prediction(
+    config: &Config,
+    graph: &TaskGraph,
+    tasks: &[String],
+    json: bool,
+) -> Result<()> {
+    let mut plan: Vec<&TaskNode> = Vec::new();
+    for task in tasks {
+        for node in graph.execution_order(task)? {
+            if !plan.iter().any(|n| n.name == node.name) {
+                plan.push(node);
+            }
+        }
+    }
+
+    let cache = if config.settings.cache {
+        open_cache(config)?
+    } else {
+        cache::Cache::disabled()
+    };
+    let entries: Vec<(&str, &config::TaskConfig)> =
+        plan.iter().map(|n| (n.name.as_str(), &n.config)).collect();
+    let statuses = cache.precompute(&entries, &std::env::current_dir()?)?;
+    let count = |s: CacheStatus| statuses.iter().filter(|(_, st)| *st == s).count();
+
+    if json {
+        let tasks: Vec<_> = statuses
+            .iter()
+            .map(|(name, status)| serde_json::json!({ "name": name, "status": status.to_string() }))
+            .collect();
+        return print_json(&serde_json::json!({
+            "tasks": tasks,
+            "summary": {
+                "hit": count(CacheStatus::Hit),
+                "miss": count(CacheStatus::Miss),
+                "stale": count(CacheStatus::Stale),
+            }
+        }));
+    }
+
+    println!("{}", style("Predicted cache status:").bold());
+    println!();
+    let width = statuses.iter().map(|(n, _)| n.len()).max().unwrap_or(0);
+    for (name, status) in &statuses {
+        let label = match status {
+            CacheStatus::Hit => style(status.to_string()).green(),
+            CacheStatus::Miss => style(status.to_string()).red(),
+            CacheStatus::Stale => style(status.to_string()).yellow(),
+        };
+        println!(
+            "  {}{}  {label}",
+            style(name).cyan(),
+            " ".repeat(width - name.len())
+        );
+    }
+    println!();
+    println!(
+        "{} hit, {} stale, {} miss",
+        count(CacheStatus::Hit),
+        count(CacheStatus::Stale),
+        count(CacheStatus::Miss)
+    );
+    Ok(())
+}
+
+/// Re-run `task` whenever its watched files change.
+async fn run_watch_command(
+    tasks: &[String],
+    watch_once: bool,
+    on_change_script: Option<&std::path::Path>,
+    cli: &Cli,
+) -> Result<()> {
+    let (mut config, _) = load_config(cli)?;
+    if let Some(path) = on_change_script {
+        let script = std::fs::read_to_string(path)?;
+        for name in tasks {
+            if let Some(task) = config.tasks.get_mut(name) {
+                task.on_change_script = Some(script.clone());
+            }
+        }
+    }
+    let graph = TaskGraph::from_config(&config)?;
+
+    let exec_config = ExecutorConfig {
+        verbose: cli.verbose,
+        quiet: cli.quiet,
+        cwd: std::env::current_dir()?,
+        watch_until_success: watch_once,
+        ..Default::default()
+    };
+
+    let tasks: Vec<&str> = tasks.iter().map(String::as_str).collect();
+    watch::watch_and_run_all(&config, &graph, &tasks, exec_config).await
+}
+
+/// Run a task fresh and diff its output against the last cached run. The new
+/// output is cached as usual, so the next diff compares against this run.
+async fn run_diff_command(task: &str, format: &DiffFormat, cli: &Cli) -> Result<()> {
+    let (config, _) = load_config(cli)?;
+    let graph = TaskGraph::from_config(&config)?;
+    if !graph.has_task(task) {
+        return Err(YatrError::TaskNotFound {
+            name: task.to_string(),
+            available: graph
+                .task_names_sorted()
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        });
+    }
+
+    let cache = if config.settings.cache {
+        Some(open_cache(&config)?)
+    } else {
+        None
+    };
+    // Look up the previous output before the fresh run replaces it.
+    let previous = match &cache {
+        Some(c) => c.last_result(task)?.map(|r| r.stdout),
+        None => None,
+    };
+
+    let exec_config = ExecutorConfig {
+        force: true,
+        cwd: std::env::current_dir()?,
+        verbose: cli.verbose,
+        json: true, // suppress per-task human output; the diff is the output
+        ..Default::default()
+    };
+    let executor = Executor::new(config, exec_config, cache);
+    let results = executor.execute(&graph, task).await?;
+    let current = results
+        .iter()
+        .find(|r| r.name == task)
+        .and_then(|r| r.output.clone())
+        .unwrap_or_default();
+
+    let Some(previous) = previous else {
+        if matches!(format, DiffFormat::Json) {
+            return print_json(&serde_json::json!({
+                "task": task,
+                "cached": false,
+                "output": current,
+            }));
+        }
+        println!(
+            "{} No cached output for '{task}' — showing current output",
+            style("ℹ").cyan()
+        );
+        println!();
+        print!("{current}");
+        return Ok(());
+    };
+
+    let stats = diff::stats(&previous, &current);
+    match format {
+        DiffFormat::Json => {
+            let mut doc = diff::to_json(&previous, &current);
+            doc["task"] = serde_json::json!(task);
+            doc["cached"] = serde_json::json!(true);
+            return print_json(&doc);
+        }
+        DiffFormat::Stats => {}
+        _ if stats.is_unchanged() => {}
+        DiffFormat::Unified => print!("{}", diff::unified(&previous, &current)),
+        DiffFormat::SideBySide => {
+            let columns = usize::from(console::Term::stdout().size().1);
+            let width = (columns.saturating_sub(3) / 2).max(20);
+            print!("{}", diff::side_by_side(&previous, &current, width));
+        }
+    }
+
+    if stats.is_unchanged() {
+        println!("{} Output of '{task}' is unchanged", style("✓").green());
+    } else {
+        println!(
+            "{} {} {}, {} {}, {} unchanged",
+            style(task).bold(),
+            style(format!("+{}", stats.inserted)).green(),
+            if stats.inserted == 1 { "line" } else { "lines" },
+            style(format!("-{}", stats.deleted)).red(),
+            if stats.deleted == 1 { "line" } else { "lines" },
+            stats.unchanged,
+        );
+    }
+    Ok(())
+}
+
 /// Write a Chrome Trace Event Format file (viewable in `chrome://tracing` or
 /// Perfetto): one complete event per task, placed on the run timeline.
 fn write_profile(results: &[TaskResult], path: &std::path::Path) -> Result<()> {
@@ -457,6 +833,7 @@ fn print_run_json(results: &[TaskResult]) -> Result<()> {
                 "name": r.name,
                 "success": r.success,
                 "cached": r.cached,
+                "skipped": r.skipped,
                 "duration_ms": ms(r.duration),
                 "output": r.output,
                 "error": r.error,
@@ -470,6 +847,7 @@ fn print_run_json(results: &[TaskResult]) -> Result<()> {
             "succeeded": results.iter().filter(|r| r.success).count(),
             "failed": results.iter().filter(|r| !r.success).count(),
             "cached": results.iter().filter(|r| r.cached).count(),
+            "skipped": results.iter().filter(|r| r.skipped).count(),
             "duration_ms": results.iter().map(|r| ms(r.duration)).sum::<u64>(),
         }
     });
@@ -477,6 +855,91 @@ fn print_run_json(results: &[TaskResult]) -> Result<()> {
     print_json(&doc)
 }
 
+/// Print the effective config — after include resolution and merging — as
+/// TOML. Going through `toml::Value` sorts tables, so the output is stable.
+fn print_config(cli: &Cli) -> Result<()> {
+    let (config, _) = load_config(cli)?;
+    let text = toml::Value::try_from(&config)
+        .and_then(|value| toml::to_string_pretty(&value))
+        .map_err(|e| YatrError::Io(std::io::Error::other(e.to_string())))?;
+    print!("{text}");
+    Ok(())
+}
+
+/// Print the tasks imported from another tool's config as yatr TOML, with
+/// only the fields the import sets.
+fn import_config(from: &ImportFormat, path: Option<&std::path::Path>) -> Result<()> {
+    let (config, notes) = imported_config(from, path)?;
+    print!("{}", imported_config_text(&config, &notes)?);
+    Ok(())
+}
+
+/// Tasks converted from another tool's config, with notes on what couldn't be
+/// converted.
+fn imported_config(
+    from: &ImportFormat,
+    path: Option<&std::path::Path>,
+) -> Result<(Config, Vec<String>)> {
+    Ok(match from {
+        ImportFormat::PackageJson => (
+            Config::from_package_json(path.unwrap_or_else(|| "package.json".as_ref()))?,
+            Vec::new(),
+        ),
+        ImportFormat::Makefile => {
+            Config::from_makefile(path.unwrap_or_else(|| "Makefile".as_ref()))?
+        }
+    })
+}
+
+/// `yatr.toml` text for imported tasks; `notes` are listed in a leading
+/// comment.
+fn imported_config_text(config: &Config, notes: &[String]) -> Result<String> {
+    let mut tasks = toml::Table::new();
+    for name in config.task_names_sorted() {
+        let task = &config.tasks[name];
+        let mut table = toml::Table::new();
+        if let Some(desc) = &task.desc {
+            table.insert("desc".into(), desc.clone().into());
+        }
+        if !task.depends.is_empty() {
+            table.insert("depends".into(), task.depends.clone().into());
+        }
+        if !task.sources.is_empty() {
+            table.insert("sources".into(), task.sources.clone().into());
+        }
+        if let Some(shell) = task.shell {
+            table.insert("shell".into(), shell.into());
+        }
+        let run: Vec<String> = task.run.iter().map(ToString::to_string).collect();
+        table.insert("run".into(), run.into());
+        tasks.insert(name.to_string(), table.into());
+    }
+    let mut root = toml::Table::new();
+    if !config.env.is_empty() {
+        let env: toml::Table = config
+            .env
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone().into()))
+            .collect();
+        root.insert("env".into(), env.into());
+    }
+    root.insert("tasks".into(), tasks.into());
+
+    let mut text = String::new();
+    if !notes.is_empty() {
+        text.push_str("# Not converted — review and carry over by hand:\n");
+        for note in notes {
+            let _ = writeln!(text, "#   - {note}");
+        }
+        text.push('\n');
+    }
+    text.push_str(
+        &toml::to_string_pretty(&root)
+            .map_err(|e| YatrError::Io(std::io::Error::other(e.to_string())))?,
+    );
+    Ok(text)
+}
+
 /// Print a JSON value to stdout, pretty-printed.
 fn print_json(value: &serde_json::Value) -> Result<()> {
     let text = serde_json::to_string_pretty(value)
@@ -487,7 +950,7 @@ fn print_json(value: &serde_json::Value) -> Result<()> {
 
 async fn run_cache_command(cmd: &CacheCommands, cli: &Cli) -> Result<()> {
     let cache_dir = cli.config.as_ref().and_then(|_| {
-        Config::load(cli.config.as_deref())
+        load_config(cli)
             .ok()
             .and_then(|(c, _)| c.settings.cache_dir)
     });
@@ -518,20 +981,115 @@ async fn run_cache_command(cmd: &CacheCommands, cli: &Cli) -> Result<()> {
             let stats = cache.stats()?;
             println!("{}", stats.cache_dir.display());
         }
+
+        CacheCommands::Repair => {
+            let report = cache.repair()?;
+            if report.total() == 0 {
+                println!("{} Cache is intact", style("✓").green());
+            } else {
+                println!(
+                    "{} Removed {} cache {}: {report}",
+                    style("✓").green(),
+                    report.total(),
+                    if report.total() == 1 { "file" } else { "files" },
+                );
+            }
+        }
+
+        CacheCommands::Debug { task } => {
+            let (config, _) = load_config(cli)?;
+            let task_config = config
+                .tasks
+                .get(task)
+                .ok_or_else(|| YatrError::TaskNotFound {
+                    name: task.clone(),
+                    available: config
+                        .task_names_sorted()
+                        .into_iter()
+                        .map(String::from)
+                        .collect(),
+                })?;
+            let cwd = match &task_config.cwd {
+                Some(cwd) => cwd.clone(),
+                None => std::env::current_dir()?,
+            };
+            match open_cache(&config)?.explain_miss(task, task_config, &cwd)? {
+                None => println!("{} '{task}' would hit the cache", style("✓").green()),
+                Some(miss) => println!("{} '{task}' would miss: {miss}", style("✗").red()),
+            }
+        }
+
+        CacheCommands::Serve {
+            host,
+            port,
+            read_only,
+        } => {
+            let token = std::env::var(cache_server::TOKEN_ENV).ok();
+            println!(
+                "{} Serving {} on http://{host}:{port}/cache{}{}",
+                style("✓").green(),
+                cache.stats()?.cache_dir.display(),
+                if *read_only { " (read-only)" } else { "" },
+                if token.is_some() {
+                    " (token required)"
+                } else {
+                    ""
+                },
+            );
+            cache_server::CacheServer::new(cache)
+                .read_only(*read_only)
+                .with_token(token)
+                .bind_and_serve(host, *port)
+                .await?;
+        }
     }
 
     Ok(())
 }
 
-fn print_task_list(graph: &TaskGraph, config: &Config, format: &ListFormat, show_deps: bool) {
+/// `yatr script --list-functions`: signature and description of every
+/// built-in script function.
+fn print_stdlib_functions() {
+    let functions = script::ScriptEngine::stdlib_list();
+    let width = functions
+        .iter()
+        .map(|f| f.signature.len())
+        .max()
+        .unwrap_or(0);
+    for function in functions {
+        println!(
+            "  {}{}  {}",
+            style(&function.signature).cyan(),
+            " ".repeat(width - function.signature.len()),
+            style(&function.description).dim()
+        );
+    }
+}
+
+/// Print the tasks, or with `tags` only those having one of them.
+fn print_task_list(
+    graph: &TaskGraph,
+    config: &Config,
+    format: &ListFormat,
+    show_deps: bool,
+    tags: &[String],
+) {
+    let names: Vec<&str> = graph
+        .task_names_sorted()
+        .into_iter()
+        .filter(|name| {
+            tags.is_empty()
+                || graph
+                    .get_task(name)
+                    .is_some_and(|task| task.config.has_any_tag(tags))
+        })
+        .collect();
+
     match format {
         ListFormat::Table => {
             println!("{}", style("Available tasks:").bold());
             println!();
 
-            let mut names: Vec<_> = graph.task_names().collect();
-            names.sort_unstable();
-
             let max_name_len = names.iter().map(|n| n.len()).max().unwrap_or(0);
 
             for name in names {
@@ -545,6 +1103,13 @@ fn print_task_list(graph: &TaskGraph, config: &Config, format: &ListFormat, show
                         style(desc).dim()
                     );
 
+                    if !task.config.tags.is_empty() {
+                        print!(
+                            " {}",
+                            style(format!("[{}]", task.config.tags.join(", "))).magenta()
+                        );
+                    }
+
                     if show_deps {
                         if let Some(deps) = graph.dependencies(name) {
                             if !deps.is_empty() {
@@ -563,12 +1128,15 @@ fn print_task_list(graph: &TaskGraph, config: &Config, format: &ListFormat, show
 
         ListFormat::Json => {
             let mut tasks = serde_json::Map::new();
-            for name in graph.task_names() {
+            for &name in &names {
                 if let Some(task) = graph.get_task(name) {
                     let mut obj = serde_json::Map::new();
                     if let Some(desc) = &task.config.desc {
                         obj.insert("description".to_string(), serde_json::json!(desc));
                     }
+                    if !task.config.tags.is_empty() {
+                        obj.insert("tags".to_string(), serde_json::json!(task.config.tags));
+                    }
                     if show_deps {
                         if let Some(deps) = graph.dependencies(name) {
                             obj.insert("depends".to_string(), serde_json::json!(deps));
@@ -581,8 +1149,6 @@ fn print_task_list(graph: &TaskGraph, config: &Config, format: &ListFormat, show
         }
 
         ListFormat::Plain => {
-            let mut names: Vec<_> = graph.task_names().collect();
-            names.sort_unstable();
             for name in names {
                 println!("{name}");
             }
@@ -590,6 +1156,40 @@ fn print_task_list(graph: &TaskGraph, config: &Config, format: &ListFormat, show
     }
 }
 
+/// `list --groups`: tasks clustered into independent connected components.
+fn print_task_groups(graph: &TaskGraph, format: &ListFormat) {
+    let groups = graph.connected_components();
+    match format {
+        ListFormat::Table => {
+            for (i, group) in groups.iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                println!(
+                    "{} {}",
+                    style(format!("Group {}", i + 1)).bold(),
+                    style(format!("({} tasks)", group.len())).dim()
+                );
+                for name in group {
+                    let desc = graph
+                        .get_task(name)
+                        .and_then(|t| t.config.desc.as_deref())
+                        .unwrap_or("");
+                    println!("  {}  {}", style(name).cyan().bold(), style(desc).dim());
+                }
+            }
+        }
+        ListFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&groups).unwrap());
+        }
+        ListFormat::Plain => {
+            for group in &groups {
+                println!("{}", group.join(" "));
+            }
+        }
+    }
+}
+
 fn print_graph(graph: &TaskGraph, task: Option<&str>, format: &GraphFormat) -> Result<()> {
     let tasks = if let Some(name) = task {
         graph.execution_order(name)?
@@ -618,20 +1218,16 @@ fn print_graph(graph: &TaskGraph, task: Option<&str>, format: &GraphFormat) -> R
             }
         }
 
-        GraphFormat::Dot => {
-            println!("digraph yatr {{");
-            println!("  rankdir=LR;");
-            println!("  node [shape=box];");
+        GraphFormat::Dot => print!("{}", graph.dot_for(&tasks, None)),
 
-            for task_node in &tasks {
-                if let Some(deps) = graph.dependencies(&task_node.name) {
-                    for dep in deps {
-                        println!("  \"{}\" -> \"{}\";", dep, task_node.name);
-                    }
-                }
-            }
+        GraphFormat::Mermaid => print!("{}", graph.mermaid_for(&tasks)),
 
-            println!("}}");
+        GraphFormat::Nix => {
+            let name = task.ok_or_else(|| YatrError::InvalidConfig {
+                message: "--format nix needs a task, e.g. `yatr graph --format nix build`"
+                    .to_string(),
+            })?;
+            print!("{}", graph.to_nix_derivation(name)?);
         }
 
         GraphFormat::Json => {
@@ -666,17 +1262,223 @@ fn print_graph(graph: &TaskGraph, task: Option<&str>, format: &GraphFormat) -> R
     Ok(())
 }
 
+fn run_graph_command(
+    task: Option<&str>,
+    format: &GraphFormat,
+    stats: bool,
+    path: &[String],
+    cli: &Cli,
+) -> Result<()> {
+    let (config, _) = load_config(cli)?;
+    let graph = TaskGraph::from_config(&config)?;
+    if !path.is_empty() {
+        print_dependency_path(&graph, path)
+    } else if stats {
+        print_graph_stats(&graph, format);
+        Ok(())
+    } else {
+        print_graph(&graph, task, format)
+    }
+}
+
+/// Print a task's description, dependencies, commands and accepted args.
+fn explain_task(graph: &TaskGraph, name: &str) -> Result<()> {
+    let task = graph
+        .get_task(name)
+        .ok_or_else(|| YatrError::TaskNotFound {
+            name: name.to_string(),
+            available: graph
+                .task_names_sorted()
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        })?;
+    let config = &task.config;
+
+    println!("{}", style(name).cyan().bold());
+    if let Some(desc) = &config.desc {
+        println!("  {desc}");
+    }
+    if !config.depends.is_empty() {
+        println!(
+            "  {} {}",
+            style("depends:").dim(),
+            config.depends.join(", ")
+        );
+    }
+    if let Some(condition) = &config.condition_script {
+        println!("  {} {condition}", style("only if:").dim());
+    }
+    if let Some(check) = &config.pre_exec_check {
+        println!("  {} {check}", style("skip if ok:").dim());
+    }
+    if !config.run_if_changed.is_empty() {
+        println!(
+            "  {} {}",
+            style("if changed:").dim(),
+            config.run_if_changed.join(", ")
+        );
+    }
+    for cmd in &config.run {
+        println!("  {} {cmd}", style("$").dim());
+    }
+    if config.script.is_some() {
+        println!("  {}", style("(Rhai script)").dim());
+    }
+    if !config.args.is_empty() {
+        println!("  {}", style("args:").dim());
+        let mut args: Vec<_> = config.args.iter().collect();
+        args.sort();
+        for (arg, default) in args {
+            println!("    --{arg} (default: {default:?})");
+        }
+    }
+    println!("  {}", style("env set by yatr:").dim());
+    for (var, meaning) in executor::CONTEXT_ENV_VARS {
+        println!("    {var}: {meaning}");
+    }
+    Ok(())
+}
+
+/// Print the shortest chain from `--path from=A` to `--path to=B`.
+fn print_dependency_path(graph: &TaskGraph, args: &[String]) -> Result<()> {
+    let mut from = None;
+    let mut to = None;
+    for arg in args {
+        match arg.split_once('=') {
+            Some(("from", task)) => from = Some(task),
+            Some(("to", task)) => to = Some(task),
+            _ => {
+                return Err(YatrError::InvalidConfig {
+                    message: format!("invalid --path '{arg}' (expected from=<task> or to=<task>)"),
+                })
+            }
+        }
+    }
+    let (Some(from), Some(to)) = (from, to) else {
+        return Err(YatrError::InvalidConfig {
+            message: "--path needs both from=<task> and to=<task>".to_string(),
+        });
+    };
+
+    match graph.shortest_dependency_path(from, to)? {
+        Some(path) => println!("{}", path.join(" → ")),
+        None => println!("'{to}' does not depend on '{from}'"),
+    }
+    Ok(())
+}
+
+/// Print each task's transitive dependency count, most-coupled first.
+fn print_graph_stats(graph: &TaskGraph, format: &GraphFormat) {
+    let mut counts: Vec<(String, usize)> =
+        graph.transitive_dependency_counts().into_iter().collect();
+    counts.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then_with(|| a_name.cmp(b_name)));
+
+    if matches!(format, GraphFormat::Json) {
+        let tasks: Vec<_> = counts
+            .iter()
+            .map(|(name, count)| serde_json::json!({ "name": name, "transitive_dependencies": count }))
+            .collect();
+        let deepest = graph.deepest_task().map(
+            |(name, count)| serde_json::json!({ "name": name, "transitive_dependencies": count }),
+        );
+        let output =
+            serde_json::json!({ "summary": graph.stats(), "tasks": tasks, "deepest": deepest });
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        return;
+    }
+
+    print_graph_summary(&graph.stats());
+    println!();
+    println!("{}", style("Transitive dependencies per task:").bold());
+    println!();
+    for (name, count) in &counts {
+        println!("  {:>4}  {}", count, style(name).cyan());
+    }
+    if let Some((name, count)) = graph.deepest_task() {
+        println!();
+        println!(
+            "Deepest: {} ({count} transitive dependencies)",
+            style(name).cyan().bold()
+        );
+    }
+}
+
+/// The [`graph::GraphStats`] overview printed by `yatr graph --stats`.
+fn print_graph_summary(stats: &graph::GraphStats) {
+    let or_none = |name: &str| {
+        if name.is_empty() {
+            "-".to_string()
+        } else {
+            name.to_string()
+        }
+    };
+    println!("{}", style("Graph:").bold());
+    println!();
+    println!(
+        "  {} tasks ({} roots, {} leaves)",
+        stats.total_tasks, stats.root_tasks, stats.leaf_tasks
+    );
+    println!("  max depth:          {}", stats.max_depth);
+    println!("  avg dependencies:   {:.2}", stats.avg_dependencies);
+    println!(
+        "  most depended upon: {}",
+        style(or_none(&stats.most_depended_upon)).cyan()
+    );
+    println!(
+        "  most dependencies:  {}",
+        style(or_none(&stats.most_dependencies)).cyan()
+    );
+}
+
+/// `yatr init --from <format>`: write the converted config to `yatr.toml`.
+fn init_config_from(
+    from: &ImportFormat,
+    path: Option<&std::path::Path>,
+    force: bool,
+) -> Result<()> {
+    let target = std::path::Path::new(config::CONFIG_FILES[0]);
+    if target.exists() && !force {
+        return Err(YatrError::Io(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!(
+                "{} already exists (use --force to overwrite)",
+                target.display()
+            ),
+        )));
+    }
+
+    let (config, notes) = imported_config(from, path)?;
+    std::fs::write(target, imported_config_text(&config, &notes)?)?;
+    println!(
+        "{} Created {} with {} tasks",
+        style("✓").green(),
+        style(target.display()).bold(),
+        config.tasks.len()
+    );
+    if !notes.is_empty() {
+        println!(
+            "  {} unconverted feature(s) are listed at the top of the file",
+            notes.len()
+        );
+    }
+    Ok(())
+}
+
 fn init_config(force: bool) -> Result<()> {
-    let path = std::path::Path::new("YATR.toml");
+    let path = std::path::Path::new(config::CONFIG_FILES[0]);
 
     if path.exists() && !force {
         return Err(YatrError::Io(std::io::Error::new(
             std::io::ErrorKind::AlreadyExists,
-            "YATR.toml already exists (use --force to overwrite)",
+            format!(
+                "{} already exists (use --force to overwrite)",
+                path.display()
+            ),
         )));
     }
 
-    let template = r#"# YATR.toml - Task runner configuration
+    let template = r#"# yatr.toml - Task runner configuration
 # See https://github.com/yourusername/yatr for documentation
 
 [env]
@@ -716,7 +1518,7 @@ run = ["cargo build --release"]
     println!(
         "{} Created {}",
         style("✓").green(),
-        style("YATR.toml").bold()
+        style(path.display()).bold()
     );
 
     Ok(())
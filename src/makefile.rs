@@ -0,0 +1,537 @@
+//! Makefile import (`yatr init --from makefile`, `yatr import --from makefile`)
+//!
+//! Converts the explicit rules of a Makefile into tasks: `.PHONY` targets (or
+//! every explicit target when nothing is declared phony) become tasks, recipe
+//! lines become `run` commands and prerequisites become `depends` (or
+//! `sources`, for plain files). Variables become `[env]` entries referenced as
+//! `${NAME}`. Only the common subset of Make is understood; everything else —
+//! functions, conditionals, pattern rules, includes — is reported as a note so
+//! it can be carried over by hand.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::config::TaskConfig;
+
+/// Characters that make a recipe line need a shell in yatr.
+const SHELL_SYNTAX: &[char] = &['&', '|', ';', '>', '<', '$', '`', '*', '?', '~'];
+
+/// A Makefile converted to tasks.
+#[derive(Debug, Default)]
+pub struct MakefileImport {
+    /// Converted tasks by target name
+    pub tasks: HashMap<String, TaskConfig>,
+    /// Make variables the tasks use, for `[env]`
+    pub env: HashMap<String, String>,
+    /// Make features that weren't converted, one human-readable line each
+    pub notes: Vec<String>,
+}
+
+/// One explicit rule, merged across every line that names its target.
+#[derive(Debug, Default)]
+struct Rule {
+    prerequisites: Vec<String>,
+    recipe: Vec<String>,
+}
+
+/// Convert Makefile `content` into tasks.
+#[must_use]
+pub fn import(content: &str) -> MakefileImport {
+    let mut parser = Parser::default();
+    for line in logical_lines(content) {
+        parser.line(&line);
+    }
+    parser.finish()
+}
+
+/// Lines with `\` continuations joined, keeping the leading tab of recipes.
+fn logical_lines(content: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut pending: Option<String> = None;
+    for raw in content.lines() {
+        let (text, continued) = raw
+            .strip_suffix('\\')
+            .map_or((raw, false), |text| (text, true));
+        let line = pending.take().map_or_else(
+            || text.to_string(),
+            |mut joined| {
+                joined.push(' ');
+                joined.push_str(text.trim_start());
+                joined
+            },
+        );
+        if continued {
+            pending = Some(line);
+        } else {
+            lines.push(line);
+        }
+    }
+    lines.extend(pending);
+    lines
+}
+
+#[derive(Debug, Default)]
+struct Parser {
+    /// Explicit rules in order of first appearance
+    rules: Vec<(String, Rule)>,
+    /// Targets the current recipe lines belong to
+    current: Vec<usize>,
+    phony: HashSet<String>,
+    variables: BTreeMap<String, String>,
+    notes: Vec<String>,
+}
+
+impl Parser {
+    fn line(&mut self, line: &str) {
+        if let Some(recipe) = line.strip_prefix('\t') {
+            let recipe = recipe.trim();
+            if !recipe.is_empty() && !recipe.starts_with('#') {
+                for &i in &self.current {
+                    self.rules[i].1.recipe.push(recipe.to_string());
+                }
+            }
+            return;
+        }
+        let text = line.trim();
+        if text.is_empty() || text.starts_with('#') {
+            return;
+        }
+        self.current.clear();
+
+        let directive = text.split_whitespace().next().unwrap_or_default();
+        if matches!(
+            directive,
+            "include"
+                | "-include"
+                | "sinclude"
+                | "ifeq"
+                | "ifneq"
+                | "ifdef"
+                | "ifndef"
+                | "else"
+                | "endif"
+                | "define"
+                | "endef"
+                | "export"
+                | "unexport"
+                | "override"
+                | "vpath"
+        ) {
+            self.notes
+                .push(format!("`{directive}` directive not converted: {text}"));
+        } else if let Some((name, value)) = assignment(text) {
+            self.variables.insert(name.to_string(), value.to_string());
+        } else if let Some((targets, rest)) = text.split_once(':') {
+            // Double-colon rules (`a:: b`) are treated like ordinary ones.
+            self.rule(targets, rest.strip_prefix(':').unwrap_or(rest), text);
+        } else {
+            self.notes
+                .push(format!("unrecognised line skipped: {text}"));
+        }
+    }
+
+    fn rule(&mut self, targets: &str, rest: &str, text: &str) {
+        // `a: b ; cmd` carries its first recipe line inline.
+        let (prerequisites, inline) = rest
+            .split_once(';')
+            .map_or((rest, None), |(p, cmd)| (p, Some(cmd.trim())));
+        // Order-only prerequisites (`| dir`) still have to exist first.
+        let prerequisites: Vec<String> = prerequisites
+            .split_whitespace()
+            .filter(|p| *p != "|")
+            .map(str::to_string)
+            .collect();
+
+        if targets.trim() == ".PHONY" {
+            self.phony.extend(prerequisites);
+            return;
+        }
+        for target in targets.split_whitespace() {
+            if target.contains('%') {
+                self.notes
+                    .push(format!("pattern rule not converted: {text}"));
+                continue;
+            }
+            if target.starts_with('.') {
+                self.notes
+                    .push(format!("special target `{target}` not converted"));
+                continue;
+            }
+            let i = self.rule_index(target);
+            self.rules[i]
+                .1
+                .prerequisites
+                .extend(prerequisites.iter().cloned());
+            if let Some(cmd) = inline.filter(|c| !c.is_empty()) {
+                self.rules[i].1.recipe.push(cmd.to_string());
+            }
+            self.current.push(i);
+        }
+    }
+
+    fn rule_index(&mut self, target: &str) -> usize {
+        if let Some(i) = self.rules.iter().position(|(name, _)| name == target) {
+            return i;
+        }
+        self.rules.push((target.to_string(), Rule::default()));
+        self.rules.len() - 1
+    }
+
+    fn finish(mut self) -> MakefileImport {
+        // Without `.PHONY`, every explicit target is taken to be a task.
+        let converted: HashSet<String> = if self.phony.is_empty() {
+            self.rules.iter().map(|(name, _)| name.clone()).collect()
+        } else {
+            self.phony.clone()
+        };
+        let mut ruleless: Vec<&String> = self
+            .phony
+            .iter()
+            .filter(|p| !self.rules.iter().any(|(n, _)| n == *p))
+            .collect();
+        ruleless.sort_unstable();
+        for name in ruleless {
+            self.notes
+                .push(format!("`.PHONY` target `{name}` has no rule"));
+        }
+
+        let mut tasks = HashMap::new();
+        let mut notes = Vec::new();
+        let mut used = Vec::new();
+        for (name, rule) in &self.rules {
+            if !converted.contains(name) {
+                notes.push(format!(
+                    "file target `{name}` not converted; add it as a task if something needs to build it"
+                ));
+                continue;
+            }
+            let (task, task_notes) = self.task(name, rule, &converted, &mut used);
+            notes.extend(task_notes.into_iter().map(|n| format!("{name}: {n}")));
+            tasks.insert(name.clone(), task);
+        }
+        self.notes.extend(notes);
+        let env = self.env(used);
+        MakefileImport {
+            tasks,
+            env,
+            notes: self.notes,
+        }
+    }
+
+    /// The assignments of the `used` variables and of those their values
+    /// refer to, rewritten like recipes. Names the Makefile doesn't assign
+    /// are left to the environment.
+    fn env(&mut self, mut used: Vec<String>) -> HashMap<String, String> {
+        let mut env = HashMap::new();
+        while let Some(name) = used.pop() {
+            if env.contains_key(&name) {
+                continue;
+            }
+            let Some(value) = self.variables.get(&name) else {
+                continue;
+            };
+            match rewrite_variables(value, |_| None) {
+                Ok((value, refs)) => {
+                    used.extend(refs);
+                    env.insert(name, value);
+                }
+                Err(reference) => self.notes.push(format!(
+                    "variable {name} uses `{reference}`; set it in [env] by hand"
+                )),
+            }
+        }
+        env
+    }
+
+    fn task(
+        &self,
+        name: &str,
+        rule: &Rule,
+        converted: &HashSet<String>,
+        used: &mut Vec<String>,
+    ) -> (TaskConfig, Vec<String>) {
+        let mut notes = Vec::new();
+        let mut depends = Vec::new();
+        let mut sources = Vec::new();
+        for prerequisite in &rule.prerequisites {
+            if converted.contains(prerequisite) {
+                depends.push(prerequisite.clone());
+            } else if self.rules.iter().any(|(n, _)| n == prerequisite) {
+                notes.push(format!(
+                    "prerequisite `{prerequisite}` is a file target that wasn't converted"
+                ));
+            } else {
+                sources.push(prerequisite.clone());
+            }
+        }
+
+        let mut run = Vec::new();
+        let mut vars = Vec::new();
+        for line in &rule.recipe {
+            match Self::recipe_command(name, rule, line, &mut notes) {
+                Ok((cmd, refs)) => {
+                    run.push(cmd);
+                    vars.extend(refs);
+                }
+                Err(reference) => {
+                    notes.push(format!(
+                        "recipe not converted: `{line}` uses `{reference}`; add its commands by hand"
+                    ));
+                    run.clear();
+                    vars.clear();
+                    break;
+                }
+            }
+        }
+        let mut unset: Vec<&String> = vars
+            .iter()
+            .filter(|var| !self.variables.contains_key(*var))
+            .collect();
+        unset.sort_unstable();
+        unset.dedup();
+        for var in unset {
+            notes.push(format!(
+                "uses ${{{var}}}, which the Makefile doesn't set; set it in [env] or the environment"
+            ));
+        }
+        // `${NAME}` is filled in by yatr, so only the rest may need a shell.
+        let shell = run.iter().any(|cmd| {
+            let literal = vars.iter().fold(cmd.clone(), |cmd, var| {
+                cmd.replace(&format!("${{{var}}}"), "")
+            });
+            literal.contains(SHELL_SYNTAX)
+        });
+        used.extend(vars);
+        let task = TaskConfig {
+            desc: Some(format!("make {name}")),
+            depends,
+            sources,
+            shell: shell.then_some(true),
+            run: run.into_iter().map(Into::into).collect(),
+            ..TaskConfig::default()
+        };
+        (task, notes)
+    }
+
+    /// A recipe line as a yatr command: echo/ignore prefixes stripped,
+    /// automatic variables expanded, `$$` unescaped and Make variables
+    /// rewritten as `${NAME}`, returned with the names used. Fails with the
+    /// reference when the line uses a Make function or an automatic variable
+    /// yatr has no stand-in for.
+    fn recipe_command(
+        target: &str,
+        rule: &Rule,
+        line: &str,
+        notes: &mut Vec<String>,
+    ) -> Result<(String, Vec<String>), String> {
+        let prefix_len = line.len() - line.trim_start_matches(['@', '-', '+']).len();
+        if line[..prefix_len].contains('-') {
+            notes.push(format!(
+                "`-` (ignore errors) dropped from `{line}`; consider `allow_failure = true`"
+            ));
+        }
+        let line = line[prefix_len..].trim_start();
+        rewrite_variables(line, |c| match c {
+            '@' => Some(target.to_string()),
+            '<' => Some(rule.prerequisites.first().cloned().unwrap_or_default()),
+            '^' => Some(rule.prerequisites.join(" ")),
+            _ => None,
+        })
+    }
+}
+
+/// Rewrite Make references in `text`: `$$` becomes `$`, `$(NAME)`, `${NAME}`
+/// and `$N` become `${NAME}`, and the one-character `$x` that `automatic`
+/// knows are replaced. Returns the text and the variable names used, or the
+/// first reference that can't be rewritten, such as a Make function call
+/// (`$(shell ...)`) or an unknown automatic variable (`$*`).
+fn rewrite_variables(
+    text: &str,
+    automatic: impl Fn(char) -> Option<String>,
+) -> Result<(String, Vec<String>), String> {
+    let is_name = |name: &str| {
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+    let mut out = String::new();
+    let mut used = Vec::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            out.push(c);
+            continue;
+        }
+        let name = match chars.next() {
+            Some('$') | None => {
+                out.push('$');
+                continue;
+            }
+            Some(open @ ('(' | '{')) => {
+                let close = if open == '(' { ')' } else { '}' };
+                let name: String = chars.by_ref().take_while(|&c| c != close).collect();
+                if !is_name(&name) {
+                    return Err(format!("${open}{name}{close}"));
+                }
+                name
+            }
+            Some(other) => {
+                if let Some(value) = automatic(other) {
+                    out.push_str(&value);
+                    continue;
+                }
+                if !is_name(&other.to_string()) {
+                    return Err(format!("${other}"));
+                }
+                other.to_string()
+            }
+        };
+        out.push_str("${");
+        out.push_str(&name);
+        out.push('}');
+        used.push(name);
+    }
+    Ok((out, used))
+}
+
+/// `NAME = value` (also `:=`, `::=`, `?=`, `+=`, `!=`), or `None` when `text`
+/// isn't a variable assignment.
+fn assignment(text: &str) -> Option<(&str, &str)> {
+    let eq = text.find('=')?;
+    let colon = text.find(':');
+    // `a: b=c` is a rule with a target-specific variable, not an assignment.
+    if colon.is_some_and(|c| c < eq && !text[c..eq].chars().all(|c| c == ':')) {
+        return None;
+    }
+    let name = text[..eq].trim_end_matches([':', '?', '+', '!']).trim();
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((name, text[eq + 1..].trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RunCommand;
+
+    /// The command lines of a `run` list
+    fn commands(run: &[RunCommand]) -> Vec<&str> {
+        run.iter().map(RunCommand::cmd).collect()
+    }
+
+    const MAKEFILE: &str = "\
+CC := gcc
+CFLAGS = -O2 \\
+\t-Wall
+
+.PHONY: all test clean
+
+all: test app
+
+app: main.c util.c
+\t$(CC) $(CFLAGS) -o $@ $^
+
+test: app
+\t@echo running
+\t-./app --selftest
+
+clean:
+\trm -rf app *.o
+
+%.o: %.c
+\t$(CC) -c $<
+
+ifdef DEBUG
+endif
+";
+
+    #[test]
+    fn converts_phony_targets_and_flags_the_rest() {
+        let import = import(MAKEFILE);
+        let mut names: Vec<_> = import.tasks.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        assert_eq!(names, ["all", "clean", "test"]);
+
+        let test = &import.tasks["test"];
+        assert_eq!(commands(&test.run), ["echo running", "./app --selftest"]);
+        assert!(test.depends.is_empty());
+        assert_eq!(import.tasks["all"].depends, ["test"]);
+        assert_eq!(import.tasks["clean"].shell, Some(true));
+        assert_eq!(import.tasks["clean"].desc.as_deref(), Some("make clean"));
+
+        let notes = import.notes.join("\n");
+        assert!(
+            notes.contains("pattern rule not converted: %.o: %.c"),
+            "{notes}"
+        );
+        assert!(notes.contains("`ifdef` directive"), "{notes}");
+        assert!(notes.contains("file target `app` not converted"), "{notes}");
+        assert!(
+            notes.contains("test: `-` (ignore errors) dropped"),
+            "{notes}"
+        );
+    }
+
+    #[test]
+    fn without_phony_every_target_is_a_task() {
+        let import = import(
+            "CC = cc\nbuild: main.c\n\t$(CC) -o $@ $<\n\t$(LD) --version\nrun: build ; ./build\n",
+        );
+        let build = &import.tasks["build"];
+        assert_eq!(
+            commands(&build.run),
+            ["${CC} -o build main.c", "${LD} --version"]
+        );
+        assert_eq!(build.shell, None);
+        assert_eq!(build.sources, ["main.c"]);
+        assert_eq!(import.tasks["run"].depends, ["build"]);
+        assert_eq!(commands(&import.tasks["run"].run), ["./build"]);
+        assert_eq!(import.env, HashMap::from([("CC".into(), "cc".into())]));
+
+        let notes = import.notes.join("\n");
+        assert!(
+            notes.contains("build: uses ${LD}, which the Makefile doesn't set"),
+            "{notes}"
+        );
+    }
+
+    #[test]
+    fn variables_become_env_and_functions_are_refused() {
+        let import = import(
+            "OPT = -O2\nCFLAGS = $(OPT) -Wall\nUNUSED = x\nSRCS = $(wildcard *.c)\n\
+             build:\n\tcc ${CFLAGS} -o out $$(cat files)\n\
+             list:\n\techo $(SRCS)\n\
+             gen:\n\techo $(shell date)\n\techo done\n",
+        );
+        assert_eq!(
+            commands(&import.tasks["build"].run),
+            ["cc ${CFLAGS} -o out $(cat files)"]
+        );
+        assert_eq!(import.tasks["build"].shell, Some(true));
+        assert_eq!(commands(&import.tasks["list"].run), ["echo ${SRCS}"]);
+        assert!(import.tasks["gen"].run.is_empty());
+        assert_eq!(import.env.len(), 2, "{:?}", import.env);
+        assert_eq!(import.env["CFLAGS"], "${OPT} -Wall");
+        assert_eq!(import.env["OPT"], "-O2");
+
+        let notes = import.notes.join("\n");
+        assert!(
+            notes.contains("gen: recipe not converted: `echo $(shell date)` uses `$(shell date)`"),
+            "{notes}"
+        );
+        assert!(
+            notes.contains("variable SRCS uses `$(wildcard *.c)`"),
+            "{notes}"
+        );
+    }
+
+    #[test]
+    fn continuations_and_assignments() {
+        assert_eq!(
+            logical_lines("a = 1 \\\n    2\n\tcmd \\\n\t  --flag\n"),
+            ["a = 1  2", "\tcmd  --flag"]
+        );
+        assert_eq!(assignment("CC ?= gcc"), Some(("CC", "gcc")));
+        assert_eq!(assignment("X ::= y"), Some(("X", "y")));
+        assert_eq!(assignment("all: FOO=1"), None);
+        assert_eq!(assignment("build: main.c"), None);
+    }
+}
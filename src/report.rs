@@ -0,0 +1,243 @@
+//! Run reports for CI (`yatr run --report <format> --report-file <path>`).
+//!
+//! - **`JUnit`** XML: one `<testcase>` per task, so CI systems (GitLab, Jenkins,
+//!   GitHub test reporters) can show which tasks failed and for how long.
+//! - **HTML**: a standalone page with a summary table and an execution
+//!   timeline; no external assets, so it can be archived as a build artifact.
+
+use std::fmt::Write as _;
+use std::path::Path;
+use std::time::Duration;
+
+use clap::ValueEnum;
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesDecl, BytesText, Event};
+use quick_xml::Writer;
+
+use crate::error::{Result, YatrError};
+use crate::executor::TaskResult;
+
+/// Name of the `JUnit` test suite (and HTML page title).
+const SUITE: &str = "yatr";
+
+/// Output format of a run report.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// `JUnit` XML, one `<testcase>` per task
+    Junit,
+    /// Standalone HTML page with an execution timeline
+    Html,
+}
+
+/// Render `results` as `format` and write them to `path`.
+pub fn write_report(format: ReportFormat, results: &[TaskResult], path: &Path) -> Result<()> {
+    let text = match format {
+        ReportFormat::Junit => junit_xml(results)?,
+        ReportFormat::Html => html(results),
+    };
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, text)?;
+    Ok(())
+}
+
+/// Render a `JUnit` XML document. Failed tasks get a `<failure>` carrying the
+/// error message and stderr; captured stdout goes into `<system-out>`.
+pub fn junit_xml(results: &[TaskResult]) -> Result<String> {
+    let failures = results.iter().filter(|r| !r.success).count().to_string();
+    let skipped = results.iter().filter(|r| r.skipped).count().to_string();
+    let tests = results.len().to_string();
+    let total = seconds(results.iter().map(|r| r.duration).sum());
+
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+    writer
+        .create_element("testsuites")
+        .with_attributes([
+            ("name", SUITE),
+            ("tests", &tests),
+            ("failures", &failures),
+            ("time", &total),
+        ])
+        .write_inner_content(|w| {
+            w.create_element("testsuite")
+                .with_attributes([
+                    ("name", SUITE),
+                    ("tests", &*tests),
+                    ("failures", &failures),
+                    ("skipped", &skipped),
+                    ("time", &total),
+                ])
+                .write_inner_content(|w| {
+                    for result in results {
+                        write_testcase(w, result)?;
+                    }
+                    Ok(())
+                })?;
+            Ok(())
+        })?;
+
+    String::from_utf8(writer.into_inner())
+        .map_err(|e| YatrError::Io(std::io::Error::other(e.to_string())))
+}
+
+fn write_testcase(w: &mut Writer<Vec<u8>>, result: &TaskResult) -> std::io::Result<()> {
+    let time = seconds(result.duration);
+    let testcase = w.create_element("testcase").with_attributes([
+        ("classname", result.name.as_str()),
+        ("name", &result.name),
+        ("time", &time),
+    ]);
+
+    let stdout = result.output.as_deref().filter(|s| !s.is_empty());
+    if result.success && !result.skipped && stdout.is_none() {
+        testcase.write_empty()?;
+        return Ok(());
+    }
+    testcase.write_inner_content(|w| {
+        if result.skipped {
+            w.create_element("skipped")
+                .with_attribute((
+                    "message",
+                    "skipped by condition_script, pre_exec_check or run_if_changed",
+                ))
+                .write_empty()?;
+        }
+        if !result.success {
+            let message = result.error.as_deref().unwrap_or("task failed");
+            let details = result.stderr.as_deref().unwrap_or(message);
+            w.create_element("failure")
+                .with_attribute(("message", message))
+                .write_text_content(BytesText::new(details))?;
+        }
+        if let Some(stdout) = stdout {
+            w.create_element("system-out")
+                .write_text_content(BytesText::new(stdout))?;
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Render a standalone HTML report: a per-task table and a timeline where each
+/// bar spans the task's start offset and duration within the run.
+#[must_use]
+pub fn html(results: &[TaskResult]) -> String {
+    let span = results
+        .iter()
+        .map(|r| r.start_offset + r.duration)
+        .max()
+        .unwrap_or_default()
+        .max(Duration::from_millis(1));
+    let percent = |d: Duration| d.as_secs_f64() / span.as_secs_f64() * 100.0;
+    let failed = results.iter().filter(|r| !r.success).count();
+
+    let mut rows = String::new();
+    for r in results {
+        let (class, status) = match (r.success, r.cached, r.skipped) {
+            (false, _, _) => ("failed", "failed"),
+            (true, _, true) => ("cached", "skipped"),
+            (true, true, false) => ("cached", "cached"),
+            (true, false, false) => ("ok", "ok"),
+        };
+        let _ = writeln!(
+            rows,
+            "<tr><td>{name}</td><td class=\"{class}\">{status}</td><td>{time}s</td>\
+             <td class=\"track\"><div class=\"bar {class}\" style=\"left:{left:.2}%;width:{width:.2}%\"></div></td></tr>",
+            name = escape(r.name.as_str()),
+            time = seconds(r.duration),
+            left = percent(r.start_offset),
+            width = percent(r.duration).max(0.5),
+        );
+        if let Some(error) = &r.error {
+            let _ = writeln!(
+                rows,
+                "<tr><td colspan=\"4\"><pre>{}</pre></td></tr>",
+                escape(error.as_str())
+            );
+        }
+    }
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{SUITE} run report</title>
+<style>
+body {{ font-family: system-ui, sans-serif; margin: 2rem; color: #222; }}
+table {{ border-collapse: collapse; width: 100%; }}
+td, th {{ padding: 0.3rem 0.6rem; border-bottom: 1px solid #eee; text-align: left; white-space: nowrap; }}
+td.track {{ position: relative; width: 60%; }}
+.bar {{ position: absolute; top: 25%; height: 50%; border-radius: 3px; }}
+.bar.ok {{ background: #2e9d5b; }}
+.bar.cached {{ background: #8fa3b8; }}
+.bar.failed {{ background: #d64545; }}
+td.failed {{ color: #d64545; font-weight: bold; }}
+pre {{ margin: 0; color: #d64545; white-space: pre-wrap; }}
+</style>
+</head>
+<body>
+<h1>{SUITE} run report</h1>
+<p>{total} tasks, {failed} failed, {span}s wall time</p>
+<table>
+<tr><th>Task</th><th>Status</th><th>Duration</th><th>Timeline</th></tr>
+{rows}</table>
+</body>
+</html>
+"#,
+        total = results.len(),
+        span = seconds(span),
+    )
+}
+
+/// Seconds with millisecond precision, as `JUnit` expects.
+fn seconds(d: Duration) -> String {
+    format!("{:.3}", d.as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(name: &str, success: bool, start_ms: u64, ms: u64) -> TaskResult {
+        TaskResult {
+            name: name.to_string(),
+            success,
+            duration: Duration::from_millis(ms),
+            start_offset: Duration::from_millis(start_ms),
+            cached: false,
+            skipped: false,
+            output: Some(format!("{name} out")),
+            stderr: (!success).then(|| "boom <&>".to_string()),
+            exit_code: Some(i32::from(!success)),
+            error: (!success).then(|| "exit code 1".to_string()),
+            cache_created_at: None,
+            cache_duration_ms: None,
+        }
+    }
+
+    #[test]
+    fn junit_has_a_testcase_per_task() {
+        let xml = junit_xml(&[
+            result("build", true, 0, 1500),
+            result("test", false, 1500, 250),
+        ])
+        .unwrap();
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>"));
+        assert!(xml.contains(r#"<testsuite name="yatr" tests="2" failures="1""#));
+        assert!(xml.contains(r#"<testcase classname="build" name="build" time="1.500">"#));
+        assert!(xml.contains(r#"<failure message="exit code 1">boom &lt;&amp;&gt;</failure>"#));
+        assert!(xml.contains("<system-out>test out</system-out>"));
+    }
+
+    #[test]
+    fn html_places_tasks_on_the_timeline() {
+        let page = html(&[result("a", true, 0, 500), result("b<x>", false, 500, 500)]);
+        assert!(page.contains("2 tasks, 1 failed"));
+        assert!(page.contains("left:50.00%;width:50.00%"));
+        assert!(page.contains("b&lt;x&gt;"));
+        assert!(!page.contains("b<x>"));
+    }
+}
@@ -6,68 +6,410 @@
 #![allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
 
 use std::collections::HashMap;
+use std::future::Future;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use rhai::{Dynamic, Engine, EvalAltResult, Scope, AST};
+use rhai::{Dynamic, Engine, EvalAltResult, RhaiNativeFunc, Scope, AST};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{RunCommand, TaskConfig};
+use crate::executor::DANGEROUS_ENV_VARS;
+
+/// Sandbox limits applied to every Rhai engine a [`ScriptEngine`] creates.
+///
+/// Configurable from `yatr.toml` via `[settings.script_limits]`; any field left
+/// out keeps its default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+#[allow(clippy::struct_field_names)]
+pub struct ScriptLimits {
+    /// Maximum number of operations a script may perform before it is aborted
+    pub max_operations: u64,
+    /// Maximum length of any string, in bytes
+    pub max_string_size: usize,
+    /// Maximum number of elements in any array
+    pub max_array_size: usize,
+    /// Maximum number of entries in any object map
+    pub max_map_size: usize,
+    /// Maximum nesting depth of expressions (and of expressions inside functions)
+    pub max_expr_depth: usize,
+    /// Wall-clock time, in milliseconds, a script may run before it is aborted
+    /// (unlimited when unset). `sleep_ms` that would overrun it fails at once.
+    pub timeout_ms: Option<u64>,
+}
+
+impl ScriptLimits {
+    /// The built-in limits, usable in `const` contexts.
+    pub const DEFAULT: Self = Self {
+        max_operations: 100_000,
+        max_string_size: 1024 * 1024, // 1MB
+        max_array_size: 100_000,
+        max_map_size: 100_000,
+        max_expr_depth: 64,
+        timeout_ms: None,
+    };
+}
+
+impl Default for ScriptLimits {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Script state that outlives a single run (e.g. across watch-mode rebuilds).
+///
+/// Top-level `let` variables persist from one
+/// [`ScriptEngine::execute_with_context`] call to the next, as does the
+/// `state` map every context starts with:
+///
+/// ```rhai
+/// state.rebuilds = (state.rebuilds ?? 0) + 1;
+/// ```
+///
+/// `env` and `cwd` are refreshed on every run, and `runs` holds the number of
+/// earlier runs in this context.
+#[derive(Debug, Clone)]
+pub struct ScriptContext {
+    scope: Scope<'static>,
+    runs: i64,
+}
+
+impl Default for ScriptContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScriptContext {
+    /// An empty context with an empty `state` map.
+    #[must_use]
+    pub fn new() -> Self {
+        let mut scope = Scope::new();
+        scope.push("state", rhai::Map::new());
+        Self { scope, runs: 0 }
+    }
+
+    /// Number of scripts run in this context so far.
+    #[must_use]
+    pub const fn runs(&self) -> i64 {
+        self.runs
+    }
+
+    /// The current value of a persisted variable.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<Dynamic> {
+        self.scope.get(name).cloned()
+    }
+
+    /// Set a variable for the next script run in this context, replacing any
+    /// earlier value.
+    pub fn set(&mut self, name: &str, value: Dynamic) {
+        self.scope.set_value(name.to_string(), value);
+    }
+
+    /// Refresh the per-run variables before a script runs.
+    fn prepare(&mut self, env: &HashMap<String, String>, cwd: &Path) {
+        self.scope.set_value("env", env_map(env));
+        self.scope
+            .set_value("cwd", cwd.to_string_lossy().to_string());
+        self.scope.set_value("runs", self.runs);
+    }
+
+    /// Count the run and drop variables shadowed by a later `let` of the same
+    /// name, so a context reused for hours doesn't grow with every run.
+    fn finish(&mut self) {
+        self.runs += 1;
+        let mut seen = std::collections::HashSet::new();
+        let mut latest: Vec<_> = self
+            .scope
+            .iter()
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .filter(|(name, _, _)| seen.insert(name.to_string()))
+            .map(|(name, constant, value)| (name.to_string(), constant, value))
+            .collect();
+        latest.reverse();
+
+        let mut scope = Scope::new();
+        for (name, constant, value) in latest {
+            if constant {
+                scope.push_constant_dynamic(name, value);
+            } else {
+                scope.push_dynamic(name, value);
+            }
+        }
+        self.scope = scope;
+    }
+}
+
+/// Documentation of a built-in script function (see
+/// [`ScriptEngine::stdlib_list`]).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StdlibFn {
+    /// Function name, as called from scripts
+    pub name: String,
+    /// Parameters and return type, e.g. `read_file(path: string) -> string`
+    pub signature: String,
+    /// One-line summary of what the function does
+    pub description: String,
+}
 
 /// Script execution engine
 #[derive(Debug, Clone)]
 pub struct ScriptEngine {
-    _marker: std::marker::PhantomData<()>,
+    limits: ScriptLimits,
+    inherit_env: bool,
+    sanitize_env: bool,
 }
 
 impl ScriptEngine {
     /// Create a new script engine with standard functions registered
     #[must_use]
     pub const fn new() -> Self {
+        Self::new_with_limits(ScriptLimits::DEFAULT)
+    }
+
+    /// Create a script engine with custom sandbox limits
+    #[must_use]
+    pub const fn new_with_limits(limits: ScriptLimits) -> Self {
         Self {
-            _marker: std::marker::PhantomData,
+            limits,
+            inherit_env: true,
+            sanitize_env: false,
         }
     }
 
-    /// Create a configured engine instance
-    fn create_engine() -> Engine {
+    /// Start `exec`, `exec_output` and `exec_quiet` commands the way task
+    /// commands are: without the host environment (but for `PATH`) unless
+    /// `inherit_env`, and without [`DANGEROUS_ENV_VARS`] if `sanitize_env`.
+    #[must_use]
+    pub const fn with_host_env(mut self, inherit_env: bool, sanitize_env: bool) -> Self {
+        self.inherit_env = inherit_env;
+        self.sanitize_env = sanitize_env;
+        self
+    }
+
+    /// Every built-in function available to scripts, in registration order
+    #[must_use]
+    pub fn stdlib_list() -> Vec<StdlibFn> {
+        Self::register_stdlib(&mut Engine::new_raw(), None, &Arc::default())
+    }
+
+    /// The sandbox limits this engine applies
+    #[must_use]
+    pub const fn limits(&self) -> &ScriptLimits {
+        &self.limits
+    }
+
+    /// Create a configured engine instance whose commands run with `env`
+    fn create_engine(&self, env: &HashMap<String, String>) -> Engine {
+        let limits = &self.limits;
         let mut engine = Engine::new();
 
         // Configure sandboxing
-        engine.set_max_expr_depths(64, 64);
-        engine.set_max_operations(100_000);
+        engine.set_max_expr_depths(limits.max_expr_depth, limits.max_expr_depth);
+        engine.set_max_operations(limits.max_operations);
         engine.set_max_modules(10);
-        engine.set_max_string_size(1024 * 1024); // 1MB
+        engine.set_max_string_size(limits.max_string_size);
+        engine.set_max_array_size(limits.max_array_size);
+        engine.set_max_map_size(limits.max_map_size);
+
+        // The deadline is checked alongside the operation counter, so a
+        // script stuck in a loop is caught as well as one stuck sleeping
+        let deadline = limits
+            .timeout_ms
+            .map(|ms| Instant::now() + Duration::from_millis(ms));
+        if let Some(deadline) = deadline {
+            engine.on_progress(move |_| {
+                (Instant::now() >= deadline).then(|| Dynamic::from("script timeout"))
+            });
+        }
 
         // Register standard library functions
-        Self::register_stdlib(&mut engine);
+        let command_env = Arc::new(CommandEnv {
+            vars: env.clone(),
+            inherit: self.inherit_env,
+            sanitize: self.sanitize_env,
+        });
+        Self::register_stdlib(&mut engine, deadline, &command_env);
 
         engine
     }
 
     /// Execute a script with the given environment and working directory
-    #[allow(clippy::unused_self)]
     pub fn execute(
         &self,
         script: &str,
         env: &HashMap<String, String>,
         cwd: &Path,
     ) -> Result<String, Box<EvalAltResult>> {
+        self.run_in_scope(Self::base_scope(env, cwd), script, env)
+    }
+
+    /// Execute a task's script. Besides `env` and `cwd`, the script sees a
+    /// `task` map with the task's `name`, `depends`, `run`, `outputs`,
+    /// `sources` and `timeout` (unit when unset).
+    pub fn execute_task(
+        &self,
+        script: &str,
+        task_name: &str,
+        task: &TaskConfig,
+        env: &HashMap<String, String>,
+        cwd: &Path,
+    ) -> Result<String, Box<EvalAltResult>> {
+        let mut scope = Self::base_scope(env, cwd);
+        scope.push("task", Self::task_metadata(task_name, task)?);
+        self.run_in_scope(scope, script, env)
+    }
+
+    /// Execute a script in a [`ScriptContext`], so variables it defines are
+    /// still there the next time a script runs in the same context.
+    pub fn execute_with_context(
+        &self,
+        script: &str,
+        ctx: &mut ScriptContext,
+        env: &HashMap<String, String>,
+        cwd: &Path,
+    ) -> Result<String, Box<EvalAltResult>> {
+        ctx.prepare(env, cwd);
+        let result = self.run_with_scope(&mut ctx.scope, script, env);
+        ctx.finish();
+        result
+    }
+
+    /// [`execute_task`](Self::execute_task) in a [`ScriptContext`]. The `task`
+    /// map is refreshed on every run.
+    pub fn execute_task_with_context(
+        &self,
+        script: &str,
+        task_name: &str,
+        task: &TaskConfig,
+        ctx: &mut ScriptContext,
+        env: &HashMap<String, String>,
+        cwd: &Path,
+    ) -> Result<String, Box<EvalAltResult>> {
+        ctx.scope
+            .set_value("task", Self::task_metadata(task_name, task)?);
+        self.execute_with_context(script, ctx, env, cwd)
+    }
+
+    /// The `task` map scripts see: `name`, `depends`, `run`, `outputs`,
+    /// `sources` and `timeout` (unit when unset).
+    fn task_metadata(task_name: &str, task: &TaskConfig) -> Result<Dynamic, Box<EvalAltResult>> {
+        json_to_dynamic(serde_json::json!({
+            "name": task_name,
+            "depends": task.depends,
+            "run": task.run.iter().map(RunCommand::cmd).collect::<Vec<_>>(),
+            "outputs": task.outputs,
+            "sources": task.sources,
+            "timeout": task.timeout,
+        }))
+    }
+
+    /// Like [`execute`](Self::execute), but runs the (synchronous) Rhai
+    /// evaluation on tokio's blocking pool so it never stalls async workers.
+    pub fn execute_async(
+        &self,
+        script: &str,
+        env: &HashMap<String, String>,
+        cwd: &Path,
+    ) -> impl Future<Output = Result<String, Box<EvalAltResult>>> {
+        let engine = self.clone();
+        let (script, env, cwd) = (script.to_string(), env.clone(), cwd.to_path_buf());
+        Self::run_blocking(move || engine.execute(&script, &env, &cwd))
+    }
+
+    /// Like [`execute_task`](Self::execute_task), but off the async runtime
+    /// (see [`execute_async`](Self::execute_async)).
+    pub fn execute_task_async(
+        &self,
+        script: &str,
+        task_name: &str,
+        task: &TaskConfig,
+        env: &HashMap<String, String>,
+        cwd: &Path,
+    ) -> impl Future<Output = Result<String, Box<EvalAltResult>>> {
+        let engine = self.clone();
+        let (script, task_name, task) = (script.to_string(), task_name.to_string(), task.clone());
+        let (env, cwd) = (env.clone(), cwd.to_path_buf());
+        Self::run_blocking(move || engine.execute_task(&script, &task_name, &task, &env, &cwd))
+    }
+
+    /// Like [`execute_task_with_context`](Self::execute_task_with_context), but
+    /// off the async runtime. The context is handed back with the result; if
+    /// the script panics, it is replaced by a fresh one.
+    pub fn execute_task_with_context_async(
+        &self,
+        script: &str,
+        task_name: &str,
+        task: &TaskConfig,
+        mut ctx: ScriptContext,
+        env: &HashMap<String, String>,
+        cwd: &Path,
+    ) -> impl Future<Output = (ScriptContext, Result<String, Box<EvalAltResult>>)> {
+        let engine = self.clone();
+        let (script, task_name, task) = (script.to_string(), task_name.to_string(), task.clone());
+        let (env, cwd) = (env.clone(), cwd.to_path_buf());
+        async move {
+            tokio::task::spawn_blocking(move || {
+                let result = engine
+                    .execute_task_with_context(&script, &task_name, &task, &mut ctx, &env, &cwd);
+                (ctx, result)
+            })
+            .await
+            .unwrap_or_else(|e| (ScriptContext::new(), Err(Box::new(panicked(e)))))
+        }
+    }
+
+    /// Await `f` on `spawn_blocking`, surfacing a panic as a script error.
+    async fn run_blocking(
+        f: impl FnOnce() -> Result<String, Box<EvalAltResult>> + Send + 'static,
+    ) -> Result<String, Box<EvalAltResult>> {
+        tokio::task::spawn_blocking(f)
+            .await
+            .unwrap_or_else(|e| Err(Box::new(panicked(e))))
+    }
+
+    /// Build a scope holding `env` (a map) and `cwd` (a string).
+    fn base_scope(env: &HashMap<String, String>, cwd: &Path) -> Scope<'static> {
         let mut scope = Scope::new();
 
         // Inject environment variables
-        let env_map: rhai::Map = env
-            .iter()
-            .map(|(k, v)| (k.clone().into(), Dynamic::from(v.clone())))
-            .collect();
-        scope.push("env", env_map);
+        scope.push("env", env_map(env));
 
         // Inject working directory
         scope.push("cwd", cwd.to_string_lossy().to_string());
 
+        scope
+    }
+
+    /// Run `script` in `scope`, returning everything it printed.
+    fn run_in_scope(
+        &self,
+        mut scope: Scope<'static>,
+        script: &str,
+        env: &HashMap<String, String>,
+    ) -> Result<String, Box<EvalAltResult>> {
+        self.run_with_scope(&mut scope, script, env)
+    }
+
+    /// Run `script` in a borrowed `scope`, which keeps its top-level variables.
+    fn run_with_scope(
+        &self,
+        scope: &mut Scope<'static>,
+        script: &str,
+        env: &HashMap<String, String>,
+    ) -> Result<String, Box<EvalAltResult>> {
         // Capture output
         let output = Arc::new(std::sync::Mutex::new(String::new()));
         let output_clone = Arc::clone(&output);
 
         // Create a custom print function that captures output
-        let mut engine = Self::create_engine();
+        let mut engine = self.create_engine(env);
         engine.on_print(move |s| {
             let mut out = output_clone.lock().unwrap();
             out.push_str(s);
@@ -75,21 +417,91 @@ impl ScriptEngine {
         });
 
         // Execute the script
-        engine.run_with_scope(&mut scope, script)?;
+        engine.run_with_scope(scope, script)?;
 
         let result = output.lock().unwrap().clone();
         Ok(result)
     }
 
+    /// Evaluate a task's `condition_script` with the same `env`, `cwd` and
+    /// `task` map as [`execute_task`](Self::execute_task). Returns whether the
+    /// value is truthy: `false`, `()`, `0`, `0.0` and empty strings, arrays
+    /// and maps are falsy; everything else is truthy.
+    pub fn evaluate_condition(
+        &self,
+        script: &str,
+        task_name: &str,
+        task: &TaskConfig,
+        env: &HashMap<String, String>,
+        cwd: &Path,
+    ) -> Result<bool, Box<EvalAltResult>> {
+        let mut scope = Self::base_scope(env, cwd);
+        scope.push("task", Self::task_metadata(task_name, task)?);
+        let engine = self.create_engine(env);
+        let value: Dynamic = engine.eval_with_scope(&mut scope, script)?;
+        Ok(is_truthy(&value))
+    }
+
+    /// Like [`evaluate_condition`](Self::evaluate_condition), but off the
+    /// async runtime (conditions may `exec` commands).
+    pub fn evaluate_condition_async(
+        &self,
+        script: &str,
+        task_name: &str,
+        task: &TaskConfig,
+        env: &HashMap<String, String>,
+        cwd: &Path,
+    ) -> impl Future<Output = Result<bool, Box<EvalAltResult>>> {
+        let engine = self.clone();
+        let (script, task_name, task) = (script.to_string(), task_name.to_string(), task.clone());
+        let (env, cwd) = (env.clone(), cwd.to_path_buf());
+        async move {
+            tokio::task::spawn_blocking(move || {
+                engine.evaluate_condition(&script, &task_name, &task, &env, &cwd)
+            })
+            .await
+            .unwrap_or_else(|e| Err(Box::new(panicked(e))))
+        }
+    }
+
+    /// Run a watch-mode `on_change_script` for `task` with `changed_files`
+    /// (an array of paths) and `task` (the watched task's name) in scope, on
+    /// top of `env` and `cwd`. Returns the tasks it queued with
+    /// `run_task(name)`, in call order and without duplicates.
+    pub fn evaluate_on_change(
+        &self,
+        script: &str,
+        task: &str,
+        changed_files: &[String],
+        env: &HashMap<String, String>,
+        cwd: &Path,
+    ) -> Result<Vec<String>, Box<EvalAltResult>> {
+        let mut scope = Self::base_scope(env, cwd);
+        scope.push("task", task.to_string());
+        scope.push("changed_files", vec_to_array(changed_files.to_vec()));
+
+        let queued = Arc::new(std::sync::Mutex::new(Vec::<String>::new()));
+        let queue = Arc::clone(&queued);
+        let mut engine = self.create_engine(env);
+        engine.register_fn("run_task", move |name: &str| {
+            let mut queue = queue.lock().unwrap();
+            if !queue.iter().any(|n| n == name) {
+                queue.push(name.to_string());
+            }
+        });
+        engine.run_with_scope(&mut scope, script)?;
+
+        let queued = queued.lock().unwrap().clone();
+        Ok(queued)
+    }
+
     /// Compile a script for repeated execution
-    #[allow(clippy::unused_self)]
     pub fn compile(&self, script: &str) -> Result<AST, Box<EvalAltResult>> {
-        let engine = Self::create_engine();
+        let engine = self.create_engine(&HashMap::new());
         engine.compile(script).map_err(std::convert::Into::into)
     }
 
     /// Execute a pre-compiled script
-    #[allow(clippy::unused_self)]
     pub fn execute_ast(
         &self,
         ast: &AST,
@@ -108,7 +520,7 @@ impl ScriptEngine {
         let output = Arc::new(std::sync::Mutex::new(String::new()));
         let output_clone = Arc::clone(&output);
 
-        let mut engine = Self::create_engine();
+        let mut engine = self.create_engine(env);
         engine.on_print(move |s| {
             let mut out = output_clone.lock().unwrap();
             out.push_str(s);
@@ -121,133 +533,331 @@ impl ScriptEngine {
         Ok(result)
     }
 
-    /// Register standard library functions
+    /// Register standard library functions, returning their documentation
     #[allow(clippy::too_many_lines)]
-    fn register_stdlib(engine: &mut Engine) {
+    fn register_stdlib(
+        engine: &mut Engine,
+        deadline: Option<Instant>,
+        command_env: &Arc<CommandEnv>,
+    ) -> Vec<StdlibFn> {
+        let mut lib = Stdlib {
+            engine,
+            functions: Vec::new(),
+        };
+
         // File operations
-        engine.register_fn(
-            "read_file",
+        lib.register(
+            "read_file(path: string) -> string",
+            "Read a file as text",
             |path: &str| -> Result<String, Box<EvalAltResult>> {
                 std::fs::read_to_string(path)
                     .map_err(|e| format!("Failed to read file '{path}': {e}").into())
             },
         );
 
-        engine.register_fn(
-            "write_file",
+        lib.register(
+            "write_file(path: string, content: string)",
+            "Write text to a file, replacing it",
             |path: &str, content: &str| -> Result<(), Box<EvalAltResult>> {
                 std::fs::write(path, content)
                     .map_err(|e| format!("Failed to write file '{path}': {e}").into())
             },
         );
 
-        engine.register_fn("file_exists", |path: &str| -> bool {
-            std::path::Path::new(path).exists()
-        });
+        lib.register(
+            "file_exists(path: string) -> bool",
+            "Whether a file or directory exists",
+            |path: &str| -> bool { std::path::Path::new(path).exists() },
+        );
 
-        engine.register_fn("is_file", |path: &str| -> bool {
-            std::path::Path::new(path).is_file()
-        });
+        lib.register(
+            "is_file(path: string) -> bool",
+            "Whether the path is a regular file",
+            |path: &str| -> bool { std::path::Path::new(path).is_file() },
+        );
 
-        engine.register_fn("is_dir", |path: &str| -> bool {
-            std::path::Path::new(path).is_dir()
-        });
+        lib.register(
+            "is_dir(path: string) -> bool",
+            "Whether the path is a directory",
+            |path: &str| -> bool { std::path::Path::new(path).is_dir() },
+        );
 
         // Directory operations
-        engine.register_fn("mkdir", |path: &str| -> Result<(), Box<EvalAltResult>> {
-            std::fs::create_dir_all(path)
-                .map_err(|e| format!("Failed to create directory '{path}': {e}").into())
-        });
+        lib.register(
+            "mkdir(path: string)",
+            "Create a directory and any missing parents",
+            |path: &str| -> Result<(), Box<EvalAltResult>> {
+                std::fs::create_dir_all(path)
+                    .map_err(|e| format!("Failed to create directory '{path}': {e}").into())
+            },
+        );
 
-        engine.register_fn("rmdir", |path: &str| -> Result<(), Box<EvalAltResult>> {
-            std::fs::remove_dir_all(path)
-                .map_err(|e| format!("Failed to remove directory '{path}': {e}").into())
-        });
+        lib.register(
+            "rmdir(path: string)",
+            "Remove a directory and everything in it",
+            |path: &str| -> Result<(), Box<EvalAltResult>> {
+                std::fs::remove_dir_all(path)
+                    .map_err(|e| format!("Failed to remove directory '{path}': {e}").into())
+            },
+        );
 
-        engine.register_fn(
-            "list_dir",
+        lib.register(
+            "list_dir(path: string) -> array",
+            "Paths of the entries in a directory",
             |path: &str| -> Result<rhai::Array, Box<EvalAltResult>> {
-                let entries: Result<Vec<_>, _> = std::fs::read_dir(path)
+                let entries: Vec<String> = std::fs::read_dir(path)
                     .map_err(|e| format!("Failed to read directory '{path}': {e}"))?
-                    .map(|e| e.map(|e| Dynamic::from(e.path().to_string_lossy().to_string())))
-                    .collect();
-
-                entries.map_err(|e: std::io::Error| e.to_string().into())
+                    .map(|e| e.map(|e| e.path().to_string_lossy().to_string()))
+                    .collect::<Result<_, _>>()
+                    .map_err(|e| e.to_string())?;
+                Ok(vec_to_array(entries))
             },
         );
 
         // Path operations
-        engine.register_fn("join_path", |a: &str, b: &str| -> String {
-            std::path::Path::new(a)
-                .join(b)
-                .to_string_lossy()
-                .to_string()
-        });
+        lib.register_typed(
+            "join_paths(parts: array) -> string",
+            "Join an array of path components",
+            |parts: Vec<String>| {
+                let path: std::path::PathBuf = parts.iter().collect();
+                Ok(path.to_string_lossy().to_string())
+            },
+        );
 
-        engine.register_fn("parent_path", |path: &str| -> String {
-            std::path::Path::new(path)
-                .parent()
-                .map(|p| p.to_string_lossy().to_string())
-                .unwrap_or_default()
-        });
+        lib.register(
+            "join_path(a: string, b: string) -> string",
+            "Join two path components",
+            |a: &str, b: &str| -> String {
+                std::path::Path::new(a)
+                    .join(b)
+                    .to_string_lossy()
+                    .to_string()
+            },
+        );
 
-        engine.register_fn("file_name", |path: &str| -> String {
-            std::path::Path::new(path)
-                .file_name()
-                .map(|n| n.to_string_lossy().to_string())
-                .unwrap_or_default()
-        });
+        lib.register(
+            "parent_path(path: string) -> string",
+            "Parent directory of a path (empty if none)",
+            |path: &str| -> String {
+                std::path::Path::new(path)
+                    .parent()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            },
+        );
 
-        engine.register_fn("extension", |path: &str| -> String {
-            std::path::Path::new(path)
-                .extension()
-                .map(|e| e.to_string_lossy().to_string())
-                .unwrap_or_default()
-        });
+        lib.register(
+            "file_name(path: string) -> string",
+            "Final component of a path (empty if none)",
+            |path: &str| -> String {
+                std::path::Path::new(path)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            },
+        );
+
+        lib.register(
+            "extension(path: string) -> string",
+            "Extension of a path, without the dot (empty if none)",
+            |path: &str| -> String {
+                std::path::Path::new(path)
+                    .extension()
+                    .map(|e| e.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            },
+        );
 
         // Shell command execution
-        engine.register_fn("exec", |cmd: &str| -> Result<String, Box<EvalAltResult>> {
-            let output = if cfg!(windows) {
-                std::process::Command::new("cmd").args(["/C", cmd]).output()
-            } else {
-                std::process::Command::new("sh").args(["-c", cmd]).output()
-            };
-
-            match output {
-                Ok(o) if o.status.success() => Ok(String::from_utf8_lossy(&o.stdout).to_string()),
-                Ok(o) => {
-                    let stderr = String::from_utf8_lossy(&o.stderr);
-                    Err(format!("Command failed: {stderr}").into())
+        let env = Arc::clone(command_env);
+        lib.register(
+            "exec(cmd: string) -> string",
+            "Run a shell command and return its stdout; fails on a non-zero exit",
+            move |cmd: &str| -> Result<String, Box<EvalAltResult>> {
+                match env.command(cmd).output() {
+                    Ok(o) if o.status.success() => {
+                        Ok(String::from_utf8_lossy(&o.stdout).to_string())
+                    }
+                    Ok(o) => {
+                        let stderr = String::from_utf8_lossy(&o.stderr);
+                        Err(format!("Command failed: {stderr}").into())
+                    }
+                    Err(e) => Err(format!("Failed to execute command: {e}").into()),
                 }
-                Err(e) => Err(format!("Failed to execute command: {e}").into()),
-            }
-        });
+            },
+        );
+
+        let env = Arc::clone(command_env);
+        lib.register(
+            "exec_output(cmd: string) -> map",
+            "Run a shell command; returns #{stdout, stderr, exit_code, success}",
+            move |cmd: &str| -> Result<rhai::Map, Box<EvalAltResult>> {
+                let o = env
+                    .command(cmd)
+                    .output()
+                    .map_err(|e| format!("Failed to execute command: {e}"))?;
+                let mut result = rhai::Map::new();
+                result.insert(
+                    "stdout".into(),
+                    String::from_utf8_lossy(&o.stdout).to_string().into(),
+                );
+                result.insert(
+                    "stderr".into(),
+                    String::from_utf8_lossy(&o.stderr).to_string().into(),
+                );
+                // -1 when the process was killed by a signal
+                result.insert(
+                    "exit_code".into(),
+                    i64::from(o.status.code().unwrap_or(-1)).into(),
+                );
+                result.insert("success".into(), o.status.success().into());
+                Ok(result)
+            },
+        );
+
+        let env = Arc::clone(command_env);
+        lib.register(
+            "exec_quiet(cmd: string) -> bool",
+            "Run a shell command with output discarded; returns whether it succeeded",
+            move |cmd: &str| -> Result<bool, Box<EvalAltResult>> {
+                env.command(cmd)
+                    .stdout(std::process::Stdio::null())
+                    .stderr(std::process::Stdio::null())
+                    .status()
+                    .map(|status| status.success())
+                    .map_err(|e| format!("Failed to execute command: {e}").into())
+            },
+        );
+
+        // Diagnostics: straight to yatr's stderr, never into the captured
+        // (and cached) output that `print` feeds
+        lib.register(
+            "print_err(s: string)",
+            "Write a line to stderr, outside the task's captured output",
+            |s: &str| eprintln!("{s}"),
+        );
+
+        lib.register(
+            "warn(msg: string)",
+            "Write a line prefixed with [WARN] to stderr",
+            |msg: &str| eprintln!("[WARN] {msg}"),
+        );
+
+        // Timing
+        lib.register(
+            "time_now() -> int",
+            "Current Unix time in milliseconds",
+            || -> i64 { chrono::Utc::now().timestamp_millis() },
+        );
+
+        lib.register(
+            "time_elapsed_ms(start_ms: int) -> int",
+            "Milliseconds since `start_ms` (a `time_now()` value)",
+            |start_ms: i64| -> i64 { chrono::Utc::now().timestamp_millis() - start_ms },
+        );
+
+        lib.register(
+            "sleep_ms(ms: int)",
+            "Pause for `ms` milliseconds (fails if that would pass the script timeout)",
+            move |ms: i64| -> Result<(), Box<EvalAltResult>> {
+                let duration = u64::try_from(ms)
+                    .map(Duration::from_millis)
+                    .map_err(|_| format!("sleep_ms: negative duration {ms}"))?;
+                if deadline.is_some_and(|deadline| Instant::now() + duration > deadline) {
+                    return Err(format!("sleep_ms({ms}) would exceed the script timeout").into());
+                }
+                std::thread::sleep(duration);
+                Ok(())
+            },
+        );
+
+        lib.register(
+            "time_format(ms: int, format: string) -> string",
+            "Format a duration as \"1m 23s\" (format \"duration\"), or a Unix time in \
+             milliseconds with a strftime format (UTC)",
+            |ms: i64, format: &str| -> Result<String, Box<EvalAltResult>> {
+                if format == "duration" {
+                    return Ok(format_duration_ms(ms));
+                }
+                let items = chrono::format::StrftimeItems::new(format)
+                    .parse()
+                    .map_err(|_| format!("time_format: invalid format '{format}'"))?;
+                let time = chrono::DateTime::from_timestamp_millis(ms)
+                    .ok_or_else(|| format!("time_format: timestamp {ms} out of range"))?;
+                Ok(time.format_with_items(items.iter()).to_string())
+            },
+        );
 
         // Environment
-        engine.register_fn("get_env", |key: &str| -> String {
-            std::env::var(key).unwrap_or_default()
-        });
+        lib.register(
+            "get_env(key: string) -> string",
+            "Value of an environment variable (empty if unset)",
+            |key: &str| -> String { std::env::var(key).unwrap_or_default() },
+        );
 
-        engine.register_fn("set_env", |key: &str, value: &str| {
-            std::env::set_var(key, value);
-        });
+        lib.register(
+            "set_env(key: string, value: string)",
+            "Set an environment variable for the rest of the run",
+            |key: &str, value: &str| {
+                std::env::set_var(key, value);
+            },
+        );
 
         // String utilities
-        engine.register_fn(
-            "glob",
+        lib.register(
+            "glob(pattern: string) -> array",
+            "Paths matching a glob pattern",
             |pattern: &str| -> Result<rhai::Array, Box<EvalAltResult>> {
-                let paths: Vec<_> = glob::glob(pattern)
+                let paths: Vec<String> = glob::glob(pattern)
                     .map_err(|e| format!("Invalid glob pattern: {e}"))?
                     .filter_map(std::result::Result::ok)
-                    .map(|p| Dynamic::from(p.to_string_lossy().to_string()))
+                    .map(|p| p.to_string_lossy().to_string())
                     .collect();
-                Ok(paths)
+                Ok(vec_to_array(paths))
+            },
+        );
+
+        lib.register(
+            "pad_left(s: string, width: int, char: string) -> string",
+            "Pad `s` on the left with `char` (one character) to `width` characters",
+            |s: &str, width: i64, pad: &str| -> Result<String, Box<EvalAltResult>> {
+                let mut chars = pad.chars();
+                let (Some(pad), None) = (chars.next(), chars.next()) else {
+                    return Err(format!("pad_left: '{pad}' is not a single character").into());
+                };
+                let width = usize::try_from(width).unwrap_or(0);
+                let fill = width.saturating_sub(s.chars().count());
+                Ok(std::iter::repeat_n(pad, fill).chain(s.chars()).collect())
+            },
+        );
+
+        // Formatting for reports
+        lib.register(
+            "format_duration(ms: int) -> string",
+            "A duration in milliseconds as \"1h 23m 45s\", \"45.2s\" or \"345ms\"",
+            format_duration_ms,
+        );
+
+        lib.register(
+            "format_bytes(bytes: int) -> string",
+            "A size as \"45 B\", \"234 KB\" or \"1.5 GB\" (1 KB = 1024 B)",
+            format_bytes,
+        );
+
+        lib.register(
+            "format_number(n: float, precision: int) -> string",
+            "`n` with `precision` decimals and `,` between thousands",
+            |n: f64, precision: i64| -> Result<String, Box<EvalAltResult>> {
+                let precision = usize::try_from(precision)
+                    .map_err(|_| format!("format_number: negative precision {precision}"))?;
+                Ok(format_number(n, precision))
             },
         );
 
         // JSON operations
-        engine.register_fn(
-            "parse_json",
+        lib.register(
+            "parse_json(s: string) -> dynamic",
+            "Parse JSON into maps, arrays and scalars",
             |s: &str| -> Result<Dynamic, Box<EvalAltResult>> {
                 let value: serde_json::Value =
                     serde_json::from_str(s).map_err(|e| format!("Failed to parse JSON: {e}"))?;
@@ -255,8 +865,9 @@ impl ScriptEngine {
             },
         );
 
-        engine.register_fn(
-            "to_json",
+        lib.register(
+            "to_json(value: dynamic) -> string",
+            "Serialize a value as pretty-printed JSON",
             |value: Dynamic| -> Result<String, Box<EvalAltResult>> {
                 let json = dynamic_to_json(value)?;
                 serde_json::to_string_pretty(&json)
@@ -265,8 +876,9 @@ impl ScriptEngine {
         );
 
         // TOML operations
-        engine.register_fn(
-            "parse_toml",
+        lib.register(
+            "parse_toml(s: string) -> dynamic",
+            "Parse TOML into maps, arrays and scalars",
             |s: &str| -> Result<Dynamic, Box<EvalAltResult>> {
                 let value: toml::Value =
                     toml::from_str(s).map_err(|e| format!("Failed to parse TOML: {e}"))?;
@@ -275,8 +887,9 @@ impl ScriptEngine {
         );
 
         // Version comparison (useful for version bumping)
-        engine.register_fn(
-            "semver_bump",
+        lib.register(
+            "semver_bump(version: string, part: string) -> string",
+            "Bump the `major`, `minor` or `patch` part of a version",
             |version: &str, part: &str| -> Result<String, Box<EvalAltResult>> {
                 let parts: Vec<u32> = version.split('.').map(|s| s.parse().unwrap_or(0)).collect();
 
@@ -296,6 +909,7 @@ impl ScriptEngine {
                 Ok(new_version)
             },
         );
+        lib.functions
     }
 }
 
@@ -305,6 +919,111 @@ impl Default for ScriptEngine {
     }
 }
 
+/// `env` as a Rhai object map.
+fn env_map(env: &HashMap<String, String>) -> rhai::Map {
+    env.iter()
+        .map(|(k, v)| (k.clone().into(), Dynamic::from(v.clone())))
+        .collect()
+}
+
+/// Truthiness of a condition result (see [`ScriptEngine::evaluate_condition`]).
+fn is_truthy(value: &Dynamic) -> bool {
+    if value.is_unit() {
+        false
+    } else if value.is_bool() {
+        value.as_bool().unwrap_or(false)
+    } else if value.is_int() {
+        value.as_int().is_ok_and(|i| i != 0)
+    } else if value.is_float() {
+        value.as_float().is_ok_and(|f| f != 0.0)
+    } else if value.is_string() {
+        value
+            .clone()
+            .into_immutable_string()
+            .is_ok_and(|s| !s.is_empty())
+    } else if value.is_array() {
+        value.clone().into_array().is_ok_and(|a| !a.is_empty())
+    } else if value.is_map() {
+        value
+            .read_lock::<rhai::Map>()
+            .is_some_and(|m| !m.is_empty())
+    } else {
+        true
+    }
+}
+
+/// A duration as `format_duration` shows it: `1h 2m 3s`, `1m 23s`, `45.2s`,
+/// or `350ms` under a second.
+fn format_duration_ms(ms: i64) -> String {
+    let sign = if ms < 0 { "-" } else { "" };
+    let ms = ms.unsigned_abs();
+    if ms < 1000 {
+        return format!("{sign}{ms}ms");
+    }
+    let secs = ms / 1000;
+    let (hours, mins, secs) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{sign}{hours}h {mins}m {secs}s")
+    } else if mins > 0 {
+        format!("{sign}{mins}m {secs}s")
+    } else {
+        format!("{sign}{secs}.{}s", ms % 1000 / 100)
+    }
+}
+
+/// A byte count as `format_bytes` shows it: whole bytes, then one decimal
+/// below 10 of a unit (`1.5 GB`) and none above (`234 KB`).
+fn format_bytes(bytes: i64) -> String {
+    const UNITS: [&str; 5] = ["KB", "MB", "GB", "TB", "PB"];
+    let sign = if bytes < 0 { "-" } else { "" };
+    let bytes = bytes.unsigned_abs();
+    if bytes < 1024 {
+        return format!("{sign}{bytes} B");
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if value < 10.0 {
+        format!("{sign}{value:.1} {}", UNITS[unit])
+    } else {
+        format!("{sign}{value:.0} {}", UNITS[unit])
+    }
+}
+
+/// `n` rounded to `precision` decimals, with `,` between groups of three
+/// integer digits.
+fn format_number(n: f64, precision: usize) -> String {
+    let fixed = format!("{:.precision$}", n.abs());
+    let (int, frac) = fixed
+        .split_once('.')
+        .map_or((fixed.as_str(), None), |(i, f)| (i, Some(f)));
+
+    let mut out = String::new();
+    if n.is_sign_negative() && fixed.chars().any(|c| c.is_ascii_digit() && c != '0') {
+        out.push('-');
+    }
+    for (i, digit) in int.chars().enumerate() {
+        if i > 0 && (int.len() - i) % 3 == 0 {
+            out.push(',');
+        }
+        out.push(digit);
+    }
+    if let Some(frac) = frac {
+        out.push('.');
+        out.push_str(frac);
+    }
+    out
+}
+
+/// The error reported when a script panics on the blocking pool.
+fn panicked(e: tokio::task::JoinError) -> EvalAltResult {
+    EvalAltResult::ErrorSystem("script evaluation panicked".to_string(), Box::new(e))
+}
+
 /// Convert `serde_json::Value` to Rhai Dynamic
 fn json_to_dynamic(value: serde_json::Value) -> Result<Dynamic, Box<EvalAltResult>> {
     use serde_json::Value;
@@ -390,10 +1109,214 @@ fn toml_to_dynamic(value: toml::Value) -> Result<Dynamic, Box<EvalAltResult>> {
     })
 }
 
+/// Registers stdlib functions on an engine, recording each one's
+/// documentation so the list can't drift from what is registered.
+struct Stdlib<'e> {
+    engine: &'e mut Engine,
+    functions: Vec<StdlibFn>,
+}
+
+impl Stdlib<'_> {
+    /// Register `func` under the name that starts `signature`.
+    fn register<A, const N: usize, const X: bool, R, const F: bool>(
+        &mut self,
+        signature: &'static str,
+        description: &str,
+        func: impl RhaiNativeFunc<A, N, X, R, F> + Send + Sync + 'static,
+    ) where
+        A: 'static,
+        R: Clone + Send + Sync + 'static,
+    {
+        self.engine.register_fn(
+            Self::document(&mut self.functions, signature, description),
+            func,
+        );
+    }
+
+    /// Register `f`, which takes a typed `Vec`, as a Rhai function taking an
+    /// array. Elements are type-checked on the way in, so a script passing
+    /// the wrong kind of array gets an error naming the offending element.
+    fn register_typed<A, R>(
+        &mut self,
+        signature: &'static str,
+        description: &str,
+        f: impl Fn(Vec<A>) -> Result<R, Box<EvalAltResult>> + Send + Sync + 'static,
+    ) where
+        A: ArrayElement,
+        R: Into<Dynamic>,
+    {
+        let name = Self::document(&mut self.functions, signature, description);
+        self.engine.register_fn(
+            name,
+            move |array: rhai::Array| -> Result<Dynamic, Box<EvalAltResult>> {
+                f(array_to_vec(name, array)?).map(Into::into)
+            },
+        );
+    }
+
+    /// Record a function's documentation and return its name.
+    fn document(
+        functions: &mut Vec<StdlibFn>,
+        signature: &'static str,
+        description: &str,
+    ) -> &'static str {
+        let name = signature.split('(').next().unwrap_or(signature);
+        functions.push(StdlibFn {
+            name: name.to_string(),
+            signature: signature.to_string(),
+            description: description.to_string(),
+        });
+        name
+    }
+}
+
+/// Element types that convert between Rhai array items and a typed `Vec`
+/// (see `Stdlib::register_typed`).
+pub trait ArrayElement: Sized + Send + Sync + 'static {
+    /// Rhai's name for the type, used in error messages
+    const TYPE_NAME: &'static str;
+
+    /// Convert an array item, or `None` if it has a different type.
+    fn from_dynamic(value: Dynamic) -> Option<Self>;
+
+    /// Convert back into an array item.
+    fn into_dynamic(self) -> Dynamic;
+}
+
+impl ArrayElement for String {
+    const TYPE_NAME: &'static str = "string";
+
+    fn from_dynamic(value: Dynamic) -> Option<Self> {
+        value.into_string().ok()
+    }
+
+    fn into_dynamic(self) -> Dynamic {
+        self.into()
+    }
+}
+
+impl ArrayElement for i64 {
+    const TYPE_NAME: &'static str = "i64";
+
+    fn from_dynamic(value: Dynamic) -> Option<Self> {
+        value.as_int().ok()
+    }
+
+    fn into_dynamic(self) -> Dynamic {
+        self.into()
+    }
+}
+
+/// Convert a Rhai array into a typed `Vec`, failing on the first element of
+/// the wrong type with an error that names `fn_name` and the element.
+pub fn array_to_vec<T: ArrayElement>(
+    fn_name: &str,
+    array: rhai::Array,
+) -> Result<Vec<T>, Box<EvalAltResult>> {
+    array
+        .into_iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let type_name = value.type_name();
+            T::from_dynamic(value).ok_or_else(|| {
+                format!(
+                    "{fn_name}: expected an array of {}, but element {i} is {type_name}",
+                    T::TYPE_NAME
+                )
+                .into()
+            })
+        })
+        .collect()
+}
+
+/// Convert a typed `Vec` into a Rhai array.
+pub fn vec_to_array<T: ArrayElement>(values: Vec<T>) -> rhai::Array {
+    values.into_iter().map(ArrayElement::into_dynamic).collect()
+}
+
+/// The environment `exec` and friends start commands in: the script's `env`
+/// on top of the host's, filtered as the executor filters task commands.
+#[derive(Debug)]
+struct CommandEnv {
+    vars: HashMap<String, String>,
+    inherit: bool,
+    sanitize: bool,
+}
+
+impl Default for CommandEnv {
+    fn default() -> Self {
+        Self {
+            vars: HashMap::new(),
+            inherit: true,
+            sanitize: false,
+        }
+    }
+}
+
+impl CommandEnv {
+    /// Build a command that runs `cmd` through the platform shell. Without
+    /// `inherit`, the host environment is dropped except for `PATH`.
+    fn command(&self, cmd: &str) -> std::process::Command {
+        let mut command = shell_command(cmd);
+        if !self.inherit {
+            command.env_clear();
+            if let (false, Some(path)) = (self.vars.contains_key("PATH"), std::env::var_os("PATH"))
+            {
+                command.env("PATH", path);
+            }
+        }
+        command.envs(&self.vars);
+        if self.sanitize {
+            for var in DANGEROUS_ENV_VARS {
+                command.env_remove(var);
+            }
+        }
+        command
+    }
+}
+
+/// Build a command that runs `cmd` through the platform shell.
+fn shell_command(cmd: &str) -> std::process::Command {
+    let mut command = if cfg!(windows) {
+        let mut c = std::process::Command::new("cmd");
+        c.arg("/C");
+        c
+    } else {
+        let mut c = std::process::Command::new("sh");
+        c.arg("-c");
+        c
+    };
+    command.arg(cmd);
+    command
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn stdlib_list_names_registered_functions() {
+        let functions = ScriptEngine::stdlib_list();
+        let names: std::collections::HashSet<_> = functions.iter().map(|f| &f.name).collect();
+        assert_eq!(names.len(), functions.len(), "duplicate stdlib entries");
+
+        let join = functions.iter().find(|f| f.name == "join_paths").unwrap();
+        assert_eq!(join.signature, "join_paths(parts: array) -> string");
+        assert!(!join.description.is_empty());
+
+        // Names are derived from signatures; the derived ones must be callable.
+        let engine = ScriptEngine::new();
+        let cwd = std::env::current_dir().unwrap();
+        let output = engine
+            .execute(
+                r#"print(file_name(join_paths(["a", "b.txt"])));"#,
+                &HashMap::new(),
+                &cwd,
+            )
+            .unwrap();
+        assert_eq!(output.trim(), "b.txt");
+    }
+
     #[test]
     fn test_basic_script() {
         let engine = ScriptEngine::new();
@@ -417,6 +1340,260 @@ mod tests {
         assert_eq!(result.unwrap().trim(), "test_value");
     }
 
+    #[test]
+    fn test_typed_array_arguments() {
+        let engine = ScriptEngine::new();
+        let env = HashMap::new();
+        let cwd = std::env::current_dir().unwrap();
+
+        let output = engine
+            .execute(r#"print(join_paths(["a", "b", "c.txt"]));"#, &env, &cwd)
+            .unwrap();
+        assert_eq!(
+            output.trim(),
+            Path::new("a").join("b").join("c.txt").to_string_lossy()
+        );
+
+        let err = engine
+            .execute(r#"join_paths(["a", 2]);"#, &env, &cwd)
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("join_paths: expected an array of string, but element 1 is i64"));
+
+        let ints: Vec<i64> = array_to_vec("f", vec![1_i64.into(), 2_i64.into()]).unwrap();
+        assert_eq!(ints, [1, 2]);
+        assert!(array_to_vec::<i64>("f", vec!["x".into()]).is_err());
+    }
+
+    #[test]
+    fn test_context_persists_across_runs() {
+        let engine = ScriptEngine::new();
+        let mut ctx = ScriptContext::new();
+        let script = r#"
+            state.builds = (state.builds ?? 0) + 1;
+            let last = if runs == 0 { "none" } else { previous };
+            let previous = env.VERSION;
+            print(`${state.builds} ${last}`);
+        "#;
+        let run = |ctx: &mut ScriptContext, version: &str| {
+            let env = HashMap::from([("VERSION".to_string(), version.to_string())]);
+            engine
+                .execute_with_context(script, ctx, &env, Path::new("."))
+                .unwrap()
+        };
+
+        assert_eq!(run(&mut ctx, "1.0"), "1 none\n");
+        assert_eq!(run(&mut ctx, "1.1"), "2 1.0\n");
+        assert_eq!(run(&mut ctx, "1.2"), "3 1.1\n");
+        assert_eq!(ctx.runs(), 3);
+        assert_eq!(ctx.get("previous").unwrap().into_string().unwrap(), "1.2");
+
+        // Shadowed `let`s are compacted away rather than piling up.
+        assert_eq!(
+            ctx.scope
+                .iter()
+                .filter(|(n, _, _)| *n == "previous")
+                .count(),
+            1
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exec_output_and_quiet() {
+        let engine = ScriptEngine::new();
+        let env = HashMap::new();
+        let cwd = std::env::current_dir().unwrap();
+
+        let script = r#"
+            let r = exec_output("echo out; echo err >&2; exit 3");
+            let out = r.stdout;
+            let err = r.stderr;
+            out.trim();
+            err.trim();
+            print(out);
+            print(err);
+            print(r.exit_code);
+            print(r.success);
+            print(exec_quiet("true"));
+            print(exec_quiet("echo noisy; false"));
+        "#;
+        let output = engine.execute(script, &env, &cwd).unwrap();
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines, ["out", "err", "3", "false", "true", "false"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exec_uses_the_script_env() {
+        let env = HashMap::from([
+            ("GREETING".to_string(), "hi".to_string()),
+            ("DYLD_INSERT_LIBRARIES".to_string(), "evil.so".to_string()),
+        ]);
+        let cwd = std::env::current_dir().unwrap();
+        let script = r#"
+            let out = exec("echo $GREETING ${HOME-none} ${DYLD_INSERT_LIBRARIES-none}");
+            out.trim();
+            print(out);
+        "#;
+
+        let inherited = ScriptEngine::new().execute(script, &env, &cwd).unwrap();
+        assert!(inherited.starts_with("hi /"), "{inherited}");
+        assert!(inherited.trim_end().ends_with(" evil.so"), "{inherited}");
+
+        let hermetic = ScriptEngine::new()
+            .with_host_env(false, true)
+            .execute(script, &env, &cwd)
+            .unwrap();
+        assert_eq!(hermetic, "hi none none\n");
+    }
+
+    #[test]
+    fn test_stderr_functions_bypass_captured_output() {
+        let engine = ScriptEngine::new();
+        let cwd = std::env::current_dir().unwrap();
+
+        let script = r#"
+            print("kept");
+            print_err("diagnostic");
+            warn("careful");
+        "#;
+        let output = engine.execute(script, &HashMap::new(), &cwd).unwrap();
+        assert_eq!(output, "kept\n");
+    }
+
+    #[test]
+    fn test_timing_functions() {
+        let engine = ScriptEngine::new();
+        let cwd = std::env::current_dir().unwrap();
+
+        let script = r#"
+            let start = time_now();
+            sleep_ms(20);
+            print(time_elapsed_ms(start) >= 20);
+            print(time_format(83000, "duration"));
+            print(time_format(350, "duration"));
+            print(time_format(0, "%Y-%m-%d %H:%M"));
+        "#;
+        let output = engine.execute(script, &HashMap::new(), &cwd).unwrap();
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines, ["true", "1m 23s", "350ms", "1970-01-01 00:00"]);
+
+        let limited = ScriptEngine::new_with_limits(ScriptLimits {
+            timeout_ms: Some(1000),
+            // Unlimited, so only the timeout can stop `loop {}`
+            max_operations: 0,
+            ..ScriptLimits::DEFAULT
+        });
+        let err = limited
+            .execute("sleep_ms(60000);", &HashMap::new(), &cwd)
+            .unwrap_err();
+        assert!(err.to_string().contains("script timeout"), "{err}");
+        let err = limited
+            .execute("loop {}", &HashMap::new(), &cwd)
+            .unwrap_err();
+        assert!(matches!(*err, EvalAltResult::ErrorTerminated(..)), "{err}");
+    }
+
+    #[test]
+    fn test_formatting_functions() {
+        let engine = ScriptEngine::new();
+        let cwd = std::env::current_dir().unwrap();
+
+        let script = r#"
+            print(format_duration(5025000));
+            print(format_duration(45200));
+            print(format_duration(345));
+            print(format_bytes(45));
+            print(format_bytes(234 * 1024));
+            print(format_bytes(1610612736));
+            print(format_number(1234567.891, 2));
+            print(format_number(-0.4, 0));
+            print(pad_left("7", 3, "0"));
+            print(pad_left("long", 2, " "));
+        "#;
+        let output = engine.execute(script, &HashMap::new(), &cwd).unwrap();
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "1h 23m 45s",
+                "45.2s",
+                "345ms",
+                "45 B",
+                "234 KB",
+                "1.5 GB",
+                "1,234,567.89",
+                "0",
+                "007",
+                "long"
+            ]
+        );
+        assert!(engine
+            .execute(r#"pad_left("7", 3, "ab");"#, &HashMap::new(), &cwd)
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_execute_async() {
+        let engine = ScriptEngine::new();
+        let env = HashMap::from([("MY_VAR".to_string(), "async".to_string())]);
+        let cwd = std::env::current_dir().unwrap();
+
+        let result = engine
+            .execute_async(r#"print(env["MY_VAR"]);"#, &env, &cwd)
+            .await;
+        assert_eq!(result.unwrap().trim(), "async");
+        assert!(engine.execute_async("throw 42;", &env, &cwd).await.is_err());
+    }
+
+    #[test]
+    fn test_task_metadata_in_scope() {
+        let engine = ScriptEngine::new();
+        let task: TaskConfig = toml::from_str(
+            r#"
+            depends = ["build"]
+            outputs = ["dist", "coverage"]
+            script = ""
+            "#,
+        )
+        .unwrap();
+        let cwd = std::env::current_dir().unwrap();
+
+        let script = r"
+            print(task.name);
+            print(task.depends[0]);
+            for out in task.outputs { print(out); }
+            print(task.timeout == ());
+        ";
+        let result = engine
+            .execute_task(script, "clean", &task, &HashMap::new(), &cwd)
+            .unwrap();
+        assert_eq!(result, "clean\nbuild\ndist\ncoverage\ntrue\n");
+    }
+
+    #[test]
+    fn test_on_change_queues_tasks() {
+        let engine = ScriptEngine::new();
+        let cwd = std::env::current_dir().unwrap();
+        let script = r#"
+            if changed_files.some(|f| f.starts_with("benches/")) { run_task(task); }
+            run_task("lint");
+            run_task("lint");
+        "#;
+        let changed = ["benches/parse.rs".to_string(), "README.md".to_string()];
+        let queued = engine
+            .evaluate_on_change(script, "bench", &changed, &HashMap::new(), &cwd)
+            .unwrap();
+        assert_eq!(queued, ["bench", "lint"]);
+
+        let queued = engine
+            .evaluate_on_change(script, "bench", &changed[1..], &HashMap::new(), &cwd)
+            .unwrap();
+        assert_eq!(queued, ["lint"]);
+    }
+
     #[test]
     fn test_semver_bump() {
         let engine = ScriptEngine::new();
@@ -431,4 +1608,20 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.unwrap().trim(), "1.3.0");
     }
+
+    #[test]
+    fn test_custom_limits_are_enforced() {
+        let engine = ScriptEngine::new_with_limits(ScriptLimits {
+            max_operations: 50,
+            ..ScriptLimits::default()
+        });
+        let env = HashMap::new();
+        let cwd = std::env::current_dir().unwrap();
+
+        let result = engine.execute("let x = 0; while true { x += 1; }", &env, &cwd);
+        assert!(result.is_err());
+
+        let result = engine.execute(r#"print("ok");"#, &env, &cwd);
+        assert!(result.is_ok());
+    }
 }
@@ -0,0 +1,153 @@
+//! Lightweight remote execution over SSH.
+//!
+//! A task whose `cwd` is an `ssh://[user@]host:/path` URL runs its commands on
+//! that host: each command is wrapped as `ssh [opts] user@host 'cd /path && cmd'`
+//! and executed locally through the shell. There is no file sync or container
+//! orchestration — the remote directory must already hold whatever the task
+//! needs. Only the command's exit status and output come back.
+
+use std::path::Path;
+
+use crate::config::SshExecutorConfig;
+use crate::error::{Result, YatrError};
+
+/// URL scheme that marks a task `cwd` as remote.
+pub const SCHEME: &str = "ssh://";
+
+/// A parsed `ssh://[user@]host:/path` working directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshTarget {
+    pub user: Option<String>,
+    pub host: String,
+    pub path: String,
+}
+
+impl SshTarget {
+    /// Parse an SSH URL of the form `ssh://[user@]host:/path`.
+    pub fn parse(url: &str) -> Result<Self> {
+        let invalid = |reason: &str| YatrError::InvalidConfig {
+            message: format!(
+                "invalid SSH cwd '{url}': {reason} (expected ssh://[user@]host:/path)"
+            ),
+        };
+
+        let rest = url
+            .strip_prefix(SCHEME)
+            .ok_or_else(|| invalid("missing ssh:// scheme"))?;
+        let (authority, path) = rest
+            .split_once(':')
+            .ok_or_else(|| invalid("missing ':' before the remote path"))?;
+        let (user, host) = match authority.split_once('@') {
+            Some((user, host)) => (Some(user), host),
+            None => (None, authority),
+        };
+
+        if host.is_empty() {
+            return Err(invalid("missing host"));
+        }
+        if user.is_some_and(str::is_empty) {
+            return Err(invalid("empty user"));
+        }
+        if path.is_empty() {
+            return Err(invalid("missing remote path"));
+        }
+
+        Ok(Self {
+            user: user.map(String::from),
+            host: host.to_string(),
+            path: path.to_string(),
+        })
+    }
+
+    /// Parse a task `cwd` if it is an SSH URL; `None` for ordinary local paths.
+    #[must_use]
+    pub fn from_cwd(cwd: &Path) -> Option<Result<Self>> {
+        let cwd = cwd.to_string_lossy();
+        cwd.starts_with(SCHEME).then(|| Self::parse(&cwd))
+    }
+
+    /// `user@host`, or just `host` when no user was given.
+    #[must_use]
+    pub fn destination(&self) -> String {
+        self.user
+            .as_ref()
+            .map_or_else(|| self.host.clone(), |user| format!("{user}@{}", self.host))
+    }
+
+    /// Wrap `cmd` into a local shell command that runs it in the remote
+    /// directory over SSH.
+    #[must_use]
+    pub fn wrap_command(&self, cmd: &str, options: Option<&SshExecutorConfig>) -> String {
+        let mut parts = vec!["ssh".to_string()];
+        if let Some(opts) = options {
+            if let Some(key) = &opts.key_file {
+                parts.push("-i".to_string());
+                parts.push(shell_quote(&expand_home(&key.to_string_lossy())));
+            }
+            if let Some(port) = opts.port {
+                parts.push("-p".to_string());
+                parts.push(port.to_string());
+            }
+            if let Some(timeout) = opts.timeout {
+                parts.push("-o".to_string());
+                parts.push(format!("ConnectTimeout={timeout}"));
+            }
+        }
+        parts.push(shell_quote(&self.destination()));
+        parts.push(shell_quote(&format!(
+            "cd {} && {cmd}",
+            shell_quote(&self.path)
+        )));
+        parts.join(" ")
+    }
+}
+
+/// Expand a leading `~/`, which would otherwise be quoted literally.
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{home}/{rest}"),
+        _ => path.to_string(),
+    }
+}
+
+/// Single-quote `s` for a POSIX shell.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ssh_url() {
+        let target = SshTarget::parse("ssh://deploy@build.example.com:/srv/app").unwrap();
+        assert_eq!(target.user.as_deref(), Some("deploy"));
+        assert_eq!(target.host, "build.example.com");
+        assert_eq!(target.path, "/srv/app");
+
+        let target = SshTarget::parse("ssh://box:/tmp").unwrap();
+        assert_eq!(target.user, None);
+        assert_eq!(target.destination(), "box");
+
+        assert!(SshTarget::parse("ssh://box").is_err());
+        assert!(SshTarget::parse("ssh://:/tmp").is_err());
+        assert!(SshTarget::parse("ssh://box:").is_err());
+        assert!(SshTarget::from_cwd(Path::new("./local")).is_none());
+    }
+
+    #[test]
+    fn test_wrap_command() {
+        let target = SshTarget::parse("ssh://me@host:/srv/app").unwrap();
+        let opts = SshExecutorConfig {
+            key_file: Some("/keys/ci".into()),
+            port: Some(2222),
+            timeout: Some(5),
+        };
+        assert_eq!(
+            target.wrap_command("make test", Some(&opts)),
+            r"ssh -i '/keys/ci' -p 2222 -o ConnectTimeout=5 'me@host' 'cd '\''/srv/app'\'' && make test'"
+        );
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+    }
+}
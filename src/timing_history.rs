@@ -0,0 +1,166 @@
+//! Per-task run time history, shown in verbose mode.
+//!
+//! Each task that actually runs (not cached or skipped) and succeeds adds its
+//! duration to the history, and verbose output compares the new duration with
+//! the mean and standard deviation of the earlier ones — a quick way to spot a
+//! flaky task or a performance regression across repeated `yatr watch` runs.
+//!
+//! Histories live in a `timings/` directory beside the action cache, one JSON
+//! file per working directory, keeping each task's last [`MAX_RUNS`] runs.
+
+#![allow(clippy::missing_errors_doc)]
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::cache::Cache;
+use crate::error::Result;
+
+/// Runs kept per task; older ones are dropped first.
+pub const MAX_RUNS: usize = 100;
+
+/// Recorded durations of each task's earlier successful runs.
+#[derive(Debug, Clone, Default)]
+pub struct TimingHistory {
+    path: PathBuf,
+    runs: HashMap<String, Vec<Duration>>,
+}
+
+/// Summary of a task's recorded runs (see [`TimingHistory::stats`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimingStats {
+    pub mean: Duration,
+    /// Population standard deviation
+    pub std_dev: Duration,
+    pub runs: usize,
+}
+
+impl std::fmt::Display for TimingStats {
+    /// `avg: 11.8s ±1.2s, 15 runs`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "avg: {:.1}s ±{:.1}s, {} run{}",
+            self.mean.as_secs_f64(),
+            self.std_dev.as_secs_f64(),
+            self.runs,
+            if self.runs == 1 { "" } else { "s" }
+        )
+    }
+}
+
+impl TimingHistory {
+    /// The history for `cwd` in `cache_dir` (or the default cache directory
+    /// when unset). A missing or unreadable file starts an empty history.
+    #[must_use]
+    pub fn load(cache_dir: Option<&Path>, cwd: &Path) -> Self {
+        let cache_dir = cache_dir.map_or_else(Cache::default_dir, Path::to_path_buf);
+        let cwd = std::path::absolute(cwd).unwrap_or_else(|_| cwd.to_path_buf());
+        let name = blake3::hash(cwd.to_string_lossy().as_bytes()).to_hex();
+        let path = cache_dir
+            .join("timings")
+            .join(format!("{}.json", &name[..32]));
+
+        let millis: HashMap<String, Vec<u64>> = std::fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        let runs = millis
+            .into_iter()
+            .map(|(task, ms)| (task, ms.into_iter().map(Duration::from_millis).collect()))
+            .collect();
+        Self { path, runs }
+    }
+
+    /// Mean and standard deviation of `task`'s recorded runs, or `None`
+    /// before its first.
+    #[must_use]
+    pub fn stats(&self, task: &str) -> Option<TimingStats> {
+        let runs = self.runs.get(task).filter(|runs| !runs.is_empty())?;
+        #[allow(clippy::cast_precision_loss)]
+        let n = runs.len() as f64;
+        let mean = runs.iter().map(Duration::as_secs_f64).sum::<f64>() / n;
+        let variance = runs
+            .iter()
+            .map(|d| (d.as_secs_f64() - mean).powi(2))
+            .sum::<f64>()
+            / n;
+        Some(TimingStats {
+            mean: Duration::from_secs_f64(mean),
+            std_dev: Duration::from_secs_f64(variance.sqrt()),
+            runs: runs.len(),
+        })
+    }
+
+    /// Add a run of `task`, dropping its oldest beyond [`MAX_RUNS`].
+    pub fn record(&mut self, task: &str, duration: Duration) {
+        let runs = self.runs.entry(task.to_string()).or_default();
+        runs.push(duration);
+        if runs.len() > MAX_RUNS {
+            runs.drain(..runs.len() - MAX_RUNS);
+        }
+    }
+
+    /// Write the history back to its file.
+    pub fn save(&self) -> Result<()> {
+        let millis: HashMap<&str, Vec<u64>> = self
+            .runs
+            .iter()
+            .map(|(task, runs)| {
+                let ms = runs
+                    .iter()
+                    .map(|d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX))
+                    .collect();
+                (task.as_str(), ms)
+            })
+            .collect();
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_vec(&millis).map_err(std::io::Error::other)?;
+        Cache::write_atomic(&self.path, &json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_survive_a_reload() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let work = tempfile::tempdir().unwrap();
+
+        let mut history = TimingHistory::load(Some(cache_dir.path()), work.path());
+        assert_eq!(history.stats("test"), None);
+        for secs in [10, 12, 14] {
+            history.record("test", Duration::from_secs(secs));
+        }
+        history.save().unwrap();
+
+        let history = TimingHistory::load(Some(cache_dir.path()), work.path());
+        let stats = history.stats("test").unwrap();
+        assert_eq!(stats.runs, 3);
+        assert_eq!(stats.mean, Duration::from_secs(12));
+        assert_eq!(stats.to_string(), "avg: 12.0s ±1.6s, 3 runs");
+
+        // Other working directories keep their own history.
+        let other = tempfile::tempdir().unwrap();
+        assert_eq!(
+            TimingHistory::load(Some(cache_dir.path()), other.path()).stats("test"),
+            None
+        );
+    }
+
+    #[test]
+    fn keeps_only_the_latest_runs() {
+        let mut history = TimingHistory::default();
+        for ms in 0..=MAX_RUNS as u64 {
+            history.record("build", Duration::from_millis(ms));
+        }
+        let runs = &history.runs["build"];
+        assert_eq!(runs.len(), MAX_RUNS);
+        assert_eq!(runs[0], Duration::from_millis(1));
+    }
+}
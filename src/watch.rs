@@ -5,7 +5,7 @@
 #![allow(clippy::missing_errors_doc)]
 
 use std::collections::HashSet;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use globset::{Glob, GlobSet, GlobSetBuilder};
@@ -13,10 +13,11 @@ use notify::RecursiveMode;
 use notify_debouncer_mini::{new_debouncer, DebouncedEvent, Debouncer};
 use tokio::sync::mpsc;
 
-use crate::config::Config;
+use crate::config::{Config, TaskConfig};
 use crate::error::{Result, YatrError};
-use crate::executor::{Executor, ExecutorConfig};
+use crate::executor::{Executor, ExecutorConfig, TaskResult};
 use crate::graph::TaskGraph;
+use crate::script::ScriptEngine;
 
 /// File watcher for tasks
 pub struct TaskWatcher {
@@ -24,8 +25,17 @@ pub struct TaskWatcher {
     debouncer: Debouncer<notify::RecommendedWatcher>,
     /// Receive channel for events
     rx: mpsc::Receiver<Vec<PathBuf>>,
-    /// Glob patterns to watch
+    /// Glob patterns to watch, as written (`!pattern` excludes)
+    pattern_list: Vec<String>,
+    /// Compiled inclusion patterns of `pattern_list`
     patterns: GlobSet,
+    /// Compiled `!` exclusions of `pattern_list`, applied after `patterns`
+    exclusions: GlobSet,
+    /// Paths currently registered with the underlying watcher
+    watched_paths: Vec<PathBuf>,
+    /// Directory relative patterns and watch dirs are resolved against (the
+    /// working directory at creation)
+    root: PathBuf,
     /// Task to re-run
     task_name: String,
 }
@@ -35,17 +45,8 @@ impl TaskWatcher {
     pub fn new(task_name: &str, patterns: &[String], debounce_ms: u64) -> Result<Self> {
         let (tx, rx) = mpsc::channel(16);
 
-        // Build glob set
-        let mut builder = GlobSetBuilder::new();
-        for pattern in patterns {
-            let glob = Glob::new(pattern).map_err(|e| YatrError::Watch {
-                source: notify::Error::generic(&format!("Invalid glob '{pattern}': {e}")),
-            })?;
-            builder.add(glob);
-        }
-        let patterns = builder.build().map_err(|e| YatrError::Watch {
-            source: notify::Error::generic(&format!("Failed to build glob set: {e}")),
-        })?;
+        let pattern_list = patterns.to_vec();
+        let (patterns, exclusions) = Self::build_globsets(&pattern_list)?;
 
         // Create debounced watcher
         let tx_clone = tx;
@@ -63,32 +64,143 @@ impl TaskWatcher {
         Ok(Self {
             debouncer,
             rx,
+            pattern_list,
             patterns,
+            exclusions,
+            watched_paths: Vec::new(),
+            root: std::env::current_dir().unwrap_or_default(),
             task_name: task_name.to_string(),
         })
     }
 
+    /// Compile glob patterns into an inclusion and an exclusion (`!pattern`) set
+    fn build_globsets(patterns: &[String]) -> Result<(GlobSet, GlobSet)> {
+        let mut includes = GlobSetBuilder::new();
+        let mut excludes = GlobSetBuilder::new();
+        for pattern in patterns {
+            let negated = pattern.strip_prefix('!');
+            let glob = Glob::new(negated.unwrap_or(pattern)).map_err(|e| YatrError::Watch {
+                source: notify::Error::generic(&format!("Invalid glob '{pattern}': {e}")),
+            })?;
+            if negated.is_some() {
+                excludes.add(glob);
+            } else {
+                includes.add(glob);
+            }
+        }
+        let build = |builder: GlobSetBuilder| {
+            builder.build().map_err(|e| YatrError::Watch {
+                source: notify::Error::generic(&format!("Failed to build glob set: {e}")),
+            })
+        };
+        Ok((build(includes)?, build(excludes)?))
+    }
+
     /// Start watching paths
     pub fn watch(&mut self, paths: &[PathBuf]) -> Result<()> {
         for path in paths {
-            self.debouncer
-                .watcher()
-                .watch(path, RecursiveMode::Recursive)
-                .map_err(|e| YatrError::Watch { source: e })?;
+            self.watch_path(path)?;
         }
         Ok(())
     }
 
+    /// Start watching a single path (recursively)
+    pub fn watch_path(&mut self, path: &Path) -> Result<()> {
+        self.debouncer
+            .watcher()
+            .watch(path, RecursiveMode::Recursive)
+            .map_err(|e| YatrError::Watch { source: e })?;
+        if !self.watched_paths.iter().any(|p| p == path) {
+            self.watched_paths.push(path.to_path_buf());
+        }
+        Ok(())
+    }
+
+    /// Watch another directory (recursively), e.g. a shared library outside
+    /// the working directory. A relative `dir` is resolved against the
+    /// working directory; one already inside a watched path is skipped.
+    /// Changes anywhere are filtered by the same patterns (see
+    /// [`Self::matches`]).
+    pub fn add_watch_dir(&mut self, dir: &Path) -> Result<()> {
+        let dir = normalize(&self.root.join(dir));
+        if self
+            .watched_paths
+            .iter()
+            .any(|p| dir.starts_with(normalize(p)))
+        {
+            return Ok(());
+        }
+        self.watch_path(&dir)
+    }
+
+    /// Stop watching a path previously passed to [`Self::watch_path`]
+    pub fn unwatch_path(&mut self, path: &Path) -> Result<()> {
+        self.debouncer
+            .watcher()
+            .unwatch(path)
+            .map_err(|e| YatrError::Watch { source: e })?;
+        self.watched_paths.retain(|p| p != path);
+        Ok(())
+    }
+
+    /// Add a glob pattern to the set of relevant changes
+    pub fn add_pattern(&mut self, pattern: &str) -> Result<()> {
+        if self.pattern_list.iter().any(|p| p == pattern) {
+            return Ok(());
+        }
+        let mut list = self.pattern_list.clone();
+        list.push(pattern.to_string());
+        // Compile before committing so an invalid glob leaves the watcher as-is.
+        (self.patterns, self.exclusions) = Self::build_globsets(&list)?;
+        self.pattern_list = list;
+        Ok(())
+    }
+
+    /// Remove a glob pattern. Returns whether it was present.
+    pub fn remove_pattern(&mut self, pattern: &str) -> Result<bool> {
+        let before = self.pattern_list.len();
+        self.pattern_list.retain(|p| p != pattern);
+        if self.pattern_list.len() == before {
+            return Ok(false);
+        }
+        (self.patterns, self.exclusions) = Self::build_globsets(&self.pattern_list)?;
+        Ok(true)
+    }
+
+    /// The glob patterns currently in effect
+    #[must_use]
+    pub fn patterns(&self) -> &[String] {
+        &self.pattern_list
+    }
+
+    /// The paths currently being watched
+    #[must_use]
+    pub fn watched_paths(&self) -> &[PathBuf] {
+        &self.watched_paths
+    }
+
+    /// Whether a changed path matches an inclusion pattern and no exclusion.
+    /// An absolute path is also tried relative to the working directory
+    /// (with `..` for paths outside it), so `src/*.rs` and
+    /// `../shared/**/*.rs` match whichever watch reported the change.
+    #[must_use]
+    pub fn matches(&self, path: &Path) -> bool {
+        let relative = path
+            .is_absolute()
+            .then(|| relative_to(path, &self.root))
+            .flatten();
+        let candidates = || std::iter::once(path).chain(relative.as_deref());
+        candidates().any(|p| self.patterns.is_match(p))
+            && !candidates().any(|p| self.exclusions.is_match(p))
+    }
+
     /// Wait for the next relevant file change
     pub async fn wait_for_change(&mut self) -> Option<Vec<PathBuf>> {
         loop {
             let paths = self.rx.recv().await?;
 
             // Filter to only matching paths
-            let matching: Vec<PathBuf> = paths
-                .into_iter()
-                .filter(|p| self.patterns.is_match(p))
-                .collect();
+            let matching: Vec<PathBuf> = paths.into_iter().filter(|p| self.matches(p)).collect();
 
             if !matching.is_empty() {
                 return Some(matching);
@@ -109,39 +221,32 @@ pub async fn watch_and_run(
     graph: &TaskGraph,
     task_name: &str,
     exec_config: ExecutorConfig,
+) -> Result<()> {
+    watch_and_run_all(config, graph, &[task_name], exec_config).await
+}
+
+/// Run several tasks in watch mode: one watcher over all of their patterns,
+/// and on each change a single combined run (see [`Executor::execute_all`]).
+pub async fn watch_and_run_all(
+    config: &Config,
+    graph: &TaskGraph,
+    task_names: &[&str],
+    exec_config: ExecutorConfig,
 ) -> Result<()> {
     use console::style;
 
-    let task = graph
-        .get_task(task_name)
-        .ok_or_else(|| YatrError::TaskNotFound {
-            name: task_name.to_string(),
-            available: graph
-                .task_names()
-                .map(std::string::ToString::to_string)
-                .collect(),
-        })?;
-
-    // Determine watch patterns
-    let patterns = if task.config.watch.is_empty() {
-        // Default: watch source files if specified, otherwise watch common patterns
-        if task.config.sources.is_empty() {
-            vec![
-                "**/*.rs".to_string(),
-                "**/*.toml".to_string(),
-                "Cargo.lock".to_string(),
-            ]
-        } else {
-            task.config.sources.clone()
-        }
-    } else {
-        task.config.watch.clone()
-    };
+    let patterns = watch_patterns(graph, task_names)?;
+    let label = task_names.join(", ");
 
     println!(
-        "{} Watching for changes to run task '{}'",
+        "{} Watching for changes to run {} '{}'",
         style("👀").cyan(),
-        style(task_name).bold()
+        if task_names.len() == 1 {
+            "task"
+        } else {
+            "tasks"
+        },
+        style(&label).bold()
     );
     println!("   Patterns: {}", style(patterns.join(", ")).dim());
     println!();
@@ -154,18 +259,46 @@ pub async fn watch_and_run(
     );
 
     println!("{}", style("─".repeat(60)).dim());
-    let _ = executor.execute(graph, task_name).await;
+    let result = executor.execute_all(graph, task_names).await;
+    if matches!(result, Err(YatrError::Interrupted { .. })) {
+        return result.map(|_| ());
+    }
     println!("{}", style("─".repeat(60)).dim());
+    spawn_webhook(config, &label, &result, &[]);
+    if exec_config.watch_until_success && run_succeeded(&result) {
+        return Ok(());
+    }
 
-    // Set up watcher
-    let mut watcher = TaskWatcher::new(task_name, &patterns, config.settings.watch_debounce_ms)?;
+    let mut watcher = start_watcher(&label, &patterns, config)?;
 
-    // Watch current directory
-    watcher.watch(&[std::env::current_dir()?])?;
+    // Stopping before the first success is an interruption: none of the
+    // targets has completed successfully yet.
+    let stopped = || {
+        if exec_config.watch_until_success {
+            return Err(YatrError::Interrupted {
+                completed: 0,
+                total: task_names.len(),
+            });
+        }
+        Ok(())
+    };
+    // Listen before announcing the wait, so a Ctrl-C right after the
+    // announcement can't slip past.
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    if futures::poll!(&mut ctrl_c).is_ready() {
+        return stopped();
+    }
+    println!("{} Waiting for changes...", style("👀").cyan());
 
     // Watch loop
     loop {
-        if let Some(changed) = watcher.wait_for_change().await {
+        let change = tokio::select! {
+            change = watcher.wait_for_change() => change,
+            _ = &mut ctrl_c => return stopped(),
+        };
+
+        if let Some(changed) = change {
             println!();
             println!(
                 "{} Changed: {}",
@@ -177,19 +310,250 @@ pub async fn watch_and_run(
                     .join(", ")
             );
 
+            let to_run = tasks_to_run(config, graph, task_names, &changed).await;
+            if to_run.is_empty() {
+                println!(
+                    "{} on_change_script queued no tasks; waiting for changes...",
+                    style("👀").cyan()
+                );
+                continue;
+            }
+            let to_run: Vec<&str> = to_run.iter().map(String::as_str).collect();
+
             // Clear screen option could go here
             println!("{}", style("─".repeat(60)).dim());
 
             let executor = Executor::new(config.clone(), exec_config.clone(), None);
 
-            let _ = executor.execute(graph, task_name).await;
+            let result = executor.execute_all(graph, &to_run).await;
+            if matches!(result, Err(YatrError::Interrupted { .. })) {
+                return result.map(|_| ());
+            }
             println!("{}", style("─".repeat(60)).dim());
+            spawn_webhook(config, &label, &result, &changed);
+            if exec_config.watch_until_success && run_succeeded(&result) {
+                return Ok(());
+            }
             println!("{} Waiting for changes...", style("👀").cyan());
         }
     }
 }
 
-/// Collect all watch patterns from a task and its dependencies
+/// The watch patterns of `task_names`, in order and without duplicates
+fn watch_patterns(graph: &TaskGraph, task_names: &[&str]) -> Result<Vec<String>> {
+    let mut patterns: Vec<String> = Vec::new();
+    for &task_name in task_names {
+        let task = graph
+            .get_task(task_name)
+            .ok_or_else(|| YatrError::TaskNotFound {
+                name: task_name.to_string(),
+                available: graph
+                    .task_names_sorted()
+                    .into_iter()
+                    .map(String::from)
+                    .collect(),
+            })?;
+        for pattern in task_patterns(&task.config) {
+            if !patterns.contains(&pattern) {
+                patterns.push(pattern);
+            }
+        }
+    }
+    Ok(patterns)
+}
+
+/// Watch the current directory, plus any directory a pattern reaches outside
+/// it, for changes matching `patterns`.
+fn start_watcher(label: &str, patterns: &[String], config: &Config) -> Result<TaskWatcher> {
+    let mut watcher = TaskWatcher::new(label, patterns, config.settings.watch_debounce_ms)?;
+    watcher.watch(&[std::env::current_dir()?])?;
+    for dir in patterns.iter().filter_map(|p| pattern_dir(p)) {
+        if let Err(e) = watcher.add_watch_dir(&dir) {
+            eprintln!("not watching {}: {e}", dir.display());
+        }
+    }
+    Ok(watcher)
+}
+
+/// The tasks to run for a change: each watched task itself, or — when it has
+/// an `on_change_script` — whatever the script queued with `run_task`. A
+/// failing script or an unknown queued task is reported and skipped.
+async fn tasks_to_run(
+    config: &Config,
+    graph: &TaskGraph,
+    task_names: &[&str],
+    changed: &[PathBuf],
+) -> Vec<String> {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let changed_files: Vec<String> = changed
+        .iter()
+        .map(|p| p.strip_prefix(&cwd).unwrap_or(p).display().to_string())
+        .collect();
+
+    let mut to_run: Vec<String> = Vec::new();
+    for &name in task_names {
+        let Some(task) = graph.get_task(name) else {
+            continue;
+        };
+        let Some(script) = task.config.on_change_script.clone() else {
+            to_run.push(name.to_string());
+            continue;
+        };
+        let engine = ScriptEngine::new_with_limits(config.settings.script_limits)
+            .with_host_env(config.settings.inherit_env, config.settings.sanitize_env);
+        let (task_name, files) = (name.to_string(), changed_files.clone());
+        let env = config.task_env(&task.config);
+        let task_cwd = task.config.cwd.clone().unwrap_or_else(|| cwd.clone());
+        let queued = tokio::task::spawn_blocking(move || {
+            engine.evaluate_on_change(&script, &task_name, &files, &env, &task_cwd)
+        })
+        .await;
+        match queued {
+            Ok(Ok(queued)) => to_run.extend(queued),
+            Ok(Err(e)) => eprintln!("on_change_script of '{name}' failed: {e}"),
+            Err(e) => eprintln!("on_change_script of '{name}' panicked: {e}"),
+        }
+    }
+
+    let mut seen = HashSet::new();
+    to_run.retain(|name| {
+        if !graph.has_task(name) {
+            eprintln!("on_change_script queued unknown task '{name}'");
+            return false;
+        }
+        seen.insert(name.clone())
+    });
+    to_run
+}
+
+/// Watched when a task names no files of its own: common Rust project files.
+const DEFAULT_WATCH_PATTERNS: [&str; 3] = ["**/*.rs", "**/*.toml", "Cargo.lock"];
+
+/// A task's own watch patterns: `watch`, else its `sources`, else
+/// [`DEFAULT_WATCH_PATTERNS`]. A `watch` list of only `!` exclusions narrows
+/// what would be watched without it instead of leaving nothing to watch.
+fn task_patterns(task: &TaskConfig) -> Vec<String> {
+    let only_exclusions = |patterns: &[String]| patterns.iter().all(|p| p.starts_with('!'));
+    let mut patterns = task.watch.clone();
+    if only_exclusions(&patterns) {
+        patterns.extend(task.sources.iter().cloned());
+    }
+    if only_exclusions(&patterns) {
+        patterns.extend(DEFAULT_WATCH_PATTERNS.map(String::from));
+    }
+    patterns
+}
+
+/// The directory a watch pattern's matches live under: its leading
+/// components without wildcards (`../shared/src` for `../shared/src/**/*.rs`).
+/// `None` for `!` exclusions and patterns starting with a wildcard.
+fn pattern_dir(pattern: &str) -> Option<PathBuf> {
+    if pattern.starts_with('!') {
+        return None;
+    }
+    let mut components: Vec<&str> = pattern
+        .split('/')
+        .take_while(|c| !c.contains(['*', '?', '[', '{']))
+        .collect();
+    if components.len() == pattern.split('/').count() {
+        components.pop();
+    }
+    let dir: PathBuf = components.iter().collect();
+    (!dir.as_os_str().is_empty()).then_some(dir)
+}
+
+/// Resolve `.` and `..` components without touching the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            c => normalized.push(c),
+        }
+    }
+    normalized
+}
+
+/// `path` relative to `base`, using `..` to climb out of it. `None` when they
+/// share no root (e.g. different Windows drives).
+fn relative_to(path: &Path, base: &Path) -> Option<PathBuf> {
+    let (path, base) = (normalize(path), normalize(base));
+    let common = path
+        .components()
+        .zip(base.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    if common == 0 {
+        return None;
+    }
+    let ups = base.components().count() - common;
+    Some(
+        std::iter::repeat_n(std::path::Component::ParentDir.as_os_str(), ups)
+            .chain(
+                path.components()
+                    .skip(common)
+                    .map(std::path::Component::as_os_str),
+            )
+            .collect(),
+    )
+}
+
+/// Whether a watch run finished with every task succeeding.
+fn run_succeeded(result: &Result<Vec<TaskResult>>) -> bool {
+    result
+        .as_ref()
+        .is_ok_and(|results| results.iter().all(|r| r.success))
+}
+
+/// Fire the `watch_webhook` (if configured) in the background, so a slow or
+/// unreachable endpoint never holds up the watch loop.
+fn spawn_webhook(
+    config: &Config,
+    task_name: &str,
+    result: &Result<Vec<TaskResult>>,
+    changed: &[PathBuf],
+) {
+    let Some(url) = config.settings.watch_webhook.clone() else {
+        return;
+    };
+    let success = run_succeeded(result);
+    let payload = serde_json::json!({
+        "task": task_name,
+        "success": success,
+        "changed_files": changed.iter().map(|p| p.display().to_string()).collect::<Vec<_>>(),
+    });
+    tokio::spawn(async move {
+        if let Err(e) = notify_webhook(&url, &payload).await {
+            tracing::warn!("watch webhook failed: {e}");
+        }
+    });
+}
+
+/// POST `payload` as JSON to `url`, giving up after two seconds.
+pub async fn notify_webhook(url: &str, payload: &serde_json::Value) -> Result<()> {
+    let webhook_err =
+        |e: reqwest::Error| YatrError::Io(std::io::Error::other(format!("webhook {url}: {e}")));
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(2))
+        .user_agent(concat!("yatr/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(webhook_err)?;
+    client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(payload.to_string())
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(webhook_err)?;
+    Ok(())
+}
+
+/// Collect all watch patterns from a task and its dependencies. `!pattern`
+/// exclusions are kept as written and apply to the combined set.
 pub fn collect_watch_patterns(graph: &TaskGraph, task_name: &str) -> Result<Vec<String>> {
     let tasks = graph.execution_order(task_name)?;
     let mut patterns = HashSet::new();
@@ -203,10 +567,9 @@ pub fn collect_watch_patterns(graph: &TaskGraph, task_name: &str) -> Result<Vec<
         }
     }
 
-    // Add default patterns if none specified
-    if patterns.is_empty() {
-        patterns.insert("**/*.rs".to_string());
-        patterns.insert("**/Cargo.toml".to_string());
+    // Add default patterns if no inclusions are specified
+    if patterns.iter().all(|p| p.starts_with('!')) {
+        patterns.extend(DEFAULT_WATCH_PATTERNS.map(String::from));
     }
 
     Ok(patterns.into_iter().collect())
@@ -216,6 +579,65 @@ pub fn collect_watch_patterns(graph: &TaskGraph, task_name: &str) -> Result<Vec<
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn watch_once_returns_after_first_success() {
+        let dir = tempfile::tempdir().unwrap();
+        let config: Config = toml::from_str(
+            r#"
+            [tasks.test]
+            run = ["true"]
+            "#,
+        )
+        .unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let exec_config = ExecutorConfig {
+            cwd: dir.path().to_path_buf(),
+            watch_until_success: true,
+            ..Default::default()
+        };
+
+        // Without `watch_until_success` this would wait for changes forever.
+        let run = watch_and_run(&config, &graph, "test", exec_config);
+        tokio::time::timeout(Duration::from_secs(10), run)
+            .await
+            .expect("watch --watch-once should exit after a passing run")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn watch_runs_several_tasks_as_one_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let config: Config = toml::from_str(
+            r#"
+            [tasks.test]
+            run = ["true"]
+            [tasks.clippy]
+            run = ["true"]
+            "#,
+        )
+        .unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let exec_config = ExecutorConfig {
+            cwd: dir.path().to_path_buf(),
+            watch_until_success: true,
+            ..Default::default()
+        };
+
+        let run = watch_and_run_all(&config, &graph, &["test", "clippy"], exec_config);
+        tokio::time::timeout(Duration::from_secs(10), run)
+            .await
+            .expect("watch --watch-once should exit after a passing run")
+            .unwrap();
+        assert!(watch_and_run_all(
+            &config,
+            &graph,
+            &["test", "nope"],
+            ExecutorConfig::default()
+        )
+        .await
+        .is_err());
+    }
+
     #[test]
     fn test_collect_patterns() {
         let config: Config = toml::from_str(
@@ -235,4 +657,149 @@ mod tests {
         assert!(patterns.contains(&"tests/**/*.rs".to_string()));
         assert!(patterns.contains(&"Cargo.toml".to_string()));
     }
+
+    #[test]
+    fn test_add_and_remove_patterns() {
+        let mut watcher = TaskWatcher::new("t", &["**/*.rs".to_string()], 50).unwrap();
+        assert!(watcher.matches(Path::new("src/main.rs")));
+        assert!(!watcher.matches(Path::new("web/app.ts")));
+
+        watcher.add_pattern("**/*.ts").unwrap();
+        assert!(watcher.matches(Path::new("web/app.ts")));
+        assert!(watcher.add_pattern("[").is_err());
+        assert_eq!(watcher.patterns().len(), 2);
+
+        assert!(watcher.remove_pattern("**/*.rs").unwrap());
+        assert!(!watcher.remove_pattern("**/*.rs").unwrap());
+        assert!(!watcher.matches(Path::new("src/main.rs")));
+    }
+
+    #[test]
+    fn test_negated_patterns_exclude_matches() {
+        let config: Config = toml::from_str(
+            r#"
+            [tasks.gen]
+            run = ["true"]
+            watch = ["!**/generated/**"]
+            [tasks.test]
+            depends = ["gen"]
+            run = ["true"]
+            watch = ["src/**/*.rs"]
+            "#,
+        )
+        .unwrap();
+        let graph = TaskGraph::from_config(&config).unwrap();
+        let patterns = collect_watch_patterns(&graph, "test").unwrap();
+        assert_eq!(patterns.len(), 2);
+
+        let mut watcher = TaskWatcher::new("test", &patterns, 50).unwrap();
+        assert!(watcher.matches(Path::new("src/lib.rs")));
+        assert!(!watcher.matches(Path::new("src/generated/api.rs")));
+
+        assert!(watcher.remove_pattern("!**/generated/**").unwrap());
+        assert!(watcher.matches(Path::new("src/generated/api.rs")));
+        assert!(watcher.add_pattern("![").is_err());
+
+        // Only exclusions: the defaults still apply, minus the exclusion.
+        let patterns = collect_watch_patterns(&graph, "gen").unwrap();
+        let watcher = TaskWatcher::new("gen", &patterns, 50).unwrap();
+        assert!(watcher.matches(Path::new("src/main.rs")));
+        assert!(!watcher.matches(Path::new("src/generated/api.rs")));
+    }
+
+    #[test]
+    fn test_task_patterns_with_only_exclusions() {
+        let config: Config = toml::from_str(
+            r#"
+            [tasks.gen]
+            run = ["true"]
+            watch = ["!**/generated/**"]
+            [tasks.docs]
+            run = ["true"]
+            sources = ["docs/**/*.md"]
+            watch = ["!docs/drafts/**"]
+            [tasks.lint]
+            run = ["true"]
+            sources = ["src/**/*.rs"]
+            "#,
+        )
+        .unwrap();
+        let patterns = |name: &str| task_patterns(&config.tasks[name]);
+
+        // The patterns `yatr watch` uses: exclusions narrow the defaults...
+        let watcher = TaskWatcher::new("gen", &patterns("gen"), 50).unwrap();
+        assert!(watcher.matches(Path::new("src/main.rs")));
+        assert!(!watcher.matches(Path::new("src/generated/api.rs")));
+
+        // ...or the task's sources, when it has some.
+        let watcher = TaskWatcher::new("docs", &patterns("docs"), 50).unwrap();
+        assert!(watcher.matches(Path::new("docs/guide.md")));
+        assert!(!watcher.matches(Path::new("docs/drafts/next.md")));
+        assert!(!watcher.matches(Path::new("src/main.rs")));
+
+        assert_eq!(patterns("lint"), ["src/**/*.rs"]);
+    }
+
+    #[test]
+    fn test_watch_and_unwatch_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut watcher = TaskWatcher::new("t", &[], 50).unwrap();
+
+        watcher.watch_path(dir.path()).unwrap();
+        assert_eq!(watcher.watched_paths(), [dir.path().to_path_buf()]);
+
+        watcher.unwatch_path(dir.path()).unwrap();
+        assert!(watcher.watched_paths().is_empty());
+    }
+
+    #[test]
+    fn test_add_watch_dir_outside_cwd() {
+        let cwd = std::env::current_dir().unwrap();
+        let patterns = ["src/*.rs".to_string(), "../shared/**/*.rs".to_string()];
+        let mut watcher = TaskWatcher::new("t", &patterns, 50).unwrap();
+        assert!(watcher.matches(&cwd.join("src/main.rs")));
+        assert!(watcher.matches(&cwd.join("../shared/lib/a.rs")));
+        assert!(!watcher.matches(&cwd.join("../other/a.rs")));
+
+        watcher.watch_path(&cwd).unwrap();
+        watcher.add_watch_dir(Path::new("src")).unwrap();
+        assert_eq!(watcher.watched_paths().len(), 1, "already covered by cwd");
+
+        let outside = tempfile::tempdir().unwrap();
+        watcher.add_watch_dir(outside.path()).unwrap();
+        watcher.add_watch_dir(&outside.path().join(".")).unwrap();
+        assert_eq!(watcher.watched_paths().len(), 2);
+
+        assert_eq!(pattern_dir("../shared/**/*.rs"), Some("../shared".into()));
+        assert_eq!(pattern_dir("Cargo.toml"), None);
+        assert_eq!(pattern_dir("**/*.rs"), None);
+        assert_eq!(pattern_dir("!../shared/**"), None);
+    }
+
+    #[tokio::test]
+    async fn test_notify_webhook_posts_json() {
+        use wiremock::matchers::{body_json, header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let payload = serde_json::json!({
+            "task": "build",
+            "success": true,
+            "changed_files": ["src/main.rs"],
+        });
+        Mock::given(method("POST"))
+            .and(path("/changed"))
+            .and(header("content-type", "application/json"))
+            .and(body_json(&payload))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let url = format!("{}/changed", server.uri());
+        notify_webhook(&url, &payload).await.unwrap();
+
+        let missing = format!("{}/nope", server.uri());
+        assert!(notify_webhook(&missing, &payload).await.is_err());
+    }
 }
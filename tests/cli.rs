@@ -4,7 +4,8 @@ use std::io::Write;
 
 use assert_cmd::Command;
 
-/// `yatr schema` prints a valid draft-07 JSON Schema titled "Config".
+/// `yatr schema` prints a valid draft-07 JSON Schema titled "Config", with
+/// field descriptions.
 #[test]
 fn schema_prints_valid_json_schema() {
     let output = Command::cargo_bin("yatr")
@@ -18,6 +19,12 @@ fn schema_prints_valid_json_schema() {
         serde_json::from_slice(&output.stdout).expect("schema is valid JSON");
     assert_eq!(json["title"], "Config");
     assert!(json["definitions"]["TaskConfig"]["properties"]["outputs"].is_object());
+    // Doc comments on config fields become descriptions for editor tooltips.
+    assert!(
+        json["definitions"]["TaskConfig"]["properties"]["depends"]["description"]
+            .as_str()
+            .is_some_and(|d| !d.is_empty())
+    );
 }
 
 /// `yatr run --json` emits a structured document and no human chrome.
@@ -47,6 +54,27 @@ fn run_json_emits_structured_output() {
     assert_eq!(json["tasks"][0]["success"], true);
 }
 
+/// `yatr run --timing` rates the finished run's parallel efficiency.
+#[test]
+fn run_timing_reports_parallel_efficiency() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("yatr.toml"),
+        "[settings]\ncache = false\n[tasks.hello]\nrun = [\"echo hi\"]\n",
+    )
+    .unwrap();
+
+    let output = Command::cargo_bin("yatr")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(["run", "--timing", "hello"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Parallel efficiency: 100%"), "{stdout}");
+}
+
 /// `yatr run --json --dry-run` emits the execution plan instead of running.
 #[test]
 fn run_json_dry_run_emits_plan() {
@@ -105,6 +133,109 @@ fn run_profile_writes_chrome_trace() {
     assert!(names.contains(&"a") && names.contains(&"b"));
 }
 
+/// `yatr run --output-dir` writes stdout, stderr, and metadata per task.
+#[test]
+fn run_output_dir_writes_task_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut cfg = std::fs::File::create(dir.path().join("yatr.toml")).unwrap();
+    write!(
+        cfg,
+        "[settings]\ncache = false\n[tasks.hello]\nshell = true\nrun = [\"echo out; echo err >&2\"]\n"
+    )
+    .unwrap();
+
+    let out_dir = dir.path().join("logs/run");
+    let output = Command::cargo_bin("yatr")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(["run", "--output-dir"])
+        .arg(&out_dir)
+        .arg("hello")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stdout = std::fs::read_to_string(out_dir.join("hello.stdout")).unwrap();
+    let stderr = std::fs::read_to_string(out_dir.join("hello.stderr")).unwrap();
+    assert!(stdout.contains("out"));
+    assert!(stderr.contains("err"));
+
+    let meta: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(out_dir.join("hello.json")).unwrap())
+            .expect("metadata is valid JSON");
+    assert_eq!(meta["exit_code"], 0);
+    assert_eq!(meta["cached"], false);
+    assert!(meta["duration_ms"].is_number());
+}
+
+/// `yatr run --predict-cache` reports hit/miss without running anything.
+#[test]
+fn run_predict_cache_reports_status() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut cfg = std::fs::File::create(dir.path().join("yatr.toml")).unwrap();
+    write!(
+        cfg,
+        "[settings]\ncache_dir = \".cache\"\n[tasks.a]\nrun = [\"echo a\"]\n[tasks.b]\ndepends = [\"a\"]\nrun = [\"touch ran-b\"]\n"
+    )
+    .unwrap();
+
+    let yatr = || {
+        let mut cmd = Command::cargo_bin("yatr").unwrap();
+        cmd.current_dir(dir.path());
+        cmd
+    };
+    assert!(yatr().args(["run", "a"]).output().unwrap().status.success());
+
+    let output = yatr()
+        .args(["run", "--predict-cache", "--json", "b"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(!dir.path().join("ran-b").exists(), "nothing should run");
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["tasks"][0]["name"], "a");
+    assert_eq!(json["tasks"][0]["status"], "hit");
+    assert_eq!(json["tasks"][1]["name"], "b");
+    assert_eq!(json["tasks"][1]["status"], "miss");
+}
+
+/// `yatr --print-config` dumps the merged config as TOML and runs nothing.
+#[test]
+fn print_config_dumps_resolved_toml() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("yatr.toml"),
+        "include = [\"shared.toml\"]\n[tasks.build]\ndepends = [\"lint\"]\nrun = [\"touch ran\"]\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("shared.toml"),
+        "[tasks.lint]\nrun = [\"echo lint\"]\n",
+    )
+    .unwrap();
+
+    let output = Command::cargo_bin("yatr")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(["--print-config", "build"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(!dir.path().join("ran").exists(), "nothing should run");
+
+    let dumped: toml::Table = toml::from_str(&String::from_utf8_lossy(&output.stdout)).unwrap();
+    assert_eq!(
+        dumped["tasks"]["build"]["depends"][0].as_str(),
+        Some("lint")
+    );
+    assert_eq!(
+        dumped["tasks"]["lint"]["run"][0].as_str(),
+        Some("echo lint")
+    );
+    assert_eq!(dumped["include"].as_array().map(Vec::len), Some(0));
+}
+
 /// `yatr affected <ref>` lists only tasks whose sources changed since the ref.
 #[test]
 fn affected_lists_tasks_touched_by_changes() {
@@ -279,3 +410,588 @@ fn check_validates_files_and_warns() {
     let stdout = String::from_utf8_lossy(&out.stdout);
     assert!(stdout.contains("warning:"), "expected a warning: {stdout}");
 }
+
+/// `yatr check --strict-deps` fails when a task reads another's outputs
+/// without depending on it.
+#[test]
+fn check_strict_deps_rejects_implicit_ordering() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("yatr.toml"),
+        "[tasks.gen]\nrun = [\"gen\"]\noutputs = [\"dist\"]\n[tasks.pack]\nrun = [\"pack\"]\nsources = [\"dist/**\"]\n",
+    )
+    .unwrap();
+    let check = |args: &[&str]| {
+        Command::cargo_bin("yatr")
+            .unwrap()
+            .current_dir(dir.path())
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    assert!(check(&["check"]).status.success());
+    let out = check(&["check", "--strict-deps"]);
+    assert!(!out.status.success());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(
+        stdout.contains("task 'pack': source 'dist/**' overlaps output 'dist' of task 'gen'"),
+        "{stdout}"
+    );
+}
+
+/// `yatr diff --format json` reports changed lines against the last cached run.
+#[test]
+fn diff_json_compares_against_cached_output() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("yatr.toml"),
+        "[settings]\ncache_dir = \".cache\"\n[tasks.show]\nshell = true\nrun = [\"cat data.txt\"]\n",
+    )
+    .unwrap();
+    let diff = |contents: &str| {
+        std::fs::write(dir.path().join("data.txt"), contents).unwrap();
+        let out = Command::cargo_bin("yatr")
+            .unwrap()
+            .current_dir(dir.path())
+            .args(["diff", "show", "--format", "json"])
+            .output()
+            .unwrap();
+        assert!(out.status.success());
+        serde_json::from_slice::<serde_json::Value>(&out.stdout).unwrap()
+    };
+
+    // First run: nothing cached yet.
+    assert_eq!(diff("one\ntwo\n")["cached"], false);
+
+    let json = diff("one\nthree\n");
+    assert_eq!(json["cached"], true);
+    assert_eq!(json["stats"]["inserted"], 1);
+    assert_eq!(json["stats"]["deleted"], 1);
+}
+
+/// Arguments after `--` fill in `${args.name}` placeholders.
+#[test]
+fn run_passes_task_args() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("yatr.toml"),
+        "[settings]\ncache = false\n[tasks.greet]\nargs = { name = \"world\" }\nrun = [\"echo hello-${args.name}\"]\n",
+    )
+    .unwrap();
+    let run = |args: &[&str]| {
+        let out = Command::cargo_bin("yatr")
+            .unwrap()
+            .current_dir(dir.path())
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "{out:?}");
+        String::from_utf8_lossy(&out.stdout).into_owned()
+    };
+
+    assert!(run(&["run", "greet"]).contains("hello-world"));
+    assert!(run(&["run", "greet", "--", "--name", "bob"]).contains("hello-bob"));
+    assert!(run(&["greet", "--", "--name=amy"]).contains("hello-amy"));
+}
+
+/// `--report junit` is written even when a task fails.
+#[test]
+fn run_report_junit_records_failures() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("yatr.toml"),
+        "[settings]\ncache = false\n[tasks.ok]\nrun = [\"true\"]\n[tasks.bad]\ndepends = [\"ok\"]\nrun = [\"false\"]\n",
+    )
+    .unwrap();
+
+    let out = Command::cargo_bin("yatr")
+        .unwrap()
+        .current_dir(dir.path())
+        .args([
+            "run",
+            "bad",
+            "--report",
+            "junit",
+            "--report-file",
+            "out/junit.xml",
+        ])
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+
+    let xml = std::fs::read_to_string(dir.path().join("out/junit.xml")).unwrap();
+    assert!(xml.contains(r#"tests="2" failures="1""#), "{xml}");
+    assert!(xml.contains(r#"<testcase classname="ok" name="ok""#));
+    assert!(xml.contains("<failure"));
+}
+
+/// Wait (up to 10s) for `path` to exist, e.g. a marker a task creates once
+/// it's running.
+#[cfg(unix)]
+fn wait_for_file(path: &std::path::Path) {
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while !path.exists() {
+        assert!(
+            std::time::Instant::now() < deadline,
+            "{} never appeared",
+            path.display()
+        );
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    }
+}
+
+/// Read `child`'s stdout until a line containing `text`, e.g. one it
+/// prints once it's ready for a signal. Panics if stdout ends first.
+#[cfg(unix)]
+fn wait_for_line(child: &mut std::process::Child, text: &str) {
+    use std::io::BufRead;
+
+    let stdout = child.stdout.as_mut().expect("stdout is piped");
+    let mut lines = std::io::BufReader::new(stdout).lines();
+    assert!(
+        lines.any(|line| line.unwrap().contains(text)),
+        "stdout ended before {text:?}"
+    );
+}
+
+/// Ctrl-C during a run stops it with exit code 130 and a summary of progress.
+#[cfg(unix)]
+#[test]
+fn sigint_reports_interrupted_with_exit_130() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("yatr.toml"),
+        "[tasks.slow]\nshell = true\nrun = [\"touch started && sleep 30\"]\n",
+    )
+    .unwrap();
+    let child = std::process::Command::new(assert_cmd::cargo::cargo_bin("yatr"))
+        .current_dir(dir.path())
+        .args(["--no-color", "run", "slow"])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    wait_for_file(&dir.path().join("started"));
+    let killed = std::process::Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(killed.success());
+
+    let out = child.wait_with_output().unwrap();
+    assert_eq!(out.status.code(), Some(130));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        stderr.contains("Interrupted after 0 of 1 tasks"),
+        "{stderr}"
+    );
+}
+
+/// Ctrl-C while waiting for a `foreground` task is an interruption too (exit
+/// 130), not a successful run.
+#[cfg(unix)]
+#[test]
+fn sigint_while_waiting_for_foreground_exits_130() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("yatr.toml"),
+        "[tasks.serve]\nforeground = true\nrun = [\"sleep 30\"]\n",
+    )
+    .unwrap();
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("yatr"))
+        .current_dir(dir.path())
+        .args(["--no-color", "run", "serve"])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    wait_for_line(&mut child, "Waiting for serve");
+    let killed = std::process::Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(killed.success());
+
+    let out = child.wait_with_output().unwrap();
+    assert_eq!(out.status.code(), Some(130));
+}
+
+/// `watch --watch-once` interrupted before a successful run exits 130 like
+/// any other interrupted run.
+#[cfg(unix)]
+#[test]
+fn sigint_before_watch_once_succeeds_exits_130() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("yatr.toml"),
+        "[settings]\ncache = false\n[tasks.flaky]\nrun = [\"false\"]\n",
+    )
+    .unwrap();
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("yatr"))
+        .current_dir(dir.path())
+        .args(["--no-color", "watch", "--watch-once", "flaky"])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    wait_for_line(&mut child, "Waiting for changes");
+    let killed = std::process::Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(killed.success());
+
+    let out = child.wait_with_output().unwrap();
+    assert_eq!(out.status.code(), Some(130));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        stderr.contains("Interrupted after 0 of 1 tasks"),
+        "{stderr}"
+    );
+}
+
+/// `yatr script --list-functions` prints each stdlib signature with its summary.
+#[test]
+fn script_list_functions_prints_stdlib() {
+    let output = Command::cargo_bin("yatr")
+        .unwrap()
+        .args(["script", "--list-functions"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout
+        .lines()
+        .find(|l| l.contains("read_file(path: string) -> string"))
+        .expect("read_file is listed");
+    assert!(line.contains("Read a file as text"));
+}
+
+/// `yatr init --from makefile` writes a loadable `yatr.toml` from `.PHONY`
+/// targets and lists what it couldn't convert.
+#[test]
+fn init_from_makefile_writes_config() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("Makefile"),
+        "GREETING := hello\n.PHONY: all test\nall: test\ntest:\n\techo $(GREETING)\n\
+         ifdef DEBUG\nendif\n",
+    )
+    .unwrap();
+
+    let output = Command::cargo_bin("yatr")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(["init", "--from", "makefile"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let text = std::fs::read_to_string(dir.path().join("yatr.toml")).unwrap();
+    assert!(text.starts_with("# Not converted"), "{text}");
+    assert!(text.contains("`ifdef` directive"), "{text}");
+    assert!(text.contains("GREETING = \"hello\""), "{text}");
+
+    let output = Command::cargo_bin("yatr")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(["list", "--format", "plain"])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "all\ntest\n");
+
+    let output = Command::cargo_bin("yatr")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(["run", "test"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("hello"));
+}
+
+/// `yatr init` writes the file yatr loads.
+#[test]
+fn init_writes_loadable_config() {
+    let dir = tempfile::tempdir().unwrap();
+    let yatr = |args: &[&str]| {
+        Command::cargo_bin("yatr")
+            .unwrap()
+            .current_dir(dir.path())
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    assert!(yatr(&["init"]).status.success());
+    assert!(dir.path().join("yatr.toml").is_file());
+    let list = yatr(&["list", "--format", "plain"]);
+    assert!(list.status.success(), "{list:?}");
+    assert!(String::from_utf8_lossy(&list.stdout).contains("build\n"));
+    assert!(!yatr(&["init"]).status.success());
+}
+
+/// `YATR_CONFIG_CONTENT` supplies the config instead of a file; `--config`
+/// still wins.
+#[test]
+fn config_from_environment_variable() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("yatr.toml"),
+        "[settings]\ncache = false\n[tasks.from-file]\nrun = [\"echo file\"]\n",
+    )
+    .unwrap();
+    let yatr = |args: &[&str]| {
+        let out = Command::cargo_bin("yatr")
+            .unwrap()
+            .current_dir(dir.path())
+            .env(
+                "YATR_CONFIG_CONTENT",
+                "[settings]\ncache = false\n[tasks.from-env]\nrun = [\"echo env\"]\n",
+            )
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "{out:?}");
+        String::from_utf8_lossy(&out.stdout).into_owned()
+    };
+
+    assert_eq!(yatr(&["list", "--format", "plain"]).trim(), "from-env");
+    assert!(yatr(&["check"]).contains("<env:YATR_CONFIG_CONTENT> is valid"));
+    assert_eq!(
+        yatr(&["--config", "yatr.toml", "list", "--format", "plain"]).trim(),
+        "from-file"
+    );
+}
+
+/// `yatr completion bash` prints a script covering the subcommands, with task
+/// names completed through `yatr list`.
+#[test]
+fn completion_prints_bash_script() {
+    let output = Command::cargo_bin("yatr")
+        .unwrap()
+        .args(["completion", "bash"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let script = String::from_utf8(output.stdout).unwrap();
+    assert!(script.starts_with("# yatr bash completion."), "{script}");
+    assert!(script.contains("_yatr()"));
+    assert!(script.contains("yatr__subcmd__run"));
+    assert!(script.contains("yatr list --format plain"));
+}
+
+/// A `${VAR}` set only in yatr's own environment stays in the command for the
+/// shell to expand, so a dry run never prints its value.
+#[cfg(unix)]
+#[test]
+fn host_secrets_are_not_interpolated_into_commands() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("yatr.toml"),
+        "[settings]\ncache = false\n[tasks.deploy]\nrun = [\"echo \\\"token ${API_TOKEN}\\\"\"]\n",
+    )
+    .unwrap();
+    let yatr = |args: &[&str]| {
+        let out = Command::cargo_bin("yatr")
+            .unwrap()
+            .current_dir(dir.path())
+            .env("API_TOKEN", "supersecret")
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "{out:?}");
+        String::from_utf8_lossy(&out.stdout).into_owned()
+    };
+
+    let plan = yatr(&["run", "--dry-run", "deploy"]);
+    assert!(plan.contains("${API_TOKEN}"), "{plan}");
+    assert!(!plan.contains("supersecret"), "{plan}");
+    // The shell still expands it (and the output is masked as usual).
+    let run = yatr(&["run", "--shell", "deploy"]);
+    assert!(run.contains("token ****"), "{run}");
+}
+
+/// `--config-profile` works after the subcommand too, and its env reaches
+/// the command; `run --profile` keeps writing a Chrome trace.
+#[cfg(unix)]
+#[test]
+fn config_profile_env_reaches_commands() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("yatr.toml"),
+        "[settings]\ncache = false\n[env]\nMODE = \"dev\"\n\
+         [profiles.ci]\nenv = { MODE = \"ci\" }\n\
+         [tasks.show]\nrun = [\"echo mode=$MODE\"]\n",
+    )
+    .unwrap();
+    let yatr = |args: &[&str]| {
+        let out = Command::cargo_bin("yatr")
+            .unwrap()
+            .current_dir(dir.path())
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "{out:?}");
+        String::from_utf8_lossy(&out.stdout).into_owned()
+    };
+
+    assert!(yatr(&["run", "--shell", "show"]).contains("mode=dev"));
+    let out = yatr(&[
+        "run",
+        "--shell",
+        "show",
+        "--config-profile",
+        "ci",
+        "--profile",
+        "trace.json",
+    ]);
+    assert!(out.contains("mode=ci"), "{out}");
+    assert!(dir.path().join("trace.json").is_file());
+    assert!(yatr(&["--config-profile", "ci", "run", "--shell", "show"]).contains("mode=ci"));
+}
+
+/// `run --tag` adds the tagged tasks to the named ones, `list --tag` only
+/// lists them, and a tag no task carries is an error.
+#[cfg(unix)]
+#[test]
+fn tag_selects_tasks_to_run_and_list() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("yatr.toml"),
+        "[settings]\ncache = false\n\
+         [tasks.lint]\nrun = [\"echo ran-lint\"]\ntags = [\"check\"]\n\
+         [tasks.test]\nrun = [\"echo ran-test\"]\ntags = [\"check\", \"slow\"]\n\
+         [tasks.build]\nrun = [\"echo ran-build\"]\n",
+    )
+    .unwrap();
+    let yatr = |args: &[&str]| {
+        Command::cargo_bin("yatr")
+            .unwrap()
+            .current_dir(dir.path())
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let run = yatr(&["run", "--shell", "--tag", "check"]);
+    assert!(run.status.success(), "{run:?}");
+    let stdout = String::from_utf8_lossy(&run.stdout);
+    assert!(
+        stdout.contains("ran-lint") && stdout.contains("ran-test"),
+        "{stdout}"
+    );
+    assert!(!stdout.contains("ran-build"), "{stdout}");
+
+    let run = yatr(&["run", "--shell", "build", "--tag", "slow"]);
+    assert!(run.status.success(), "{run:?}");
+    let stdout = String::from_utf8_lossy(&run.stdout);
+    assert!(
+        stdout.contains("ran-build") && stdout.contains("ran-test"),
+        "{stdout}"
+    );
+    assert!(!stdout.contains("ran-lint"), "{stdout}");
+
+    let list = yatr(&["list", "--format", "plain", "--tag", "slow"]);
+    assert!(list.status.success(), "{list:?}");
+    assert_eq!(String::from_utf8_lossy(&list.stdout), "test\n");
+
+    let missing = yatr(&["run", "--tag", "nope"]);
+    assert!(!missing.status.success());
+    let stderr = String::from_utf8_lossy(&missing.stderr);
+    assert!(stderr.contains("no tasks tagged nope"), "{stderr}");
+}
+
+/// `--env K=V` reaches the command, overriding both the global `[env]` and
+/// the task's own `env`.
+#[cfg(unix)]
+#[test]
+fn env_flag_overrides_config_env() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("yatr.toml"),
+        "[settings]\ncache = false\n[env]\nK = \"global\"\n\
+         [tasks.show]\nrun = [\"echo k=$K\"]\n\
+         [tasks.own]\nrun = [\"echo k=$K\"]\nenv = { K = \"task\" }\n",
+    )
+    .unwrap();
+    let yatr = |args: &[&str]| {
+        let out = Command::cargo_bin("yatr")
+            .unwrap()
+            .current_dir(dir.path())
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "{out:?}");
+        String::from_utf8_lossy(&out.stdout).into_owned()
+    };
+
+    assert!(yatr(&["run", "--shell", "own"]).contains("k=task"));
+    let out = yatr(&["run", "--shell", "--env", "K=V", "show"]);
+    assert!(out.contains("k=V"), "{out}");
+    let out = yatr(&["--env", "K=V", "run", "--shell", "own"]);
+    assert!(out.contains("k=V"), "{out}");
+}
+
+/// `yatr check` doesn't call an env var substituted into `run` unused.
+#[test]
+fn check_counts_interpolated_env_as_used() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("yatr.toml"),
+        "[tasks.build]\nenv = { TARGET = \"x86_64\" }\nrun = [\"echo ${TARGET}\"]\n",
+    )
+    .unwrap();
+
+    let output = Command::cargo_bin("yatr")
+        .unwrap()
+        .current_dir(dir.path())
+        .arg("check")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    let all = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(!all.contains("never referenced"), "{all}");
+}
+
+/// A host-only `${VAR}` reaches a command run without a shell, but stays out
+/// of the dry-run plan.
+#[cfg(unix)]
+#[test]
+fn host_variables_reach_commands_without_a_shell() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("yatr.toml"),
+        "[settings]\ncache = false\n[tasks.show]\nrun = [\"echo token=${HOST_ONLY_VAR}\"]\n",
+    )
+    .unwrap();
+    let yatr = |args: &[&str]| {
+        let out = Command::cargo_bin("yatr")
+            .unwrap()
+            .current_dir(dir.path())
+            .env("HOST_ONLY_VAR", "abc")
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "{out:?}");
+        String::from_utf8_lossy(&out.stdout).into_owned()
+    };
+
+    let run = yatr(&["run", "show"]);
+    assert!(run.contains("token=abc"), "{run}");
+    let plan = yatr(&["run", "--dry-run", "show"]);
+    assert!(
+        plan.contains("${HOST_ONLY_VAR}") && !plan.contains("abc"),
+        "{plan}"
+    );
+}